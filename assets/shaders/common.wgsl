@@ -30,6 +30,11 @@ struct CameraUniform {
 struct MaterialUniform {
     base_color: vec4<f32>,
     properties: vec4<f32>, // x: metallic, y: roughness, z: ao, w: texture_scale
+    emissive: vec4<f32>,
+    alpha_cutoff: f32,
+    alpha_mode: u32, // 0: opaque, 1: mask, 2: blend
+    double_sided: u32,
+    uv_transforms: array<mat3x3<f32>, 5>, // diffuse, normal, metallic roughness, ao, emissive
 };
 
 struct PointLight {
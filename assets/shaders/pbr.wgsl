@@ -1,5 +1,5 @@
 #define_import_path weaver::pbr
-#import weaver::common::{ModelTransform, CameraUniform, MaterialUniform, VertexInput, MIN_LIGHT_INTENSITY, PI};
+#import weaver::common::{ModelTransform, CameraUniform, MaterialUniform, MIN_LIGHT_INTENSITY, PI};
 
 struct PointLight {
     position: vec4<f32>,
@@ -18,6 +18,11 @@ struct PointLight {
 @group(0) @binding(6) var          roughness_sampler: sampler;
 @group(0) @binding(7) var          ao_tex: texture_2d<f32>;
 @group(0) @binding(8) var          ao_sampler: sampler;
+@group(0) @binding(9) var          emissive_tex: texture_2d<f32>;
+@group(0) @binding(10) var         emissive_sampler: sampler;
+
+const ALPHA_MODE_OPAQUE: u32 = 0u;
+const ALPHA_MODE_MASK: u32 = 1u;
 
 // camera information
 @group(1) @binding(0) var<uniform> camera: CameraUniform;
@@ -32,6 +37,30 @@ struct PointLight {
 @group(3) @binding(3) var          env_map_brdf: texture_2d<f32>;
 @group(3) @binding(4) var          env_map_sampler: sampler;
 
+// skin information
+@group(4) @binding(0) var<storage> joint_matrices: array<mat4x4<f32>>;
+
+fn skin_matrix(joints: vec4<u32>, weights: vec4<f32>) -> mat4x4<f32> {
+    let total = weights.x + weights.y + weights.z + weights.w;
+    if total <= 0.0 {
+        return mat4x4<f32>(
+            vec4(1.0, 0.0, 0.0, 0.0),
+            vec4(0.0, 1.0, 0.0, 0.0),
+            vec4(0.0, 0.0, 1.0, 0.0),
+            vec4(0.0, 0.0, 0.0, 1.0),
+        );
+    }
+    let w = weights / total;
+    return joint_matrices[joints.x] * w.x
+        + joint_matrices[joints.y] * w.y
+        + joint_matrices[joints.z] * w.z
+        + joint_matrices[joints.w] * w.w;
+}
+
+fn transform_uv(uv: vec2<f32>, texture: u32) -> vec2<f32> {
+    return (material.uv_transforms[texture] * vec3(uv, 1.0)).xy;
+}
+
 fn saturate(x: f32) -> f32 {
     return clamp(x, 0.0, 1.0);
 }
@@ -142,7 +171,7 @@ fn calculate_ibl(
     let irradiance = textureSample(env_map_diffuse, env_map_sampler, N).rgb;
     let diffuse = irradiance * albedo;
 
-    let prefiltered_color = textureSampleLevel(env_map_specular, env_map_sampler, R, roughness * f32(textureNumLevels(env_map_specular))).rgb;
+    let prefiltered_color = textureSampleLevel(env_map_specular, env_map_sampler, R, roughness * f32(textureNumLevels(env_map_specular) - 1u)).rgb;
     let brdf = textureSample(env_map_brdf, env_map_sampler, vec2(NdotV, roughness)).rg;
     let specular = prefiltered_color * (kS * brdf.x + brdf.y);
 
@@ -150,6 +179,24 @@ fn calculate_ibl(
 }
 
 
+// the optional attributes are only declared if the mesh has them
+struct VertexInput {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) tangent: vec3<f32>,
+#ifdef VERTEX_UV_0
+    @location(3) uv: vec2<f32>,
+#endif
+#ifdef VERTEX_JOINT_INDICES
+    @location(4) joints: vec4<u32>,
+    @location(5) weights: vec4<f32>,
+#endif
+#ifdef VERTEX_COLOR
+    @location(6) color: vec4<f32>,
+#endif
+};
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) world_position: vec3<f32>,
@@ -157,6 +204,9 @@ struct VertexOutput {
     @location(2) world_binormal: vec3<f32>,
     @location(3) world_tangent: vec3<f32>,
     @location(4) uv: vec2<f32>,
+#ifdef VERTEX_COLOR
+    @location(5) color: vec4<f32>,
+#endif
 }
 
 struct FragmentOutput {
@@ -167,14 +217,26 @@ struct FragmentOutput {
 fn vs_main(input: VertexInput) -> VertexOutput {
     var output: VertexOutput;
 
-    let world_position = (model_transform * vec4<f32>(input.position, 1.0));
+#ifdef VERTEX_JOINT_INDICES
+    let model = model_transform * skin_matrix(input.joints, input.weights);
+#else
+    let model = model_transform;
+#endif
+    let world_position = (model * vec4<f32>(input.position, 1.0));
 
     output.world_position = world_position.xyz;
     output.clip_position = camera.proj * camera.view * world_position;
+#ifdef VERTEX_UV_0
     output.uv = input.uv;
-
-    var N = normalize((model_transform * vec4<f32>(input.normal, 0.0)).xyz);
-    var T = normalize((model_transform * vec4<f32>(input.tangent, 0.0)).xyz);
+#else
+    output.uv = vec2<f32>(0.0);
+#endif
+#ifdef VERTEX_COLOR
+    output.color = input.color;
+#endif
+
+    var N = normalize((model * vec4<f32>(input.normal, 0.0)).xyz);
+    var T = normalize((model * vec4<f32>(input.tangent, 0.0)).xyz);
     var B = normalize(cross(N, T));
 
     output.world_tangent = T;
@@ -185,16 +247,21 @@ fn vs_main(input: VertexInput) -> VertexOutput {
 }
 
 @fragment
-fn fs_main(vertex: VertexOutput) -> FragmentOutput {
+fn fs_main(vertex: VertexOutput, @builtin(front_facing) front_facing: bool) -> FragmentOutput {
     var output: FragmentOutput;
 
     let uv = vertex.uv * material.properties.w;
 
-    let tex_color = textureSample(diffuse_tex, diffuse_sampler, uv).rgba * material.base_color.rgba;
-    let albedo = pow(tex_color, vec4(2.2));
+    var tex_color = textureSample(diffuse_tex, diffuse_sampler, transform_uv(uv, 0u)).rgba * material.base_color.rgba;
+    var albedo = pow(tex_color, vec4(2.2));
+#ifdef VERTEX_COLOR
+    // vertex colors are linear
+    albedo = albedo * vertex.color;
+    tex_color.a = tex_color.a * vertex.color.a;
+#endif
 
 
-    var tex_normal = textureSample(normal_tex, normal_sampler, uv).rgb;
+    var tex_normal = textureSample(normal_tex, normal_sampler, transform_uv(uv, 1u)).rgb;
     var N: vec3<f32>;
     if tex_normal.r == 0.0 && tex_normal.g == 0.0 && tex_normal.b == 0.0 {
         N = vertex.world_normal;
@@ -205,9 +272,14 @@ fn fs_main(vertex: VertexOutput) -> FragmentOutput {
         );
     }
 
+    // back faces of double-sided materials are lit like front faces
+    if !front_facing {
+        N = -N;
+    }
+
     let V = normalize(camera.camera_position - vertex.world_position);
 
-    let metallic_roughness = textureSample(roughness_tex, roughness_sampler, uv);
+    let metallic_roughness = textureSample(roughness_tex, roughness_sampler, transform_uv(uv, 2u));
     let roughness = metallic_roughness.g * material.properties.y;
     let metallic = metallic_roughness.b * material.properties.x;
 
@@ -228,16 +300,29 @@ fn fs_main(vertex: VertexOutput) -> FragmentOutput {
         illumination += calculate_lighting(albedo.rgb, roughness, metallic, N, L, V, light.color.rgb, attenuation);
     }
 
-    let tex_ao = textureSample(ao_tex, ao_sampler, uv).r * material.properties.z;
+    let tex_ao = textureSample(ao_tex, ao_sampler, transform_uv(uv, 3u)).r * material.properties.z;
     let ibl = calculate_ibl(albedo.rgb, roughness, metallic, N, V);
     let ambient = ibl * tex_ao;
 
-    var out_color = ambient + illumination;
+    let emissive = textureSample(emissive_tex, emissive_sampler, transform_uv(uv, 4u)).rgb * material.emissive.rgb;
+
+    var out_color = ambient + illumination + emissive;
 
     // gamma correction
     out_color = pow(out_color, vec3(1.0 / 2.2));
 
-    output.color = vec4<f32>(out_color, tex_color.a);
+    // discarding only after all textures are sampled keeps the samples in uniform control flow
+    var alpha = tex_color.a;
+    if material.alpha_mode == ALPHA_MODE_OPAQUE {
+        alpha = 1.0;
+    } else if material.alpha_mode == ALPHA_MODE_MASK {
+        if alpha < material.alpha_cutoff {
+            discard;
+        }
+        alpha = 1.0;
+    }
+
+    output.color = vec4<f32>(out_color, alpha);
 
     return output;
 }
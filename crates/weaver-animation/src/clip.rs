@@ -0,0 +1,300 @@
+use std::ops::{Add, Mul};
+
+use weaver_asset::prelude::Asset;
+use weaver_core::prelude::{Quat, Vec3};
+use weaver_util::prelude::*;
+
+/// Keyframed curves that animate the entities of a scene, e.g. one of the animations of a glTF file.
+///
+/// Channels address their entities by index, so that a clip can be played on any copy of the scene it was made for;
+/// the [`AnimationPlayer`](crate::player::AnimationPlayer) maps the indices to entities.
+#[derive(Debug, Clone, Default, Asset)]
+pub struct AnimationClip {
+    pub name: Option<String>,
+    channels: Vec<AnimationChannel>,
+    duration: f32,
+}
+
+impl AnimationClip {
+    pub fn new(name: Option<String>) -> Self {
+        Self {
+            name,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_channel(&mut self, channel: AnimationChannel) {
+        self.duration = self.duration.max(channel.duration());
+        self.channels.push(channel);
+    }
+
+    pub fn channels(&self) -> &[AnimationChannel] {
+        &self.channels
+    }
+
+    /// The time of the last keyframe of any channel, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+}
+
+/// How the values between two keyframes are computed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    /// The value of the previous keyframe is held until the next one.
+    Step,
+    #[default]
+    Linear,
+    /// Cubic Hermite spline through the keyframes, with tangents stored alongside the values.
+    CubicSpline,
+}
+
+impl Interpolation {
+    fn values_per_keyframe(self) -> usize {
+        match self {
+            Interpolation::CubicSpline => 3,
+            Interpolation::Step | Interpolation::Linear => 1,
+        }
+    }
+}
+
+/// The values of a channel's keyframes.
+///
+/// With [`Interpolation::CubicSpline`] every keyframe has three values: its in-tangent, its value and its out-tangent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Keyframes {
+    Translation(Vec<Vec3>),
+    Rotation(Vec<Quat>),
+    Scale(Vec<Vec3>),
+    /// The weights of all morph targets of a mesh, one after another for every keyframe.
+    Weights(Vec<f32>),
+}
+
+impl Keyframes {
+    fn len(&self) -> usize {
+        match self {
+            Keyframes::Translation(values) => values.len(),
+            Keyframes::Rotation(values) => values.len(),
+            Keyframes::Scale(values) => values.len(),
+            Keyframes::Weights(values) => values.len(),
+        }
+    }
+}
+
+/// The value of a channel at some point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelValue {
+    Translation(Vec3),
+    Rotation(Quat),
+    Scale(Vec3),
+    Weights(Vec<f32>),
+}
+
+/// Animates one property of one entity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationChannel {
+    /// The index of the animated entity in the [`AnimationPlayer`](crate::player::AnimationPlayer)'s targets.
+    pub target: usize,
+    interpolation: Interpolation,
+    times: Vec<f32>,
+    keyframes: Keyframes,
+}
+
+impl AnimationChannel {
+    /// Creates a channel from keyframe times in seconds (in increasing order) and their values.
+    pub fn new(
+        target: usize,
+        interpolation: Interpolation,
+        times: Vec<f32>,
+        keyframes: Keyframes,
+    ) -> Result<Self> {
+        ensure!(!times.is_empty(), "Animation channel has no keyframes");
+        ensure!(
+            times.windows(2).all(|pair| pair[0] <= pair[1]),
+            "Animation channel keyframe times are not in increasing order"
+        );
+        let expected = times.len() * interpolation.values_per_keyframe();
+        match &keyframes {
+            Keyframes::Weights(values) => ensure!(
+                values.len() % expected == 0,
+                "Animation channel has {} morph target weights, which isn't a multiple of {}",
+                values.len(),
+                expected
+            ),
+            keyframes => ensure!(
+                keyframes.len() == expected,
+                "Animation channel has {} values for {} keyframes",
+                keyframes.len(),
+                times.len()
+            ),
+        }
+
+        Ok(Self {
+            target,
+            interpolation,
+            times,
+            keyframes,
+        })
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    pub fn times(&self) -> &[f32] {
+        &self.times
+    }
+
+    pub fn keyframes(&self) -> &Keyframes {
+        &self.keyframes
+    }
+
+    /// The time of the last keyframe, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.times.last().copied().unwrap_or(0.0)
+    }
+
+    /// Samples the channel at a time in seconds. Times outside of the keyframes hold the first or last value.
+    pub fn sample(&self, time: f32) -> ChannelValue {
+        match &self.keyframes {
+            Keyframes::Translation(values) => {
+                ChannelValue::Translation(self.sample_element(values, 1, 0, time))
+            }
+            Keyframes::Rotation(values) => {
+                ChannelValue::Rotation(self.sample_element(values, 1, 0, time).normalize())
+            }
+            Keyframes::Scale(values) => {
+                ChannelValue::Scale(self.sample_element(values, 1, 0, time))
+            }
+            Keyframes::Weights(values) => {
+                let width =
+                    values.len() / self.interpolation.values_per_keyframe() / self.times.len();
+                ChannelValue::Weights(
+                    (0..width)
+                        .map(|element| self.sample_element(values, width, element, time))
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// Samples one of the `width` values that every keyframe has.
+    fn sample_element<T: Interpolate>(
+        &self,
+        values: &[T],
+        width: usize,
+        element: usize,
+        time: f32,
+    ) -> T {
+        let stride = self.interpolation.values_per_keyframe();
+        // the value itself is the middle one of a cubic spline keyframe
+        let value_offset = stride / 2;
+        let value =
+            |keyframe: usize, offset: usize| values[(keyframe * stride + offset) * width + element];
+
+        let next = self.times.partition_point(|&keyframe| keyframe <= time);
+        if next == 0 {
+            return value(0, value_offset);
+        }
+        if next == self.times.len() {
+            return value(next - 1, value_offset);
+        }
+        let previous = next - 1;
+        let delta = self.times[next] - self.times[previous];
+        let t = (time - self.times[previous]) / delta;
+
+        match self.interpolation {
+            Interpolation::Step => value(previous, 0),
+            Interpolation::Linear => value(previous, 0).interpolate(value(next, 0), t),
+            Interpolation::CubicSpline => hermite(
+                value(previous, 1),
+                value(previous, 2),
+                value(next, 1),
+                value(next, 0),
+                t,
+                delta,
+            ),
+        }
+    }
+}
+
+trait Interpolate: Copy + Add<Output = Self> + Mul<f32, Output = Self> {
+    fn interpolate(self, other: Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Vec3 {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+}
+
+impl Interpolate for Quat {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self.slerp(other, t)
+    }
+}
+
+/// Cubic Hermite spline from `from` to `to`, with the tangents scaled by the time between the keyframes.
+fn hermite<T: Interpolate>(from: T, out_tangent: T, to: T, in_tangent: T, t: f32, delta: f32) -> T {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    from * (2.0 * t3 - 3.0 * t2 + 1.0)
+        + out_tangent * ((t3 - 2.0 * t2 + t) * delta)
+        + to * (-2.0 * t3 + 3.0 * t2)
+        + in_tangent * ((t3 - t2) * delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_interpolations() {
+        let times = vec![0.0, 1.0, 2.0];
+        let values = vec![Vec3::ZERO, Vec3::X, Vec3::Y];
+
+        let linear = AnimationChannel::new(
+            0,
+            Interpolation::Linear,
+            times.clone(),
+            Keyframes::Translation(values.clone()),
+        )
+        .unwrap();
+        assert_eq!(linear.sample(0.5), ChannelValue::Translation(Vec3::X * 0.5));
+        assert_eq!(linear.sample(-1.0), ChannelValue::Translation(Vec3::ZERO));
+        assert_eq!(linear.sample(3.0), ChannelValue::Translation(Vec3::Y));
+
+        let step = AnimationChannel::new(
+            0,
+            Interpolation::Step,
+            times.clone(),
+            Keyframes::Translation(values),
+        )
+        .unwrap();
+        assert_eq!(step.sample(1.5), ChannelValue::Translation(Vec3::X));
+
+        // flat tangents ease in and out, so the curve passes the midpoint halfway
+        let cubic = AnimationChannel::new(
+            0,
+            Interpolation::CubicSpline,
+            vec![0.0, 1.0],
+            Keyframes::Weights(vec![
+                0.0, 0.0, 2.0, 4.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+            ]),
+        )
+        .unwrap();
+        assert_eq!(cubic.sample(0.5), ChannelValue::Weights(vec![1.0, 2.0]));
+        assert_eq!(cubic.sample(1.0), ChannelValue::Weights(vec![0.0, 0.0]));
+
+        assert!(
+            AnimationChannel::new(0, Interpolation::Linear, times, Keyframes::Scale(vec![]))
+                .is_err()
+        );
+    }
+}
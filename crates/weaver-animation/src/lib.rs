@@ -0,0 +1,172 @@
+use clip::AnimationClip;
+use player::{advance_animations, apply_animations};
+use skin::compute_joint_matrices;
+use tween::{TweenCompleted, animate_asset_tweens, animate_component_tweens};
+use weaver_app::{
+    App, AppStage,
+    plugin::{Plugin, PluginId},
+};
+use weaver_asset::{Asset, AssetApp};
+use weaver_core::{CoreTypesPlugin, hierarchy::propagate_transforms, transform::Transform};
+use weaver_ecs::{component::Component, system::IntoSystemConfig};
+use weaver_event::Events;
+use weaver_util::prelude::*;
+
+pub mod clip;
+pub mod player;
+pub mod skin;
+pub mod tween;
+
+pub mod prelude {
+    pub use crate::AnimationPlugin;
+    pub use crate::clip::*;
+    pub use crate::player::*;
+    pub use crate::skin::*;
+    pub use crate::tween::*;
+    pub use crate::{AssetTweenPlugin, TweenPlugin};
+}
+
+/// Plays [`AnimationClip`]s, computes the joint matrices of skinned meshes and adds a [`TweenPlugin`] for
+/// [`Transform`] [`Tween`](tween::Tween)s.
+///
+/// Animations are applied after the `Update` stage and before transforms are propagated, so they override changes that
+/// systems make to animated transforms.
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<CoreTypesPlugin>()]
+    }
+
+    fn build(&self, app: &mut App) -> Result<()> {
+        app.add_asset::<AnimationClip>();
+        if !app.has_plugin::<TweenPlugin<Transform>>() {
+            app.add_plugin(TweenPlugin::<Transform>::default())?;
+        }
+
+        app.add_system(
+            advance_animations.before(propagate_transforms),
+            AppStage::PostUpdate,
+        );
+        app.add_system(
+            apply_animations
+                .after(advance_animations)
+                .before(propagate_transforms),
+            AppStage::PostUpdate,
+        );
+        app.add_system(
+            compute_joint_matrices.after(propagate_transforms),
+            AppStage::PostUpdate,
+        );
+        Ok(())
+    }
+}
+
+/// Animates the [`Tween`](tween::Tween)s of components of type `T`.
+///
+/// [`AnimationPlugin`] already adds the plugin for [`Transform`]; adding it again is skipped like any other
+/// duplicate plugin.
+pub struct TweenPlugin<T: Component>(std::marker::PhantomData<T>);
+
+impl<T: Component> Default for TweenPlugin<T> {
+    fn default() -> Self {
+        Self(std::marker::PhantomData)
+    }
+}
+
+impl<T: Component> Plugin for TweenPlugin<T> {
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<CoreTypesPlugin>()]
+    }
+
+    fn build(&self, app: &mut App) -> Result<()> {
+        add_tween_events(app);
+        app.add_system(animate_component_tweens::<T>, AppStage::Update);
+        Ok(())
+    }
+}
+
+/// Animates the [`Tween`](tween::Tween)s of assets of type `T`, which are applied to the asset that the tween's
+/// entity has a `Handle<T>` to. Entities that share an asset all see its animated values.
+pub struct AssetTweenPlugin<T: Asset>(std::marker::PhantomData<T>);
+
+impl<T: Asset> Default for AssetTweenPlugin<T> {
+    fn default() -> Self {
+        Self(std::marker::PhantomData)
+    }
+}
+
+impl<T: Asset> Plugin for AssetTweenPlugin<T> {
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<CoreTypesPlugin>()]
+    }
+
+    fn build(&self, app: &mut App) -> Result<()> {
+        add_tween_events(app);
+        app.add_system(animate_asset_tweens::<T>, AppStage::Update);
+        Ok(())
+    }
+}
+
+/// Tween plugins share the [`TweenCompleted`] event, which is only added by the first of them.
+fn add_tween_events(app: &mut App) {
+    if !app.has_resource::<Events<TweenCompleted>>() {
+        app.add_event::<TweenCompleted>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use weaver_app::settings::AppSettings;
+    use weaver_asset::{AssetPlugin, AssetSettings};
+    use weaver_core::{prelude::Vec3, time::Time};
+    use weaver_task::{task_pool::TaskPool, usages::GlobalTaskPool};
+
+    use super::*;
+    use crate::tween::{EaseFunction, Tween};
+
+    #[test]
+    fn test_transform_tweens_advance_once_per_frame() {
+        GlobalTaskPool::get_or_init(TaskPool::new);
+
+        let mut settings = AppSettings::new();
+        settings
+            .set_section(&AssetSettings {
+                watch_for_changes: false,
+                write_meta_files: false,
+                ..Default::default()
+            })
+            .unwrap();
+        let mut app = App::new();
+        app.insert_resource(settings);
+        app.add_plugin(AssetPlugin).unwrap();
+        app.add_plugin(CoreTypesPlugin).unwrap();
+        app.add_plugin(AnimationPlugin).unwrap();
+        // Skipped as a duplicate, so the tween isn't advanced twice per frame.
+        app.add_plugin(TweenPlugin::<Transform>::default()).unwrap();
+
+        let mut time = Time::new();
+        time.delta_time = 0.25;
+        app.insert_resource(time);
+        let entity = app.main_app_mut().world_mut().spawn((
+            Transform::default(),
+            Tween::<Transform>::to(
+                |transform| &mut transform.translation,
+                Vec3::ZERO,
+                Vec3::X,
+                1.0,
+                EaseFunction::Linear,
+            ),
+        ));
+
+        app.init();
+        app.update();
+
+        let world = app.main_app().world();
+        let translation = world
+            .query::<&Transform>()
+            .get(entity)
+            .map(|transform| transform.translation);
+        assert_eq!(translation, Some(Vec3::X * 0.25));
+    }
+}
@@ -0,0 +1,373 @@
+use weaver_asset::{Assets, Handle};
+use weaver_core::{
+    prelude::{Quat, Vec3},
+    time::Time,
+    transform::Transform,
+};
+use weaver_ecs::{
+    entity::Entity,
+    prelude::{Res, ResMut},
+    query::Query,
+};
+use weaver_util::prelude::*;
+
+use crate::clip::{AnimationClip, ChannelValue};
+
+/// The weights of the morph targets of an entity's mesh, animated by weight channels of [`AnimationClip`]s.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MorphWeights(pub Vec<f32>);
+
+/// Plays [`AnimationClip`]s on a group of entities, e.g. the nodes of a spawned scene.
+///
+/// Clips that play at the same time are blended by their weights.
+#[derive(Debug, Clone, Default)]
+pub struct AnimationPlayer {
+    /// The entities that the clips animate, indexed by [`AnimationChannel::target`](crate::clip::AnimationChannel::target).
+    /// Channels whose target is `None` or out of range are skipped.
+    pub targets: Vec<Option<Entity>>,
+    animations: Vec<PlayingAnimation>,
+}
+
+impl AnimationPlayer {
+    pub fn new(targets: Vec<Option<Entity>>) -> Self {
+        Self {
+            targets,
+            animations: Vec::new(),
+        }
+    }
+
+    /// Starts playing a clip from the beginning at full weight, or returns it if it's already playing.
+    pub fn play(&mut self, clip: Handle<AnimationClip>) -> &mut PlayingAnimation {
+        match self
+            .animations
+            .iter()
+            .position(|animation| animation.clip == clip)
+        {
+            Some(index) => &mut self.animations[index],
+            None => {
+                self.animations.push(PlayingAnimation::new(clip));
+                self.animations.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// Fades a clip in over `duration` seconds while fading out all other clips, which stop once they're silent.
+    pub fn crossfade(
+        &mut self,
+        clip: Handle<AnimationClip>,
+        duration: f32,
+    ) -> &mut PlayingAnimation {
+        for animation in self.animations.iter_mut() {
+            if animation.clip != clip {
+                animation.fade_to(0.0, duration);
+            }
+        }
+        let is_new = !self.is_playing(&clip);
+        let animation = self.play(clip);
+        if is_new {
+            animation.weight = 0.0;
+        }
+        animation.fade_to(1.0, duration);
+        animation
+    }
+
+    pub fn stop(&mut self, clip: &Handle<AnimationClip>) {
+        self.animations.retain(|animation| animation.clip != *clip);
+    }
+
+    pub fn stop_all(&mut self) {
+        self.animations.clear();
+    }
+
+    pub fn is_playing(&self, clip: &Handle<AnimationClip>) -> bool {
+        self.animation(clip).is_some()
+    }
+
+    pub fn animation(&self, clip: &Handle<AnimationClip>) -> Option<&PlayingAnimation> {
+        self.animations
+            .iter()
+            .find(|animation| animation.clip == *clip)
+    }
+
+    pub fn animation_mut(&mut self, clip: &Handle<AnimationClip>) -> Option<&mut PlayingAnimation> {
+        self.animations
+            .iter_mut()
+            .find(|animation| animation.clip == *clip)
+    }
+
+    pub fn animations(&self) -> impl Iterator<Item = &PlayingAnimation> {
+        self.animations.iter()
+    }
+}
+
+/// A clip that's playing on an [`AnimationPlayer`].
+#[derive(Debug, Clone)]
+pub struct PlayingAnimation {
+    pub clip: Handle<AnimationClip>,
+    /// The current time in the clip, in seconds.
+    pub time: f32,
+    /// Playback speed, negative to play backwards.
+    pub speed: f32,
+    /// How much the clip contributes when it's blended with other clips.
+    pub weight: f32,
+    /// Whether the clip starts over once it reaches its end, rather than holding its last pose.
+    pub repeat: bool,
+    pub paused: bool,
+    fade: Option<Fade>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fade {
+    target: f32,
+    /// Weight change per second.
+    rate: f32,
+}
+
+impl PlayingAnimation {
+    fn new(clip: Handle<AnimationClip>) -> Self {
+        Self {
+            clip,
+            time: 0.0,
+            speed: 1.0,
+            weight: 1.0,
+            repeat: false,
+            paused: false,
+            fade: None,
+        }
+    }
+
+    pub fn repeat(&mut self) -> &mut Self {
+        self.repeat = true;
+        self
+    }
+
+    pub fn set_speed(&mut self, speed: f32) -> &mut Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn set_weight(&mut self, weight: f32) -> &mut Self {
+        self.weight = weight;
+        self.fade = None;
+        self
+    }
+
+    pub fn seek(&mut self, time: f32) -> &mut Self {
+        self.time = time;
+        self
+    }
+
+    /// Changes the weight gradually over `duration` seconds. Clips that fade to zero are stopped.
+    pub fn fade_to(&mut self, weight: f32, duration: f32) -> &mut Self {
+        let rate = if duration > 0.0 {
+            (weight - self.weight).abs() / duration
+        } else {
+            f32::MAX
+        };
+        self.fade = Some(Fade {
+            target: weight,
+            rate,
+        });
+        self
+    }
+
+    /// Whether a clip that doesn't repeat has played to its end.
+    pub fn is_finished(&self, clip: &AnimationClip) -> bool {
+        !self.repeat
+            && if self.speed < 0.0 {
+                self.time <= 0.0
+            } else {
+                self.time >= clip.duration()
+            }
+    }
+
+    /// Advances the time and the fade. Returns false once the clip has faded out.
+    fn advance(&mut self, delta_time: f32, duration: f32) -> bool {
+        if !self.paused {
+            self.time += delta_time * self.speed;
+        }
+        if self.repeat && duration > 0.0 {
+            self.time = self.time.rem_euclid(duration);
+        } else {
+            self.time = self.time.clamp(0.0, duration);
+        }
+
+        if let Some(fade) = self.fade {
+            let step = fade.rate * delta_time;
+            if (fade.target - self.weight).abs() <= step {
+                self.weight = fade.target;
+                self.fade = None;
+                return fade.target > 0.0;
+            }
+            self.weight += step.copysign(fade.target - self.weight);
+        }
+        true
+    }
+}
+
+/// Advances the time and fades of every [`AnimationPlayer`]'s clips.
+pub async fn advance_animations(
+    time: Res<Time>,
+    mut players: Query<&mut AnimationPlayer>,
+    mut clips: ResMut<Assets<AnimationClip>>,
+) {
+    for mut player in players.iter() {
+        player.animations.retain_mut(|animation| {
+            // clips that are still loading start once they're loaded
+            let Some(clip) = clips.get(&animation.clip) else {
+                return true;
+            };
+            animation.advance(time.delta_time, clip.duration())
+        });
+    }
+}
+
+/// The blended value of one property of one target.
+struct Blend<T> {
+    value: T,
+    weight: f32,
+}
+
+#[derive(Default)]
+struct TargetPose {
+    translation: Option<Blend<Vec3>>,
+    rotation: Option<Blend<Quat>>,
+    scale: Option<Blend<Vec3>>,
+    weights: Option<Blend<Vec<f32>>>,
+}
+
+impl TargetPose {
+    fn add(&mut self, value: ChannelValue, weight: f32) {
+        match value {
+            ChannelValue::Translation(translation) => {
+                add_weighted(&mut self.translation, translation, weight, Vec3::lerp)
+            }
+            ChannelValue::Rotation(rotation) => {
+                add_weighted(&mut self.rotation, rotation, weight, Quat::slerp)
+            }
+            ChannelValue::Scale(scale) => add_weighted(&mut self.scale, scale, weight, Vec3::lerp),
+            ChannelValue::Weights(weights) => {
+                add_weighted(&mut self.weights, weights, weight, |blended, weights, t| {
+                    blended
+                        .iter()
+                        .zip(weights.iter().chain(std::iter::repeat(&0.0)))
+                        .map(|(blended, weight)| blended + (weight - blended) * t)
+                        .collect()
+                })
+            }
+        }
+    }
+}
+
+/// Blends a value into a running weighted average, so that every value contributes in proportion to its weight.
+fn add_weighted<T>(
+    blend: &mut Option<Blend<T>>,
+    value: T,
+    weight: f32,
+    interpolate: impl FnOnce(T, T, f32) -> T,
+) {
+    match blend.take() {
+        Some(Blend {
+            value: blended,
+            weight: total,
+        }) => {
+            let total = total + weight;
+            *blend = Some(Blend {
+                value: interpolate(blended, value, weight / total),
+                weight: total,
+            });
+        }
+        None => *blend = Some(Blend { value, weight }),
+    }
+}
+
+/// Writes the blended poses of every [`AnimationPlayer`]'s clips to the [`Transform`]s and [`MorphWeights`] of its targets.
+pub async fn apply_animations(
+    mut players: Query<&AnimationPlayer>,
+    mut transforms: Query<&mut Transform>,
+    mut morph_weights: Query<&mut MorphWeights>,
+    mut clips: ResMut<Assets<AnimationClip>>,
+) {
+    for player in players.iter() {
+        let mut poses: FxHashMap<usize, TargetPose> = FxHashMap::default();
+        for animation in player.animations.iter() {
+            if animation.weight <= 0.0 {
+                continue;
+            }
+            let Some(clip) = clips.get(&animation.clip) else {
+                continue;
+            };
+            for channel in clip.channels() {
+                poses
+                    .entry(channel.target)
+                    .or_default()
+                    .add(channel.sample(animation.time), animation.weight);
+            }
+        }
+
+        for (target, pose) in poses {
+            let Some(&Some(entity)) = player.targets.get(target) else {
+                continue;
+            };
+            if let Some(mut transform) = transforms.get(entity) {
+                if let Some(translation) = pose.translation {
+                    transform.translation = translation.value;
+                }
+                if let Some(rotation) = pose.rotation {
+                    transform.rotation = rotation.value.normalize();
+                }
+                if let Some(scale) = pose.scale {
+                    transform.scale = scale.value;
+                }
+            }
+            if let (Some(weights), Some(mut morph_weights)) =
+                (pose.weights, morph_weights.get(entity))
+            {
+                morph_weights.0 = weights.value;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossfade_and_blend() {
+        let walk = Handle::<AnimationClip>::from_u128(1);
+        let run = Handle::<AnimationClip>::from_u128(2);
+
+        let mut player = AnimationPlayer::default();
+        player.play(walk.clone()).repeat();
+        player.crossfade(run.clone(), 1.0);
+        assert_eq!(player.animation(&run).unwrap().weight, 0.0);
+
+        for animation in player.animations.iter_mut() {
+            assert!(animation.advance(0.5, 2.0));
+        }
+        assert_eq!(player.animation(&walk).unwrap().weight, 0.5);
+        assert_eq!(player.animation(&run).unwrap().weight, 0.5);
+
+        // fully faded out clips are stopped
+        player
+            .animations
+            .retain_mut(|animation| animation.advance(0.5, 2.0));
+        assert!(!player.is_playing(&walk));
+        assert_eq!(player.animation(&run).unwrap().weight, 1.0);
+
+        // repeating clips wrap around, others hold their last pose
+        let mut animation = PlayingAnimation::new(walk);
+        animation.repeat();
+        animation.advance(2.5, 2.0);
+        assert_eq!(animation.time, 0.5);
+        let mut animation = PlayingAnimation::new(run);
+        animation.advance(2.5, 2.0);
+        assert_eq!(animation.time, 2.0);
+
+        let mut pose = TargetPose::default();
+        pose.add(ChannelValue::Translation(Vec3::ZERO), 1.0);
+        pose.add(ChannelValue::Translation(Vec3::X * 4.0), 3.0);
+        assert_eq!(pose.translation.unwrap().value, Vec3::X * 3.0);
+    }
+}
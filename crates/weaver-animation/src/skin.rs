@@ -0,0 +1,63 @@
+use weaver_core::{hierarchy::GlobalTransform, prelude::Mat4, transform::Transform};
+use weaver_ecs::{entity::Entity, prelude::Commands, query::Query};
+
+/// Deforms the mesh of an entity with the transforms of other entities, its joints.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Skin {
+    /// The joint entities, indexed by the joint indices of the mesh's vertices.
+    pub joints: Vec<Entity>,
+    /// The inverse of each joint's transform at the time the mesh was bound to it, in the mesh's space.
+    pub inverse_bind_matrices: Vec<Mat4>,
+}
+
+/// The matrices that move the vertices of a skinned mesh from their bind pose to the current pose of its joints,
+/// relative to the mesh entity. Kept up to date by [`compute_joint_matrices`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JointMatrices(pub Vec<Mat4>);
+
+fn world_matrix(
+    transforms: &mut Query<(&Transform, Option<&GlobalTransform>)>,
+    entity: Entity,
+) -> Option<Mat4> {
+    let (transform, global) = transforms.get(entity)?;
+    Some(match global {
+        Some(global) => global.matrix(),
+        None => transform.matrix(),
+    })
+}
+
+/// Computes the [`JointMatrices`] of all entities with a [`Skin`] from the current transforms of their joints.
+pub async fn compute_joint_matrices(
+    commands: Commands,
+    mut skins: Query<(Entity, &Skin, Option<&mut JointMatrices>)>,
+    mut transforms: Query<(&Transform, Option<&GlobalTransform>)>,
+) {
+    let mut missing = Vec::new();
+    for (entity, skin, joint_matrices) in skins.iter() {
+        let mesh_inverse = world_matrix(&mut transforms, entity)
+            .unwrap_or(Mat4::IDENTITY)
+            .inverse();
+        let matrices = skin
+            .joints
+            .iter()
+            .zip(skin.inverse_bind_matrices.iter())
+            .map(|(&joint, inverse_bind)| {
+                // despawned joints leave their vertices where they were bound
+                match world_matrix(&mut transforms, joint) {
+                    Some(joint) => mesh_inverse * joint * *inverse_bind,
+                    None => Mat4::IDENTITY,
+                }
+            })
+            .collect();
+
+        match joint_matrices {
+            Some(mut joint_matrices) => joint_matrices.0 = matrices,
+            None => missing.push((entity, JointMatrices(matrices))),
+        }
+    }
+
+    drop((skins, transforms));
+    for (entity, joint_matrices) in missing {
+        commands.insert_component(entity, joint_matrices);
+    }
+}
@@ -0,0 +1,467 @@
+use std::f32::consts::PI;
+
+use weaver_asset::{Asset, Assets, Handle};
+use weaver_core::{
+    color::Color,
+    prelude::{Quat, Vec2, Vec3, Vec4},
+    time::Time,
+    transform::Transform,
+};
+use weaver_ecs::{
+    component::Component,
+    entity::Entity,
+    prelude::{Res, ResMut},
+    query::Query,
+};
+use weaver_event::EventTx;
+
+/// Shapes the progress between two keyframes of a [`Tween`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EaseFunction {
+    #[default]
+    Linear,
+    /// Holds the previous value until the keyframe is reached.
+    Step,
+    QuadraticIn,
+    QuadraticOut,
+    QuadraticInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    /// Overshoots the keyframe's value slightly before settling on it.
+    BackOut,
+}
+
+impl EaseFunction {
+    /// Maps linear progress in `0..=1` to eased progress. Both ends are kept in place.
+    pub fn ease(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            EaseFunction::Linear => t,
+            EaseFunction::Step => {
+                if t < 1.0 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            EaseFunction::QuadraticIn => t * t,
+            EaseFunction::QuadraticOut => 1.0 - (1.0 - t) * (1.0 - t),
+            EaseFunction::QuadraticInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            EaseFunction::CubicIn => t * t * t,
+            EaseFunction::CubicOut => 1.0 - (1.0 - t).powi(3),
+            EaseFunction::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            EaseFunction::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            EaseFunction::SineOut => (t * PI / 2.0).sin(),
+            EaseFunction::SineInOut => -((t * PI).cos() - 1.0) / 2.0,
+            EaseFunction::BackOut => {
+                const C1: f32 = 1.70158;
+                const C3: f32 = C1 + 1.0;
+                1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2)
+            }
+        }
+    }
+}
+
+/// A value that a [`Tween`] can interpolate.
+pub trait Animatable: Clone + Send + Sync + 'static {
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Animatable for Vec2 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(*other, t)
+    }
+}
+
+impl Animatable for Vec3 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(*other, t)
+    }
+}
+
+impl Animatable for Vec4 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(*other, t)
+    }
+}
+
+impl Animatable for Quat {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.slerp(*other, t)
+    }
+}
+
+impl Animatable for Color {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Color::lerp(*self, *other, t)
+    }
+}
+
+impl Animatable for Transform {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Transform::new(
+            self.translation.lerp(other.translation, t),
+            self.rotation.slerp(other.rotation, t),
+            self.scale.lerp(other.scale, t),
+        )
+    }
+}
+
+/// The value of a property at some point of a [`Tween`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe<V> {
+    /// The time of the keyframe, in seconds from the start of the tween.
+    pub time: f32,
+    pub value: V,
+    /// How the property moves from the previous keyframe's value to this one.
+    pub ease: EaseFunction,
+}
+
+impl<V> Keyframe<V> {
+    pub fn new(time: f32, value: V, ease: EaseFunction) -> Self {
+        Self { time, value, ease }
+    }
+}
+
+/// Gives a track mutable access to the property it animates.
+type Accessor<T, V> = Box<dyn Fn(&mut T) -> &mut V + Send + Sync>;
+
+trait Track<T>: Send + Sync {
+    fn duration(&self) -> f32;
+
+    fn apply(&self, target: &mut T, time: f32);
+}
+
+struct PropertyTrack<T, V> {
+    accessor: Accessor<T, V>,
+    keyframes: Vec<Keyframe<V>>,
+}
+
+impl<T: 'static, V: Animatable> Track<T> for PropertyTrack<T, V> {
+    fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    fn apply(&self, target: &mut T, time: f32) {
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        let value = if next == 0 {
+            self.keyframes[0].value.clone()
+        } else if next == self.keyframes.len() {
+            self.keyframes[next - 1].value.clone()
+        } else {
+            let (from, to) = (&self.keyframes[next - 1], &self.keyframes[next]);
+            let t = (time - from.time) / (to.time - from.time);
+            from.value.interpolate(&to.value, to.ease.ease(t))
+        };
+        *(self.accessor)(target) = value;
+    }
+}
+
+/// What a [`Tween`] does once it reaches its end.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RepeatMode {
+    /// Stops at the end and sends a [`TweenCompleted`] event.
+    #[default]
+    Once,
+    /// Starts over from the beginning.
+    Loop,
+    /// Plays backwards to the beginning, then forwards again.
+    PingPong,
+}
+
+/// Sent when a [`Tween`] that doesn't repeat reaches its end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TweenCompleted {
+    /// The entity that has the tween.
+    pub entity: Entity,
+}
+
+/// Animates fields of a component (or of an asset that the entity has a handle to) along keyframed tracks.
+///
+/// Every track addresses one field through an accessor closure:
+/// ```ignore
+/// commands.insert_component(
+///     light,
+///     Tween::<PointLight>::new()
+///         .with_property(|light| &mut light.color, [
+///             Keyframe::new(0.0, Color::WHITE, EaseFunction::Linear),
+///             Keyframe::new(2.0, Color::RED, EaseFunction::SineInOut),
+///         ])
+///         .with_repeat(RepeatMode::PingPong),
+/// );
+/// ```
+/// `Tween<T>` only has an effect once the [`TweenPlugin<T>`](crate::TweenPlugin) (or for assets the
+/// [`AssetTweenPlugin<T>`](crate::AssetTweenPlugin)) is added; the [`AnimationPlugin`](crate::AnimationPlugin) adds
+/// the one for [`Transform`].
+pub struct Tween<T> {
+    tracks: Vec<Box<dyn Track<T>>>,
+    duration: f32,
+    /// The current time in the tween, in seconds.
+    pub time: f32,
+    /// Playback speed, negative to play backwards.
+    pub speed: f32,
+    pub repeat: RepeatMode,
+    pub paused: bool,
+    completed: bool,
+}
+
+impl<T> Default for Tween<T> {
+    fn default() -> Self {
+        Self {
+            tracks: Vec::new(),
+            duration: 0.0,
+            time: 0.0,
+            speed: 1.0,
+            repeat: RepeatMode::Once,
+            paused: false,
+            completed: false,
+        }
+    }
+}
+
+impl<T: 'static> Tween<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A tween that moves a single property from one value to another over `duration` seconds.
+    pub fn to<V: Animatable>(
+        accessor: impl Fn(&mut T) -> &mut V + Send + Sync + 'static,
+        from: V,
+        to: V,
+        duration: f32,
+        ease: EaseFunction,
+    ) -> Self {
+        Self::new().with_property(
+            accessor,
+            [
+                Keyframe::new(0.0, from, EaseFunction::Linear),
+                Keyframe::new(duration, to, ease),
+            ],
+        )
+    }
+
+    /// Adds a track that animates the property returned by `accessor` through keyframes in increasing time order.
+    /// Tracks without keyframes are ignored.
+    pub fn with_property<V: Animatable>(
+        mut self,
+        accessor: impl Fn(&mut T) -> &mut V + Send + Sync + 'static,
+        keyframes: impl IntoIterator<Item = Keyframe<V>>,
+    ) -> Self {
+        let mut keyframes = keyframes.into_iter().collect::<Vec<_>>();
+        if keyframes.is_empty() {
+            return self;
+        }
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let track = PropertyTrack {
+            accessor: Box::new(accessor),
+            keyframes,
+        };
+        self.duration = self.duration.max(track.duration());
+        self.tracks.push(Box::new(track));
+        self
+    }
+
+    pub fn with_repeat(mut self, repeat: RepeatMode) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// The time of the last keyframe of any track, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    /// Whether a tween that doesn't repeat has reached its end.
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+
+    /// Starts the tween over from the beginning.
+    pub fn restart(&mut self) {
+        self.time = 0.0;
+        self.speed = self.speed.abs();
+        self.completed = false;
+    }
+
+    /// Writes the values of all tracks at the current time to `target`.
+    pub fn apply(&self, target: &mut T) {
+        for track in self.tracks.iter() {
+            track.apply(target, self.time);
+        }
+    }
+
+    /// Advances the time, returning true if the tween has just completed.
+    fn advance(&mut self, delta_time: f32) -> bool {
+        if self.paused || self.completed {
+            return false;
+        }
+
+        self.time += delta_time * self.speed;
+        let duration = self.duration;
+        match self.repeat {
+            RepeatMode::Once => {
+                if (self.speed >= 0.0 && self.time >= duration)
+                    || (self.speed < 0.0 && self.time <= 0.0)
+                {
+                    self.time = self.time.clamp(0.0, duration);
+                    self.completed = true;
+                }
+            }
+            RepeatMode::Loop if duration > 0.0 => self.time = self.time.rem_euclid(duration),
+            RepeatMode::PingPong if duration > 0.0 => {
+                // bounce off either end, reversing the direction each time
+                let period = self.time.div_euclid(duration);
+                let time = self.time.rem_euclid(duration);
+                if period as i64 % 2 == 0 {
+                    self.time = time;
+                } else {
+                    self.time = duration - time;
+                    self.speed = -self.speed;
+                }
+            }
+            RepeatMode::Loop | RepeatMode::PingPong => self.time = 0.0,
+        }
+        self.completed
+    }
+}
+
+/// Advances the [`Tween`]s of components of type `T` and applies them.
+pub async fn animate_component_tweens<T: Component>(
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Tween<T>, &mut T)>,
+    mut completed: EventTx<TweenCompleted>,
+) {
+    let mut completed_entities = Vec::new();
+    for (entity, mut tween, mut target) in query.iter() {
+        if tween.is_completed() {
+            continue;
+        }
+        if tween.advance(time.delta_time) {
+            completed_entities.push(entity);
+        }
+        tween.apply(&mut target);
+    }
+
+    drop(query);
+
+    for entity in completed_entities {
+        completed.send(TweenCompleted { entity }).await;
+    }
+}
+
+/// Advances the [`Tween`]s of assets of type `T` and applies them to the assets that their entities have handles to.
+pub async fn animate_asset_tweens<T: Asset>(
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Tween<T>, &Handle<T>)>,
+    mut assets: ResMut<Assets<T>>,
+    mut completed: EventTx<TweenCompleted>,
+) {
+    let mut completed_entities = Vec::new();
+    for (entity, mut tween, handle) in query.iter() {
+        if tween.is_completed() {
+            continue;
+        }
+        if tween.advance(time.delta_time) {
+            completed_entities.push(entity);
+        }
+        // assets that are still loading are animated once they're loaded
+        if let Some(mut asset) = assets.get_mut(&handle) {
+            tween.apply(&mut asset);
+        }
+    }
+
+    drop(query);
+
+    for entity in completed_entities {
+        completed.send(TweenCompleted { entity }).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tween_properties() {
+        for ease in [
+            EaseFunction::Linear,
+            EaseFunction::QuadraticInOut,
+            EaseFunction::CubicOut,
+            EaseFunction::SineIn,
+            EaseFunction::BackOut,
+        ] {
+            assert!(ease.ease(0.0).abs() < 1e-6);
+            assert!((ease.ease(1.0) - 1.0).abs() < 1e-6);
+        }
+
+        let mut tween = Tween::<Transform>::to(
+            |transform| &mut transform.translation,
+            Vec3::ZERO,
+            Vec3::X * 2.0,
+            2.0,
+            EaseFunction::Linear,
+        )
+        .with_property(
+            |transform| &mut transform.scale,
+            [
+                Keyframe::new(0.0, Vec3::ONE, EaseFunction::Linear),
+                Keyframe::new(1.0, Vec3::ONE * 3.0, EaseFunction::Step),
+            ],
+        );
+        assert_eq!(tween.duration(), 2.0);
+
+        let mut transform = Transform::default();
+        assert!(!tween.advance(0.5));
+        tween.apply(&mut transform);
+        assert_eq!(transform.translation, Vec3::X * 0.5);
+        assert_eq!(transform.scale, Vec3::ONE);
+
+        assert!(tween.advance(2.0));
+        tween.apply(&mut transform);
+        assert_eq!(transform.translation, Vec3::X * 2.0);
+        assert_eq!(transform.scale, Vec3::ONE * 3.0);
+        // completed tweens only report it once
+        assert!(!tween.advance(1.0));
+
+        let mut tween = Tween::<f32>::to(|value| value, 0.0, 1.0, 1.0, EaseFunction::Linear)
+            .with_repeat(RepeatMode::PingPong);
+        tween.advance(1.25);
+        assert_eq!(tween.time, 0.75);
+        assert!(tween.speed < 0.0);
+        tween.advance(1.0);
+        assert_eq!(tween.time, 0.25);
+        assert!(tween.speed > 0.0);
+        assert!(!tween.is_completed());
+    }
+}
@@ -0,0 +1,23 @@
+//! A plugin that the tests of this crate load from its dynamic library, to check that `weaver-app` can load and reload
+//! dynamic plugins. Every update it records that it ran.
+
+use weaver_app::prelude::*;
+use weaver_ecs::prelude::ResMut;
+use weaver_util::prelude::*;
+
+// only std types are shared with the app, so that the tests can name the resource
+async fn count(mut counts: ResMut<Vec<u32>>) {
+    counts.push(1);
+}
+
+pub struct CounterPlugin;
+
+impl Plugin for CounterPlugin {
+    fn build(&self, app: &mut App) -> Result<()> {
+        app.init_resource::<Vec<u32>>();
+        app.add_system(count, AppStage::Update);
+        Ok(())
+    }
+}
+
+weaver_app::export_plugin!(CounterPlugin);
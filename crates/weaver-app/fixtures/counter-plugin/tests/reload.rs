@@ -0,0 +1,53 @@
+//! Loads this crate's library as a dynamic plugin. The library is built by the same cargo invocation as this test, so
+//! both link the same build of `weaver-app`.
+
+use std::{path::PathBuf, time::Duration};
+
+use weaver_app::App;
+use weaver_task::{task_pool::TaskPool, usages::GlobalTaskPool};
+
+fn library_path() -> PathBuf {
+    // the test executable is in `<target dir>/<profile>/deps`, next to the library
+    std::env::current_exe().unwrap().with_file_name(format!(
+        "{}weaver_app_counter_plugin{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_SUFFIX
+    ))
+}
+
+fn counts(app: &App) -> Vec<u32> {
+    app.main_app()
+        .world()
+        .get_resource::<Vec<u32>>()
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn test_reload_dynamic_plugin() {
+    GlobalTaskPool::get_or_init(TaskPool::new);
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    let path = dir.join(library_path().file_name().unwrap());
+    std::fs::copy(library_path(), &path).unwrap();
+
+    let mut app = App::new();
+    app.add_dynamic_plugin(&path).unwrap();
+    assert!(app.add_dynamic_plugin(&path).is_err());
+    app.init();
+    app.update();
+    assert_eq!(counts(&app), [1]);
+
+    app.reload_dynamic_plugin(&path).unwrap();
+    app.update();
+    // the counts survived the reload, and only the new instance's system ran
+    assert_eq!(counts(&app), [1, 1]);
+
+    // a rebuilt library is picked up once it has settled
+    std::fs::copy(library_path(), &path).unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+    app.update();
+    app.update();
+    assert_eq!(counts(&app), [1, 1, 1, 1]);
+}
@@ -0,0 +1,473 @@
+use std::{
+    any::TypeId,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime},
+};
+
+use libloading::Library;
+use weaver_ecs::{system::SystemIndex, system_schedule::InternedSystemStage};
+use weaver_util::prelude::*;
+
+use crate::{
+    App,
+    plugin::{DummyPlugin, Plugin, check_dependencies},
+};
+
+/// Name of the symbol exported by [`export_plugin!`](crate::export_plugin).
+pub const CREATE_PLUGIN_SYMBOL: &[u8] = b"_weaver_create_plugin";
+
+type CreatePluginFn = fn() -> Box<dyn Plugin>;
+
+/// The systems a plugin added to the main world, by stage.
+type PluginSystems = Vec<(InternedSystemStage, SystemIndex)>;
+
+/// How long a changed library must stay untouched before it is reloaded, so that half-written files are skipped.
+const RELOAD_SETTLE_TIME: Duration = Duration::from_millis(250);
+
+/// Exports a plugin from a `cdylib` crate so that it can be loaded with [`App::add_dynamic_plugin`].
+///
+/// ```ignore
+/// weaver_app::export_plugin!(GameplayPlugin::default());
+/// ```
+#[macro_export]
+macro_rules! export_plugin {
+    ($plugin:expr) => {
+        #[unsafe(no_mangle)]
+        pub fn _weaver_create_plugin() -> ::std::boxed::Box<dyn $crate::plugin::Plugin> {
+            ::std::boxed::Box::new($plugin)
+        }
+    };
+}
+
+/// A plugin loaded from a dynamic library, along with everything it added to the main world.
+pub(crate) struct DynamicPlugin {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    plugin_id: TypeId,
+    systems: PluginSystems,
+    resources: Vec<TypeId>,
+}
+
+/// Every library that has been loaded. Libraries are never unloaded, since resources, events and
+/// plugin instances created by an old version may still point into its code.
+#[derive(Default)]
+pub(crate) struct DynamicLibraries {
+    plugins: Vec<DynamicPlugin>,
+    libraries: Vec<Library>,
+}
+
+impl DynamicLibraries {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    fn load(&mut self, path: &Path) -> Result<Box<dyn Plugin>> {
+        static LOAD_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        // the dynamic loader caches libraries by path, and the build overwrites the original file,
+        // so every version is loaded from its own copy
+        let dir = std::env::temp_dir().join("weaver-dynamic-plugins");
+        std::fs::create_dir_all(&dir)?;
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| anyhow!("Invalid dynamic plugin path: {:?}", path))?;
+        let copy = dir.join(format!(
+            "{}-{}-{}",
+            std::process::id(),
+            LOAD_COUNT.fetch_add(1, Ordering::Relaxed),
+            file_name.to_string_lossy()
+        ));
+        std::fs::copy(path, &copy)
+            .map_err(|e| anyhow!("Failed to copy dynamic plugin {:?}: {}", path, e))?;
+
+        // SAFETY: the library must be built with the same compiler and weaver version as the app,
+        // and export its plugin with `export_plugin!`
+        let plugin = unsafe {
+            let library = Library::new(&copy)?;
+            let create = library
+                .get::<CreatePluginFn>(CREATE_PLUGIN_SYMBOL)
+                .map_err(|e| anyhow!("{:?} does not export a plugin: {}", path, e))?;
+            let plugin = create();
+            self.libraries.push(library);
+            plugin
+        };
+
+        Ok(plugin)
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+fn plugin_type_id(plugin: &dyn Plugin) -> TypeId {
+    plugin.as_any().type_id()
+}
+
+impl App {
+    /// Loads a plugin from a dynamic library that exports it with [`export_plugin!`](crate::export_plugin).
+    ///
+    /// The plugin is built and finished right away. Whenever the library changes on disk, it is reloaded at the start of the
+    /// next [`update`](App::update): the old instance is cleaned up, the systems it added to the main world are replaced by
+    /// those of the new instance, and the resources it created keep their values.
+    ///
+    /// The library must link the same build of weaver as the app, for example by being part of the same workspace, or the
+    /// type ids they see won't agree. Resource types defined in the library must keep their layout across reloads, and the
+    /// plugin should only add systems to the main world.
+    pub fn add_dynamic_plugin(&mut self, path: impl AsRef<Path>) -> Result<&mut Self> {
+        let path = path.as_ref().to_path_buf();
+        let plugin = self.dynamic_libraries.load(&path)?;
+        self.add_reloadable_plugin(path, plugin)
+    }
+
+    fn add_reloadable_plugin(
+        &mut self,
+        path: PathBuf,
+        plugin: Box<dyn Plugin>,
+    ) -> Result<&mut Self> {
+        let modified = modified(&path);
+        let plugin_id = plugin_type_id(&*plugin);
+
+        // unlike static plugins, the library is already loaded, so a second copy isn't silently skipped
+        if self.has_plugin_id(plugin_id) {
+            bail!(
+                "dynamic plugin `{}` has already been added",
+                plugin.type_name()
+            );
+        }
+        check_dependencies(&self.plugins, &*plugin)?;
+        if !plugin.ready(self) {
+            bail!(
+                "dynamic plugin `{}` must be ready when it is added",
+                plugin.type_name()
+            );
+        }
+
+        log::debug!(
+            "Adding dynamic plugin: {:?} from {:?}",
+            plugin.type_name(),
+            path
+        );
+
+        let (systems, resources) = self.build_reloadable_plugin(&*plugin)?;
+
+        self.plugins.push((plugin_id, plugin));
+        self.dynamic_libraries.plugins.push(DynamicPlugin {
+            path,
+            modified,
+            plugin_id,
+            systems,
+            resources,
+        });
+
+        Ok(self)
+    }
+
+    /// Reloads every dynamic plugin whose library has changed since it was loaded.
+    pub fn reload_changed_dynamic_plugins(&mut self) -> Result<()> {
+        let changed = self
+            .dynamic_libraries
+            .plugins
+            .iter()
+            .enumerate()
+            .filter(|(_, dynamic)| {
+                let Some(modified) = modified(&dynamic.path) else {
+                    return false;
+                };
+                Some(modified) != dynamic.modified
+                    && modified.elapsed().unwrap_or_default() >= RELOAD_SETTLE_TIME
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        for index in changed {
+            self.reload_dynamic_plugin_at(index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reloads the dynamic plugin that was loaded from `path`, whether or not it changed.
+    pub fn reload_dynamic_plugin(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let index = self
+            .dynamic_libraries
+            .plugins
+            .iter()
+            .position(|dynamic| dynamic.path == path)
+            .ok_or_else(|| anyhow!("No dynamic plugin was loaded from {:?}", path))?;
+        self.reload_dynamic_plugin_at(index)
+    }
+
+    fn reload_dynamic_plugin_at(&mut self, index: usize) -> Result<()> {
+        let path = self.dynamic_libraries.plugins[index].path.clone();
+        let modified = modified(&path);
+
+        // load the new version first, so that a broken build leaves the old one running
+        self.dynamic_libraries.plugins[index].modified = modified;
+        let plugin = self.dynamic_libraries.load(&path)?;
+        self.replace_reloadable_plugin(index, plugin)
+    }
+
+    /// Builds and finishes a reloadable plugin, returning the systems and resources it added to the main world. If it
+    /// fails, whatever it added so far is removed again.
+    fn build_reloadable_plugin(
+        &mut self,
+        plugin: &dyn Plugin,
+    ) -> Result<(PluginSystems, Vec<TypeId>)> {
+        let systems_before = self.main_app().world().system_indices();
+        let resources_before = self.main_app().world().resource_ids();
+
+        let result = plugin.build(self).and_then(|()| plugin.finish(self));
+
+        let systems = self
+            .main_app()
+            .world()
+            .system_indices()
+            .into_iter()
+            .filter(|system| !systems_before.contains(system))
+            .collect::<Vec<_>>();
+        let resources = self
+            .main_app()
+            .world()
+            .resource_ids()
+            .into_iter()
+            .filter(|id| !resources_before.contains(id))
+            .collect::<Vec<_>>();
+
+        if let Err(err) = result {
+            for &(stage, system) in &systems {
+                self.main_app_mut().world_mut().remove_system(stage, system);
+            }
+            for &id in &resources {
+                self.main_app().world().remove_resource_by_id(id);
+            }
+            return Err(err);
+        }
+        Ok((systems, resources))
+    }
+
+    /// Replaces a tracked plugin with a new instance, keeping its resources.
+    ///
+    /// If the new instance fails to build, the old one is built again in its place. If that fails too, the plugin is
+    /// dropped from the app, keeping the resources it had, so that its stale entry can't be reloaded.
+    fn replace_reloadable_plugin(&mut self, index: usize, plugin: Box<dyn Plugin>) -> Result<()> {
+        let dynamic = &self.dynamic_libraries.plugins[index];
+        let path = dynamic.path.clone();
+        let old_id = dynamic.plugin_id;
+
+        let Some(plugin_index) = self.plugins.iter().position(|(id, _)| *id == old_id) else {
+            self.dynamic_libraries.plugins.remove(index);
+            bail!(
+                "Dynamic plugin from {:?} is no longer part of the app",
+                path
+            );
+        };
+        let (_, old_plugin) = std::mem::replace(
+            &mut self.plugins[plugin_index],
+            (TypeId::of::<DummyPlugin>(), Box::new(DummyPlugin)),
+        );
+
+        log::info!(
+            "Reloading dynamic plugin: {:?} from {:?}",
+            old_plugin.type_name(),
+            path
+        );
+
+        if let Err(err) = old_plugin.cleanup(self) {
+            self.plugins[plugin_index] = (old_id, old_plugin);
+            return Err(err);
+        }
+
+        let dynamic = &mut self.dynamic_libraries.plugins[index];
+        let old_systems = std::mem::take(&mut dynamic.systems);
+        let old_resources = std::mem::take(&mut dynamic.resources);
+
+        // the resources that `cleanup` left are set aside while the plugin is rebuilt, so that `build` can't replace them
+        let world = self.main_app().world();
+        let stashed = old_resources
+            .iter()
+            .filter_map(|id| world.remove_resource_by_id(*id))
+            .collect::<Vec<_>>();
+
+        for (stage, system) in old_systems {
+            self.main_app_mut().world_mut().remove_system(stage, system);
+        }
+
+        let (plugin, (systems, mut resources), result) = match self
+            .build_reloadable_plugin(&*plugin)
+        {
+            Ok(built) => (plugin, built, Ok(())),
+            Err(err) => {
+                log::error!(
+                    "Failed to rebuild dynamic plugin from {:?}, restoring the previous version: {}",
+                    path,
+                    err
+                );
+                match self.build_reloadable_plugin(&*old_plugin) {
+                    Ok(built) => (old_plugin, built, Err(err)),
+                    Err(restore_err) => {
+                        for resource in stashed {
+                            self.main_app().world().insert_erased_resource(resource);
+                        }
+                        self.plugins.remove(plugin_index);
+                        self.dynamic_libraries.plugins.remove(index);
+                        bail!(
+                            "Failed to rebuild dynamic plugin from {:?}: {}, and to restore its previous version: {}",
+                            path,
+                            err,
+                            restore_err
+                        );
+                    }
+                }
+            }
+        };
+
+        for resource in stashed {
+            if !resources.contains(&resource.id()) {
+                resources.push(resource.id());
+            }
+            self.main_app().world().insert_erased_resource(resource);
+        }
+
+        self.main_app_mut()
+            .world_mut()
+            .initialize_systems_by_index(&systems);
+
+        let plugin_id = plugin_type_id(&*plugin);
+        self.plugins[plugin_index] = (plugin_id, plugin);
+
+        let dynamic = &mut self.dynamic_libraries.plugins[index];
+        dynamic.plugin_id = plugin_id;
+        dynamic.systems = systems;
+        dynamic.resources = resources;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use weaver_ecs::prelude::ResMut;
+    use weaver_task::{task_pool::TaskPool, usages::GlobalTaskPool};
+
+    use super::*;
+    use crate::AppStage;
+
+    #[derive(Default)]
+    struct Counter(u32);
+
+    /// Stands in for two versions of a library, each counting in its own way.
+    struct CounterPlugin {
+        version: u32,
+    }
+
+    async fn count_by_one(mut counter: ResMut<Counter>) {
+        counter.0 += 1;
+    }
+
+    async fn count_by_ten(mut counter: ResMut<Counter>) {
+        counter.0 += 10;
+    }
+
+    impl Plugin for CounterPlugin {
+        fn build(&self, app: &mut App) -> Result<()> {
+            app.init_resource::<Counter>();
+            if self.version == 1 {
+                app.add_system(count_by_one, AppStage::Update);
+            } else {
+                app.add_system(count_by_ten, AppStage::Update);
+            }
+            Ok(())
+        }
+
+        fn cleanup(&self, app: &mut App) -> Result<()> {
+            // the plugin's resources are still there while it is cleaned up
+            ensure!(
+                app.has_resource::<Counter>(),
+                "Counter was removed before cleanup"
+            );
+            Ok(())
+        }
+    }
+
+    fn counter(app: &App) -> u32 {
+        app.main_app().world().get_resource::<Counter>().unwrap().0
+    }
+
+    #[test]
+    fn test_replace_reloadable_plugin() {
+        GlobalTaskPool::get_or_init(TaskPool::new);
+
+        let mut app = App::new();
+        app.add_reloadable_plugin(
+            PathBuf::from("counter"),
+            Box::new(CounterPlugin { version: 1 }),
+        )
+        .unwrap();
+        app.init();
+        app.update();
+        assert_eq!(counter(&app), 1);
+
+        app.replace_reloadable_plugin(0, Box::new(CounterPlugin { version: 2 }))
+            .unwrap();
+        app.update();
+        // the counter survived the reload, and only the new system ran
+        assert_eq!(counter(&app), 11);
+        assert!(
+            !app.main_app()
+                .world()
+                .has_system(&count_by_one, AppStage::Update)
+        );
+    }
+
+    /// A new version of a library that adds a system and a resource before failing to build.
+    struct BrokenPlugin;
+
+    struct BrokenResource;
+
+    impl Plugin for BrokenPlugin {
+        fn build(&self, app: &mut App) -> Result<()> {
+            app.insert_resource(BrokenResource);
+            app.add_system(count_by_ten, AppStage::Update);
+            bail!("BrokenPlugin failed to build")
+        }
+    }
+
+    #[test]
+    fn test_restore_plugin_that_fails_to_rebuild() {
+        GlobalTaskPool::get_or_init(TaskPool::new);
+
+        let mut app = App::new();
+        app.add_reloadable_plugin(
+            PathBuf::from("counter"),
+            Box::new(CounterPlugin { version: 1 }),
+        )
+        .unwrap();
+        app.init();
+        app.update();
+
+        assert!(
+            app.replace_reloadable_plugin(0, Box::new(BrokenPlugin))
+                .is_err()
+        );
+        // the previous version runs again with its counter, and nothing of the broken one is left
+        app.update();
+        assert_eq!(counter(&app), 2);
+        assert!(!app.has_resource::<BrokenResource>());
+        assert!(
+            !app.main_app()
+                .world()
+                .has_system(&count_by_ten, AppStage::Update)
+        );
+
+        // and it can still be reloaded
+        app.replace_reloadable_plugin(0, Box::new(CounterPlugin { version: 2 }))
+            .unwrap();
+        app.update();
+        assert_eq!(counter(&app), 12);
+    }
+}
@@ -1,6 +1,13 @@
-use std::any::TypeId;
+use std::{
+    any::TypeId,
+    num::NonZeroU8,
+    process::ExitCode,
+    time::{Duration, Instant},
+};
 
-use plugin::{DummyPlugin, Plugin};
+use dynamic_plugin::DynamicLibraries;
+use plugin::{DummyPlugin, Plugin, PluginError, PluginGroup, check_dependencies};
+use settings::{AppSettings, LogSettings};
 
 use weaver_ecs::{
     SystemStage,
@@ -10,17 +17,25 @@ use weaver_ecs::{
     system_schedule::SystemStage,
     world::{ConstructFromWorld, World},
 };
-use weaver_event::{Event, Events};
+use weaver_event::{Event, EventRx, Events, prelude::StreamExt};
 use weaver_task::{
     task_pool::TaskPool,
     usages::{GlobalTaskPool, tick_task_pools},
 };
 use weaver_util::prelude::*;
 
+pub mod dynamic_plugin;
 pub mod plugin;
+pub mod settings;
 
 pub mod prelude {
-    pub use crate::{App, AppStage, AppStage::*, SubApp, plugin::Plugin};
+    pub use crate::{
+        App, AppExit, AppStage,
+        AppStage::*,
+        AppTimings, SubApp, SubAppTiming,
+        plugin::{Plugin, PluginGroup, PluginGroupBuilder, PluginId},
+        settings::{AppSettings, SettingsSection},
+    };
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemStage)]
@@ -34,15 +49,91 @@ pub enum AppStage {
     Shutdown,
 }
 
+/// Event that requests the app to shut down after the current frame.
+///
+/// Runners check for it after every update, run the `Shutdown` stage of every sub-app, and return it from [`App::run`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AppExit {
+    #[default]
+    Success,
+    Error(NonZeroU8),
+}
+
+impl AppExit {
+    /// An error exit with the generic exit code `1`.
+    pub const fn error() -> Self {
+        Self::Error(NonZeroU8::MIN)
+    }
+
+    pub fn from_code(code: u8) -> Self {
+        match NonZeroU8::new(code) {
+            Some(code) => Self::Error(code),
+            None => Self::Success,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Success)
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, Self::Error(_))
+    }
+
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Success => 0,
+            Self::Error(code) => code.get(),
+        }
+    }
+}
+
+impl From<AppExit> for ExitCode {
+    fn from(exit: AppExit) -> Self {
+        ExitCode::from(exit.code())
+    }
+}
+
+impl std::process::Termination for AppExit {
+    fn report(self) -> ExitCode {
+        self.into()
+    }
+}
+
+/// The first exit requested this run. An error exit takes precedence over a successful one.
+#[derive(Default)]
+struct PendingAppExit(Option<AppExit>);
+
+impl PendingAppExit {
+    fn request(&mut self, exit: AppExit) {
+        if self
+            .0
+            .is_none_or(|pending| pending.is_success() && exit.is_error())
+        {
+            self.0 = Some(exit);
+        }
+    }
+}
+
+async fn receive_app_exit(mut events: EventRx<AppExit>, mut pending: ResMut<PendingAppExit>) {
+    while let Some(exit) = events.next().await {
+        pending.request(exit);
+    }
+}
+
+async fn clear_events<T: Event>(mut events: ResMut<Events<T>>, world_ticks: WorldTicks) {
+    events.update(world_ticks.change_tick).await;
+}
+
 pub trait Runner: 'static {
-    fn run(&self, app: &mut App) -> Result<()>;
+    fn run(&self, app: &mut App) -> Result<AppExit>;
 }
 
 impl<T> Runner for T
 where
-    T: Fn(&mut App) -> Result<()> + 'static,
+    T: Fn(&mut App) -> Result<AppExit> + 'static,
 {
-    fn run(&self, app: &mut App) -> Result<()> {
+    fn run(&self, app: &mut App) -> Result<AppExit> {
         self(app)
     }
 }
@@ -52,6 +143,7 @@ pub type ExtractFn = Box<dyn Fn(&mut World, &mut World) -> Result<()> + Send + S
 pub trait AppLabel: 'static {}
 
 pub struct SubApp {
+    name: &'static str,
     world: World,
     plugins: Vec<(TypeId, Box<dyn Plugin>)>,
     unready_plugins: TypeIdSet,
@@ -63,6 +155,7 @@ impl Default for SubApp {
         let world = World::new();
 
         Self {
+            name: "SubApp",
             world,
             plugins: Vec::new(),
             unready_plugins: TypeIdSet::default(),
@@ -80,9 +173,21 @@ impl SubApp {
     where
         F: FnOnce(&mut App) -> R,
     {
+        // plugins added while acting as an app are tracked by the sub-app, so that they get finished
+        // and can be depended on like any other plugin
         let mut app = App::empty();
         std::mem::swap(app.main_app_mut(), self);
+        std::mem::swap(&mut app.plugins, &mut app.sub_apps.main.plugins);
+        std::mem::swap(
+            &mut app.unready_plugins,
+            &mut app.sub_apps.main.unready_plugins,
+        );
         let result = f(&mut app);
+        std::mem::swap(&mut app.plugins, &mut app.sub_apps.main.plugins);
+        std::mem::swap(
+            &mut app.unready_plugins,
+            &mut app.sub_apps.main.unready_plugins,
+        );
         std::mem::swap(app.main_app_mut(), self);
         result
     }
@@ -111,14 +216,11 @@ impl SubApp {
     }
 
     pub fn add_plugin<T: Plugin>(&mut self, plugin: T) -> Result<&mut Self> {
-        if self
-            .plugins
-            .iter()
-            .any(|(_, plugin)| (**plugin).type_id() == TypeId::of::<T>())
-        {
+        if self.has_plugin::<T>() {
             log::warn!("Plugin already added: {:?}", plugin.type_name());
             return Ok(self);
         }
+        check_dependencies(&self.plugins, &plugin)?;
 
         log::debug!("Adding plugin: {:?}", plugin.type_name());
         self.as_app(|app| plugin.build(app))?;
@@ -129,6 +231,15 @@ impl SubApp {
         Ok(self)
     }
 
+    pub fn has_plugin<T: Plugin>(&self) -> bool {
+        self.plugins.iter().any(|(id, _)| *id == TypeId::of::<T>())
+    }
+
+    /// The name of the sub-app's label, or `"Main"` for the main app.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
     pub fn set_extract(&mut self, extract: ExtractFn) -> &mut Self {
         self.extract_fn = Some(extract);
         self
@@ -155,9 +266,6 @@ impl SubApp {
     }
 
     pub fn add_event<T: Event>(&mut self, clear_events_stage: impl SystemStage) -> &mut Self {
-        async fn clear_events<T: Event>(mut events: ResMut<Events<T>>, world_ticks: WorldTicks) {
-            events.update(world_ticks.change_tick).await;
-        }
         self.world_mut().insert_resource(Events::<T>::new());
         self.world_mut()
             .add_system(clear_events::<T>, clear_events_stage);
@@ -188,11 +296,32 @@ impl SubApps {
     }
 
     pub fn update(&mut self) {
+        let start = Instant::now();
         self.main.world_mut().update().unwrap();
+        let main = start.elapsed();
+
+        let mut sub_apps = Vec::with_capacity(self.sub_apps.len());
         for (_, sub_app) in self.sub_apps.iter_mut() {
+            let extract_start = Instant::now();
             sub_app.extract_from(&mut self.main.world).unwrap();
+            let extract = extract_start.elapsed();
+
+            let update_start = Instant::now();
             sub_app.world_mut().update().unwrap();
+            let update = update_start.elapsed();
+
+            sub_apps.push(SubAppTiming {
+                name: sub_app.name,
+                extract,
+                update,
+            });
         }
+
+        self.main.world().insert_resource(AppTimings {
+            main,
+            sub_apps,
+            total: start.elapsed(),
+        });
     }
 
     pub fn shutdown(&mut self) {
@@ -203,11 +332,36 @@ impl SubApps {
     }
 }
 
+/// Time spent in one sub-app during the last [`App::update`].
+#[derive(Debug, Clone)]
+pub struct SubAppTiming {
+    pub name: &'static str,
+    pub extract: Duration,
+    pub update: Duration,
+}
+
+/// Where the time of the last [`App::update`] went. Inserted into the main world after every update.
+#[derive(Debug, Clone, Default)]
+pub struct AppTimings {
+    /// Time spent updating the main world.
+    pub main: Duration,
+    pub sub_apps: Vec<SubAppTiming>,
+    pub total: Duration,
+}
+
+impl AppTimings {
+    pub fn sub_app(&self, name: &str) -> Option<&SubAppTiming> {
+        self.sub_apps.iter().find(|timing| timing.name == name)
+    }
+}
+
 pub struct App {
     plugins: Vec<(TypeId, Box<dyn Plugin>)>,
     unready_plugins: TypeIdSet,
     runner: Option<Box<dyn Runner>>,
     sub_apps: SubApps,
+    // must be dropped last, the worlds and plugins may still reference code in these libraries
+    dynamic_libraries: DynamicLibraries,
 }
 
 impl App {
@@ -217,20 +371,53 @@ impl App {
             plugins: Vec::new(),
             unready_plugins: TypeIdSet::default(),
             sub_apps: SubApps {
-                main: SubApp::new(),
+                main: SubApp {
+                    name: "Main",
+                    ..SubApp::new()
+                },
                 sub_apps: FxHashMap::default(),
             },
+            dynamic_libraries: DynamicLibraries::default(),
         }
     }
 
+    /// Creates an app with the [`AppSettings`] of the current process (see [`AppSettings::from_env`]).
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
+        match AppSettings::from_env() {
+            Ok(settings) => Self::with_settings(settings),
+            Err(e) => {
+                let this = Self::with_settings(AppSettings::default());
+                log::error!("Failed to load app settings, using defaults: {}", e);
+                this
+            }
+        }
+    }
+
+    pub fn with_settings(settings: AppSettings) -> Self {
+        let log_settings = settings.section::<LogSettings>();
+        let filter = log_settings
+            .as_ref()
+            .map(|log_settings| log_settings.filter.clone())
+            .unwrap_or_else(|_| LogSettings::default().filter);
         tracing_subscriber::FmtSubscriber::builder()
-            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .with_env_filter(
+                tracing_subscriber::EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(&filter)),
+            )
             .try_init()
             .ok();
+        // the logger only exists now, so invalid log settings are reported after falling back to the defaults
+        if let Err(e) = log_settings {
+            log::error!("Invalid log settings, using the defaults: {}", e);
+        }
+
+        if let Some(path) = settings.path() {
+            log::info!("Loaded app settings from {:?}", path);
+        }
 
         let mut this = Self::empty();
+        this.insert_resource(settings);
 
         this.main_app_mut()
             .world_mut()
@@ -256,6 +443,16 @@ impl App {
             .world_mut()
             .push_shutdown_stage(AppStage::Shutdown);
 
+        this.init_resource::<PendingAppExit>();
+        this.add_event::<AppExit>();
+        this.add_system(receive_app_exit, AppStage::FinishFrame);
+        // both touch `Events<AppExit>`, so the exit must be received before the events are cleared
+        this.order_systems(
+            receive_app_exit,
+            clear_events::<AppExit>,
+            AppStage::FinishFrame,
+        );
+
         this
     }
 
@@ -263,28 +460,63 @@ impl App {
         &self.sub_apps.main
     }
 
+    /// The app's settings, or the defaults if the app was created without any.
+    pub fn settings(&self) -> AppSettings {
+        self.main_app()
+            .world()
+            .get_resource::<AppSettings>()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+
     pub fn main_app_mut(&mut self) -> &mut SubApp {
         &mut self.sub_apps.main
     }
 
     pub fn add_plugin<T: Plugin>(&mut self, plugin: T) -> Result<&mut Self> {
-        if self
-            .plugins
-            .iter()
-            .any(|(_, plugin)| (**plugin).type_id() == TypeId::of::<T>())
-        {
+        self.add_boxed_plugin(TypeId::of::<T>(), Box::new(plugin))
+    }
+
+    pub(crate) fn add_boxed_plugin(
+        &mut self,
+        type_id: TypeId,
+        plugin: Box<dyn Plugin>,
+    ) -> Result<&mut Self> {
+        if self.has_plugin_id(type_id) {
             log::warn!("Plugin already added: {:?}", plugin.type_name());
             return Ok(self);
         }
+        check_dependencies(&self.plugins, &*plugin)?;
 
         log::debug!("Adding plugin: {:?}", plugin.type_name());
         plugin.build(self)?;
 
-        self.plugins.push((TypeId::of::<T>(), Box::new(plugin)));
-        self.unready_plugins.insert(TypeId::of::<T>());
+        self.plugins.push((type_id, plugin));
+        self.unready_plugins.insert(type_id);
         Ok(self)
     }
 
+    /// Adds every enabled plugin in the group, in order.
+    pub fn add_plugins<G: PluginGroup>(&mut self, group: G) -> Result<&mut Self> {
+        group.build().finish(self)?;
+        Ok(self)
+    }
+
+    pub fn has_plugin<T: Plugin>(&self) -> bool {
+        self.has_plugin_id(TypeId::of::<T>())
+    }
+
+    pub(crate) fn has_plugin_id(&self, type_id: TypeId) -> bool {
+        self.plugins.iter().any(|(id, _)| *id == type_id)
+    }
+
+    pub fn get_plugin<T: Plugin>(&self) -> Option<&T> {
+        self.plugins
+            .iter()
+            .find(|(id, _)| *id == TypeId::of::<T>())
+            .and_then(|(_, plugin)| plugin.downcast_ref())
+    }
+
     pub fn configure_plugin<T: Plugin>(&mut self, f: impl FnOnce(&mut T)) -> &mut Self {
         if let Some(index) = self
             .plugins
@@ -307,7 +539,8 @@ impl App {
         self.runner = Some(Box::new(runner));
     }
 
-    pub fn add_sub_app<T: AppLabel>(&mut self, app: SubApp) {
+    pub fn add_sub_app<T: AppLabel>(&mut self, mut app: SubApp) {
+        app.name = std::any::type_name::<T>();
         self.sub_apps.sub_apps.insert(TypeId::of::<T>(), app);
     }
 
@@ -319,6 +552,19 @@ impl App {
         self.sub_apps.sub_apps.get_mut(&TypeId::of::<T>())
     }
 
+    /// Like [`App::get_sub_app_mut`], but returns an error naming the missing sub-app.
+    pub fn sub_app_mut<T: AppLabel>(&mut self) -> Result<&mut SubApp> {
+        self.sub_apps
+            .sub_apps
+            .get_mut(&TypeId::of::<T>())
+            .ok_or_else(|| {
+                PluginError::MissingSubApp {
+                    sub_app: std::any::type_name::<T>(),
+                }
+                .into()
+            })
+    }
+
     pub fn configure_sub_app<T: AppLabel>(&mut self, f: impl FnOnce(&mut SubApp)) -> &mut Self {
         if let Some(sub_app) = self.get_sub_app_mut::<T>() {
             f(sub_app);
@@ -345,9 +591,6 @@ impl App {
     }
 
     pub fn add_event<T: Event>(&mut self) -> &mut Self {
-        async fn clear_events<T: Event>(mut events: ResMut<Events<T>>, world_ticks: WorldTicks) {
-            events.update(world_ticks.change_tick).await;
-        }
         self.insert_resource(Events::<T>::new());
         self.main_app_mut()
             .world_mut()
@@ -403,6 +646,12 @@ impl App {
     }
 
     pub fn update(&mut self) {
+        if !self.dynamic_libraries.is_empty()
+            && let Err(e) = self.reload_changed_dynamic_plugins()
+        {
+            log::error!("Failed to reload dynamic plugin: {}", e);
+        }
+
         while !self.unready_plugins.is_empty() {
             self.finish_plugins();
         }
@@ -415,6 +664,23 @@ impl App {
         self.sub_apps.shutdown();
     }
 
+    /// Requests the app to exit, as if an [`AppExit`] event had been sent.
+    pub fn exit(&mut self, exit: AppExit) {
+        if let Some(mut pending) = self.main_app().world().get_resource_mut::<PendingAppExit>() {
+            pending.request(exit);
+        } else {
+            self.insert_resource(PendingAppExit(Some(exit)));
+        }
+    }
+
+    /// Returns the exit requested through [`App::exit`] or an [`AppExit`] event, if any.
+    pub fn exit_requested(&self) -> Option<AppExit> {
+        self.main_app()
+            .world()
+            .get_resource::<PendingAppExit>()
+            .and_then(|pending| pending.0)
+    }
+
     pub fn finish_plugins(&mut self) {
         let plugins = std::mem::take(&mut self.plugins);
         let unready_plugins = self.unready_plugins.clone();
@@ -434,7 +700,8 @@ impl App {
         self.sub_apps.finish_plugins();
     }
 
-    pub fn run(&mut self) -> Result<()> {
+    /// Runs the app with its runner until it exits, and returns how it exited.
+    pub fn run(&mut self) -> Result<AppExit> {
         GlobalTaskPool::get_or_init(TaskPool::new);
 
         if let Some(runner) = self.runner.take() {
@@ -445,7 +712,65 @@ impl App {
             self.runner = Some(runner);
             result
         } else {
-            Ok(())
+            Ok(AppExit::Success)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use weaver_event::EventTx;
+
+    use super::*;
+
+    #[test]
+    fn test_app_exit_event() {
+        async fn request_exit(mut exit: EventTx<AppExit>) {
+            exit.send(AppExit::from_code(3)).await;
+        }
+
+        GlobalTaskPool::get_or_init(TaskPool::new);
+
+        let mut app = App::new();
+        app.add_system(request_exit, AppStage::Update);
+        app.init();
+        assert_eq!(app.exit_requested(), None);
+
+        for _ in 0..10 {
+            app.update();
+            assert_eq!(app.exit_requested(), Some(AppExit::from_code(3)));
         }
+
+        app.exit(AppExit::Success);
+        assert_eq!(app.exit_requested().map(|exit| exit.code()), Some(3));
+    }
+
+    #[test]
+    fn test_app_timings() {
+        struct TimedApp;
+        impl AppLabel for TimedApp {}
+
+        GlobalTaskPool::get_or_init(TaskPool::new);
+
+        let mut sub_app = SubApp::new();
+        sub_app.set_extract(Box::new(|_main_world, _world| {
+            std::thread::sleep(Duration::from_millis(5));
+            Ok(())
+        }));
+        let mut app = App::empty();
+        app.add_sub_app::<TimedApp>(sub_app);
+        app.init();
+        app.update();
+
+        let timings = app
+            .main_app()
+            .world()
+            .get_resource::<AppTimings>()
+            .unwrap()
+            .clone();
+        let timing = timings.sub_app(std::any::type_name::<TimedApp>()).unwrap();
+        assert!(timing.extract >= Duration::from_millis(5));
+        assert!(timings.total >= timings.main + timing.extract + timing.update);
+        assert!(timings.sub_app("Missing").is_none());
     }
 }
@@ -1,3 +1,5 @@
+use std::any::TypeId;
+
 use weaver_util::prelude::*;
 
 use crate::App;
@@ -8,6 +10,11 @@ pub trait Plugin: DowncastSync {
         std::any::type_name::<Self>()
     }
 
+    /// Plugins that must be added to the same app before this one.
+    fn dependencies(&self) -> Vec<PluginId> {
+        Vec::new()
+    }
+
     fn build(&self, app: &mut App) -> Result<()>;
 
     fn finish(&self, app: &mut App) -> Result<()> {
@@ -31,3 +38,363 @@ impl Plugin for DummyPlugin {
         Ok(())
     }
 }
+
+/// Identifies a plugin type, used to declare plugin dependencies.
+#[derive(Debug, Clone, Copy)]
+pub struct PluginId {
+    pub id: TypeId,
+    pub name: &'static str,
+}
+
+impl PluginId {
+    pub fn of<T: Plugin>() -> Self {
+        Self {
+            id: TypeId::of::<T>(),
+            name: std::any::type_name::<T>(),
+        }
+    }
+}
+
+impl PartialEq for PluginId {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for PluginId {}
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error(
+        "plugin `{plugin}` depends on `{dependency}`, which has not been added (add `{dependency}` before `{plugin}`)"
+    )]
+    MissingDependency {
+        plugin: String,
+        dependency: &'static str,
+    },
+    #[error(
+        "plugin `{plugin}` depends on `{dependency}`, but `{dependency}` comes after it in plugin group `{group}`"
+    )]
+    WrongOrder {
+        group: &'static str,
+        plugin: String,
+        dependency: &'static str,
+    },
+    #[error("sub-app `{sub_app}` does not exist (is the plugin that creates it added?)")]
+    MissingSubApp { sub_app: &'static str },
+}
+
+/// A set of plugins that are added to an app together, in order.
+pub trait PluginGroup: Sized {
+    fn build(self) -> PluginGroupBuilder;
+}
+
+struct PluginGroupEntry {
+    id: TypeId,
+    plugin: Box<dyn Plugin>,
+    enabled: bool,
+}
+
+/// Ordered list of plugins produced by a [`PluginGroup`].
+///
+/// Individual plugins can be replaced, disabled, or re-enabled before the group is added to an app.
+pub struct PluginGroupBuilder {
+    group: &'static str,
+    plugins: Vec<PluginGroupEntry>,
+}
+
+impl PluginGroupBuilder {
+    pub fn start<G: PluginGroup>() -> Self {
+        Self {
+            group: std::any::type_name::<G>(),
+            plugins: Vec::new(),
+        }
+    }
+
+    fn index_of<T: Plugin>(&self) -> Option<usize> {
+        self.plugins
+            .iter()
+            .position(|entry| entry.id == TypeId::of::<T>())
+    }
+
+    fn entry<T: Plugin>(plugin: T) -> PluginGroupEntry {
+        PluginGroupEntry {
+            id: TypeId::of::<T>(),
+            plugin: Box::new(plugin),
+            enabled: true,
+        }
+    }
+
+    pub fn contains<T: Plugin>(&self) -> bool {
+        self.index_of::<T>().is_some()
+    }
+
+    pub fn enabled<T: Plugin>(&self) -> bool {
+        self.index_of::<T>()
+            .is_some_and(|index| self.plugins[index].enabled)
+    }
+
+    /// Appends a plugin to the end of the group.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add<T: Plugin>(mut self, plugin: T) -> Self {
+        self.plugins.push(Self::entry(plugin));
+        self
+    }
+
+    /// Inserts a plugin directly before the plugin of type `Target`.
+    ///
+    /// Panics if `Target` is not part of the group.
+    pub fn add_before<Target: Plugin, T: Plugin>(mut self, plugin: T) -> Self {
+        let index = self.index_of::<Target>().unwrap_or_else(|| {
+            panic!(
+                "plugin `{}` is not part of plugin group `{}`",
+                std::any::type_name::<Target>(),
+                self.group
+            )
+        });
+        self.plugins.insert(index, Self::entry(plugin));
+        self
+    }
+
+    /// Inserts a plugin directly after the plugin of type `Target`.
+    ///
+    /// Panics if `Target` is not part of the group.
+    pub fn add_after<Target: Plugin, T: Plugin>(mut self, plugin: T) -> Self {
+        let index = self.index_of::<Target>().unwrap_or_else(|| {
+            panic!(
+                "plugin `{}` is not part of plugin group `{}`",
+                std::any::type_name::<Target>(),
+                self.group
+            )
+        });
+        self.plugins.insert(index + 1, Self::entry(plugin));
+        self
+    }
+
+    /// Replaces the plugin of the same type, keeping its position in the group.
+    /// If the group doesn't contain a plugin of this type, it is appended instead.
+    pub fn set<T: Plugin>(mut self, plugin: T) -> Self {
+        match self.index_of::<T>() {
+            Some(index) => self.plugins[index].plugin = Box::new(plugin),
+            None => self.plugins.push(Self::entry(plugin)),
+        }
+        self
+    }
+
+    pub fn disable<T: Plugin>(mut self) -> Self {
+        if let Some(index) = self.index_of::<T>() {
+            self.plugins[index].enabled = false;
+        }
+        self
+    }
+
+    pub fn enable<T: Plugin>(mut self) -> Self {
+        if let Some(index) = self.index_of::<T>() {
+            self.plugins[index].enabled = true;
+        }
+        self
+    }
+
+    /// Checks that the dependencies of every enabled plugin are either already in the app or listed before it, so
+    /// that a group is never left partially added because of them.
+    fn validate(&self, app: &App) -> Result<(), PluginError> {
+        let enabled = self
+            .plugins
+            .iter()
+            .filter(|entry| entry.enabled)
+            .collect::<Vec<_>>();
+
+        for (index, entry) in enabled.iter().enumerate() {
+            for dependency in entry.plugin.dependencies() {
+                if app.has_plugin_id(dependency.id)
+                    || enabled[..index]
+                        .iter()
+                        .any(|other| other.id == dependency.id)
+                {
+                    continue;
+                }
+                if enabled[index + 1..]
+                    .iter()
+                    .any(|other| other.id == dependency.id)
+                {
+                    return Err(PluginError::WrongOrder {
+                        group: self.group,
+                        plugin: entry.plugin.type_name().to_string(),
+                        dependency: dependency.name,
+                    });
+                }
+                return Err(PluginError::MissingDependency {
+                    plugin: entry.plugin.type_name().to_string(),
+                    dependency: dependency.name,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds every enabled plugin in the group to the app, in order. Plugins the app already has are skipped.
+    ///
+    /// Dependencies are checked for the whole group before any plugin is added.
+    pub fn finish(self, app: &mut App) -> Result<()> {
+        self.validate(app)?;
+
+        for entry in self.plugins {
+            if !entry.enabled {
+                log::debug!(
+                    "Skipping disabled plugin: {:?} (in group {:?})",
+                    entry.plugin.type_name(),
+                    self.group
+                );
+                continue;
+            }
+            app.add_boxed_plugin(entry.id, entry.plugin)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl PluginGroup for PluginGroupBuilder {
+    fn build(self) -> PluginGroupBuilder {
+        self
+    }
+}
+
+/// Checks that the dependencies of `plugin` are in an app that already contains `plugins`.
+pub(crate) fn check_dependencies(
+    plugins: &[(TypeId, Box<dyn Plugin>)],
+    plugin: &dyn Plugin,
+) -> Result<(), PluginError> {
+    for dependency in plugin.dependencies() {
+        if !plugins.iter().any(|(other, _)| *other == dependency.id) {
+            return Err(PluginError::MissingDependency {
+                plugin: plugin.type_name().to_string(),
+                dependency: dependency.name,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    struct A;
+    struct B;
+
+    impl Plugin for A {
+        fn build(&self, _app: &mut App) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Plugin for B {
+        fn dependencies(&self) -> Vec<PluginId> {
+            vec![PluginId::of::<A>()]
+        }
+
+        fn build(&self, _app: &mut App) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    struct TestGroup;
+
+    impl PluginGroup for TestGroup {
+        fn build(self) -> PluginGroupBuilder {
+            PluginGroupBuilder::start::<Self>().add(A).add(B)
+        }
+    }
+
+    fn plugin_error(result: Result<&mut App>) -> PluginError {
+        result
+            .err()
+            .expect("expected an error")
+            .downcast::<PluginError>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_plugin_group() {
+        let mut app = App::empty();
+        app.add_plugins(TestGroup).unwrap();
+        assert!(app.has_plugin::<A>());
+        assert!(app.has_plugin::<B>());
+    }
+
+    static COUNTED_BUILDS: AtomicU32 = AtomicU32::new(0);
+
+    struct Counted;
+
+    impl Plugin for Counted {
+        fn build(&self, _app: &mut App) -> Result<()> {
+            COUNTED_BUILDS.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_duplicate_plugin() {
+        // adding a plugin twice is skipped with a warning, so the plugin is only built once
+        let mut app = App::empty();
+        app.add_plugin(Counted).unwrap();
+        app.add_plugin(Counted).unwrap();
+        app.add_plugins(PluginGroupBuilder::start::<TestGroup>().add(Counted))
+            .unwrap();
+        assert_eq!(COUNTED_BUILDS.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_missing_dependency() {
+        let mut app = App::empty();
+        let err = plugin_error(app.add_plugins(TestGroup.build().disable::<A>()));
+        assert!(matches!(err, PluginError::MissingDependency { .. }));
+        assert!(!app.has_plugin::<B>());
+    }
+
+    #[test]
+    fn test_wrong_order() {
+        let mut app = App::empty();
+        let group = PluginGroupBuilder::start::<TestGroup>().add(B).add(A);
+        let err = plugin_error(app.add_plugins(group));
+        assert!(matches!(err, PluginError::WrongOrder { .. }));
+        assert!(!app.has_plugin::<A>());
+    }
+
+    #[test]
+    fn test_group_validated_before_adding() {
+        struct C;
+
+        impl Plugin for C {
+            fn dependencies(&self) -> Vec<PluginId> {
+                vec![PluginId::of::<DummyPlugin>()]
+            }
+
+            fn build(&self, _app: &mut App) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        // the missing dependency of the last plugin keeps the first ones from being added
+        let mut app = App::empty();
+        let group = PluginGroupBuilder::start::<TestGroup>()
+            .add(A)
+            .add(B)
+            .add(C);
+        let err = plugin_error(app.add_plugins(group));
+        assert!(matches!(err, PluginError::MissingDependency { .. }));
+        assert!(!app.has_plugin::<A>());
+        assert!(!app.has_plugin::<B>());
+
+        // dependencies the app already has don't need to be in the group
+        app.add_plugin(A).unwrap();
+        app.add_plugins(PluginGroupBuilder::start::<TestGroup>().add(B))
+            .unwrap();
+        assert!(app.has_plugin::<B>());
+    }
+}
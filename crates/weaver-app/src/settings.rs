@@ -0,0 +1,278 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use weaver_util::prelude::*;
+
+/// Default settings file, loaded from the working directory if it exists.
+pub const DEFAULT_SETTINGS_FILE: &str = "weaver.toml";
+/// Environment variable that points to a settings file.
+pub const SETTINGS_FILE_ENV: &str = "WEAVER_SETTINGS";
+/// Prefix of environment variables that override single settings, e.g. `WEAVER__WINDOW__TITLE=Demo`.
+pub const SETTINGS_ENV_PREFIX: &str = "WEAVER__";
+
+/// A typed section of the [`AppSettings`], e.g. `[window]`.
+///
+/// Sections are owned by the crate that uses them and are read by plugins during `build`.
+pub trait SettingsSection: Serialize + DeserializeOwned + Send + Sync + 'static {
+    const NAME: &'static str;
+}
+
+/// App configuration, deserialized from a TOML file and overridable through environment variables and command line arguments.
+///
+/// Available as a resource in the main world. Later sources take precedence:
+/// 1. the settings file (`--settings <path>`, `$WEAVER_SETTINGS`, or `weaver.toml`)
+/// 2. environment variables: `WEAVER__<SECTION>__<KEY>=<value>`
+/// 3. command line arguments: `--set <section>.<key>=<value>`
+///
+/// Values given through the environment or command line are parsed as TOML values, falling back to plain strings.
+#[derive(Debug, Clone, Default)]
+pub struct AppSettings {
+    table: toml::Table,
+    path: Option<PathBuf>,
+}
+
+impl AppSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self> {
+        Ok(Self {
+            table: toml::from_str(source)?,
+            path: None,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read settings file {:?}: {}", path, e))?;
+        let mut this = Self::from_toml(&source)
+            .map_err(|e| anyhow!("Failed to parse settings file {:?}: {}", path, e))?;
+        this.path = Some(path.to_path_buf());
+        Ok(this)
+    }
+
+    /// Loads the settings of the current process from the settings file, environment variables and command line arguments.
+    pub fn from_env() -> Result<Self> {
+        let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+        let path = args
+            .iter()
+            .position(|arg| arg == "--settings")
+            .and_then(|index| args.get(index + 1))
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os(SETTINGS_FILE_ENV).map(PathBuf::from));
+
+        let mut this = match path {
+            Some(path) => Self::load(path)?,
+            None if Path::new(DEFAULT_SETTINGS_FILE).exists() => Self::load(DEFAULT_SETTINGS_FILE)?,
+            None => Self::new(),
+        };
+
+        this.apply_env(std::env::vars())?;
+        this.apply_args(args)?;
+
+        Ok(this)
+    }
+
+    /// The file these settings were loaded from, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn table(&self) -> &toml::Table {
+        &self.table
+    }
+
+    /// Sets a single value by its dotted key, e.g. `window.width`.
+    pub fn set(&mut self, key: &str, value: impl Into<toml::Value>) -> Result<()> {
+        let mut table = &mut self.table;
+        let mut parts = key.split('.').peekable();
+
+        while let Some(part) = parts.next() {
+            if part.is_empty() {
+                bail!("Invalid settings key: {:?}", key);
+            }
+
+            if parts.peek().is_none() {
+                table.insert(part.to_string(), value.into());
+                return Ok(());
+            }
+
+            let entry = table
+                .entry(part.to_string())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            table = entry
+                .as_table_mut()
+                .ok_or_else(|| anyhow!("Settings key {:?} is not a table", part))?;
+        }
+
+        bail!("Invalid settings key: {:?}", key)
+    }
+
+    /// Sets a single value from its textual form, as given on the command line or in an environment variable.
+    pub fn set_str(&mut self, key: &str, value: &str) -> Result<()> {
+        self.set(key, parse_value(value))
+    }
+
+    /// Applies every `WEAVER__<SECTION>__<KEY>` variable.
+    pub fn apply_env(&mut self, vars: impl IntoIterator<Item = (String, String)>) -> Result<()> {
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(SETTINGS_ENV_PREFIX) else {
+                continue;
+            };
+            let key = key.to_lowercase().replace("__", ".");
+            self.set_str(&key, &value)?;
+        }
+        Ok(())
+    }
+
+    /// Applies every `--set <key>=<value>` (or `--set=<key>=<value>`) argument. Other arguments are ignored.
+    pub fn apply_args(&mut self, args: impl IntoIterator<Item = String>) -> Result<()> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let assignment = if arg == "--set" {
+                args.next()
+                    .ok_or_else(|| anyhow!("Missing value for --set"))?
+            } else if let Some(assignment) = arg.strip_prefix("--set=") {
+                assignment.to_string()
+            } else {
+                continue;
+            };
+
+            let (key, value) = assignment
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Expected --set <key>=<value>, got {:?}", assignment))?;
+            self.set_str(key.trim(), value.trim())?;
+        }
+        Ok(())
+    }
+
+    pub fn has_section<T: SettingsSection>(&self) -> bool {
+        self.table.contains_key(T::NAME)
+    }
+
+    /// Reads a section, using `T::default()` for everything that isn't configured.
+    pub fn section<T: SettingsSection + Default>(&self) -> Result<T> {
+        self.section_or(T::default())
+    }
+
+    /// Reads a section, using `defaults` for everything that isn't configured.
+    pub fn section_or<T: SettingsSection>(&self, defaults: T) -> Result<T> {
+        let Some(overrides) = self.table.get(T::NAME) else {
+            return Ok(defaults);
+        };
+
+        let mut value = toml::Value::try_from(defaults)?;
+        merge(&mut value, overrides.clone());
+
+        value
+            .try_into()
+            .map_err(|e| anyhow!("Invalid settings section [{}]: {}", T::NAME, e))
+    }
+
+    /// Replaces a section with the given values.
+    pub fn set_section<T: SettingsSection>(&mut self, section: &T) -> Result<()> {
+        self.table
+            .insert(T::NAME.to_string(), toml::Value::try_from(section)?);
+        Ok(())
+    }
+}
+
+fn parse_value(value: &str) -> toml::Value {
+    format!("value = {}", value)
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+fn merge(base: &mut toml::Value, overrides: toml::Value) {
+    match (base, overrides) {
+        (toml::Value::Table(base), toml::Value::Table(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// The `[log]` section.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogSettings {
+    /// Log filter directives, in the same format as `RUST_LOG` (which takes precedence if set).
+    pub filter: String,
+}
+
+impl SettingsSection for LogSettings {
+    const NAME: &'static str = "log";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct TestSection {
+        name: String,
+        size: (u32, u32),
+        enabled: bool,
+    }
+
+    impl SettingsSection for TestSection {
+        const NAME: &'static str = "test";
+    }
+
+    fn defaults() -> TestSection {
+        TestSection {
+            name: "default".into(),
+            size: (800, 600),
+            enabled: false,
+        }
+    }
+
+    #[test]
+    fn test_section_overrides() {
+        let mut settings = AppSettings::from_toml("[test]\nname = \"file\"\n").unwrap();
+        settings
+            .apply_env([
+                ("WEAVER__TEST__ENABLED".to_string(), "true".to_string()),
+                ("UNRELATED".to_string(), "1".to_string()),
+            ])
+            .unwrap();
+        settings
+            .apply_args(["--set".to_string(), "test.size=[1600, 900]".to_string()])
+            .unwrap();
+
+        let section = settings.section_or(defaults()).unwrap();
+        assert_eq!(
+            section,
+            TestSection {
+                name: "file".into(),
+                size: (1600, 900),
+                enabled: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_section() {
+        let settings = AppSettings::new();
+        assert_eq!(settings.section_or(defaults()).unwrap(), defaults());
+    }
+
+    #[test]
+    fn test_string_fallback() {
+        let mut settings = AppSettings::new();
+        settings.set_str("test.name", "Hello World").unwrap();
+        assert_eq!(settings.section_or(defaults()).unwrap().name, "Hello World");
+    }
+}
@@ -15,3 +15,87 @@ pub fn derive_asset(input: TokenStream) -> TokenStream {
 
     expanded.into()
 }
+
+/// Where the asset of a field of a typed asset collection is loaded from.
+enum AssetSource {
+    Path(syn::LitStr),
+    Folder(syn::LitStr),
+}
+
+fn asset_source(field: &syn::Field) -> syn::Result<Option<AssetSource>> {
+    let mut source = None;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("asset"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if source.is_some() {
+                return Err(meta.error("expected a single `path` or `folder`"));
+            }
+            if meta.path.is_ident("path") {
+                source = Some(AssetSource::Path(meta.value()?.parse()?));
+            } else if meta.path.is_ident("folder") {
+                source = Some(AssetSource::Folder(meta.value()?.parse()?));
+            } else {
+                return Err(meta.error("expected `path` or `folder`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(source)
+}
+
+#[proc_macro_derive(TypedAssetCollection, attributes(asset))]
+pub fn derive_typed_asset_collection(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let name = &input.ident;
+
+    let syn::Data::Struct(syn::DataStruct {
+        fields: syn::Fields::Named(fields),
+        ..
+    }) = &input.data
+    else {
+        return syn::Error::new_spanned(
+            &input,
+            "TypedAssetCollection can only be derived for structs with named fields",
+        )
+        .to_compile_error()
+        .into();
+    };
+
+    let mut loads = Vec::new();
+    let mut handles = Vec::new();
+    for field in fields.named.iter() {
+        let ident = field.ident.as_ref().unwrap();
+        match asset_source(field) {
+            Ok(Some(AssetSource::Path(path))) => {
+                loads.push(quote! { #ident: server.load(#path)? });
+                handles.push(quote! { self.#ident.untyped() });
+            }
+            Ok(Some(AssetSource::Folder(path))) => {
+                loads.push(quote! { #ident: server.load_folder(#path)? });
+                handles.push(quote! { self.#ident.untyped() });
+            }
+            Ok(None) => loads.push(quote! { #ident: ::core::default::Default::default() }),
+            Err(e) => return e.to_compile_error().into(),
+        }
+    }
+
+    let expanded = quote! {
+        impl #impl_generics ::weaver_asset::collection::TypedAssetCollection for #name #ty_generics #where_clause {
+            fn load(server: &::weaver_asset::server::AssetServer) -> ::weaver_asset::__private::Result<Self> {
+                ::core::result::Result::Ok(Self {
+                    #(#loads,)*
+                })
+            }
+
+            fn handles(&self) -> ::std::vec::Vec<::weaver_asset::UntypedHandle> {
+                ::std::vec![#(#handles),*]
+            }
+        }
+    };
+
+    expanded.into()
+}
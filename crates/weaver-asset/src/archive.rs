@@ -0,0 +1,420 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use weaver_util::prelude::*;
+use zip::{CompressionMethod, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::processor::{
+    AssetProcessor, INDEX_FILE, ProcessedIndex, collect_files, hash_source, index_key,
+};
+
+/// Name of the manifest that [`AssetPacker`] writes into every archive.
+pub const MANIFEST_FILE: &str = "manifest.toml";
+/// Directory in an archive that holds the processed assets and their [`ProcessedIndex`].
+pub const PROCESSED_DIR: &str = ".processed";
+
+/// Extensions of files that are compressed already, so they're stored as they are.
+const STORED_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "ktx2", "glb", "zip", "ogg", "mp3"];
+
+/// A file in an archive, as listed in its [`PackManifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackedFile {
+    /// Hash of the contents, see [`hash_source`].
+    pub hash: String,
+    pub size: u64,
+    /// Lowercase file extension, e.g. `png`, or the kind of a processed artifact.
+    pub kind: String,
+}
+
+/// Lists every file in an archive, so that lookups don't have to scan the archive's entries.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackManifest {
+    pub files: BTreeMap<String, PackedFile>,
+}
+
+impl PackManifest {
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&PackedFile> {
+        self.files.get(&index_key(path.as_ref()))
+    }
+
+    pub fn contains(&self, path: impl AsRef<Path>) -> bool {
+        self.get(path).is_some()
+    }
+
+    /// Every file under a directory, not counting processed assets.
+    pub fn files_in(&self, dir: impl AsRef<Path>) -> Vec<PathBuf> {
+        let dir = PathBuf::from(index_key(dir.as_ref()));
+        self.files
+            .keys()
+            .map(PathBuf::from)
+            .filter(|file| file.starts_with(&dir) && !file.starts_with(PROCESSED_DIR))
+            .collect()
+    }
+}
+
+/// Packs an asset directory into a zip archive with a [`PackManifest`], optionally together with its processed assets.
+///
+/// The archives are read with [`Filesystem::add_archive`](crate::Filesystem::add_archive).
+#[derive(Default)]
+pub struct AssetPacker<'a> {
+    processor: Option<&'a AssetProcessor>,
+    processed_dir: Option<PathBuf>,
+}
+
+impl<'a> AssetPacker<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Processes the assets into `cache_dir` before packing, and packs the processed assets too.
+    pub fn with_processor(
+        mut self,
+        processor: &'a AssetProcessor,
+        cache_dir: impl AsRef<Path>,
+    ) -> Self {
+        self.processor = Some(processor);
+        self.processed_dir = Some(cache_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Packs the processed assets in `cache_dir`, which were processed before.
+    pub fn with_processed_dir(mut self, cache_dir: impl AsRef<Path>) -> Self {
+        self.processed_dir = Some(cache_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Writes every file in `source_dir` to a new archive at `archive_path`.
+    pub fn pack(
+        &self,
+        source_dir: impl AsRef<Path>,
+        archive_path: impl AsRef<Path>,
+    ) -> Result<PackManifest> {
+        let source_dir = source_dir.as_ref();
+        if let (Some(processor), Some(cache_dir)) = (self.processor, self.processed_dir.as_ref()) {
+            let stats = processor.process_dir(source_dir, cache_dir)?;
+            if stats.failed > 0 {
+                log::warn!(
+                    "{} assets failed to process and are packed as they are",
+                    stats.failed
+                );
+            }
+        }
+
+        let mut writer = ZipWriter::new(File::create(archive_path.as_ref())?);
+        let mut manifest = PackManifest::default();
+
+        let mut files = Vec::new();
+        collect_files(source_dir, &mut files)?;
+        files.sort();
+        for path in files {
+            let name = index_key(path.strip_prefix(source_dir)?);
+            let kind = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .unwrap_or_default()
+                .to_lowercase();
+            write_file(
+                &mut writer,
+                &mut manifest,
+                name,
+                kind,
+                &std::fs::read(&path)?,
+            )?;
+        }
+
+        if let Some(cache_dir) = self.processed_dir.as_ref() {
+            let index = ProcessedIndex::load(cache_dir)?;
+            let mut artifacts = index
+                .files
+                .values()
+                .flatten()
+                .map(|file| (file.artifact.clone(), file.kind.clone()))
+                .collect::<Vec<_>>();
+            artifacts.sort();
+            artifacts.dedup();
+            for (artifact, kind) in artifacts {
+                let bytes = std::fs::read(cache_dir.join(&artifact))?;
+                let name = format!("{}/{}", PROCESSED_DIR, artifact);
+                write_file(&mut writer, &mut manifest, name, kind, &bytes)?;
+            }
+            let name = format!("{}/{}", PROCESSED_DIR, INDEX_FILE);
+            write_file(
+                &mut writer,
+                &mut manifest,
+                name,
+                "toml".into(),
+                toml::to_string(&index)?.as_bytes(),
+            )?;
+        }
+
+        writer.start_file(MANIFEST_FILE, SimpleFileOptions::default())?;
+        writer.write_all(toml::to_string(&manifest)?.as_bytes())?;
+        writer.finish()?;
+
+        Ok(manifest)
+    }
+}
+
+fn write_file(
+    writer: &mut ZipWriter<File>,
+    manifest: &mut PackManifest,
+    name: String,
+    kind: String,
+    bytes: &[u8],
+) -> Result<()> {
+    let compression = if STORED_EXTENSIONS.contains(&kind.as_str()) {
+        CompressionMethod::Stored
+    } else {
+        CompressionMethod::Deflated
+    };
+    writer.start_file(
+        name.as_str(),
+        SimpleFileOptions::default().compression_method(compression),
+    )?;
+    writer.write_all(bytes)?;
+    manifest.files.insert(
+        name,
+        PackedFile {
+            hash: hash_source(bytes),
+            size: bytes.len() as u64,
+            kind,
+        },
+    );
+    Ok(())
+}
+
+/// Checks that every file listed in the manifest of an archive is in it, with the listed size and hash.
+pub fn verify_archive(archive_path: impl AsRef<Path>) -> Result<PackManifest> {
+    let archive = MountedArchive::open(archive_path)?;
+    let Some(manifest) = archive.manifest.as_ref() else {
+        bail!("Archive has no {}", MANIFEST_FILE);
+    };
+
+    for (name, file) in manifest.files.iter() {
+        let Some(bytes) = archive.read_entry(name)? else {
+            bail!("Archive is missing {}", name);
+        };
+        if bytes.len() as u64 != file.size || hash_source(&bytes) != file.hash {
+            bail!("Archive entry {} does not match the manifest", name);
+        }
+    }
+    Ok(manifest.clone())
+}
+
+/// An archive file that every reader reads with its own position, so that readers don't have to take turns.
+///
+/// Cloning a `ZipArchive<ArchiveFile>` shares the parsed central directory and the open file.
+#[derive(Clone)]
+struct ArchiveFile {
+    file: Arc<File>,
+    len: u64,
+    position: u64,
+    /// Platforms without positional reads seek the shared file, so readers do take turns there.
+    #[cfg(not(any(unix, windows)))]
+    seek_lock: Arc<Lock<()>>,
+}
+
+impl ArchiveFile {
+    fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            file: Arc::new(file),
+            len,
+            position: 0,
+            #[cfg(not(any(unix, windows)))]
+            seek_lock: Arc::new(Lock::new(())),
+        })
+    }
+
+    #[cfg(unix)]
+    fn read_at(&self, buf: &mut [u8]) -> std::io::Result<usize> {
+        std::os::unix::fs::FileExt::read_at(&*self.file, buf, self.position)
+    }
+
+    #[cfg(windows)]
+    fn read_at(&self, buf: &mut [u8]) -> std::io::Result<usize> {
+        std::os::windows::fs::FileExt::seek_read(&*self.file, buf, self.position)
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn read_at(&self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let _turn = self.seek_lock.write();
+        let mut file = &*self.file;
+        file.seek(SeekFrom::Start(self.position))?;
+        file.read(buf)
+    }
+}
+
+impl Read for ArchiveFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.read_at(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for ArchiveFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        let Some(position) = position else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "seek to a negative position",
+            ));
+        };
+        self.position = position;
+        Ok(position)
+    }
+}
+
+/// An archive added to a [`Filesystem`](crate::Filesystem), with the manifest and processed assets of archives made by an
+/// [`AssetPacker`].
+pub(crate) struct MountedArchive {
+    zip: ZipArchive<ArchiveFile>,
+    manifest: Option<PackManifest>,
+    pub(crate) processed: Option<ProcessedIndex>,
+}
+
+impl MountedArchive {
+    pub(crate) fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let mut this = Self {
+            zip: ZipArchive::new(ArchiveFile::open(path)?)?,
+            manifest: None,
+            processed: None,
+        };
+        if let Some(manifest) = this.read_entry(MANIFEST_FILE)? {
+            this.manifest = Some(toml::from_str(std::str::from_utf8(&manifest)?)?);
+        }
+        if let Some(index) = this.read_entry(&format!("{}/{}", PROCESSED_DIR, INDEX_FILE))? {
+            this.processed = Some(toml::from_str(std::str::from_utf8(&index)?)?);
+        }
+        Ok(this)
+    }
+
+    /// The name of the entry of a file. Archives with a manifest use normalized names.
+    fn entry_name(&self, path: &Path) -> Option<String> {
+        match self.manifest.as_ref() {
+            Some(manifest) => manifest.contains(path).then(|| index_key(path)),
+            None => path.to_str().map(str::to_string),
+        }
+    }
+
+    fn read_entry(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        // clones share the central directory, so that reads don't have to take turns
+        let mut zip = self.zip.clone();
+        let Ok(mut file) = zip.by_name(name) else {
+            return Ok(None);
+        };
+        let mut bytes = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut bytes)?;
+        Ok(Some(bytes))
+    }
+
+    pub(crate) fn exists(&self, path: &Path) -> bool {
+        match self.manifest.as_ref() {
+            Some(manifest) => manifest.contains(path),
+            None => path
+                .to_str()
+                .is_some_and(|name| self.zip.index_for_name(name).is_some()),
+        }
+    }
+
+    pub(crate) fn read(&self, path: &Path) -> Result<Option<Vec<u8>>> {
+        match self.entry_name(path) {
+            Some(name) => self.read_entry(&name),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn read_artifact(&self, artifact: &str) -> Result<Vec<u8>> {
+        self.read_entry(&format!("{}/{}", PROCESSED_DIR, artifact))?
+            .ok_or_else(|| anyhow!("Archive is missing processed asset {}", artifact))
+    }
+
+    pub(crate) fn files_in(&self, dir: &Path) -> Vec<PathBuf> {
+        if let Some(manifest) = self.manifest.as_ref() {
+            return manifest.files_in(dir);
+        }
+        self.zip
+            .file_names()
+            .map(PathBuf::from)
+            .filter(|file| file.starts_with(dir))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Filesystem,
+        processor::{AssetBytes, Process},
+    };
+
+    struct Upper;
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    struct UpperSettings {}
+
+    impl crate::meta::LoaderSettings for UpperSettings {
+        const NAME: &'static str = "upper";
+    }
+
+    impl Process for Upper {
+        const KIND: &'static str = "upper";
+        const VERSION: u32 = 1;
+        type Settings = UpperSettings;
+
+        fn process(&self, source: &[u8], _settings: &UpperSettings) -> Result<Vec<u8>> {
+            Ok(source.to_ascii_uppercase())
+        }
+    }
+
+    #[test]
+    fn test_pack_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let source_dir = dir.join("assets");
+        std::fs::create_dir_all(source_dir.join("text")).unwrap();
+        std::fs::write(source_dir.join("text/a.txt"), "hello").unwrap();
+        std::fs::write(source_dir.join("b.png"), [1, 2, 3]).unwrap();
+
+        let mut processor = AssetProcessor::new();
+        processor
+            .add_processor(&["txt"], Upper, UpperSettings::default())
+            .unwrap();
+        let archive_path = dir.join("assets.zip");
+        let manifest = AssetPacker::new()
+            .with_processor(&processor, dir.join("processed"))
+            .pack(&source_dir, &archive_path)
+            .unwrap();
+        assert_eq!(manifest.get("text/a.txt").unwrap().kind, "txt");
+        assert_eq!(manifest.get("./b.png").unwrap().size, 3);
+        assert_eq!(verify_archive(&archive_path).unwrap().files, manifest.files);
+
+        let fs = Filesystem::new().with_archive(&archive_path).unwrap();
+        assert!(fs.exists("text/./a.txt"));
+        assert!(!fs.exists("text/c.txt"));
+        assert_eq!(fs.read_sub_path("text/a.txt").unwrap(), b"hello");
+        assert_eq!(
+            fs.read_dir("text").unwrap(),
+            vec![PathBuf::from("text/a.txt")]
+        );
+        assert_eq!(fs.read_dir("").unwrap().len(), 2);
+        assert!(matches!(
+            fs.read_processed::<Upper>("text/a.txt").unwrap(),
+            AssetBytes::Processed(bytes) if bytes == b"HELLO"
+        ));
+    }
+}
@@ -0,0 +1,77 @@
+//! Packs an asset directory into a zip archive that `Filesystem::add_archive` reads, or verifies such an archive.
+//!
+//! ```text
+//! weaver-pack <asset-dir> <archive> [--processed <cache-dir>]
+//! weaver-pack --verify <archive>
+//! ```
+//!
+//! Processors are registered by plugins, so this packs an existing processed cache (e.g. one written with
+//! `process_on_startup`) rather than processing the assets itself.
+
+use std::path::PathBuf;
+
+use weaver_asset::archive::{AssetPacker, PackManifest, verify_archive};
+use weaver_util::prelude::*;
+
+const USAGE: &str = "usage: weaver-pack <asset-dir> <archive> [--processed <cache-dir>]
+       weaver-pack --verify <archive>";
+
+fn main() {
+    if let Err(e) = run(std::env::args().skip(1).collect()) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<()> {
+    let mut paths = Vec::new();
+    let mut processed_dir = None;
+    let mut verify = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--verify" => verify = true,
+            "--processed" => {
+                let dir = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--processed needs a directory"))?;
+                processed_dir = Some(PathBuf::from(dir));
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if arg.starts_with("--") => bail!("unknown option {}\n{}", arg, USAGE),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    match (verify, paths.as_slice()) {
+        (true, [archive]) => {
+            let manifest = verify_archive(archive)?;
+            println!("{:?} is intact: {}", archive, summary(&manifest));
+        }
+        (false, [asset_dir, archive]) => {
+            let mut packer = AssetPacker::new();
+            if let Some(processed_dir) = processed_dir {
+                packer = packer.with_processed_dir(processed_dir);
+            }
+            let manifest = packer.pack(asset_dir, archive)?;
+            verify_archive(archive)?;
+            println!(
+                "Packed {:?} into {:?}: {}",
+                asset_dir,
+                archive,
+                summary(&manifest)
+            );
+        }
+        _ => bail!("{}", USAGE),
+    }
+    Ok(())
+}
+
+fn summary(manifest: &PackManifest) -> String {
+    let size: u64 = manifest.files.values().map(|file| file.size).sum();
+    format!("{} files, {} bytes", manifest.files.len(), size)
+}
@@ -0,0 +1,269 @@
+use std::{
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use weaver_ecs::prelude::{Commands, Res};
+use weaver_util::prelude::*;
+
+use crate::{
+    Asset, AssetLoadStatus, Handle, LoadState, StrongHandle, UntypedHandle, server::AssetServer,
+};
+
+struct Member {
+    path: PathBuf,
+    handle: UntypedHandle,
+    /// Keeps the member alive as long as the collection is.
+    strong: Arc<StrongHandle>,
+}
+
+/// The assets in a folder, loaded with [`AssetServer::load_folder`].
+#[derive(Default)]
+pub struct AssetCollection {
+    members: Vec<Member>,
+}
+
+impl AssetCollection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn push(&mut self, path: PathBuf, handle: UntypedHandle, strong: Arc<StrongHandle>) {
+        self.members.push(Member {
+            path,
+            handle,
+            strong,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// The members with their paths relative to the folder.
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, UntypedHandle)> + '_ {
+        self.members
+            .iter()
+            .map(|member| (member.path.as_path(), member.handle))
+    }
+
+    /// A strong handle to the member at `path` (relative to the folder), if it's a `T`.
+    pub fn get<T: Asset>(&self, path: impl AsRef<Path>) -> Option<Handle<T>> {
+        let path = path.as_ref();
+        self.members
+            .iter()
+            .find(|member| member.path == path && member.handle.is::<T>())
+            .map(Self::strong)
+    }
+
+    /// Strong handles to all members that are `T`s.
+    pub fn handles<T: Asset>(&self) -> impl Iterator<Item = Handle<T>> + '_ {
+        self.members
+            .iter()
+            .filter(|member| member.handle.is::<T>())
+            .map(Self::strong)
+    }
+
+    fn strong<T: Asset>(member: &Member) -> Handle<T> {
+        Handle {
+            id: member.handle.id,
+            strong: Some(member.strong.clone()),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Asset for AssetCollection {
+    fn visit_dependencies(&self, visit: &mut dyn FnMut(UntypedHandle)) {
+        for member in self.members.iter() {
+            visit(member.handle);
+        }
+    }
+}
+
+/// A struct of handles that are loaded together, usually derived:
+///
+/// ```ignore
+/// #[derive(TypedAssetCollection)]
+/// struct WoodAssets {
+///     #[asset(path = "materials/Wood_025_SD/Wood_025_basecolor.jpg")]
+///     albedo: Handle<Texture>,
+///     #[asset(folder = "materials/Wood_025_SD")]
+///     folder: Handle<AssetCollection>,
+/// }
+/// ```
+///
+/// Fields without an `#[asset(...)]` attribute are set to their defaults. Once added with
+/// [`AssetApp::init_asset_collection`](crate::AssetApp::init_asset_collection), the struct is inserted as a resource as
+/// soon as all of its assets are loaded.
+pub trait TypedAssetCollection: Send + Sync + Sized + 'static {
+    /// Requests all assets of the collection.
+    fn load(server: &AssetServer) -> Result<Self>;
+
+    fn handles(&self) -> Vec<UntypedHandle>;
+
+    /// The combined [`recursive_load_state`](AssetLoadStatus::recursive_load_state) of all assets of the collection.
+    fn load_state(&self, load_status: &AssetLoadStatus) -> LoadState {
+        let mut state = LoadState::Loaded;
+        for handle in self.handles() {
+            match load_status.recursive_load_state_by_id(handle.id) {
+                LoadState::Loaded => {}
+                LoadState::Failed(error) => return LoadState::Failed(error),
+                _ => state = LoadState::Loading,
+            }
+        }
+        state
+    }
+}
+
+/// A [`TypedAssetCollection`] that is waiting for its assets.
+pub(crate) struct LoadingCollection<C: TypedAssetCollection>(pub C);
+
+/// Inserts a [`TypedAssetCollection`] as a resource once all of its assets are loaded.
+pub(crate) async fn resolve_asset_collection<C: TypedAssetCollection>(
+    commands: Commands,
+    loading: Option<Res<LoadingCollection<C>>>,
+    load_status: Res<AssetLoadStatus>,
+) {
+    let Some(loading) = loading else {
+        return;
+    };
+    match loading.0.load_state(&load_status) {
+        LoadState::Loaded => {
+            drop(loading);
+            if let Some(LoadingCollection(collection)) =
+                commands.remove_resource::<LoadingCollection<C>>()
+            {
+                log::debug!("Loaded asset collection {}", std::any::type_name::<C>());
+                commands.insert_resource(collection);
+            }
+        }
+        LoadState::Failed(error) => {
+            log::error!(
+                "Failed to load asset collection {}: {}",
+                std::any::type_name::<C>(),
+                error
+            );
+            drop(loading);
+            commands.remove_resource::<LoadingCollection<C>>();
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use weaver_app::{App, settings::AppSettings};
+    use weaver_task::{task_pool::TaskPool, usages::GlobalTaskPool};
+
+    use super::*;
+    use crate::{
+        AssetApp, AssetPlugin, AssetSettings, LoadFrom, PathAndFilesystem,
+        prelude::{Asset, TypedAssetCollection},
+    };
+
+    #[derive(Asset)]
+    struct Text(#[allow(dead_code)] String);
+
+    #[derive(Default)]
+    struct TextLoader;
+
+    impl LoadFrom<PathAndFilesystem> for TextLoader {
+        type Asset = Text;
+        type Settings = ();
+
+        async fn load(
+            &self,
+            source: PathAndFilesystem,
+            _settings: &(),
+            _commands: &Commands,
+        ) -> Result<Text> {
+            Ok(Text(String::from_utf8(source.read()?)?))
+        }
+    }
+
+    #[derive(TypedAssetCollection)]
+    struct TextAssets {
+        #[asset(path = "hello.txt")]
+        hello: Handle<Text>,
+        #[asset(folder = "docs")]
+        docs: Handle<AssetCollection>,
+        unloaded: Option<Handle<Text>>,
+    }
+
+    #[derive(TypedAssetCollection)]
+    struct MissingAssets {
+        #[asset(path = "missing.txt")]
+        #[allow(dead_code)]
+        missing: Handle<Text>,
+    }
+
+    #[test]
+    fn test_typed_asset_collection() {
+        GlobalTaskPool::get_or_init(TaskPool::new);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        std::fs::write(dir.join("hello.txt"), "hello").unwrap();
+        std::fs::create_dir_all(dir.join("docs/notes")).unwrap();
+        std::fs::write(dir.join("docs/a.txt"), "a").unwrap();
+        std::fs::write(dir.join("docs/notes/b.txt"), "b").unwrap();
+
+        let mut settings = AppSettings::new();
+        settings
+            .set_section(&AssetSettings {
+                roots: vec![dir.to_path_buf()],
+                watch_for_changes: false,
+                write_meta_files: false,
+                ..Default::default()
+            })
+            .unwrap();
+        let mut app = App::empty();
+        app.insert_resource(settings);
+        app.add_plugin(AssetPlugin).unwrap();
+        app.add_file_asset_loader::<TextLoader>(&["txt"]);
+        app.init_asset_collection::<TextAssets>();
+        app.init_asset_collection::<MissingAssets>();
+        app.init();
+
+        let start = Instant::now();
+        while !app.has_resource::<TextAssets>()
+            || app.has_resource::<LoadingCollection<MissingAssets>>()
+        {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "asset collections never resolved"
+            );
+            app.update();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // a collection with an asset that failed to load is dropped instead of inserted
+        assert!(!app.has_resource::<MissingAssets>());
+
+        let world = app.main_app().world();
+        let assets = world.get_resource::<TextAssets>().unwrap();
+        assert!(assets.unloaded.is_none());
+        assert_eq!(assets.handles().len(), 2);
+        assert!(
+            assets
+                .load_state(&world.get_resource::<AssetLoadStatus>().unwrap())
+                .is_loaded()
+        );
+        let texts = world.get_resource::<crate::Assets<Text>>().unwrap();
+        assert!(texts.contains(&assets.hello));
+        let mut collections = world
+            .get_resource_mut::<crate::Assets<AssetCollection>>()
+            .unwrap();
+        let docs = collections.get(&assets.docs).unwrap();
+        assert_eq!(docs.handles::<Text>().count(), 2);
+        assert!(docs.get::<Text>("notes/b.txt").is_some());
+    }
+}
@@ -0,0 +1,287 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use weaver_ecs::{
+    prelude::{Commands, Res},
+    world::World,
+};
+use weaver_util::prelude::*;
+
+use crate::{AssetLoadQueue, AssetLoadRequest, Handle, LoadFrom, LoadSource, UntypedHandle};
+
+type ReloadFn = Arc<dyn Fn(&World) + Send + Sync>;
+
+/// Canonicalizes a path, or its directory if the file doesn't exist (yet), like a meta file that may be created later.
+fn canonicalize(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| match (path.parent(), path.file_name()) {
+        (Some(dir), Some(file_name)) => std::fs::canonicalize(dir)
+            .map(|dir| dir.join(file_name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    })
+}
+
+/// Maps files on disk to the assets that were loaded from them.
+#[derive(Default)]
+pub struct WatchedAssets {
+    assets: Lock<FxHashMap<PathBuf, Vec<(UntypedHandle, ReloadFn)>>>,
+    /// Files registered since the watcher last checked that their directories are watched.
+    new_files: Lock<Vec<PathBuf>>,
+}
+
+impl WatchedAssets {
+    /// Reloads `handle` from `source` whenever the file at `path` changes, until it's [unwatched](Self::unwatch). The
+    /// handle should be weak, so that watching an asset doesn't keep it alive.
+    pub fn watch<L: LoadFrom<S>, S: LoadSource>(
+        &self,
+        path: impl AsRef<Path>,
+        handle: Handle<L::Asset>,
+        source: S,
+    ) {
+        let path = canonicalize(path.as_ref());
+        let untyped = handle.untyped();
+        let reload: ReloadFn = Arc::new(move |world: &World| {
+            let Some(source) = source.clone_source() else {
+                return;
+            };
+            if let Some(queue) = world.get_resource::<AssetLoadQueue<L, S>>() {
+                queue.push(AssetLoadRequest::reload(handle.clone(), source));
+            }
+        });
+
+        self.assets
+            .write()
+            .entry(path.clone())
+            .or_default()
+            .push((untyped, reload));
+        self.new_files.write().push(path);
+    }
+
+    pub fn is_watched(&self, path: impl AsRef<Path>) -> bool {
+        self.assets
+            .read()
+            .contains_key(&canonicalize(path.as_ref()))
+    }
+
+    /// Stops reloading an asset, e.g. because it was freed. Files no asset is loaded from anymore are forgotten.
+    pub fn unwatch(&self, handle: UntypedHandle) {
+        self.assets.write().retain(|_, reloads| {
+            reloads.retain(|(watched, _)| *watched != handle);
+            !reloads.is_empty()
+        });
+    }
+
+    fn reload_fns(&self, path: &Path) -> Vec<ReloadFn> {
+        self.assets
+            .read()
+            .get(path)
+            .map(|reloads| reloads.iter().map(|(_, reload)| reload.clone()).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Watches the asset roots (and the directories of assets loaded from elsewhere) for changed files.
+pub struct AssetWatcher {
+    watcher: Mutex<RecommendedWatcher>,
+    watched_dirs: Lock<Vec<PathBuf>>,
+    changed: crossbeam_channel::Receiver<PathBuf>,
+}
+
+impl AssetWatcher {
+    pub fn new(roots: &[PathBuf]) -> Result<Self> {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                    for path in event.paths {
+                        tx.send(path).ok();
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("Asset watcher error: {}", e),
+            })?;
+
+        let this = Self {
+            watcher: Mutex::new(watcher),
+            watched_dirs: Lock::new(Vec::new()),
+            changed: rx,
+        };
+
+        for root in roots.iter().filter(|root| root.is_dir()) {
+            this.watch_dir(root, RecursiveMode::Recursive)?;
+        }
+
+        Ok(this)
+    }
+
+    fn watch_dir(&self, dir: &Path, mode: RecursiveMode) -> Result<()> {
+        let dir = canonicalize(dir);
+        self.watcher.lock().unwrap().watch(&dir, mode)?;
+        log::debug!("Watching {:?} for asset changes", dir);
+        self.watched_dirs.write().push(dir);
+        Ok(())
+    }
+
+    /// Makes sure changes to the file at `path` are noticed, even if it's outside the asset roots.
+    pub fn watch_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = canonicalize(path.as_ref());
+        if self
+            .watched_dirs
+            .read()
+            .iter()
+            .any(|dir| path.starts_with(dir))
+        {
+            return Ok(());
+        }
+        let Some(dir) = path.parent() else {
+            return Ok(());
+        };
+        self.watch_dir(dir, RecursiveMode::NonRecursive)
+    }
+
+    /// Returns every file that changed since the last call, without duplicates.
+    pub fn changed_files(&self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for path in self.changed.try_iter() {
+            let path = canonicalize(&path);
+            if !changed.contains(&path) {
+                changed.push(path);
+            }
+        }
+        changed
+    }
+}
+
+/// Queues a reload for every asset whose file changed.
+pub(crate) async fn reload_changed_assets(
+    commands: Commands,
+    watcher: Res<AssetWatcher>,
+    watched_assets: Res<WatchedAssets>,
+) {
+    for path in watched_assets.new_files.write().drain(..) {
+        if let Err(e) = watcher.watch_file(&path) {
+            log::warn!("Failed to watch {:?} for changes: {}", path, e);
+        }
+    }
+
+    for path in watcher.changed_files() {
+        let reload_fns = watched_assets.reload_fns(&path);
+        if reload_fns.is_empty() {
+            continue;
+        }
+
+        log::info!("Asset file changed, reloading: {:?}", path);
+        commands.run(move |world| {
+            for reload in reload_fns {
+                reload(world);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use weaver_app::{App, settings::AppSettings};
+    use weaver_task::{task_pool::TaskPool, usages::GlobalTaskPool};
+
+    use super::*;
+    use crate::{
+        AssetApp, AssetPlugin, AssetSettings, Assets, PathAndFilesystem, server::AssetServer,
+    };
+
+    struct Text(String);
+    impl crate::Asset for Text {}
+
+    #[derive(Default)]
+    struct TextLoader;
+
+    impl LoadFrom<PathAndFilesystem> for TextLoader {
+        type Asset = Text;
+        type Settings = ();
+
+        async fn load(
+            &self,
+            source: PathAndFilesystem,
+            _settings: &(),
+            _commands: &Commands,
+        ) -> Result<Text> {
+            Ok(Text(String::from_utf8(source.read()?)?))
+        }
+    }
+
+    /// Updates the app until the text asset has the given contents.
+    fn wait_for_text(app: &mut App, handle: &Handle<Text>, text: &str) {
+        let start = Instant::now();
+        loop {
+            app.update();
+            let mut assets = app
+                .main_app()
+                .world()
+                .get_resource_mut::<Assets<Text>>()
+                .unwrap();
+            if assets.get(handle).is_some_and(|asset| asset.0 == text) {
+                return;
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "asset never became {:?}",
+                text
+            );
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_reload_changed_file() {
+        GlobalTaskPool::get_or_init(TaskPool::new);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        std::fs::write(dir.join("hello.txt"), "hello").unwrap();
+
+        let mut settings = AppSettings::new();
+        settings
+            .set_section(&AssetSettings {
+                roots: vec![dir.to_path_buf()],
+                watch_for_changes: true,
+                write_meta_files: false,
+                ..Default::default()
+            })
+            .unwrap();
+        let mut app = App::empty();
+        app.insert_resource(settings);
+        app.add_plugin(AssetPlugin).unwrap();
+        app.add_file_asset_loader::<TextLoader>(&["txt"]);
+        app.init();
+
+        let handle = app
+            .main_app()
+            .world()
+            .get_resource::<AssetServer>()
+            .unwrap()
+            .load::<Text>("hello.txt")
+            .unwrap();
+        wait_for_text(&mut app, &handle, "hello");
+
+        std::fs::write(dir.join("hello.txt"), "goodbye").unwrap();
+        wait_for_text(&mut app, &handle, "goodbye");
+
+        // freeing the asset stops watching its file
+        let is_watched = |app: &App| {
+            app.main_app()
+                .world()
+                .get_resource::<WatchedAssets>()
+                .unwrap()
+                .is_watched(dir.join("hello.txt"))
+        };
+        assert!(is_watched(&app));
+        drop(handle);
+        app.update();
+        assert!(!is_watched(&app));
+    }
+}
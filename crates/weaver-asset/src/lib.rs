@@ -1,32 +1,63 @@
 use std::{
     fmt::Debug,
-    fs::File,
     future::Future,
     hash::{Hash, Hasher},
-    io::{BufReader, Read},
     marker::PhantomData,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
 };
 
-use weaver_app::{App, SubApp};
+use archive::MountedArchive;
+use collection::{
+    AssetCollection, LoadingCollection, TypedAssetCollection, resolve_asset_collection,
+};
+use hot_reload::{AssetWatcher, WatchedAssets, reload_changed_assets};
+use meta::{AssetMeta, LoaderSettings, meta_path};
+use processor::{AssetBytes, AssetProcessor, Process, ProcessedFile, ProcessedIndex, SourceStamp};
+use serde::{Deserialize, Serialize};
+use server::queue_asset_server_loads;
+use source::{AssetReader, EMBEDDED_SOURCE, EmbeddedAssets, split_source};
+use weaver_app::{App, AppStage, SubApp, plugin::Plugin, settings::SettingsSection};
 use weaver_ecs::{
     loan::{Loan, LoanMut, LoanStorage},
     prelude::{Commands, Res, ResMut, SystemStage},
-    world::{ConstructFromWorld, FromWorld},
+    world::{ConstructFromWorld, FromWorld, World},
 };
-use weaver_event::Events;
-use weaver_task::usages::GlobalTaskPool;
+use weaver_event::{Event, Events};
+use weaver_task::{unblock, usages::GlobalTaskPool};
 use weaver_util::prelude::*;
-use zip::ZipArchive;
+
+// lets the derive macros name this crate from within it, e.g. in tests
+extern crate self as weaver_asset;
+
+pub mod archive;
+pub mod collection;
+pub mod hot_reload;
+pub mod meta;
+pub mod processor;
+pub mod server;
+pub mod source;
 
 pub mod prelude {
     pub use crate::{
-        Asset, AssetLoadQueue, Assets, DirectLoader, Filesystem, Handle, LoadFrom,
-        PathAndFilesystem, UntypedHandle,
+        Asset, AssetEvent, AssetLoadQueue, AssetLoadStatus, AssetPlugin, AssetSettings, Assets,
+        DirectLoader, Filesystem, Handle, LoadFrom, LoadState, PathAndFilesystem, UntypedHandle,
+        collection::{AssetCollection, TypedAssetCollection},
+        meta::LoaderSettings,
+        server::AssetServer,
+        source::AssetReader,
     };
-    pub use weaver_asset_macros::Asset;
+    pub use weaver_asset_macros::{Asset, TypedAssetCollection};
+}
+
+/// Items the derive macros refer to, so that crates using them don't need the dependencies themselves.
+#[doc(hidden)]
+pub mod __private {
+    pub use weaver_util::prelude::Result;
 }
 
 define_atomic_id!(AssetId);
@@ -38,22 +69,48 @@ pub trait Asset: DowncastSync {
     {
         std::any::type_name::<Self>()
     }
+
+    /// Calls `visit` with the handles of the assets this asset depends on, e.g. the textures of a material, so that
+    /// [`AssetLoadStatus`] can tell when it's loaded along with its dependencies.
+    fn visit_dependencies(&self, _visit: &mut dyn FnMut(UntypedHandle)) {}
 }
 impl_downcast!(Asset);
 
 impl Asset for () {}
-impl<T: Asset> Asset for Vec<T> {}
+impl<T: Asset> Asset for Vec<T> {
+    fn visit_dependencies(&self, visit: &mut dyn FnMut(UntypedHandle)) {
+        for asset in self {
+            asset.visit_dependencies(visit);
+        }
+    }
+}
+
+/// Sends the id of an asset to its [`Assets`] once the last strong handle to it is dropped.
+struct StrongHandle {
+    id: AssetId,
+    drop_tx: crossbeam_channel::Sender<AssetId>,
+}
+
+impl Drop for StrongHandle {
+    fn drop(&mut self) {
+        // the receiver is gone if the `Assets` were dropped first, in which case there is nothing left to free
+        self.drop_tx.send(self.id).ok();
+    }
+}
 
+/// A reference to an asset in [`Assets`].
+///
+/// Strong handles keep their asset alive: once the last strong handle to an asset is dropped, it is removed from its
+/// [`Assets`] (and an [`AssetEvent::Removed`] is sent). Weak handles, like those created with [`Handle::from_raw`] or
+/// [`Handle::clone_weak`], don't keep anything alive.
 pub struct Handle<T: Asset> {
     id: AssetId,
+    strong: Option<Arc<StrongHandle>>,
     _marker: PhantomData<T>,
 }
 
 impl<T: Asset> Handle<T> {
-    pub const INVALID: Self = Self {
-        id: AssetId::INVALID,
-        _marker: PhantomData,
-    };
+    pub const INVALID: Self = Self::from_raw(AssetId::INVALID);
 
     pub fn id(&self) -> AssetId {
         self.id
@@ -63,18 +120,38 @@ impl<T: Asset> Handle<T> {
         self.into()
     }
 
+    pub fn untyped(&self) -> UntypedHandle {
+        UntypedHandle {
+            id: self.id,
+            type_id: std::any::TypeId::of::<T>(),
+        }
+    }
+
+    /// Creates a weak handle.
     pub const fn from_raw(id: AssetId) -> Self {
         Self {
             id,
+            strong: None,
             _marker: PhantomData,
         }
     }
 
+    /// Creates a weak handle.
     pub const fn from_u128(uuid: u128) -> Self {
-        Self {
-            id: AssetId::from_u128(uuid),
-            _marker: PhantomData,
-        }
+        Self::from_raw(AssetId::from_u128(uuid))
+    }
+
+    pub fn is_strong(&self) -> bool {
+        self.strong.is_some()
+    }
+
+    pub fn is_weak(&self) -> bool {
+        self.strong.is_none()
+    }
+
+    /// Returns a weak handle to the same asset.
+    pub fn clone_weak(&self) -> Self {
+        Self::from_raw(self.id)
     }
 }
 
@@ -87,14 +164,15 @@ impl<T: Asset> Debug for Handle<T> {
 }
 
 impl<T: Asset> Clone for Handle<T> {
-    #[allow(clippy::non_canonical_clone_impl)]
     fn clone(&self) -> Self {
-        *self
+        Self {
+            id: self.id,
+            strong: self.strong.clone(),
+            _marker: PhantomData,
+        }
     }
 }
 
-impl<T: Asset> Copy for Handle<T> {}
-
 impl<T: Asset> PartialEq for Handle<T> {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
@@ -122,12 +200,27 @@ impl<T: Asset> Ord for Handle<T> {
     }
 }
 
+/// A handle with its asset type erased. Untyped handles are always weak.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct UntypedHandle {
     id: AssetId,
     type_id: std::any::TypeId,
 }
 
+impl UntypedHandle {
+    pub fn id(&self) -> AssetId {
+        self.id
+    }
+
+    pub fn type_id(&self) -> std::any::TypeId {
+        self.type_id
+    }
+
+    pub fn is<T: Asset>(&self) -> bool {
+        self.type_id == std::any::TypeId::of::<T>()
+    }
+}
+
 impl<T: Asset> From<Handle<T>> for UntypedHandle {
     fn from(handle: Handle<T>) -> Self {
         Self {
@@ -142,10 +235,7 @@ impl<T: Asset> TryFrom<UntypedHandle> for Handle<T> {
 
     fn try_from(untyped_handle: UntypedHandle) -> Result<Self, Self::Error> {
         if untyped_handle.type_id == std::any::TypeId::of::<T>() {
-            Ok(Self {
-                id: untyped_handle.id,
-                _marker: PhantomData,
-            })
+            Ok(Self::from_raw(untyped_handle.id))
         } else {
             Err(anyhow!("type mismatch"))
         }
@@ -186,14 +276,74 @@ impl<T: Asset> DerefMut for AssetMut<T> {
     }
 }
 
+/// Creates strong handles for the assets of one type. Obtained from [`Assets::handle_provider`].
+#[derive(Clone)]
+pub struct AssetHandleProvider {
+    type_id: std::any::TypeId,
+    drop_tx: crossbeam_channel::Sender<AssetId>,
+}
+
+impl AssetHandleProvider {
+    /// Creates a strong handle for an asset that will be inserted later, e.g. once it's loaded.
+    pub fn reserve_handle<T: Asset>(&self) -> Handle<T> {
+        self.strong_handle(AssetId::new())
+    }
+
+    /// Like [`reserve_handle`](AssetHandleProvider::reserve_handle), for callers that don't know the asset type.
+    pub(crate) fn reserve_untyped(&self) -> (UntypedHandle, Arc<StrongHandle>) {
+        let id = AssetId::new();
+        let strong = Arc::new(StrongHandle {
+            id,
+            drop_tx: self.drop_tx.clone(),
+        });
+        let handle = UntypedHandle {
+            id,
+            type_id: self.type_id,
+        };
+        (handle, strong)
+    }
+
+    fn strong_handle<T: Asset>(&self, id: AssetId) -> Handle<T> {
+        debug_assert_eq!(self.type_id, std::any::TypeId::of::<T>());
+        Handle {
+            id,
+            strong: Some(Arc::new(StrongHandle {
+                id,
+                drop_tx: self.drop_tx.clone(),
+            })),
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn upgrade<T: Asset>(&self, strong: &Arc<StrongHandle>) -> Handle<T> {
+        debug_assert_eq!(self.type_id, std::any::TypeId::of::<T>());
+        Handle {
+            id: strong.id,
+            strong: Some(strong.clone()),
+            _marker: PhantomData,
+        }
+    }
+}
+
 pub struct Assets<T: Asset> {
     storage: FxHashMap<AssetId, LoanStorage<T>>,
+    /// Bumped whenever an asset is replaced or marked as modified, so that derived data (like render assets) can be refreshed.
+    versions: FxHashMap<AssetId, u64>,
+    handle_provider: AssetHandleProvider,
+    dropped: crossbeam_channel::Receiver<AssetId>,
 }
 
 impl<T: Asset> Default for Assets<T> {
     fn default() -> Self {
+        let (drop_tx, dropped) = crossbeam_channel::unbounded();
         Self {
             storage: FxHashMap::default(),
+            versions: FxHashMap::default(),
+            handle_provider: AssetHandleProvider {
+                type_id: std::any::TypeId::of::<T>(),
+                drop_tx,
+            },
+            dropped,
         }
     }
 }
@@ -203,54 +353,170 @@ impl<T: Asset> Assets<T> {
         Self::default()
     }
 
-    pub fn insert_manual(&mut self, asset: T, id: AssetId) -> Handle<T> {
-        self.storage.insert(id, LoanStorage::new(asset));
+    pub fn handle_provider(&self) -> AssetHandleProvider {
+        self.handle_provider.clone()
+    }
 
-        Handle {
-            id,
-            _marker: PhantomData,
+    /// Creates a strong handle for an asset that will be inserted later with [`insert_manual`](Assets::insert_manual).
+    pub fn reserve_handle(&self) -> Handle<T> {
+        self.handle_provider.reserve_handle()
+    }
+
+    /// Inserts an asset under the given id. If there already is an asset with this id, it is replaced and marked as modified.
+    ///
+    /// Returns a weak handle: assets inserted this way stay alive until they're removed, unless the id was reserved with
+    /// [`reserve_handle`](Assets::reserve_handle).
+    pub fn insert_manual(&mut self, asset: T, id: AssetId) -> Handle<T> {
+        if self.storage.insert(id, LoanStorage::new(asset)).is_some() {
+            *self.versions.entry(id).or_default() += 1;
         }
+
+        Handle::from_raw(id)
     }
 
+    /// Inserts an asset and returns a strong handle to it.
     pub fn insert(&mut self, asset: impl Into<T>) -> Handle<T> {
-        let asset = asset.into();
-        let id = AssetId::new();
-        self.storage.insert(id, LoanStorage::new(asset));
-
-        Handle {
-            id,
-            _marker: PhantomData,
-        }
+        let handle = self.reserve_handle();
+        self.storage
+            .insert(handle.id, LoanStorage::new(asset.into()));
+        handle
     }
 
-    pub fn get(&mut self, handle: Handle<T>) -> Option<AssetRef<T>> {
+    pub fn get(&mut self, handle: &Handle<T>) -> Option<AssetRef<T>> {
         self.storage.get_mut(&handle.id).map(|asset| {
             let asset = asset.loan().expect("asset is already borrowed");
             AssetRef { asset }
         })
     }
 
-    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<AssetMut<T>> {
+    /// Reads an asset without loaning it out, for systems that only need shared access to the assets. Assets that are
+    /// mutably loaned out can't be read this way.
+    pub fn peek(&self, handle: &Handle<T>) -> Option<&T> {
+        self.storage.get(&handle.id).and_then(LoanStorage::peek)
+    }
+
+    pub fn get_mut(&mut self, handle: &Handle<T>) -> Option<AssetMut<T>> {
         self.storage.get_mut(&handle.id).map(|asset| {
             let asset = asset.loan_mut().expect("asset is already borrowed");
             AssetMut { asset }
         })
     }
 
-    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+    pub fn contains(&self, handle: &Handle<T>) -> bool {
+        self.storage.contains_key(&handle.id)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = AssetId> + '_ {
+        self.storage.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+
+    /// How many times the asset has been replaced or marked as modified.
+    pub fn version(&self, handle: &Handle<T>) -> u64 {
+        self.versions.get(&handle.id).copied().unwrap_or_default()
+    }
+
+    /// Marks an asset as modified, e.g. because an asset it depends on was reloaded.
+    pub fn mark_modified(&mut self, handle: &Handle<T>) {
+        *self.versions.entry(handle.id).or_default() += 1;
+    }
+
+    pub fn remove(&mut self, handle: &Handle<T>) -> Option<T> {
+        self.versions.remove(&handle.id);
         self.storage.remove(&handle.id).map(|asset| {
             asset
                 .into_owned()
                 .unwrap_or_else(|_| panic!("asset is borrowed"))
         })
     }
+
+    /// Removes every asset whose last strong handle was dropped. Returns the (weak) handles of the removed assets, and
+    /// separately those of assets that were dropped before they were inserted, e.g. because their load failed or is
+    /// still pending.
+    pub fn remove_unused(&mut self) -> (Vec<Handle<T>>, Vec<Handle<T>>) {
+        let dropped = self.dropped.try_iter().collect::<Vec<_>>();
+        dropped
+            .into_iter()
+            .map(Handle::from_raw)
+            .partition(|handle| self.remove(handle).is_some())
+    }
+}
+
+/// The `[assets]` section of the app settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssetSettings {
+    /// Directories searched for assets, in order.
+    pub roots: Vec<PathBuf>,
+    /// Archives searched for assets after the directories, in order.
+    pub archives: Vec<PathBuf>,
+    /// Directory that shaders and their imports are loaded from.
+    pub shader_dir: PathBuf,
+    /// Watch the asset roots and reload assets whose files change.
+    pub watch_for_changes: bool,
+    /// Cache directory for processed assets, which are preferred over their source files when they're up to date.
+    pub processed_dir: PathBuf,
+    /// Process the asset roots into `processed_dir` on startup. Only files that changed are processed again.
+    pub process_on_startup: bool,
+    /// Write the default import settings of a loader to the `.meta` file of an asset when it has none yet, so that they
+    /// can be edited.
+    pub write_meta_files: bool,
+}
+
+impl Default for AssetSettings {
+    fn default() -> Self {
+        Self {
+            roots: vec![PathBuf::from("assets")],
+            archives: Vec::new(),
+            shader_dir: PathBuf::from("assets/shaders"),
+            watch_for_changes: cfg!(debug_assertions),
+            processed_dir: PathBuf::from("processed_assets"),
+            process_on_startup: false,
+            write_meta_files: true,
+        }
+    }
+}
+
+impl SettingsSection for AssetSettings {
+    const NAME: &'static str = "assets";
 }
 
-/// Virtual filesystem created from one or more directories or archives.
+/// Virtual filesystem created from one or more directories or archives, plus named sources like `embedded://` that are
+/// mounted with [`mount`](Filesystem::mount).
+///
+/// Archives can be read from any number of threads at once. Loaders should prefer the async reads of
+/// [`PathAndFilesystem`], which don't block the task pool.
 #[derive(Default)]
 pub struct Filesystem {
+    id: FilesystemId,
     roots: Vec<PathBuf>,
-    archives: Vec<Lock<ZipArchive<BufReader<File>>>>,
+    archives: Vec<MountedArchive>,
+    sources: Lock<FxHashMap<String, Arc<dyn AssetReader>>>,
+    processed: Option<ProcessedCache>,
+}
+
+/// Unique per [`Filesystem`], so that paths that aren't on disk can be told apart between filesystems.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct FilesystemId(u64);
+
+impl Default for FilesystemId {
+    fn default() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// The cache directory that processed assets are read from.
+struct ProcessedCache {
+    dir: PathBuf,
+    index: Lock<ProcessedIndex>,
 }
 
 impl Filesystem {
@@ -258,6 +524,19 @@ impl Filesystem {
         Self::default()
     }
 
+    /// Creates a filesystem from the configured asset roots and archives.
+    pub fn from_settings(settings: &AssetSettings) -> Result<Self> {
+        let mut this = Self::new();
+        for root in settings.roots.iter() {
+            this.add_root(root);
+        }
+        for archive in settings.archives.iter() {
+            this.add_archive(archive)?;
+        }
+        this.set_processed_dir(&settings.processed_dir)?;
+        Ok(this)
+    }
+
     pub fn with_root(mut self, root: impl AsRef<Path>) -> Self {
         self.add_root(root);
         self
@@ -268,20 +547,183 @@ impl Filesystem {
         Ok(self)
     }
 
+    pub fn with_processed_dir(mut self, dir: impl AsRef<Path>) -> Result<Self> {
+        self.set_processed_dir(dir)?;
+        Ok(self)
+    }
+
+    pub fn with_source(self, name: &str, reader: impl AssetReader) -> Self {
+        self.mount(name, reader);
+        self
+    }
+
+    pub(crate) fn id(&self) -> FilesystemId {
+        self.id
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Reads paths like `<name>://<path>` from `reader`, replacing any source mounted under the same name.
+    pub fn mount(&self, name: &str, reader: impl AssetReader) {
+        self.sources
+            .write()
+            .insert(name.to_string(), Arc::new(reader));
+    }
+
+    pub fn unmount(&self, name: &str) -> bool {
+        self.sources.write().remove(name).is_some()
+    }
+
+    pub fn has_source(&self, name: &str) -> bool {
+        self.sources.read().contains_key(name)
+    }
+
+    /// The source and the path within it of paths like `<name>://<path>`.
+    fn source(&self, path: &Path) -> Option<Result<(Arc<dyn AssetReader>, PathBuf)>> {
+        let (name, path) = split_source(path)?;
+        let Some(reader) = self.sources.read().get(name).cloned() else {
+            return Some(Err(anyhow!("No asset source named {:?}", name)));
+        };
+        Some(Ok((reader, path.to_path_buf())))
+    }
+
+    /// Reads processed assets from the given cache directory, see [`AssetProcessor`].
+    pub fn set_processed_dir(&mut self, dir: impl AsRef<Path>) -> Result<()> {
+        let dir = dir.as_ref().to_path_buf();
+        let index = ProcessedIndex::load(&dir)?;
+        self.processed = Some(ProcessedCache {
+            dir,
+            index: Lock::new(index),
+        });
+        Ok(())
+    }
+
+    /// Reads the index of the processed assets again, e.g. after the [`AssetProcessor`] ran.
+    pub fn reload_processed_index(&self) -> Result<()> {
+        if let Some(processed) = self.processed.as_ref() {
+            *processed.index.write() = ProcessedIndex::load(&processed.dir)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the artifact that `P` made from a file if it's up to date, or the file itself otherwise.
+    pub fn read_processed<P: Process>(&self, path: impl AsRef<Path>) -> Result<AssetBytes> {
+        let path = path.as_ref();
+        let read_source = || self.read_sub_path(path).map(AssetBytes::Source);
+
+        // only the asset roots and packed archives are processed
+        if split_source(path).is_some() {
+            return read_source();
+        }
+        let in_cache_dir = self
+            .processed
+            .as_ref()
+            .and_then(|processed| processed.index.read().get(path, P::KIND).cloned())
+            .map(|file| (file, None));
+        let Some((file, archive)) = in_cache_dir.or_else(|| {
+            self.archives.iter().find_map(|archive| {
+                let file = archive.processed.as_ref()?.get(path, P::KIND)?;
+                Some((file.clone(), Some(archive)))
+            })
+        }) else {
+            return read_source();
+        };
+
+        if !self.is_processed_up_to_date::<P>(path, &file) {
+            log::debug!("Processed asset is out of date: {:?}", path);
+            return read_source();
+        }
+
+        let artifact = match archive {
+            Some(archive) => archive.read_artifact(&file.artifact),
+            None => {
+                let dir = &self.processed.as_ref().unwrap().dir;
+                std::fs::read(dir.join(&file.artifact)).map_err(Error::from)
+            }
+        };
+        match artifact {
+            Ok(artifact) => Ok(AssetBytes::Processed(artifact)),
+            Err(e) => {
+                log::warn!("Failed to read processed asset for {:?}: {}", path, e);
+                read_source()
+            }
+        }
+    }
+
+    /// Whether an artifact was made from the current source file with its current settings. A source file on disk with
+    /// the size and modification time it was processed with isn't read and hashed again.
+    fn is_processed_up_to_date<P: Process>(&self, path: &Path, file: &ProcessedFile) -> bool {
+        if file.version != P::VERSION {
+            return false;
+        }
+
+        let settings = match self.read_sub_path(meta_path(path)) {
+            Ok(meta) => {
+                let settings = std::str::from_utf8(&meta)
+                    .map_err(Error::from)
+                    .and_then(AssetMeta::from_toml)
+                    .and_then(|meta| meta.settings::<P::Settings>());
+                match settings {
+                    Ok(settings) => settings,
+                    Err(_) => return false,
+                }
+            }
+            Err(_) => None,
+        };
+        let settings_up_to_date = match settings {
+            Some(settings) => processor::settings_hash::<P>(&settings)
+                .is_ok_and(|settings_hash| settings_hash == file.settings_hash),
+            // the defaults the processor was added with aren't known here, so they're assumed to be unchanged
+            None => !file.settings_in_meta,
+        };
+        if !settings_up_to_date {
+            return false;
+        }
+
+        let stamp = self.full_path(path).and_then(SourceStamp::of);
+        if stamp.is_some() && stamp == file.source_stamp {
+            return true;
+        }
+        // only the artifact may be shipped, otherwise it must have been made from the current source
+        self.read_sub_path(path).map_or(true, |source| {
+            processor::hash_source(&source) == file.source_hash
+        })
+    }
+
+    /// Returns the path on disk of a file in one of the root directories. Files in archives have no path on disk.
+    pub fn full_path(&self, path: impl AsRef<Path>) -> Option<PathBuf> {
+        let path = path.as_ref();
+        if let Some(source) = self.source(path) {
+            let (reader, path) = source.ok()?;
+            return reader.full_path(&path);
+        }
+        self.roots
+            .iter()
+            .map(|root| root.join(path))
+            .find(|full_path| full_path.exists())
+    }
+
     pub fn add_root(&mut self, root: impl AsRef<Path>) {
         self.roots.push(root.as_ref().to_path_buf());
     }
 
+    /// Adds a zip archive, e.g. one made by an [`AssetPacker`](archive::AssetPacker). Files and processed assets are
+    /// looked up in its manifest if it has one.
     pub fn add_archive(&mut self, archive: impl AsRef<Path>) -> Result<()> {
-        let archive = File::open(archive)?;
-        let archive = BufReader::new(archive);
-        let archive = ZipArchive::new(archive)?;
-        self.archives.push(Lock::new(archive));
+        self.archives.push(MountedArchive::open(archive)?);
         Ok(())
     }
 
+    /// Lists the entries of a directory. In the asset roots, those are the files and subdirectories directly in the
+    /// directory, as full paths on disk. Archives and named sources list the files in the directory and its
+    /// subdirectories.
     pub fn read_dir(&self, dir_path: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
         let dir_path = dir_path.as_ref();
+        if let Some(files) = self.read_source_dir(dir_path) {
+            return files;
+        }
         for root in self.roots.iter() {
             let full_path = root.join(dir_path);
             if full_path.exists() {
@@ -292,20 +734,54 @@ impl Filesystem {
                 return Ok(entries);
             }
         }
+        self.read_archive_dir(dir_path)
+    }
 
-        let mut files = Vec::new();
-
-        for archive in self.archives.iter() {
-            let mut archive = archive.write();
-
-            for i in 0..archive.len() {
-                let file = archive.by_index(i)?;
-                let path = file.enclosed_name().unwrap();
-                if path.starts_with(dir_path) {
-                    files.push(path);
-                }
+    /// Lists the files in a directory and its subdirectories. The paths are relative to the filesystem (so they can be
+    /// passed to [`read_sub_path`](Filesystem::read_sub_path)), or `<name>://<path>` for named sources.
+    pub fn read_dir_recursive(&self, dir_path: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        let dir_path = dir_path.as_ref();
+        if let Some(files) = self.read_source_dir(dir_path) {
+            return files;
+        }
+        for root in self.roots.iter() {
+            let full_path = root.join(dir_path);
+            if full_path.is_dir() {
+                let mut files = Vec::new();
+                processor::collect_files(&full_path, &mut files)?;
+                let mut files = files
+                    .into_iter()
+                    .filter_map(|file| file.strip_prefix(root).ok().map(Path::to_path_buf))
+                    .collect::<Vec<_>>();
+                files.sort();
+                return Ok(files);
             }
         }
+        self.read_archive_dir(dir_path)
+    }
+
+    /// Lists the files below a directory of a named source, or `None` if the path isn't in one.
+    fn read_source_dir(&self, dir_path: &Path) -> Option<Result<Vec<PathBuf>>> {
+        let (reader, path) = match self.source(dir_path)? {
+            Ok(source) => source,
+            Err(e) => return Some(Err(e)),
+        };
+        let (name, _) = split_source(dir_path).unwrap();
+        Some(reader.read_dir(&path).map(|files| {
+            files
+                .into_iter()
+                .map(|file| PathBuf::from(format!("{}://{}", name, file.display())))
+                .collect()
+        }))
+    }
+
+    /// Lists the files below a directory of the archives.
+    fn read_archive_dir(&self, dir_path: &Path) -> Result<Vec<PathBuf>> {
+        let files = self
+            .archives
+            .iter()
+            .flat_map(|archive| archive.files_in(dir_path))
+            .collect::<Vec<_>>();
 
         if !files.is_empty() {
             return Ok(files);
@@ -316,6 +792,9 @@ impl Filesystem {
 
     pub fn exists(&self, path: impl AsRef<Path>) -> bool {
         let path = path.as_ref();
+        if let Some(source) = self.source(path) {
+            return source.is_ok_and(|(reader, path)| reader.exists(&path));
+        }
         for root in self.roots.iter() {
             let full_path = root.join(path);
             if full_path.exists() {
@@ -323,18 +802,15 @@ impl Filesystem {
             }
         }
 
-        for archive in self.archives.iter() {
-            let mut archive = archive.write();
-            if archive.by_name(path.as_os_str().to_str().unwrap()).is_ok() {
-                return true;
-            };
-        }
-
-        false
+        self.archives.iter().any(|archive| archive.exists(path))
     }
 
     pub fn read_sub_path(&self, path: impl AsRef<Path>) -> Result<Vec<u8>> {
         let path = path.as_ref();
+        if let Some(source) = self.source(path) {
+            let (reader, path) = source?;
+            return reader.read(&path);
+        }
         for root in self.roots.iter() {
             let full_path = root.join(path);
             if full_path.exists() {
@@ -343,12 +819,9 @@ impl Filesystem {
         }
 
         for archive in self.archives.iter() {
-            let mut archive = archive.write();
-            if let Ok(mut sub_path) = archive.by_name(path.as_os_str().to_str().unwrap()) {
-                let mut buf = Vec::new();
-                sub_path.read_to_end(&mut buf)?;
-                return Ok(buf);
-            };
+            if let Some(bytes) = archive.read(path)? {
+                return Ok(bytes);
+            }
         }
 
         Err(anyhow!("Failed to read sub path: {:?}", path))
@@ -372,6 +845,22 @@ impl PathAndFilesystem {
     pub fn read(&self) -> Result<Vec<u8>> {
         self.fs.read_sub_path(&self.path)
     }
+
+    pub fn read_processed<P: Process>(&self) -> Result<AssetBytes> {
+        self.fs.read_processed::<P>(&self.path)
+    }
+
+    /// Like [`read`](Self::read), but without blocking the task pool.
+    pub async fn read_async(&self) -> Result<Vec<u8>> {
+        let this = self.clone();
+        unblock(move || this.read()).await
+    }
+
+    /// Like [`read_processed`](Self::read_processed), but without blocking the task pool.
+    pub async fn read_processed_async<P: Process>(&self) -> Result<AssetBytes> {
+        let this = self.clone();
+        unblock(move || this.read_processed::<P>()).await
+    }
 }
 
 impl From<(PathBuf, Arc<Filesystem>)> for PathAndFilesystem {
@@ -386,17 +875,68 @@ pub trait LoadSource: Send + Sync + 'static {
     fn as_str(&self) -> Option<&str> {
         None
     }
+
+    /// The file on disk this source reads from, if any. Assets loaded from a file are reloaded when it changes.
+    fn file_path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Copies the source so that it can be loaded again. Sources that can't be copied can't be reloaded.
+    fn clone_source(&self) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// Reads the `.meta` file next to the source, which holds the import settings of its loaders.
+    fn read_meta(&self) -> Result<Option<AssetMeta>> {
+        Ok(None)
+    }
 }
 
 impl LoadSource for PathBuf {
     fn as_str(&self) -> Option<&str> {
         self.to_str()
     }
+
+    fn file_path(&self) -> Option<PathBuf> {
+        Some(self.clone())
+    }
+
+    fn read_meta(&self) -> Result<Option<AssetMeta>> {
+        let path = meta_path(self);
+        if !path.exists() {
+            return Ok(None);
+        }
+        AssetMeta::from_toml(&std::fs::read_to_string(path)?).map(Some)
+    }
+
+    fn clone_source(&self) -> Option<Self> {
+        Some(self.clone())
+    }
 }
 impl LoadSource for PathAndFilesystem {
     fn as_str(&self) -> Option<&str> {
         self.path.to_str()
     }
+
+    fn file_path(&self) -> Option<PathBuf> {
+        self.fs.full_path(&self.path)
+    }
+
+    fn read_meta(&self) -> Result<Option<AssetMeta>> {
+        let path = meta_path(&self.path);
+        if !self.fs.exists(&path) {
+            return Ok(None);
+        }
+        let meta = self.fs.read_sub_path(path)?;
+        AssetMeta::from_toml(std::str::from_utf8(&meta)?).map(Some)
+    }
+
+    fn clone_source(&self) -> Option<Self> {
+        Some(self.clone())
+    }
 }
 impl LoadSource for Vec<u8> {}
 impl LoadSource for BoxedAsset {}
@@ -404,10 +944,13 @@ impl<T: Asset> LoadSource for T {}
 
 pub trait LoadFrom<S: LoadSource>: ConstructFromWorld + Send + Sync + 'static {
     type Asset: Asset;
+    /// Import settings read from the meta file of the source, see [`LoaderSettings`].
+    type Settings: LoaderSettings;
 
     fn load(
         &self,
         source: S,
+        settings: &Self::Settings,
         commands: &Commands,
     ) -> impl Future<Output = Result<Self::Asset>> + Send + Sync;
 }
@@ -423,8 +966,9 @@ impl<T: Asset> Default for DirectLoader<T> {
 
 impl<T: Asset> LoadFrom<BoxedAsset> for DirectLoader<T> {
     type Asset = T;
+    type Settings = ();
 
-    async fn load(&self, source: BoxedAsset, _commands: &Commands) -> Result<T> {
+    async fn load(&self, source: BoxedAsset, _settings: &(), _commands: &Commands) -> Result<T> {
         source
             .downcast()
             .map_err(|_| anyhow!("failed to downcast asset"))
@@ -434,7 +978,9 @@ impl<T: Asset> LoadFrom<BoxedAsset> for DirectLoader<T> {
 
 impl<T: Asset> LoadFrom<T> for DirectLoader<T> {
     type Asset = T;
-    async fn load(&self, source: T, _commands: &Commands) -> Result<T> {
+    type Settings = ();
+
+    async fn load(&self, source: T, _settings: &(), _commands: &Commands) -> Result<T> {
         Ok(source)
     }
 }
@@ -442,15 +988,33 @@ impl<T: Asset> LoadFrom<T> for DirectLoader<T> {
 pub struct AssetLoadRequest<T: Asset, S: LoadSource> {
     handle: Handle<T>,
     source: S,
+    reload: bool,
 }
 
 impl<T: Asset, S: LoadSource> AssetLoadRequest<T, S> {
     pub fn new(handle: Handle<T>, source: S) -> Self {
-        Self { handle, source }
+        Self {
+            handle,
+            source,
+            reload: false,
+        }
     }
 
-    pub fn handle(&self) -> Handle<T> {
-        self.handle
+    /// Loads the asset again and replaces the already loaded asset of the handle.
+    pub fn reload(handle: Handle<T>, source: S) -> Self {
+        Self {
+            handle,
+            source,
+            reload: true,
+        }
+    }
+
+    pub fn is_reload(&self) -> bool {
+        self.reload
+    }
+
+    pub fn handle(&self) -> &Handle<T> {
+        &self.handle
     }
 
     pub fn source(&self) -> &S {
@@ -468,39 +1032,52 @@ impl<T: Asset, S: LoadSource> Debug for AssetLoadRequest<T, S> {
         f.debug_struct("AssetLoadRequest")
             .field("handle", &self.handle)
             .field("source", &source)
+            .field("reload", &self.reload)
             .finish()
     }
 }
 
 pub struct AssetLoadQueue<L: LoadFrom<S>, S: LoadSource> {
     queue: Lock<Vec<AssetLoadRequest<L::Asset, S>>>,
+    handle_provider: AssetHandleProvider,
+    load_status: AssetLoadStatus,
     _marker: PhantomData<L>,
 }
 
-impl<L: LoadFrom<S>, S: LoadSource> Default for AssetLoadQueue<L, S> {
-    fn default() -> Self {
-        Self {
-            queue: Lock::new(Vec::new()),
-            _marker: PhantomData,
-        }
+impl<L: LoadFrom<S>, S: LoadSource> ConstructFromWorld for AssetLoadQueue<L, S> {
+    fn from_world(world: &World) -> Self {
+        let assets = world
+            .get_resource::<Assets<L::Asset>>()
+            .expect("assets must be added before their loaders");
+        let load_status = world
+            .get_resource::<AssetLoadStatus>()
+            .expect("asset load status must be added before asset loaders");
+        Self::new(assets.handle_provider(), load_status.clone())
     }
 }
 
 impl<L: LoadFrom<S>, S: LoadSource> AssetLoadQueue<L, S> {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(handle_provider: AssetHandleProvider, load_status: AssetLoadStatus) -> Self {
+        Self {
+            queue: Lock::new(Vec::new()),
+            handle_provider,
+            load_status,
+            _marker: PhantomData,
+        }
     }
 
     pub fn enqueue(&self, source: impl Into<S>) -> Handle<L::Asset> {
-        let handle = Handle::from_raw(AssetId::new());
-        self.queue.write().push(AssetLoadRequest {
-            handle,
-            source: source.into(),
-        });
+        let handle = self.handle_provider.reserve_handle();
+        self.push(AssetLoadRequest::new(handle.clone(), source.into()));
         handle
     }
 
+    /// Queues a request, marking its asset as [`Loading`](LoadState::Loading) unless it's a reload.
     pub fn push(&self, request: AssetLoadRequest<L::Asset, S>) {
+        if !request.reload {
+            self.load_status
+                .set_load_state(&request.handle, LoadState::Loading);
+        }
         self.queue.write().push(request);
     }
 
@@ -527,6 +1104,10 @@ pub trait AssetCommands {
         source: impl Into<S> + Send + Sync + 'static,
     ) -> Handle<L::Asset>;
     fn lazy_load_asset_direct<T: Asset>(&self, asset: T) -> Handle<T>;
+    /// Loads a file through the [`AssetServer`](server::AssetServer).
+    fn load_asset<T: Asset>(&self, path: impl AsRef<Path>) -> Result<Handle<T>>;
+    /// Loads a file from the given filesystem through the [`AssetServer`](server::AssetServer).
+    fn load_asset_from<T: Asset>(&self, source: PathAndFilesystem) -> Result<Handle<T>>;
 }
 
 impl AssetCommands for Commands {
@@ -545,43 +1126,105 @@ impl AssetCommands for Commands {
     fn lazy_load_asset_direct<T: Asset>(&self, asset: T) -> Handle<T> {
         self.lazy_load_asset::<DirectLoader<T>, T>(asset)
     }
+
+    fn load_asset<T: Asset>(&self, path: impl AsRef<Path>) -> Result<Handle<T>> {
+        let path = path.as_ref().to_path_buf();
+        self.run(move |world| {
+            world
+                .get_resource::<server::AssetServer>()
+                .ok_or_else(|| anyhow!("AssetServer resource not found"))?
+                .load(path)
+        })
+    }
+
+    fn load_asset_from<T: Asset>(&self, source: PathAndFilesystem) -> Result<Handle<T>> {
+        self.run(move |world| {
+            world
+                .get_resource::<server::AssetServer>()
+                .ok_or_else(|| anyhow!("AssetServer resource not found"))?
+                .load_from(source)
+        })
+    }
 }
 
-#[derive(Debug, Copy, PartialEq, Eq, Hash)]
-pub struct AssetLoaded<T: Asset> {
-    id: AssetId,
-    _marker: PhantomData<T>,
+/// Sent by [`Assets`] when one of its assets is loaded, replaced or removed. The handles are weak.
+pub enum AssetEvent<T: Asset> {
+    /// The asset was loaded for the first time.
+    Loaded(Handle<T>),
+    /// An already loaded asset was replaced, e.g. because its file changed on disk.
+    Modified(Handle<T>),
+    /// The last strong handle to the asset was dropped, and it was removed.
+    Removed(Handle<T>),
 }
 
-impl<T: Asset> Clone for AssetLoaded<T> {
+impl<T: Asset> AssetEvent<T> {
+    pub fn handle(&self) -> &Handle<T> {
+        match self {
+            Self::Loaded(handle) | Self::Modified(handle) | Self::Removed(handle) => handle,
+        }
+    }
+
+    pub fn id(&self) -> AssetId {
+        self.handle().id()
+    }
+}
+
+impl<T: Asset> Clone for AssetEvent<T> {
     fn clone(&self) -> Self {
-        Self {
-            id: self.id,
-            _marker: PhantomData,
+        match self {
+            Self::Loaded(handle) => Self::Loaded(handle.clone()),
+            Self::Modified(handle) => Self::Modified(handle.clone()),
+            Self::Removed(handle) => Self::Removed(handle.clone()),
         }
     }
 }
 
-impl<T: Asset> AssetLoaded<T> {
-    pub fn new(id: AssetId) -> Self {
-        Self {
-            id,
-            _marker: PhantomData,
+impl<T: Asset> Debug for AssetEvent<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Loaded(handle) => f.debug_tuple("Loaded").field(handle).finish(),
+            Self::Modified(handle) => f.debug_tuple("Modified").field(handle).finish(),
+            Self::Removed(handle) => f.debug_tuple("Removed").field(handle).finish(),
         }
     }
+}
+
+/// How far along loading an asset is.
+#[derive(Debug, Clone, Default)]
+pub enum LoadState {
+    /// The asset isn't tracked, e.g. because it was inserted directly into its [`Assets`] or was removed.
+    #[default]
+    NotLoaded,
+    /// The asset is queued for loading.
+    Loading,
+    Loaded,
+    /// The loader returned an error.
+    Failed(Arc<Error>),
+}
+
+impl LoadState {
+    pub fn is_loaded(&self) -> bool {
+        matches!(self, Self::Loaded)
+    }
 
-    pub fn id(&self) -> AssetId {
-        self.id
+    pub fn is_loading(&self) -> bool {
+        matches!(self, Self::Loading)
     }
 
-    pub fn handle(&self) -> Handle<T> {
-        Handle::from_raw(self.id)
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Self::Failed(_))
     }
 }
 
-#[derive(Default)]
+/// The [`LoadState`] and dependencies of every asset loaded through a load queue or the
+/// [`AssetServer`](server::AssetServer).
+///
+/// Clones share the same state, so that load queues can mark their assets as loading as soon as they're queued.
+#[derive(Default, Clone)]
 pub struct AssetLoadStatus {
-    load_status: Lock<FxHashMap<AssetId, bool>>,
+    load_states: Arc<Lock<FxHashMap<AssetId, LoadState>>>,
+    /// The direct dependencies of every loaded asset, as reported by [`Asset::visit_dependencies`].
+    dependencies: Arc<Lock<FxHashMap<AssetId, Vec<AssetId>>>>,
 }
 
 impl AssetLoadStatus {
@@ -589,16 +1232,98 @@ impl AssetLoadStatus {
         Self::default()
     }
 
-    pub fn manually_set_loaded<T: Asset>(&self, handle: Handle<T>) {
-        self.load_status.write().insert(handle.id, true);
+    pub fn set_load_state<T: Asset>(&self, handle: &Handle<T>, state: LoadState) {
+        self.set_load_state_by_id(handle.id, state);
     }
 
-    pub fn is_loaded<T: Asset>(&self, handle: Handle<T>) -> bool {
-        self.load_status
+    pub(crate) fn set_load_state_by_id(&self, id: AssetId, state: LoadState) {
+        self.load_states.write().insert(id, state);
+    }
+
+    pub fn manually_set_loaded<T: Asset>(&self, handle: &Handle<T>) {
+        self.set_load_state(handle, LoadState::Loaded);
+    }
+
+    pub fn set_dependencies<T: Asset>(
+        &self,
+        handle: &Handle<T>,
+        dependencies: impl IntoIterator<Item = UntypedHandle>,
+    ) {
+        let dependencies = dependencies.into_iter().map(|dependency| dependency.id);
+        self.dependencies
+            .write()
+            .insert(handle.id, dependencies.collect());
+    }
+
+    /// The direct dependencies of an asset.
+    pub fn dependencies<T: Asset>(&self, handle: &Handle<T>) -> Vec<AssetId> {
+        self.dependencies
             .read()
             .get(&handle.id)
-            .copied()
-            .unwrap_or(false)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn forget<T: Asset>(&self, handle: &Handle<T>) {
+        self.load_states.write().remove(&handle.id);
+        self.dependencies.write().remove(&handle.id);
+    }
+
+    pub fn load_state<T: Asset>(&self, handle: &Handle<T>) -> LoadState {
+        self.load_states
+            .read()
+            .get(&handle.id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn is_loaded<T: Asset>(&self, handle: &Handle<T>) -> bool {
+        self.load_state(handle).is_loaded()
+    }
+
+    /// The load state of an asset together with all of its dependencies, recursively.
+    ///
+    /// This is [`Failed`](LoadState::Failed) if any of them failed, [`Loading`](LoadState::Loading) if any of them is
+    /// still loading, and [`Loaded`](LoadState::Loaded) once all of them are loaded. Dependencies that aren't tracked,
+    /// like assets inserted directly into their [`Assets`], count as loaded.
+    pub fn recursive_load_state<T: Asset>(&self, handle: &Handle<T>) -> LoadState {
+        self.recursive_load_state_by_id(handle.id)
+    }
+
+    pub(crate) fn recursive_load_state_by_id(&self, id: AssetId) -> LoadState {
+        let load_states = self.load_states.read();
+        let dependencies = self.dependencies.read();
+
+        match load_states.get(&id) {
+            None | Some(LoadState::NotLoaded) => return LoadState::NotLoaded,
+            Some(LoadState::Failed(error)) => return LoadState::Failed(error.clone()),
+            _ => {}
+        }
+
+        let mut state = LoadState::Loaded;
+        let mut visited = FxHashSet::default();
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+
+            match load_states.get(&id) {
+                Some(LoadState::Failed(error)) => return LoadState::Failed(error.clone()),
+                Some(LoadState::Loading) => state = LoadState::Loading,
+                _ => {}
+            }
+
+            if let Some(dependencies) = dependencies.get(&id) {
+                stack.extend(dependencies.iter().copied());
+            }
+        }
+
+        state
+    }
+
+    pub fn is_loaded_with_dependencies<T: Asset>(&self, handle: &Handle<T>) -> bool {
+        self.recursive_load_state(handle).is_loaded()
     }
 }
 
@@ -608,9 +1333,28 @@ pub struct AssetLoad;
 pub trait AssetApp {
     fn add_asset<T: Asset>(&mut self) -> &mut Self;
     fn add_asset_loader<L: LoadFrom<S>, S: LoadSource>(&mut self) -> &mut Self;
+    /// Adds a loader for files with the given extensions, used by the [`AssetServer`](server::AssetServer).
+    fn add_file_asset_loader<L: LoadFrom<PathAndFilesystem>>(
+        &mut self,
+        extensions: &[&str],
+    ) -> &mut Self;
     fn add_asset_load_dependency<L: LoadFrom<S>, S: LoadSource, DL: LoadFrom<DS>, DS: LoadSource>(
         &mut self,
     ) -> &mut Self;
+    /// Adds a processor for files with the given extensions, used by the [`AssetProcessor`].
+    fn add_asset_processor<P: Process>(
+        &mut self,
+        extensions: &[&str],
+        processor: P,
+        settings: P::Settings,
+    ) -> &mut Self;
+    /// Mounts a source in the filesystem of the [`AssetServer`](server::AssetServer), so that its files are loaded from
+    /// `<name>://<path>`.
+    fn add_asset_source(&mut self, name: &str, reader: impl AssetReader) -> &mut Self;
+    /// Adds a file to the [`EMBEDDED_SOURCE`], usually through [`embedded_asset!`].
+    fn embed_asset(&mut self, path: impl AsRef<Path>, bytes: &'static [u8]) -> &mut Self;
+    /// Requests the assets of a collection, which is inserted as a resource once all of them are loaded.
+    fn init_asset_collection<C: TypedAssetCollection>(&mut self) -> &mut Self;
 }
 
 impl AssetApp for SubApp {
@@ -621,6 +1365,15 @@ impl AssetApp for SubApp {
         if !self.world().has_resource::<AssetLoadStatus>() {
             self.world_mut().init_resource::<AssetLoadStatus>();
         }
+        if !self.world().has_resource::<Events<AssetEvent<T>>>() {
+            add_asset_event::<AssetEvent<T>>(self);
+
+            if !self.world().has_system_stage(AssetLoad) {
+                self.world_mut().push_update_stage(AssetLoad);
+            }
+            self.world_mut()
+                .add_system(free_unused_assets::<T>, AssetLoad);
+        }
         if !self
             .world()
             .has_resource::<AssetLoadQueue<DirectLoader<T>, BoxedAsset>>()
@@ -638,11 +1391,11 @@ impl AssetApp for SubApp {
 
     fn add_asset_loader<L: LoadFrom<S>, S: LoadSource>(&mut self) -> &mut Self {
         if !self.world().has_resource::<AssetLoadQueue<L, S>>() {
+            if !self.world().has_resource::<Assets<L::Asset>>() {
+                self.add_asset::<L::Asset>();
+            }
             self.world_mut().init_resource::<AssetLoadQueue<L, S>>();
 
-            self.world_mut()
-                .init_resource::<Events<AssetLoaded<L::Asset>>>();
-
             if !self.world().has_system_stage(AssetLoad) {
                 self.world_mut().push_update_stage(AssetLoad);
             }
@@ -653,6 +1406,30 @@ impl AssetApp for SubApp {
         self
     }
 
+    fn add_file_asset_loader<L: LoadFrom<PathAndFilesystem>>(
+        &mut self,
+        extensions: &[&str],
+    ) -> &mut Self {
+        self.add_asset_loader::<L, PathAndFilesystem>();
+        ensure_asset_server(self);
+
+        let handle_provider = self
+            .world()
+            .get_resource::<Assets<L::Asset>>()
+            .unwrap()
+            .handle_provider();
+        self.world()
+            .get_resource::<server::AssetServer>()
+            .unwrap()
+            .register_loader::<L>(extensions, handle_provider);
+        self.world_mut().order_systems(
+            queue_asset_server_loads,
+            load_all_assets::<L, PathAndFilesystem>,
+            AssetLoad,
+        );
+        self
+    }
+
     fn add_asset_load_dependency<
         L: LoadFrom<S>,
         S: LoadSource,
@@ -668,6 +1445,133 @@ impl AssetApp for SubApp {
         );
         self
     }
+
+    fn add_asset_processor<P: Process>(
+        &mut self,
+        extensions: &[&str],
+        processor: P,
+        settings: P::Settings,
+    ) -> &mut Self {
+        if !self.world().has_resource::<AssetProcessor>() {
+            self.world_mut().init_resource::<AssetProcessor>();
+        }
+        if let Err(e) = self
+            .world()
+            .get_resource_mut::<AssetProcessor>()
+            .unwrap()
+            .add_processor(extensions, processor, settings)
+        {
+            log::error!("Failed to add asset processor: {}", e);
+        }
+        self
+    }
+
+    fn add_asset_source(&mut self, name: &str, reader: impl AssetReader) -> &mut Self {
+        ensure_asset_server(self);
+        self.world()
+            .get_resource::<server::AssetServer>()
+            .unwrap()
+            .filesystem()
+            .mount(name, reader);
+        self
+    }
+
+    fn embed_asset(&mut self, path: impl AsRef<Path>, bytes: &'static [u8]) -> &mut Self {
+        if !self.world().has_resource::<EmbeddedAssets>() {
+            self.world_mut().init_resource::<EmbeddedAssets>();
+        }
+        self.world()
+            .get_resource::<EmbeddedAssets>()
+            .unwrap()
+            .insert(path, bytes);
+        self
+    }
+
+    fn init_asset_collection<C: TypedAssetCollection>(&mut self) -> &mut Self {
+        ensure_asset_server(self);
+        let collection = C::load(&self.world().get_resource::<server::AssetServer>().unwrap());
+        match collection {
+            Ok(collection) => {
+                self.world_mut()
+                    .insert_resource(LoadingCollection(collection));
+                self.world_mut()
+                    .add_system(resolve_asset_collection::<C>, AssetLoad);
+            }
+            Err(e) => log::error!(
+                "Failed to load asset collection {}: {}",
+                std::any::type_name::<C>(),
+                e
+            ),
+        }
+        self
+    }
+}
+
+/// Adds an [`AssetServer`](server::AssetServer) for the `[assets]` settings, unless the app already has one.
+fn ensure_asset_server(app: &mut SubApp) {
+    if app.world().has_resource::<server::AssetServer>() {
+        return;
+    }
+    let settings = app
+        .world()
+        .get_resource::<AssetSettings>()
+        .map(|settings| settings.clone())
+        .unwrap_or_default();
+    let server = server::AssetServer::from_settings(&settings).unwrap_or_else(|e| {
+        log::error!("Failed to open asset filesystem: {}", e);
+        server::AssetServer::new(Arc::new(Filesystem::new()))
+    });
+    init_asset_server(app, server);
+}
+
+fn init_asset_server(app: &mut SubApp, mut server: server::AssetServer) {
+    if !app.world().has_system_stage(AssetLoad) {
+        app.world_mut().push_update_stage(AssetLoad);
+    }
+    if !app.world().has_resource::<AssetLoadStatus>() {
+        app.world_mut().init_resource::<AssetLoadStatus>();
+    }
+    server.load_status = app
+        .world()
+        .get_resource::<AssetLoadStatus>()
+        .unwrap()
+        .clone();
+    if !app.world().has_resource::<EmbeddedAssets>() {
+        app.world_mut().init_resource::<EmbeddedAssets>();
+    }
+    let embedded = app
+        .world()
+        .get_resource::<EmbeddedAssets>()
+        .unwrap()
+        .reader();
+    server.filesystem().mount(EMBEDDED_SOURCE, embedded);
+    app.add_asset::<AssetCollection>();
+    server.register_collections(
+        app.world()
+            .get_resource::<Assets<AssetCollection>>()
+            .unwrap()
+            .handle_provider(),
+    );
+    app.world_mut().insert_resource(server);
+    app.world_mut()
+        .add_system(queue_asset_server_loads, AssetLoad);
+    app.world_mut().order_systems(
+        queue_asset_server_loads,
+        load_all_assets::<DirectLoader<AssetCollection>, AssetCollection>,
+        AssetLoad,
+    );
+}
+
+fn add_asset_event<T: Event>(app: &mut SubApp) {
+    if app.world().has_resource::<Events<T>>() {
+        return;
+    }
+    // events are only cleared in apps that have a `FinishFrame` stage
+    if app.world().has_system_stage(AppStage::FinishFrame) {
+        app.add_event::<T>(AppStage::FinishFrame);
+    } else {
+        app.world_mut().init_resource::<Events<T>>();
+    }
 }
 
 impl AssetApp for App {
@@ -681,6 +1585,14 @@ impl AssetApp for App {
         self
     }
 
+    fn add_file_asset_loader<L: LoadFrom<PathAndFilesystem>>(
+        &mut self,
+        extensions: &[&str],
+    ) -> &mut Self {
+        self.main_app_mut().add_file_asset_loader::<L>(extensions);
+        self
+    }
+
     fn add_asset_load_dependency<
         L: LoadFrom<S>,
         S: LoadSource,
@@ -693,15 +1605,44 @@ impl AssetApp for App {
             .add_asset_load_dependency::<L, S, DL, DS>();
         self
     }
+
+    fn add_asset_processor<P: Process>(
+        &mut self,
+        extensions: &[&str],
+        processor: P,
+        settings: P::Settings,
+    ) -> &mut Self {
+        self.main_app_mut()
+            .add_asset_processor(extensions, processor, settings);
+        self
+    }
+
+    fn add_asset_source(&mut self, name: &str, reader: impl AssetReader) -> &mut Self {
+        self.main_app_mut().add_asset_source(name, reader);
+        self
+    }
+
+    fn embed_asset(&mut self, path: impl AsRef<Path>, bytes: &'static [u8]) -> &mut Self {
+        self.main_app_mut().embed_asset(path, bytes);
+        self
+    }
+
+    fn init_asset_collection<C: TypedAssetCollection>(&mut self) -> &mut Self {
+        self.main_app_mut().init_asset_collection::<C>();
+        self
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn load_all_assets<L: LoadFrom<S> + 'static, S: LoadSource>(
     commands: Commands,
     loader: FromWorld<L>,
     load_queue: Res<AssetLoadQueue<L, S>>,
     mut assets: ResMut<Assets<L::Asset>>,
-    load_events: Res<Events<AssetLoaded<L::Asset>>>,
+    events: Res<Events<AssetEvent<L::Asset>>>,
     load_status: Res<AssetLoadStatus>,
+    watched_assets: Option<Res<WatchedAssets>>,
+    settings: Option<Res<AssetSettings>>,
 ) {
     if load_queue.is_empty() {
         return;
@@ -713,15 +1654,57 @@ async fn load_all_assets<L: LoadFrom<S> + 'static, S: LoadSource>(
     let mut handles = Vec::with_capacity(n);
 
     let loader = Arc::new(loader);
+    let write_meta_files = settings.is_some_and(|settings| settings.write_meta_files);
 
     for request in load_queue.queue.write().drain(..) {
-        if assets.get(request.handle).is_some() || load_status.is_loaded(request.handle) {
+        if request.reload {
+            // the asset may have been removed since the reload was requested
+            if !assets.contains(&request.handle) {
+                continue;
+            }
+        } else if assets.contains(&request.handle) || load_status.is_loaded(&request.handle) {
             continue;
         }
+
+        if !request.reload
+            && let Some(watched_assets) = watched_assets.as_ref()
+            && let Some(path) = request.source.file_path()
+            && let Some(source) = request.source.clone_source()
+        {
+            if !L::Settings::NAME.is_empty()
+                && let Some(meta_source) = source.clone_source()
+            {
+                watched_assets.watch::<L, S>(
+                    meta_path(&path),
+                    request.handle.clone_weak(),
+                    meta_source,
+                );
+            }
+            watched_assets.watch::<L, S>(path, request.handle.clone_weak(), source);
+        }
+
+        let AssetLoadRequest {
+            handle,
+            source,
+            reload,
+        } = request;
         let loader = loader.clone();
         let commands = commands.clone();
         let task = GlobalTaskPool::get().spawn(async move {
-            match loader.load(request.source, &commands).await {
+            // meta files are read off the task pool like the sources themselves
+            let (source, settings) = unblock(move || {
+                let settings = read_loader_settings(&source, write_meta_files);
+                (source, settings)
+            })
+            .await;
+            let settings = match settings {
+                Ok(settings) => settings,
+                Err(e) => {
+                    log::error!("Failed to read import settings of asset: {}", e);
+                    return Err(e);
+                }
+            };
+            match loader.load(source, &settings, &commands).await {
                 Ok(asset) => Ok(asset),
                 Err(e) => {
                     log::error!("Failed to load asset: {}", e);
@@ -730,14 +1713,344 @@ async fn load_all_assets<L: LoadFrom<S> + 'static, S: LoadSource>(
             }
         });
 
-        handles.push((request.handle, task));
+        // the request's handle keeps the asset alive until it's inserted
+        handles.push((handle, reload, task));
+    }
+
+    for (handle, reload, result) in handles {
+        let asset = match result.await {
+            Ok(asset) => asset,
+            // a failed reload keeps the previously loaded asset
+            Err(e) if reload => {
+                log::warn!(
+                    "Failed to reload asset {:?}, keeping the previously loaded one: {}",
+                    handle,
+                    e
+                );
+                continue;
+            }
+            Err(e) => {
+                load_status.set_load_state(&handle, LoadState::Failed(Arc::new(e)));
+                continue;
+            }
+        };
+
+        let mut dependencies = Vec::new();
+        asset.visit_dependencies(&mut |dependency| dependencies.push(dependency));
+        load_status.set_dependencies(&handle, dependencies);
+
+        assets.insert_manual(asset, handle.id);
+        if reload {
+            log::debug!("Reloaded asset: {:?}", handle);
+            events.send(AssetEvent::Modified(handle.clone_weak())).await;
+        } else {
+            load_status.manually_set_loaded(&handle);
+            events.send(AssetEvent::Loaded(handle.clone_weak())).await;
+        }
+    }
+}
+
+/// Reads the import settings of a loader from the meta file of a source, writing the defaults to it if it has none and
+/// `write_defaults` is set.
+fn read_loader_settings<T: LoaderSettings, S: LoadSource>(
+    source: &S,
+    write_defaults: bool,
+) -> Result<T> {
+    if T::NAME.is_empty() {
+        return Ok(T::default());
+    }
+
+    if let Some(settings) = source.read_meta()?.unwrap_or_default().settings::<T>()? {
+        return Ok(settings);
+    }
+
+    let settings = T::default();
+    if write_defaults && let Some(path) = source.file_path() {
+        // loaders of the same file (e.g. meshes and materials of a glTF file) can import it at the same time, so the
+        // meta file is read again under the lock to keep the sections the other loaders wrote
+        static META_WRITE_LOCK: Mutex<()> = Mutex::new(());
+        let _lock = META_WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut meta = source.read_meta()?.unwrap_or_default();
+        if let Some(settings) = meta.settings::<T>()? {
+            return Ok(settings);
+        }
+
+        let path = meta_path(path);
+        meta.set_settings(&settings)?;
+        match std::fs::write(&path, meta.to_toml()?) {
+            Ok(()) => log::debug!("Wrote default import settings to {:?}", path),
+            Err(e) => log::warn!("Failed to write meta file {:?}: {}", path, e),
+        }
+    }
+    Ok(settings)
+}
+
+/// Removes the assets whose last strong handle was dropped.
+async fn free_unused_assets<T: Asset>(
+    mut assets: ResMut<Assets<T>>,
+    events: Res<Events<AssetEvent<T>>>,
+    load_status: Option<Res<AssetLoadStatus>>,
+    watched_assets: Option<Res<WatchedAssets>>,
+) {
+    let (removed, never_inserted) = assets.remove_unused();
+    for handle in removed.iter().chain(&never_inserted) {
+        if let Some(load_status) = load_status.as_ref() {
+            load_status.forget(handle);
+        }
+        if let Some(watched_assets) = watched_assets.as_ref() {
+            watched_assets.unwatch(handle.untyped());
+        }
+    }
+    for handle in removed {
+        log::trace!("Removed unused asset: {:?}", handle);
+        events.send(AssetEvent::Removed(handle)).await;
+    }
+}
+
+/// Sets up asset loading from the `[assets]` settings section, reloads changed assets if `watch_for_changes` is set, and
+/// processes the asset roots on startup if `process_on_startup` is set.
+pub struct AssetPlugin;
+
+impl Plugin for AssetPlugin {
+    fn build(&self, app: &mut App) -> Result<()> {
+        let settings = app.settings().section::<AssetSettings>()?;
+
+        // load queues share the load status, so it must not be replaced once assets were added
+        if !app.has_resource::<AssetLoadStatus>() {
+            app.init_resource::<AssetLoadStatus>();
+        }
+        app.init_resource::<WatchedAssets>();
+
+        init_asset_server(
+            app.main_app_mut(),
+            server::AssetServer::from_settings(&settings)?,
+        );
+
+        if settings.watch_for_changes {
+            match AssetWatcher::new(&settings.roots) {
+                Ok(watcher) => {
+                    app.insert_resource(watcher);
+                    app.add_system(reload_changed_assets, AssetLoad);
+                }
+                Err(e) => log::warn!(
+                    "Failed to watch asset roots, hot reloading is disabled: {}",
+                    e
+                ),
+            }
+        }
+
+        app.insert_resource(settings);
+        Ok(())
     }
 
-    for (handle, result) in handles {
-        if let Ok(asset) = result.await {
-            assets.insert_manual(asset, handle.id);
-            load_status.manually_set_loaded(handle);
-            load_events.send(AssetLoaded::new(handle.id)).await;
+    fn finish(&self, app: &mut App) -> Result<()> {
+        let world = app.main_app().world();
+        let settings = world.get_resource::<AssetSettings>().unwrap().clone();
+        if !settings.process_on_startup {
+            return Ok(());
+        }
+        let Some(processor) = world.get_resource::<AssetProcessor>() else {
+            return Ok(());
+        };
+
+        for root in settings.roots.iter().filter(|root| root.exists()) {
+            let stats = processor.process_dir(root, &settings.processed_dir)?;
+            log::info!(
+                "Processed assets in {:?}: {} processed, {} cached, {} failed",
+                root,
+                stats.processed,
+                stats.cached,
+                stats.failed
+            );
+        }
+
+        if let Some(server) = world.get_resource::<server::AssetServer>() {
+            server.filesystem().reload_processed_index()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Text(#[allow(dead_code)] &'static str);
+    impl Asset for Text {}
+
+    #[test]
+    fn test_remove_unused() {
+        let mut assets = Assets::<Text>::new();
+        let kept = assets.insert(Text("kept"));
+        let dropped = assets.insert(Text("dropped"));
+        let manual = assets.insert_manual(Text("manual"), AssetId::new());
+        let weak = dropped.clone_weak();
+
+        let clone = dropped.clone();
+        drop(dropped);
+        assert_eq!(assets.remove_unused(), (vec![], vec![]));
+
+        drop(clone);
+        assert_eq!(assets.remove_unused(), (vec![weak.clone()], vec![]));
+
+        // handles of assets that were never inserted are reported too, so that their load states can be forgotten
+        let pending = assets.reserve_handle();
+        let pending_weak = pending.clone_weak();
+        drop(pending);
+        assert_eq!(assets.remove_unused(), (vec![], vec![pending_weak]));
+        assert!(!assets.contains(&weak));
+        assert!(assets.contains(&kept));
+        assert!(assets.contains(&manual));
+    }
+
+    #[test]
+    fn test_recursive_load_state() {
+        let assets = Assets::<Text>::new();
+        let status = AssetLoadStatus::new();
+        let model = assets.reserve_handle();
+        let texture = assets.reserve_handle();
+        let inserted = assets.reserve_handle();
+
+        assert!(matches!(
+            status.recursive_load_state(&model),
+            LoadState::NotLoaded
+        ));
+
+        status.set_load_state(&model, LoadState::Loading);
+        assert!(status.recursive_load_state(&model).is_loading());
+
+        status.set_load_state(&texture, LoadState::Loading);
+        status.manually_set_loaded(&model);
+        status.set_dependencies(&model, [texture.untyped(), inserted.untyped()]);
+        assert!(status.is_loaded(&model));
+        assert!(status.recursive_load_state(&model).is_loading());
+
+        status.manually_set_loaded(&texture);
+        assert!(status.is_loaded_with_dependencies(&model));
+
+        status.set_load_state(
+            &texture,
+            LoadState::Failed(Arc::new(anyhow!("missing file"))),
+        );
+        assert!(status.recursive_load_state(&model).is_failed());
+    }
+
+    #[test]
+    fn test_asset_settings_applied() {
+        let settings = weaver_app::settings::AppSettings::from_toml(
+            "[assets]\nroots = [\"a\", \"b\"]\nwatch_for_changes = false",
+        )
+        .unwrap();
+        let mut app = App::empty();
+        app.insert_resource(settings.clone());
+        app.add_plugin(AssetPlugin).unwrap();
+        let roots = app
+            .main_app()
+            .world()
+            .get_resource::<server::AssetServer>()
+            .unwrap()
+            .filesystem()
+            .roots()
+            .to_vec();
+        assert_eq!(roots, [PathBuf::from("a"), PathBuf::from("b")]);
+
+        // archives are opened when the plugin is built, so a missing one is an error
+        let mut settings = settings;
+        settings
+            .set("assets.archives", vec!["missing.zip".to_string()])
+            .unwrap();
+        let mut app = App::empty();
+        app.insert_resource(settings);
+        assert!(app.add_plugin(AssetPlugin).is_err());
+    }
+
+    #[test]
+    fn test_default_meta_written() {
+        #[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+        struct MeshSettings {
+            scale: f32,
+        }
+        impl LoaderSettings for MeshSettings {
+            const NAME: &'static str = "mesh";
+        }
+
+        #[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+        struct ModelSettings {
+            flip: bool,
+        }
+        impl LoaderSettings for ModelSettings {
+            const NAME: &'static str = "model";
+        }
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let path = dir.join("model.gltf");
+        std::fs::write(&path, "").unwrap();
+
+        // nothing is written unless asked to
+        read_loader_settings::<MeshSettings, _>(&path, false).unwrap();
+        assert!(!meta_path(&path).exists());
+
+        // loaders of the same file importing it at once each add their section
+        std::thread::scope(|scope| {
+            scope.spawn(|| read_loader_settings::<MeshSettings, _>(&path, true).unwrap());
+            scope.spawn(|| read_loader_settings::<ModelSettings, _>(&path, true).unwrap());
+        });
+        let meta = path.read_meta().unwrap().unwrap();
+        assert!(meta.has_settings::<MeshSettings>());
+        assert!(meta.has_settings::<ModelSettings>());
+
+        // settings that are already in the meta file are read and not overwritten
+        std::fs::write(meta_path(&path), "[mesh]\nscale = 2.0\n").unwrap();
+        let settings = read_loader_settings::<MeshSettings, _>(&path, true).unwrap();
+        assert_eq!(settings.scale, 2.0);
+        assert_eq!(
+            std::fs::read_to_string(meta_path(&path)).unwrap(),
+            "[mesh]\nscale = 2.0\n"
+        );
+    }
+
+    #[test]
+    fn test_concurrent_archive_reads() {
+        use std::io::Write;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let archive_path = dir.join("assets.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&archive_path).unwrap());
+        for i in 0..8 {
+            writer
+                .start_file(
+                    format!("data/{}.txt", i),
+                    zip::write::SimpleFileOptions::default(),
+                )
+                .unwrap();
+            writer
+                .write_all(i.to_string().repeat(1000).as_bytes())
+                .unwrap();
+        }
+        writer.finish().unwrap();
+
+        let fs = Arc::new(Filesystem::new().with_archive(&archive_path).unwrap());
+        assert!(fs.exists("data/3.txt"));
+        assert!(!fs.exists("data/8.txt"));
+        assert_eq!(fs.read_dir("data").unwrap().len(), 8);
+
+        let threads = (0..8)
+            .map(|i| {
+                let source = PathAndFilesystem::new(format!("data/{}.txt", i), fs.clone());
+                std::thread::spawn(move || {
+                    for _ in 0..16 {
+                        let bytes =
+                            weaver_task::futures_lite::future::block_on(source.read_async())
+                                .unwrap();
+                        assert_eq!(bytes, i.to_string().repeat(1000).as_bytes());
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
         }
     }
 }
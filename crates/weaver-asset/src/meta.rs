@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, de::DeserializeOwned};
+use weaver_util::prelude::*;
+
+/// Extension appended to the name of a source file to get the name of its meta file, e.g. `brick.png.meta`.
+pub const META_EXTENSION: &str = "meta";
+
+/// Import settings of a loader, read from the `[NAME]` section of the meta file next to the source file.
+///
+/// Loaders without settings use `()`, which is never read from or written to meta files.
+pub trait LoaderSettings:
+    Serialize + DeserializeOwned + Default + Clone + Send + Sync + 'static
+{
+    /// Name of the section in the meta file. Loaders (and processors) of the same file that share a section share its
+    /// settings.
+    const NAME: &'static str;
+}
+
+impl LoaderSettings for () {
+    const NAME: &'static str = "";
+}
+
+/// Returns the path of the meta file of a source file.
+pub fn meta_path(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(META_EXTENSION);
+    path.with_file_name(file_name)
+}
+
+/// The contents of a meta file: one TOML section of [`LoaderSettings`] per loader.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AssetMeta {
+    table: toml::Table,
+}
+
+impl AssetMeta {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self> {
+        Ok(Self {
+            table: toml::from_str(source)?,
+        })
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(&self.table)?)
+    }
+
+    pub fn has_settings<T: LoaderSettings>(&self) -> bool {
+        self.table.contains_key(T::NAME)
+    }
+
+    /// Reads the settings of a loader, or `None` if the meta file has no section for it. Fields missing from the section
+    /// are set to their defaults.
+    pub fn settings<T: LoaderSettings>(&self) -> Result<Option<T>> {
+        let Some(section) = self.table.get(T::NAME) else {
+            return Ok(None);
+        };
+        section
+            .clone()
+            .try_into()
+            .map(Some)
+            .map_err(|e| anyhow!("Invalid meta section [{}]: {}", T::NAME, e))
+    }
+
+    /// Replaces the settings of a loader.
+    pub fn set_settings<T: LoaderSettings>(&mut self, settings: &T) -> Result<()> {
+        self.table
+            .insert(T::NAME.to_string(), toml::Value::try_from(settings)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    struct ImageSettings {
+        linear: bool,
+        scale: f32,
+    }
+
+    impl Default for ImageSettings {
+        fn default() -> Self {
+            Self {
+                linear: false,
+                scale: 1.0,
+            }
+        }
+    }
+
+    impl LoaderSettings for ImageSettings {
+        const NAME: &'static str = "image";
+    }
+
+    #[test]
+    fn test_meta_settings() {
+        assert_eq!(
+            meta_path("textures/brick.png"),
+            PathBuf::from("textures/brick.png.meta")
+        );
+
+        let mut meta = AssetMeta::from_toml("[image]\nlinear = true\n").unwrap();
+        assert_eq!(
+            meta.settings::<ImageSettings>().unwrap(),
+            Some(ImageSettings {
+                linear: true,
+                scale: 1.0
+            })
+        );
+
+        meta.set_settings(&ImageSettings::default()).unwrap();
+        let meta = AssetMeta::from_toml(&meta.to_toml().unwrap()).unwrap();
+        assert_eq!(
+            meta.settings::<ImageSettings>().unwrap(),
+            Some(ImageSettings::default())
+        );
+
+        assert_eq!(AssetMeta::new().settings::<ImageSettings>().unwrap(), None);
+        assert!(
+            AssetMeta::from_toml("[image]\nlinear = 3\n")
+                .unwrap()
+                .settings::<ImageSettings>()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,562 @@
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+use weaver_util::prelude::*;
+
+use crate::meta::{AssetMeta, LoaderSettings, meta_path};
+
+/// Name of the file in the cache directory that maps source files to their artifacts.
+pub const INDEX_FILE: &str = "index.toml";
+
+/// Converts source files (like PNGs or glTF files) into engine-ready artifacts that are cheaper to load.
+///
+/// Artifacts are produced offline by an [`AssetProcessor`] and picked up at runtime by loaders that call
+/// [`Filesystem::read_processed`](crate::Filesystem::read_processed).
+pub trait Process: Send + Sync + 'static {
+    /// Identifies the artifacts of this processor, since a source file can be processed into several kinds of artifacts.
+    const KIND: &'static str;
+    /// Bump this whenever the artifact format changes, so that existing artifacts are rebuilt.
+    const VERSION: u32;
+
+    /// Read from the meta file of each source file like the settings of a loader. Artifacts are keyed by the TOML form of
+    /// their settings, so these must serialize to a table, i.e. be a struct.
+    type Settings: LoaderSettings;
+
+    fn process(&self, source: &[u8], settings: &Self::Settings) -> Result<Vec<u8>>;
+}
+
+/// The bytes of an asset file, as returned by [`Filesystem::read_processed`](crate::Filesystem::read_processed).
+pub enum AssetBytes {
+    /// An up-to-date artifact of the file.
+    Processed(Vec<u8>),
+    /// The source file itself, because it wasn't processed or has changed since.
+    Source(Vec<u8>),
+}
+
+/// An artifact in the cache directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessedFile {
+    pub kind: String,
+    pub version: u32,
+    /// Hash of the source file the artifact was made from, to tell whether it's stale.
+    pub source_hash: String,
+    /// Size and modification time of the source file when it was processed. While they're unchanged, the source file
+    /// isn't read and hashed again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_stamp: Option<SourceStamp>,
+    /// Hash of the processor and the settings the file was processed with, after defaults were applied, so that
+    /// writing the default settings to a meta file doesn't make the artifact stale.
+    #[serde(default)]
+    pub settings_hash: String,
+    /// Whether the settings came from the meta file rather than the defaults the processor was added with.
+    #[serde(default)]
+    pub settings_in_meta: bool,
+    /// File name of the artifact, relative to the cache directory.
+    pub artifact: String,
+}
+
+/// Maps source files (by their path relative to the asset root) to their artifacts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessedIndex {
+    pub files: BTreeMap<String, Vec<ProcessedFile>>,
+}
+
+impl ProcessedIndex {
+    /// Reads the index from a cache directory. A missing index is empty.
+    pub fn load(cache_dir: impl AsRef<Path>) -> Result<Self> {
+        let path = cache_dir.as_ref().join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, cache_dir: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(cache_dir.as_ref().join(INDEX_FILE), toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, path: impl AsRef<Path>, kind: &str) -> Option<&ProcessedFile> {
+        self.files
+            .get(&index_key(path.as_ref()))?
+            .iter()
+            .find(|file| file.kind == kind)
+    }
+
+    pub fn insert(&mut self, path: impl AsRef<Path>, file: ProcessedFile) {
+        let files = self.files.entry(index_key(path.as_ref())).or_default();
+        files.retain(|existing| existing.kind != file.kind);
+        files.push(file);
+    }
+}
+
+/// Normalizes a relative path into the form used as a key in the [`ProcessedIndex`].
+pub(crate) fn index_key(path: &Path) -> String {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => components.push(name.to_string_lossy()),
+            Component::ParentDir => {
+                components.pop();
+            }
+            _ => {}
+        }
+    }
+    components.join("/")
+}
+
+pub fn hash_source(source: &[u8]) -> String {
+    format!("{:032x}", xxhash_rust::xxh3::xxh3_128(source))
+}
+
+/// The size and modification time of a file on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceStamp {
+    pub len: u64,
+    /// Nanoseconds since the Unix epoch.
+    pub modified: u64,
+}
+
+impl SourceStamp {
+    pub fn of(path: impl AsRef<Path>) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            len: metadata.len(),
+            modified: u64::try_from(modified.as_nanos()).ok()?,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct ProcessorKey<'a, S> {
+    processor: &'a str,
+    version: u32,
+    settings: &'a S,
+}
+
+/// Identifies a processor and the settings a file is processed with.
+fn processor_key<P: Process>(settings: &P::Settings) -> Result<String> {
+    Ok(toml::to_string(&ProcessorKey {
+        processor: std::any::type_name::<P>(),
+        version: P::VERSION,
+        settings,
+    })?)
+}
+
+/// The [`ProcessedFile::settings_hash`] of files processed by `P` with the given settings.
+pub(crate) fn settings_hash<P: Process>(settings: &P::Settings) -> Result<String> {
+    Ok(hash_source(processor_key::<P>(settings)?.as_bytes()))
+}
+
+/// How many files [`AssetProcessor::process_dir`] processed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessStats {
+    /// Artifacts that were built.
+    pub processed: usize,
+    /// Artifacts that were already in the cache.
+    pub cached: usize,
+    pub failed: usize,
+}
+
+type KeyFn = dyn Fn(&AssetMeta) -> Result<(String, bool)> + Send + Sync;
+type ProcessFn = dyn Fn(&[u8], &AssetMeta) -> Result<Vec<u8>> + Send + Sync;
+
+struct ErasedProcessor {
+    kind: &'static str,
+    version: u32,
+    /// Identifies the processor and the settings of a file, so that changing either rebuilds the artifacts, and tells
+    /// whether the settings came from the meta file.
+    key: Box<KeyFn>,
+    process: Box<ProcessFn>,
+}
+
+/// Processes source files into artifacts, which are stored in a cache directory under a hash of the source file and
+/// the processor settings.
+#[derive(Default)]
+pub struct AssetProcessor {
+    /// Processors by lowercase file extension.
+    processors: FxHashMap<String, Vec<Arc<ErasedProcessor>>>,
+}
+
+impl AssetProcessor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses `processor` to process files with any of the given extensions. Files whose meta file has no settings for
+    /// the processor are processed with `settings`.
+    pub fn add_processor<P: Process>(
+        &mut self,
+        extensions: &[&str],
+        processor: P,
+        settings: P::Settings,
+    ) -> Result<&mut Self> {
+        // fail early on settings that can't be keyed
+        processor_key::<P>(&settings)?;
+
+        let default_settings = settings.clone();
+        let file_settings = move |meta: &AssetMeta| -> Result<P::Settings> {
+            Ok(meta
+                .settings::<P::Settings>()?
+                .unwrap_or_else(|| default_settings.clone()))
+        };
+        let key_settings = file_settings.clone();
+        let processor = Arc::new(ErasedProcessor {
+            kind: P::KIND,
+            version: P::VERSION,
+            key: Box::new(move |meta| {
+                let key = processor_key::<P>(&key_settings(meta)?)?;
+                Ok((key, meta.has_settings::<P::Settings>()))
+            }),
+            process: Box::new(move |source, meta| processor.process(source, &file_settings(meta)?)),
+        });
+
+        for extension in extensions {
+            let processors = self.processors.entry(extension.to_lowercase()).or_default();
+            processors.retain(|existing| existing.kind != P::KIND);
+            processors.push(processor.clone());
+        }
+        Ok(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+
+    /// Processes every file in `source_dir` that has a processor, writing the artifacts and the [`ProcessedIndex`] to
+    /// `cache_dir`. Files whose processing fails are skipped, so that they're loaded from their source instead.
+    pub fn process_dir(
+        &self,
+        source_dir: impl AsRef<Path>,
+        cache_dir: impl AsRef<Path>,
+    ) -> Result<ProcessStats> {
+        let source_dir = source_dir.as_ref();
+        let cache_dir = cache_dir.as_ref();
+        std::fs::create_dir_all(cache_dir)?;
+
+        let mut index = ProcessedIndex::load(cache_dir)?;
+        let mut stats = ProcessStats::default();
+
+        let mut files = Vec::new();
+        collect_files(source_dir, &mut files)?;
+
+        for path in files {
+            let Some(processors) = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .and_then(|extension| self.processors.get(&extension.to_lowercase()))
+            else {
+                continue;
+            };
+            let relative_path = path.strip_prefix(source_dir)?;
+            let source = std::fs::read(&path)?;
+            let source_hash = hash_source(&source);
+            let source_stamp = SourceStamp::of(&path);
+
+            let meta_bytes = std::fs::read(meta_path(&path)).ok();
+            let meta = match meta_bytes.as_deref().map(std::str::from_utf8) {
+                Some(meta) => match meta.map_err(Error::from).and_then(AssetMeta::from_toml) {
+                    Ok(meta) => meta,
+                    Err(e) => {
+                        log::warn!("Invalid meta file for {:?}: {}", relative_path, e);
+                        stats.failed += processors.len();
+                        continue;
+                    }
+                },
+                None => AssetMeta::new(),
+            };
+
+            for processor in processors {
+                let (processor_key, settings_in_meta) = match (processor.key)(&meta) {
+                    Ok(key) => key,
+                    Err(e) => {
+                        log::warn!("Failed to process {:?}: {}", relative_path, e);
+                        stats.failed += 1;
+                        continue;
+                    }
+                };
+                let mut key = source_hash.clone().into_bytes();
+                key.extend_from_slice(processor_key.as_bytes());
+                let artifact = format!("{}.{}", hash_source(&key), processor.kind);
+                let artifact_path = cache_dir.join(&artifact);
+
+                if artifact_path.exists() {
+                    stats.cached += 1;
+                } else {
+                    match (processor.process)(&source, &meta) {
+                        Ok(bytes) => {
+                            std::fs::write(&artifact_path, bytes)?;
+                            log::debug!("Processed {:?} into {:?}", relative_path, artifact);
+                            stats.processed += 1;
+                        }
+                        Err(e) => {
+                            log::warn!("Failed to process {:?}: {}", relative_path, e);
+                            stats.failed += 1;
+                            continue;
+                        }
+                    }
+                }
+
+                index.insert(
+                    relative_path,
+                    ProcessedFile {
+                        kind: processor.kind.to_string(),
+                        version: processor.version,
+                        source_hash: source_hash.clone(),
+                        source_stamp,
+                        settings_hash: hash_source(processor_key.as_bytes()),
+                        settings_in_meta,
+                        artifact,
+                    },
+                );
+            }
+        }
+
+        index.save(cache_dir)?;
+        Ok(stats)
+    }
+}
+
+pub(crate) fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Writes the little-endian binary format of artifacts.
+#[derive(Default)]
+pub struct ArtifactWriter {
+    bytes: Vec<u8>,
+}
+
+impl ArtifactWriter {
+    /// Starts an artifact with a magic number, which [`ArtifactReader::new`] checks.
+    pub fn new(magic: [u8; 4]) -> Self {
+        Self {
+            bytes: magic.to_vec(),
+        }
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Writes a length-prefixed byte slice.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_u32(bytes.len() as u32);
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Reads artifacts written with an [`ArtifactWriter`].
+pub struct ArtifactReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ArtifactReader<'a> {
+    pub fn new(bytes: &'a [u8], magic: [u8; 4]) -> Result<Self> {
+        let Some(bytes) = bytes.strip_prefix(&magic) else {
+            bail!("Artifact does not start with {:?}", magic);
+        };
+        Ok(Self { bytes })
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            bail!("Unexpected end of artifact");
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    pub fn read_bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.read_u32()? as usize;
+        self.take(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Filesystem;
+
+    struct Reverse;
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    #[serde(default)]
+    struct ReverseSettings {
+        uppercase: bool,
+    }
+
+    impl LoaderSettings for ReverseSettings {
+        const NAME: &'static str = "reverse";
+    }
+
+    impl Process for Reverse {
+        const KIND: &'static str = "reversed";
+        const VERSION: u32 = 1;
+        type Settings = ReverseSettings;
+
+        fn process(&self, source: &[u8], settings: &ReverseSettings) -> Result<Vec<u8>> {
+            let reversed = source.iter().rev();
+            if settings.uppercase {
+                Ok(reversed.map(u8::to_ascii_uppercase).collect())
+            } else {
+                Ok(reversed.copied().collect())
+            }
+        }
+    }
+
+    #[test]
+    fn test_process_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let source_dir = dir.join("assets");
+        let cache_dir = dir.join("processed");
+        std::fs::create_dir_all(source_dir.join("text")).unwrap();
+        std::fs::write(source_dir.join("text/hello.txt"), "hello").unwrap();
+        std::fs::write(source_dir.join("ignored.md"), "ignored").unwrap();
+
+        let mut processor = AssetProcessor::new();
+        processor
+            .add_processor(&["txt"], Reverse, ReverseSettings::default())
+            .unwrap();
+
+        let stats = processor.process_dir(&source_dir, &cache_dir).unwrap();
+        assert_eq!(stats.processed, 1);
+        let stats = processor.process_dir(&source_dir, &cache_dir).unwrap();
+        assert_eq!((stats.processed, stats.cached), (0, 1));
+
+        let index = ProcessedIndex::load(&cache_dir).unwrap();
+        assert!(index.get("ignored.md", Reverse::KIND).is_none());
+        let file = index.get("./text/hello.txt", Reverse::KIND).unwrap();
+        assert_eq!(file.source_hash, hash_source(b"hello"));
+        assert_eq!(
+            std::fs::read(cache_dir.join(&file.artifact)).unwrap(),
+            b"olleh"
+        );
+
+        // changing the settings makes a new artifact
+        processor
+            .add_processor(&["txt"], Reverse, ReverseSettings { uppercase: true })
+            .unwrap();
+        let stats = processor.process_dir(&source_dir, &cache_dir).unwrap();
+        assert_eq!((stats.processed, stats.cached), (1, 0));
+        let index = ProcessedIndex::load(&cache_dir).unwrap();
+        let file = index.get("text/hello.txt", Reverse::KIND).unwrap();
+        assert_eq!(
+            std::fs::read(cache_dir.join(&file.artifact)).unwrap(),
+            b"OLLEH"
+        );
+
+        // the filesystem prefers the artifact until the source changes
+        let fs = Filesystem::new()
+            .with_root(&source_dir)
+            .with_processed_dir(&cache_dir)
+            .unwrap();
+        assert!(matches!(
+            fs.read_processed::<Reverse>("text/hello.txt").unwrap(),
+            AssetBytes::Processed(bytes) if bytes == b"OLLEH"
+        ));
+        std::fs::write(source_dir.join("text/hello.txt"), "hi").unwrap();
+        assert!(matches!(
+            fs.read_processed::<Reverse>("text/hello.txt").unwrap(),
+            AssetBytes::Source(bytes) if bytes == b"hi"
+        ));
+
+        // settings in the meta file of a file override the default settings
+        std::fs::write(
+            source_dir.join("text/hello.txt.meta"),
+            "[reverse]\nuppercase = false\n",
+        )
+        .unwrap();
+        let stats = processor.process_dir(&source_dir, &cache_dir).unwrap();
+        assert_eq!(stats.processed, 1);
+        fs.reload_processed_index().unwrap();
+        assert!(matches!(
+            fs.read_processed::<Reverse>("text/hello.txt").unwrap(),
+            AssetBytes::Processed(bytes) if bytes == b"ih"
+        ));
+        // rewriting the meta file with the same settings, e.g. as defaults, keeps the artifact
+        std::fs::write(source_dir.join("text/hello.txt.meta"), "[reverse]\n").unwrap();
+        assert!(matches!(
+            fs.read_processed::<Reverse>("text/hello.txt").unwrap(),
+            AssetBytes::Processed(bytes) if bytes == b"ih"
+        ));
+        // but changing them makes it stale
+        std::fs::write(
+            source_dir.join("text/hello.txt.meta"),
+            "[reverse]\nuppercase = true\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            fs.read_processed::<Reverse>("text/hello.txt").unwrap(),
+            AssetBytes::Source(_)
+        ));
+        std::fs::write(source_dir.join("text/hello.txt.meta"), "[reverse]\n").unwrap();
+
+        // a source with the size and modification time it was processed with isn't hashed again
+        let path = source_dir.join("text/hello.txt");
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+        std::fs::write(&path, "ho").unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(modified).unwrap();
+        assert!(matches!(
+            fs.read_processed::<Reverse>("text/hello.txt").unwrap(),
+            AssetBytes::Processed(bytes) if bytes == b"ih"
+        ));
+    }
+
+    #[test]
+    fn test_artifact_roundtrip() {
+        let mut writer = ArtifactWriter::new(*b"TEST");
+        writer.write_u32(7);
+        writer.write_f32(0.5);
+        writer.write_bytes(b"data");
+        let bytes = writer.finish();
+
+        let mut reader = ArtifactReader::new(&bytes, *b"TEST").unwrap();
+        assert_eq!(reader.read_u32().unwrap(), 7);
+        assert_eq!(reader.read_f32().unwrap(), 0.5);
+        assert_eq!(reader.read_bytes().unwrap(), b"data");
+        assert!(reader.read_u8().is_err());
+        assert!(ArtifactReader::new(&bytes, *b"NOPE").is_err());
+    }
+}
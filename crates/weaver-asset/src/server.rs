@@ -0,0 +1,491 @@
+use std::{
+    any::TypeId,
+    marker::PhantomData,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Weak},
+};
+
+use weaver_ecs::{
+    prelude::{Commands, Res},
+    world::World,
+};
+use weaver_util::prelude::*;
+
+use crate::{
+    Asset, AssetHandleProvider, AssetLoadQueue, AssetLoadRequest, AssetLoadStatus, AssetSettings,
+    DirectLoader, Filesystem, FilesystemId, Handle, LoadFrom, LoadState, PathAndFilesystem,
+    StrongHandle, UntypedHandle, collection::AssetCollection, meta::META_EXTENSION,
+    source::split_source,
+};
+
+type QueueLoadFn = Arc<dyn Fn(&World, Arc<StrongHandle>, PathAndFilesystem) + Send + Sync>;
+
+/// Pushes a load to the load queue of its loader. The closure keeps the asset alive until it's loaded.
+type PendingLoad = Box<dyn FnOnce(&World) + Send + Sync>;
+
+struct FileLoader {
+    type_name: &'static str,
+    queue: QueueLoadFn,
+    handle_provider: AssetHandleProvider,
+}
+
+/// Loads assets by path, picking the loader by file extension.
+///
+/// Every file is loaded at most once per asset type: loading a path that is already loaded (or still loading) returns the
+/// existing handle.
+pub struct AssetServer {
+    fs: Arc<Filesystem>,
+    /// Loaders by asset type and lowercase file extension.
+    loaders: Lock<FxHashMap<(TypeId, String), Arc<FileLoader>>>,
+    /// The asset type that files are loaded as when no type is given, i.e. the first type registered for the extension.
+    default_types: Lock<FxHashMap<String, TypeId>>,
+    collection_handles: Lock<Option<AssetHandleProvider>>,
+    /// Handles by asset type and canonical path. They're weak, so that the server doesn't keep any assets alive.
+    handles: Lock<FxHashMap<(TypeId, CanonicalPath), Weak<StrongHandle>>>,
+    pending: Lock<Vec<PendingLoad>>,
+    /// Shared with the world's [`AssetLoadStatus`] once the server is added to an app.
+    pub(crate) load_status: AssetLoadStatus,
+}
+
+impl AssetServer {
+    pub fn new(fs: Arc<Filesystem>) -> Self {
+        Self {
+            fs,
+            loaders: Lock::new(FxHashMap::default()),
+            default_types: Lock::new(FxHashMap::default()),
+            collection_handles: Lock::new(None),
+            handles: Lock::new(FxHashMap::default()),
+            pending: Lock::new(Vec::new()),
+            load_status: AssetLoadStatus::new(),
+        }
+    }
+
+    pub fn from_settings(settings: &AssetSettings) -> Result<Self> {
+        Ok(Self::new(Arc::new(Filesystem::from_settings(settings)?)))
+    }
+
+    /// The filesystem that paths given to [`load`](AssetServer::load) are relative to.
+    pub fn filesystem(&self) -> &Arc<Filesystem> {
+        &self.fs
+    }
+
+    /// The load state of an asset requested with [`load`](AssetServer::load).
+    pub fn load_state<T: Asset>(&self, handle: &Handle<T>) -> LoadState {
+        self.load_status.load_state(handle)
+    }
+
+    /// The load state of an asset together with all of its dependencies, see
+    /// [`AssetLoadStatus::recursive_load_state`].
+    pub fn recursive_load_state<T: Asset>(&self, handle: &Handle<T>) -> LoadState {
+        self.load_status.recursive_load_state(handle)
+    }
+
+    /// Uses `L` to load files with any of the given extensions as `L::Asset`.
+    pub fn register_loader<L: LoadFrom<PathAndFilesystem>>(
+        &self,
+        extensions: &[&str],
+        handle_provider: AssetHandleProvider,
+    ) {
+        let queue: QueueLoadFn = Arc::new(|world: &World, strong, source| {
+            let handle = Handle {
+                id: strong.id,
+                strong: Some(strong),
+                _marker: PhantomData,
+            };
+            if let Some(queue) = world.get_resource::<AssetLoadQueue<L, PathAndFilesystem>>() {
+                queue.push(AssetLoadRequest::new(handle, source));
+            }
+        });
+        let loader = Arc::new(FileLoader {
+            type_name: L::Asset::type_name(),
+            queue,
+            handle_provider,
+        });
+
+        let mut loaders = self.loaders.write();
+        let mut default_types = self.default_types.write();
+        for extension in extensions {
+            let extension = extension.to_lowercase();
+            default_types
+                .entry(extension.clone())
+                .or_insert(TypeId::of::<L::Asset>());
+            loaders.insert((TypeId::of::<L::Asset>(), extension), loader.clone());
+        }
+    }
+
+    /// Lets the server create [`AssetCollection`]s for [`load_folder`](AssetServer::load_folder). The
+    /// [`AssetPlugin`](crate::AssetPlugin) does this for the server it adds.
+    pub fn register_collections(&self, handle_provider: AssetHandleProvider) {
+        *self.collection_handles.write() = Some(handle_provider);
+    }
+
+    pub fn has_loader<T: Asset>(&self, extension: &str) -> bool {
+        self.loaders
+            .read()
+            .contains_key(&(TypeId::of::<T>(), extension.to_lowercase()))
+    }
+
+    /// Loads the file at `path` (relative to the asset roots) as a `T`, or returns its handle if it was already requested.
+    ///
+    /// The asset is queued for loading and will be available in [`Assets<T>`](crate::Assets) after the next
+    /// [`AssetLoad`](crate::AssetLoad) stage or so.
+    pub fn load<T: Asset>(&self, path: impl AsRef<Path>) -> Result<Handle<T>> {
+        self.load_from(PathAndFilesystem::new(path, self.fs.clone()))
+    }
+
+    /// Like [`load`](AssetServer::load), but reads the file from the given filesystem.
+    pub fn load_from<T: Asset>(&self, source: PathAndFilesystem) -> Result<Handle<T>> {
+        let extension = extension(&source.path)?;
+        let loader = self
+            .loaders
+            .read()
+            .get(&(TypeId::of::<T>(), extension.clone()))
+            .cloned()
+            .ok_or_else(|| {
+                anyhow!(
+                    "No loader for {} files registered for asset type {}",
+                    extension,
+                    T::type_name()
+                )
+            })?;
+
+        let (_, strong) = self.load_with(&loader, TypeId::of::<T>(), source);
+        Ok(loader.handle_provider.upgrade(&strong))
+    }
+
+    /// Loads the file at `path` as the asset type of the first loader registered for its extension.
+    pub fn load_untyped(&self, path: impl AsRef<Path>) -> Result<UntypedHandle> {
+        let (handle, _) = self.load_untyped_from(PathAndFilesystem::new(path, self.fs.clone()))?;
+        Ok(handle)
+    }
+
+    fn load_untyped_from(
+        &self,
+        source: PathAndFilesystem,
+    ) -> Result<(UntypedHandle, Arc<StrongHandle>)> {
+        let extension = extension(&source.path)?;
+        let type_id = self
+            .default_types
+            .read()
+            .get(&extension)
+            .copied()
+            .ok_or_else(|| anyhow!("No loader for {} files registered", extension))?;
+        let loader = self.loaders.read()[&(type_id, extension)].clone();
+        Ok(self.load_with(&loader, type_id, source))
+    }
+
+    fn load_with(
+        &self,
+        loader: &FileLoader,
+        type_id: TypeId,
+        source: PathAndFilesystem,
+    ) -> (UntypedHandle, Arc<StrongHandle>) {
+        let key = (type_id, canonical_path(&source));
+        let mut handles = self.handles.write();
+        if let Some(strong) = handles.get(&key).and_then(Weak::upgrade) {
+            let handle = UntypedHandle {
+                id: strong.id,
+                type_id,
+            };
+            return (handle, strong);
+        }
+
+        let (handle, strong) = loader.handle_provider.reserve_untyped();
+        handles.insert(key, Arc::downgrade(&strong));
+        drop(handles);
+
+        self.load_status
+            .set_load_state_by_id(handle.id, LoadState::Loading);
+        log::debug!(
+            "Queued {:?} for loading as {}",
+            source.path,
+            loader.type_name
+        );
+        let queue = loader.queue.clone();
+        let pending_strong = strong.clone();
+        self.pending
+            .write()
+            .push(Box::new(move |world| queue(world, pending_strong, source)));
+
+        (handle, strong)
+    }
+
+    /// Loads every file in a folder (and its subfolders) that has a loader, each as the asset type of the first loader
+    /// registered for its extension. Meta files and files without a loader are skipped.
+    ///
+    /// The collection keeps its files alive, and its [`recursive_load_state`](AssetServer::recursive_load_state) is
+    /// [`Loaded`](LoadState::Loaded) once all of them are loaded.
+    pub fn load_folder(&self, path: impl AsRef<Path>) -> Result<Handle<AssetCollection>> {
+        let source = PathAndFilesystem::new(path, self.fs.clone());
+        let handle_provider =
+            self.collection_handles.read().clone().ok_or_else(|| {
+                anyhow!("Asset collections aren't registered with this asset server")
+            })?;
+
+        let key = (TypeId::of::<AssetCollection>(), canonical_path(&source));
+        if let Some(strong) = self.handles.read().get(&key).and_then(Weak::upgrade) {
+            return Ok(handle_provider.upgrade(&strong));
+        }
+
+        let mut collection = AssetCollection::new();
+        for file in self.fs.read_dir_recursive(&source.path)? {
+            if file
+                .extension()
+                .is_some_and(|extension| extension == META_EXTENSION)
+            {
+                continue;
+            }
+            let relative = match split_source(&file) {
+                Some((_, file)) => file.strip_prefix(split_source(&source.path).unwrap().1),
+                None => file.strip_prefix(&source.path),
+            }
+            .unwrap_or(&file)
+            .to_path_buf();
+            match self.load_untyped_from(PathAndFilesystem::new(&file, self.fs.clone())) {
+                Ok((handle, strong)) => collection.push(relative, handle, strong),
+                Err(e) => log::debug!("Skipping {:?} in asset folder: {}", file, e),
+            }
+        }
+
+        let handle = handle_provider.reserve_handle::<AssetCollection>();
+        self.handles
+            .write()
+            .insert(key, Arc::downgrade(handle.strong.as_ref().unwrap()));
+        self.load_status.set_load_state(&handle, LoadState::Loading);
+        log::debug!(
+            "Queued folder {:?} with {} assets for loading",
+            source.path,
+            collection.len()
+        );
+        let request = AssetLoadRequest::new(handle.clone(), collection);
+        self.pending.write().push(Box::new(move |world| {
+            if let Some(queue) = world
+                .get_resource::<AssetLoadQueue<DirectLoader<AssetCollection>, AssetCollection>>()
+            {
+                queue.push(request);
+            }
+        }));
+
+        Ok(handle)
+    }
+
+    /// Returns the handle of a file that has already been requested with [`load`](AssetServer::load), if it's still
+    /// alive.
+    pub fn get_handle<T: Asset>(&self, path: impl AsRef<Path>) -> Option<Handle<T>> {
+        let source = PathAndFilesystem::new(path, self.fs.clone());
+        let extension = source.path.extension()?.to_str()?.to_lowercase();
+        let loader = self
+            .loaders
+            .read()
+            .get(&(TypeId::of::<T>(), extension))
+            .cloned()?;
+        let strong = self
+            .handles
+            .read()
+            .get(&(TypeId::of::<T>(), canonical_path(&source)))
+            .and_then(Weak::upgrade)?;
+        Some(loader.handle_provider.upgrade(&strong))
+    }
+}
+
+fn extension(path: &Path) -> Result<String> {
+    Ok(path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .ok_or_else(|| anyhow!("Asset path has no file extension: {:?}", path))?
+        .to_lowercase())
+}
+
+/// Identifies a file across the filesystems it can be loaded from.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+enum CanonicalPath {
+    /// A file on disk, which is the same file no matter which filesystem it's loaded through.
+    Disk(PathBuf),
+    /// A file that only exists in an archive or a named source of one filesystem (or not at all).
+    Virtual(FilesystemId, PathBuf),
+}
+
+/// The canonical path of the file on disk, or the normalized path within its filesystem if the file only exists in an
+/// archive or a named source (or not at all).
+fn canonical_path(source: &PathAndFilesystem) -> CanonicalPath {
+    if let Some(canonical) = source
+        .fs
+        .full_path(&source.path)
+        .and_then(|full_path| std::fs::canonicalize(full_path).ok())
+    {
+        return CanonicalPath::Disk(canonical);
+    }
+    let path = match split_source(&source.path) {
+        Some((name, path)) => {
+            PathBuf::from(format!("{}://{}", name, normalize_path(path).display()))
+        }
+        None => normalize_path(&source.path),
+    };
+    CanonicalPath::Virtual(source.fs.id(), path)
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Hands the loads requested from the [`AssetServer`] to the load queues of their loaders.
+pub(crate) async fn queue_asset_server_loads(commands: Commands, server: Res<AssetServer>) {
+    let pending = std::mem::take(&mut *server.pending.write());
+    if pending.is_empty() {
+        return;
+    }
+
+    commands.run(move |world| {
+        for load in pending {
+            load(world);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Assets, source::MemoryReader};
+
+    struct Text(#[allow(dead_code)] String);
+    impl Asset for Text {}
+
+    #[derive(Default)]
+    struct TextLoader;
+
+    impl LoadFrom<PathAndFilesystem> for TextLoader {
+        type Asset = Text;
+
+        type Settings = ();
+
+        async fn load(
+            &self,
+            source: PathAndFilesystem,
+            _settings: &(),
+            _commands: &Commands,
+        ) -> Result<Text> {
+            Ok(Text(String::from_utf8(source.read()?)?))
+        }
+    }
+
+    #[test]
+    fn test_load_dedupes_paths() {
+        let assets = Assets::<Text>::new();
+        let server = AssetServer::new(Arc::new(Filesystem::new()));
+        server.register_loader::<TextLoader>(&["txt"], assets.handle_provider());
+
+        let a = server.load::<Text>("docs/readme.txt").unwrap();
+        let b = server.load::<Text>("./docs/../docs/readme.txt").unwrap();
+        let c = server.load::<Text>("docs/other.TXT").unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(
+            server.get_handle::<Text>("docs/readme.txt"),
+            Some(a.clone())
+        );
+        assert_eq!(server.pending.read().len(), 2);
+
+        assert!(server.load::<Text>("docs/readme.md").is_err());
+        assert!(server.load::<()>("docs/readme.txt").is_err());
+
+        // once nothing holds a strong handle anymore, the file is loaded again under a new handle
+        server.pending.write().clear();
+        let id = a.id();
+        drop((a, b));
+        assert_eq!(server.get_handle::<Text>("docs/readme.txt"), None);
+        assert_ne!(server.load::<Text>("docs/readme.txt").unwrap().id(), id);
+    }
+
+    #[test]
+    fn test_load_keeps_filesystems_apart() {
+        let assets = Assets::<Text>::new();
+        let filesystem = |text: &'static str| {
+            let memory = Arc::new(MemoryReader::new());
+            memory.insert("docs/readme.txt", text.as_bytes());
+            Arc::new(Filesystem::new().with_source("memory", memory))
+        };
+        let first = filesystem("first");
+        let second = filesystem("second");
+        let server = AssetServer::new(first.clone());
+        server.register_loader::<TextLoader>(&["txt"], assets.handle_provider());
+
+        let a = server.load::<Text>("memory://docs/readme.txt").unwrap();
+        let b = server
+            .load_from::<Text>(PathAndFilesystem::new("memory://docs/readme.txt", first))
+            .unwrap();
+        let c = server
+            .load_from::<Text>(PathAndFilesystem::new("memory://docs/readme.txt", second))
+            .unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(server.pending.read().len(), 2);
+    }
+
+    #[test]
+    fn test_load_folder() {
+        let memory = Arc::new(MemoryReader::new());
+        memory.insert("docs/a.txt", &b"a"[..]);
+        memory.insert("docs/a.txt.meta", &b""[..]);
+        memory.insert("docs/logo.png", &b""[..]);
+        memory.insert("docs/notes/b.txt", &b"b"[..]);
+        let fs = Arc::new(Filesystem::new().with_source("memory", memory));
+
+        let texts = Assets::<Text>::new();
+        let collections = Assets::<AssetCollection>::new();
+        let server = AssetServer::new(fs);
+        server.register_loader::<TextLoader>(&["txt"], texts.handle_provider());
+        server.register_collections(collections.handle_provider());
+
+        let folder = server.load_folder("memory://docs").unwrap();
+        assert_eq!(server.load_folder("memory://./docs").unwrap(), folder);
+        assert!(server.load_state(&folder).is_loading());
+        assert!(server.load_folder("memory://missing").is_err());
+        assert_eq!(
+            server.load_untyped("memory://docs/a.txt").unwrap().id(),
+            server
+                .get_handle::<Text>("memory://docs/a.txt")
+                .unwrap()
+                .id()
+        );
+
+        // the two text files and the collection itself
+        assert_eq!(server.pending.read().len(), 3);
+        let world = World::new();
+        world.insert_resource(AssetLoadQueue::<
+            DirectLoader<AssetCollection>,
+            AssetCollection,
+        >::new(
+            collections.handle_provider(), server.load_status.clone()
+        ));
+        for load in std::mem::take(&mut *server.pending.write()) {
+            load(&world);
+        }
+        let queue = world
+            .get_resource::<AssetLoadQueue<DirectLoader<AssetCollection>, AssetCollection>>()
+            .unwrap();
+        let request = queue.pop().unwrap();
+        let collection = request.source();
+        assert_eq!(request.handle(), &folder);
+        assert_eq!(
+            collection.iter().map(|(path, _)| path).collect::<Vec<_>>(),
+            vec![Path::new("a.txt"), Path::new("notes/b.txt")]
+        );
+        let a = collection.get::<Text>("a.txt").unwrap();
+        assert!(a.is_strong());
+        assert_eq!(server.get_handle::<Text>("memory://docs/a.txt"), Some(a));
+        assert!(collection.get::<()>("a.txt").is_none());
+        assert_eq!(collection.handles::<Text>().count(), 2);
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use weaver_util::prelude::*;
+
+/// Name of the source that [`AssetApp::embed_asset`](crate::AssetApp::embed_asset) adds files to, so they're loaded from
+/// `embedded://<path>`.
+pub const EMBEDDED_SOURCE: &str = "embedded";
+
+/// A named place that a [`Filesystem`](crate::Filesystem) reads assets from, next to its directories and archives.
+///
+/// Once mounted with [`Filesystem::mount`](crate::Filesystem::mount), the files of a source are addressed as
+/// `<name>://<path>`.
+pub trait AssetReader: Send + Sync + 'static {
+    fn read(&self, path: &Path) -> Result<Vec<u8>>;
+
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Lists the files in a directory (recursively, like archives do), relative to the root of the source.
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>>;
+
+    /// The path on disk of a file, if it has one. Files with a path on disk are reloaded when they change.
+    fn full_path(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+}
+
+impl<T: AssetReader> AssetReader for Arc<T> {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        (**self).read(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        (**self).exists(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        (**self).read_dir(path)
+    }
+
+    fn full_path(&self, path: &Path) -> Option<PathBuf> {
+        (**self).full_path(path)
+    }
+}
+
+/// Splits a path like `memory://textures/a.png` into the name of its source and the path within the source.
+pub fn split_source(path: &Path) -> Option<(&str, &Path)> {
+    let (name, path) = path.to_str()?.split_once("://")?;
+    Some((name, Path::new(path)))
+}
+
+/// Normalizes a path within a source, so that `./a/../b.png` and `b.png` are the same file.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => {}
+        }
+    }
+    normalized
+}
+
+/// A source that keeps its files in memory, e.g. files compiled into the binary with `include_bytes!` or files made by
+/// tests.
+#[derive(Default)]
+pub struct MemoryReader {
+    files: Lock<BTreeMap<PathBuf, Cow<'static, [u8]>>>,
+}
+
+impl MemoryReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, replacing any file at the same path.
+    pub fn insert(&self, path: impl AsRef<Path>, bytes: impl Into<Cow<'static, [u8]>>) {
+        self.files
+            .write()
+            .insert(normalize(path.as_ref()), bytes.into());
+    }
+
+    pub fn remove(&self, path: impl AsRef<Path>) -> bool {
+        self.files
+            .write()
+            .remove(&normalize(path.as_ref()))
+            .is_some()
+    }
+}
+
+impl AssetReader for MemoryReader {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        self.files
+            .read()
+            .get(&normalize(path))
+            .map(|bytes| bytes.to_vec())
+            .ok_or_else(|| anyhow!("No such file in memory: {:?}", path))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.read().contains_key(&normalize(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let dir = normalize(path);
+        let files = self
+            .files
+            .read()
+            .keys()
+            .filter(|file| file.starts_with(&dir))
+            .cloned()
+            .collect::<Vec<_>>();
+        if files.is_empty() {
+            bail!("No such directory in memory: {:?}", path);
+        }
+        Ok(files)
+    }
+}
+
+/// Files compiled into the binary, mounted as the [`EMBEDDED_SOURCE`] by the [`AssetPlugin`](crate::AssetPlugin).
+#[derive(Default, Clone)]
+pub struct EmbeddedAssets {
+    reader: Arc<MemoryReader>,
+}
+
+impl EmbeddedAssets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, path: impl AsRef<Path>, bytes: &'static [u8]) {
+        self.reader.insert(path, bytes);
+    }
+
+    pub fn reader(&self) -> Arc<MemoryReader> {
+        self.reader.clone()
+    }
+}
+
+/// Embeds a file into the binary with `include_bytes!` and adds it to the [`EMBEDDED_SOURCE`] of an app, so that it can be
+/// loaded from `embedded://<path>`. The file is looked up relative to the file the macro is called in.
+///
+/// ```ignore
+/// embedded_asset!(app, "shaders/blit.wgsl", "../assets/shaders/blit.wgsl");
+/// ```
+#[macro_export]
+macro_rules! embedded_asset {
+    ($app:expr, $path:expr, $file:expr) => {
+        $crate::AssetApp::embed_asset($app, $path, include_bytes!($file))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Filesystem;
+
+    #[test]
+    fn test_memory_source() {
+        let memory = Arc::new(MemoryReader::new());
+        memory.insert("textures/a.png", &b"a"[..]);
+        memory.insert("./textures/b.png", b"b".to_vec());
+        let fs = Filesystem::new().with_source("memory", memory.clone());
+
+        assert_eq!(fs.read_sub_path("memory://textures/a.png").unwrap(), b"a");
+        assert_eq!(
+            fs.read_sub_path("memory://textures/../textures/b.png")
+                .unwrap(),
+            b"b"
+        );
+        assert!(fs.exists("memory://textures/a.png"));
+        assert!(!fs.exists("memory://textures/c.png"));
+        assert!(!fs.exists("textures/a.png"));
+        assert!(fs.read_sub_path("other://textures/a.png").is_err());
+        assert_eq!(
+            fs.read_dir("memory://textures").unwrap(),
+            vec![
+                PathBuf::from("memory://textures/a.png"),
+                PathBuf::from("memory://textures/b.png")
+            ]
+        );
+
+        // files can be added and removed after mounting
+        memory.insert("c.txt", &b"c"[..]);
+        assert!(fs.exists("memory://c.txt"));
+        assert!(memory.remove("c.txt"));
+        assert!(!fs.exists("memory://c.txt"));
+
+        assert!(fs.unmount("memory"));
+        assert!(!fs.exists("memory://textures/a.png"));
+    }
+}
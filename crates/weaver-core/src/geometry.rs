@@ -552,10 +552,11 @@ impl Intersect<Ray> for Mesh {
     fn intersect(&self, rhs: &Ray) -> Option<Self::Output> {
         let mut closest_intersection: Option<RayMeshIntersection> = None;
 
+        let positions = self.positions();
         for indices in self.indices.chunks(3) {
-            let a = self.vertices[indices[0] as usize].position;
-            let b = self.vertices[indices[1] as usize].position;
-            let c = self.vertices[indices[2] as usize].position;
+            let a = positions[indices[0] as usize];
+            let b = positions[indices[1] as usize];
+            let c = positions[indices[2] as usize];
 
             let triangle = Triangle::new(a, b, c);
 
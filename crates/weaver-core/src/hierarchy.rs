@@ -0,0 +1,133 @@
+use glam::Mat4;
+use weaver_ecs::{
+    entity::{Entity, EntityMap},
+    prelude::Commands,
+    query::Query,
+};
+
+use crate::transform::Transform;
+
+/// The entity that an entity's [`Transform`] is relative to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Parent(pub Entity);
+
+/// The entities whose [`Parent`] is this entity.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Children(pub Vec<Entity>);
+
+/// A human-readable name of an entity, e.g. the name of the node it was spawned from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Name(pub String);
+
+impl Name {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The transform of an entity in a hierarchy relative to the world, i.e. its [`Transform`] combined with those of all of
+/// its ancestors. Kept up to date by [`propagate_transforms`]; entities outside of any hierarchy don't have one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlobalTransform(pub Mat4);
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self(Mat4::IDENTITY)
+    }
+}
+
+impl GlobalTransform {
+    pub fn matrix(&self) -> Mat4 {
+        self.0
+    }
+
+    pub fn to_transform(&self) -> Transform {
+        Transform::from_matrix(self.0)
+    }
+}
+
+/// Computes the [`GlobalTransform`]s of all entities that have a [`Parent`] or [`Children`].
+pub async fn propagate_transforms(
+    commands: Commands,
+    mut nodes: Query<(Entity, &Transform, Option<&Parent>, Option<&Children>)>,
+    mut globals: Query<&mut GlobalTransform>,
+) {
+    let mut locals = EntityMap::default();
+    let mut stack = Vec::new();
+    for (entity, transform, parent, children) in nodes.iter() {
+        if parent.is_none() && children.is_none() {
+            continue;
+        }
+        if parent.is_none() {
+            stack.push((entity, Mat4::IDENTITY));
+        }
+        let children = children.map(|children| children.0.clone());
+        locals.insert(entity, (transform.matrix(), children.unwrap_or_default()));
+    }
+
+    let mut missing = Vec::new();
+    while let Some((entity, parent_matrix)) = stack.pop() {
+        // children without a transform (or despawned ones) end the branch
+        let Some((local, children)) = locals.remove(&entity) else {
+            continue;
+        };
+        let global = GlobalTransform(parent_matrix * local);
+        match globals.get(entity) {
+            Some(mut current) => {
+                if *current != global {
+                    *current = global;
+                }
+            }
+            None => missing.push((entity, global)),
+        }
+        stack.extend(children.into_iter().map(|child| (child, global.0)));
+    }
+
+    drop((nodes, globals));
+    for (entity, global) in missing {
+        commands.insert_component(entity, global);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+    use weaver_app::{App, AppStage};
+    use weaver_task::{task_pool::TaskPool, usages::GlobalTaskPool};
+
+    use super::*;
+
+    #[test]
+    fn test_propagate_transforms() {
+        GlobalTaskPool::get_or_init(TaskPool::new);
+
+        let mut app = App::new();
+        app.add_system(propagate_transforms, AppStage::PostUpdate);
+        let world = app.main_app_mut().world_mut();
+        let root = world.spawn((Transform::from_translation(Vec3::X),));
+        let child = world.spawn((Transform::from_scale(Vec3::splat(2.0)), Parent(root)));
+        let grandchild = world.spawn((Transform::from_translation(Vec3::Y), Parent(child)));
+        let loose = world.spawn((Transform::from_translation(Vec3::Z),));
+        world.insert_component(root, Children(vec![child]));
+        world.insert_component(child, Children(vec![grandchild]));
+
+        app.init();
+        app.update();
+
+        let world = app.main_app().world();
+        let global = |entity| {
+            world
+                .query::<&GlobalTransform>()
+                .get(entity)
+                .map(|global| global.0.transform_point3(Vec3::ZERO))
+        };
+        assert_eq!(global(root), Some(Vec3::X));
+        assert_eq!(global(child), Some(Vec3::X));
+        assert_eq!(global(grandchild), Some(Vec3::new(1.0, 2.0, 0.0)));
+        assert_eq!(global(loose), None);
+    }
+}
@@ -1,15 +1,22 @@
 use std::path::PathBuf;
 
-use mesh::Mesh;
-use texture::{Texture, TextureLoader};
-use weaver_app::{App, plugin::Plugin};
-use weaver_asset::{AssetApp, PathAndFilesystem};
+use hierarchy::propagate_transforms;
+use mesh::{GltfMeshProcessor, Mesh, ObjMeshProcessor};
+use texture::{TEXTURE_EXTENSIONS, Texture, TextureLoader, TextureProcessor};
+use weaver_app::{
+    App, AppStage,
+    plugin::{Plugin, PluginId},
+};
+use weaver_asset::{AssetApp, AssetPlugin, PathAndFilesystem};
 use weaver_util::prelude::*;
 
 pub mod color;
 pub mod geometry;
+pub mod hierarchy;
 pub mod input;
 pub mod mesh;
+pub mod mesh_processing;
+pub mod primitive;
 pub mod texture;
 pub mod time;
 pub mod transform;
@@ -18,8 +25,10 @@ pub mod prelude {
     pub use crate::CoreTypesPlugin;
     pub use crate::color::*;
     pub use crate::geometry::*;
+    pub use crate::hierarchy::*;
     pub use crate::input::*;
     pub use crate::mesh::*;
+    pub use crate::primitive::*;
     pub use crate::texture::*;
     pub use crate::time::*;
     pub use crate::transform::*;
@@ -30,16 +39,26 @@ pub mod prelude {
 pub struct CoreTypesPlugin;
 
 impl Plugin for CoreTypesPlugin {
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<AssetPlugin>()]
+    }
+
     fn build(&self, app: &mut App) -> Result<()> {
         app.add_asset::<Texture>();
         app.add_asset::<Mesh>();
 
-        app.add_asset_loader::<TextureLoader<PathBuf>, _>();
+        app.add_file_asset_loader::<TextureLoader<PathBuf>>(TEXTURE_EXTENSIONS);
         app.add_asset_loader::<TextureLoader<Vec<u8>>, _>();
-        app.add_asset_loader::<mesh::ObjMeshLoader<PathAndFilesystem>, _>();
+        app.add_file_asset_loader::<mesh::ObjMeshLoader<PathAndFilesystem>>(&["obj"]);
         app.add_asset_loader::<mesh::ObjMeshLoader<Vec<u8>>, _>();
-        app.add_asset_loader::<mesh::GltfMeshLoader<PathBuf>, _>();
+        app.add_file_asset_loader::<mesh::GltfMeshLoader<PathBuf>>(&["gltf", "glb"]);
         app.add_asset_loader::<mesh::GltfMeshLoader<Vec<u8>>, _>();
+
+        app.add_asset_processor(TEXTURE_EXTENSIONS, TextureProcessor, Default::default());
+        app.add_asset_processor(&["obj"], ObjMeshProcessor, Default::default());
+        app.add_asset_processor(&["gltf", "glb"], GltfMeshProcessor, Default::default());
+
+        app.add_system(propagate_transforms, AppStage::PostUpdate);
         Ok(())
     }
 }
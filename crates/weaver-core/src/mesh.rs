@@ -1,16 +1,19 @@
-use std::path::PathBuf;
+use std::{borrow::Cow, collections::BTreeMap, path::PathBuf};
 
 use glam::{Vec2, Vec3, Vec4};
 use weaver_asset::{
     LoadSource, PathAndFilesystem,
+    meta::LoaderSettings,
     prelude::{Asset, LoadFrom},
+    processor::{ArtifactReader, ArtifactWriter, AssetBytes, Process},
 };
 use weaver_ecs::prelude::Commands;
 use weaver_util::prelude::*;
 
 use crate::prelude::{Aabb, Transform};
 
-#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+/// The standard attributes of a vertex, used to build meshes with [`Mesh::new`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
 pub struct Vertex {
     pub position: Vec3,
@@ -19,66 +22,554 @@ pub struct Vertex {
     pub tex_coords: Vec2,
 }
 
+/// The data type of the values of a vertex attribute, matching the GPU's vertex formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VertexFormat {
+    Float32,
+    Float32x2,
+    Float32x3,
+    Float32x4,
+    Uint32,
+    Uint16x4,
+}
+
+impl VertexFormat {
+    const ALL: [VertexFormat; 6] = [
+        VertexFormat::Float32,
+        VertexFormat::Float32x2,
+        VertexFormat::Float32x3,
+        VertexFormat::Float32x4,
+        VertexFormat::Uint32,
+        VertexFormat::Uint16x4,
+    ];
+
+    /// The size of one value in bytes.
+    pub fn size(self) -> u64 {
+        match self {
+            VertexFormat::Float32 | VertexFormat::Uint32 => 4,
+            VertexFormat::Float32x2 | VertexFormat::Uint16x4 => 8,
+            VertexFormat::Float32x3 => 12,
+            VertexFormat::Float32x4 => 16,
+        }
+    }
+}
+
+/// Names a vertex attribute of a [`Mesh`], together with the shader location it's bound to and its format.
+///
+/// Meshes hold at most one attribute per location. Custom attributes should use locations from
+/// [`MeshAttribute::FIRST_CUSTOM_LOCATION`] on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MeshAttribute {
+    pub name: Cow<'static, str>,
+    pub location: u32,
+    pub format: VertexFormat,
+}
+
+impl MeshAttribute {
+    pub const POSITION: Self = Self::new("position", 0, VertexFormat::Float32x3);
+    pub const NORMAL: Self = Self::new("normal", 1, VertexFormat::Float32x3);
+    pub const TANGENT: Self = Self::new("tangent", 2, VertexFormat::Float32x3);
+    pub const UV_0: Self = Self::new("uv_0", 3, VertexFormat::Float32x2);
+    /// Indices of the joints of the skin that deforms the vertex.
+    pub const JOINT_INDICES: Self = Self::new("joint_indices", 4, VertexFormat::Uint16x4);
+    /// How much each of the joint indices influences the vertex.
+    pub const JOINT_WEIGHTS: Self = Self::new("joint_weights", 5, VertexFormat::Float32x4);
+    /// Linear RGBA color.
+    pub const COLOR: Self = Self::new("color", 6, VertexFormat::Float32x4);
+    /// A second set of texture coordinates, e.g. for lightmaps.
+    pub const UV_1: Self = Self::new("uv_1", 7, VertexFormat::Float32x2);
+
+    pub const FIRST_CUSTOM_LOCATION: u32 = 8;
+
+    pub const fn new(name: &'static str, location: u32, format: VertexFormat) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            location,
+            format,
+        }
+    }
+
+    /// The value that vertices without the attribute get when meshes are combined.
+    fn default_value(&self) -> VertexAttributeValues {
+        if *self == MeshAttribute::COLOR {
+            // vertex colors multiply, so missing colors are white
+            VertexAttributeValues::Float32x4(vec![[1.0; 4]])
+        } else {
+            VertexAttributeValues::zeroed(self.format, 1)
+        }
+    }
+}
+
+/// The values of one vertex attribute of every vertex of a [`Mesh`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum VertexAttributeValues {
+    Float32(Vec<f32>),
+    Float32x2(Vec<[f32; 2]>),
+    Float32x3(Vec<[f32; 3]>),
+    Float32x4(Vec<[f32; 4]>),
+    Uint32(Vec<u32>),
+    Uint16x4(Vec<[u16; 4]>),
+}
+
+impl VertexAttributeValues {
+    pub fn zeroed(format: VertexFormat, len: usize) -> Self {
+        match format {
+            VertexFormat::Float32 => Self::Float32(vec![0.0; len]),
+            VertexFormat::Float32x2 => Self::Float32x2(vec![[0.0; 2]; len]),
+            VertexFormat::Float32x3 => Self::Float32x3(vec![[0.0; 3]; len]),
+            VertexFormat::Float32x4 => Self::Float32x4(vec![[0.0; 4]; len]),
+            VertexFormat::Uint32 => Self::Uint32(vec![0; len]),
+            VertexFormat::Uint16x4 => Self::Uint16x4(vec![[0; 4]; len]),
+        }
+    }
+
+    pub fn format(&self) -> VertexFormat {
+        match self {
+            Self::Float32(_) => VertexFormat::Float32,
+            Self::Float32x2(_) => VertexFormat::Float32x2,
+            Self::Float32x3(_) => VertexFormat::Float32x3,
+            Self::Float32x4(_) => VertexFormat::Float32x4,
+            Self::Uint32(_) => VertexFormat::Uint32,
+            Self::Uint16x4(_) => VertexFormat::Uint16x4,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Float32(values) => values.len(),
+            Self::Float32x2(values) => values.len(),
+            Self::Float32x3(values) => values.len(),
+            Self::Float32x4(values) => values.len(),
+            Self::Uint32(values) => values.len(),
+            Self::Uint16x4(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Float32(values) => bytemuck::cast_slice(values),
+            Self::Float32x2(values) => bytemuck::cast_slice(values),
+            Self::Float32x3(values) => bytemuck::cast_slice(values),
+            Self::Float32x4(values) => bytemuck::cast_slice(values),
+            Self::Uint32(values) => bytemuck::cast_slice(values),
+            Self::Uint16x4(values) => bytemuck::cast_slice(values),
+        }
+    }
+
+    /// Reads values of a format from bytes, which don't need to be aligned.
+    pub fn from_bytes(format: VertexFormat, bytes: &[u8]) -> Result<Self> {
+        ensure!(
+            (bytes.len() as u64).is_multiple_of(format.size()),
+            "{} bytes aren't a whole number of {:?} values",
+            bytes.len(),
+            format
+        );
+        Ok(match format {
+            VertexFormat::Float32 => Self::Float32(bytemuck::pod_collect_to_vec(bytes)),
+            VertexFormat::Float32x2 => Self::Float32x2(bytemuck::pod_collect_to_vec(bytes)),
+            VertexFormat::Float32x3 => Self::Float32x3(bytemuck::pod_collect_to_vec(bytes)),
+            VertexFormat::Float32x4 => Self::Float32x4(bytemuck::pod_collect_to_vec(bytes)),
+            VertexFormat::Uint32 => Self::Uint32(bytemuck::pod_collect_to_vec(bytes)),
+            VertexFormat::Uint16x4 => Self::Uint16x4(bytemuck::pod_collect_to_vec(bytes)),
+        })
+    }
+
+    /// Appends values of the same format.
+    fn extend(&mut self, other: &Self) {
+        match (self, other) {
+            (Self::Float32(values), Self::Float32(other)) => values.extend_from_slice(other),
+            (Self::Float32x2(values), Self::Float32x2(other)) => values.extend_from_slice(other),
+            (Self::Float32x3(values), Self::Float32x3(other)) => values.extend_from_slice(other),
+            (Self::Float32x4(values), Self::Float32x4(other)) => values.extend_from_slice(other),
+            (Self::Uint32(values), Self::Uint32(other)) => values.extend_from_slice(other),
+            (Self::Uint16x4(values), Self::Uint16x4(other)) => values.extend_from_slice(other),
+            (values, other) => panic!(
+                "cannot append {:?} values to {:?} values",
+                other.format(),
+                values.format()
+            ),
+        }
+    }
+
+    /// Appends copies of the first value of `value` until there are `len` values.
+    fn pad(&mut self, value: &Self, len: usize) {
+        while self.len() < len {
+            self.extend(value);
+        }
+    }
+}
+
+macro_rules! impl_vertex_attribute_values_from {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<Vec<$ty>> for VertexAttributeValues {
+                fn from(values: Vec<$ty>) -> Self {
+                    Self::$variant(bytemuck::cast_vec(values))
+                }
+            }
+        )*
+    };
+}
+
+impl_vertex_attribute_values_from!(
+    f32 => Float32,
+    [f32; 2] => Float32x2,
+    Vec2 => Float32x2,
+    [f32; 3] => Float32x3,
+    Vec3 => Float32x3,
+    [f32; 4] => Float32x4,
+    Vec4 => Float32x4,
+    u32 => Uint32,
+    [u16; 4] => Uint16x4,
+);
+
+/// Where the attributes of a [`Mesh`] are in its interleaved vertex buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MeshVertexLayout {
+    /// The attributes in increasing location order, with their byte offsets within a vertex.
+    pub attributes: Vec<(MeshAttribute, u64)>,
+    /// The size of a vertex in bytes.
+    pub stride: u64,
+}
+
+impl MeshVertexLayout {
+    pub fn offset(&self, attribute: &MeshAttribute) -> Option<u64> {
+        self.attributes
+            .iter()
+            .find(|(other, _)| other == attribute)
+            .map(|(_, offset)| *offset)
+    }
+}
+
+/// Triangles with named vertex attribute streams.
+///
+/// Every attribute has a value for every vertex. Meshes built with [`Mesh::new`] have positions, normals, tangents and
+/// the first set of texture coordinates, and meshes loaded from files have the same except for texture coordinates
+/// that the file doesn't have; colors, a second UV set, joints and custom attributes are added with
+/// [`Mesh::insert_attribute`].
 #[derive(Clone, Asset, Default)]
 pub struct Mesh {
-    pub vertices: Vec<Vertex>,
+    attributes: BTreeMap<u32, (MeshAttribute, VertexAttributeValues)>,
     pub indices: Vec<u32>,
     pub aabb: Aabb,
 }
 
 impl Mesh {
     pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
-        let mut min = Vec3::splat(f32::INFINITY);
-        let mut max = Vec3::splat(f32::NEG_INFINITY);
+        let mut mesh = Self {
+            indices,
+            ..Default::default()
+        };
+        mesh.set_attribute(
+            MeshAttribute::POSITION,
+            vertices
+                .iter()
+                .map(|vertex| vertex.position)
+                .collect::<Vec<_>>(),
+        );
+        mesh.set_attribute(
+            MeshAttribute::NORMAL,
+            vertices
+                .iter()
+                .map(|vertex| vertex.normal)
+                .collect::<Vec<_>>(),
+        );
+        mesh.set_attribute(
+            MeshAttribute::TANGENT,
+            vertices
+                .iter()
+                .map(|vertex| vertex.tangent)
+                .collect::<Vec<_>>(),
+        );
+        mesh.set_attribute(
+            MeshAttribute::UV_0,
+            vertices
+                .iter()
+                .map(|vertex| vertex.tex_coords)
+                .collect::<Vec<_>>(),
+        );
+        mesh.regenerate_aabb();
+        mesh
+    }
 
-        for vertex in &vertices {
-            min = min.min(vertex.position);
-            max = max.max(vertex.position);
+    /// Builds a mesh from the vertex streams of a file. Missing normals are computed from the triangles and missing
+    /// tangents with [`Mesh::recalculate_tangents`].
+    fn from_streams(
+        positions: Vec<Vec3>,
+        normals: Option<Vec<Vec3>>,
+        uvs: Option<Vec<Vec2>>,
+        tangents: Option<Vec<Vec3>>,
+        indices: Vec<u32>,
+    ) -> Result<Self> {
+        let mut mesh = Self {
+            indices,
+            ..Default::default()
+        };
+        mesh.set_attribute(MeshAttribute::POSITION, positions);
+        if let Some(uvs) = uvs {
+            mesh.insert_attribute(MeshAttribute::UV_0, uvs)?;
+        }
+        match normals {
+            Some(normals) => mesh.insert_attribute(MeshAttribute::NORMAL, normals)?,
+            None => mesh.recalculate_normals(),
         }
+        match tangents {
+            Some(tangents) => mesh.insert_attribute(MeshAttribute::TANGENT, tangents)?,
+            None => mesh.recalculate_tangents(),
+        }
+        mesh.regenerate_aabb();
+        Ok(mesh)
+    }
 
-        let aabb = Aabb::new(min, max);
+    /// Sets the values of an attribute, replacing any attribute at the same location.
+    ///
+    /// Fails if the values don't have the attribute's format, or if there are more or fewer of them than the mesh's
+    /// other attributes have.
+    pub fn insert_attribute(
+        &mut self,
+        attribute: MeshAttribute,
+        values: impl Into<VertexAttributeValues>,
+    ) -> Result<()> {
+        let values = values.into();
+        ensure!(
+            values.format() == attribute.format,
+            "Values of mesh attribute {:?} are {:?}, expected {:?}",
+            attribute.name,
+            values.format(),
+            attribute.format
+        );
+        if let Some((other, other_values)) = self
+            .attributes
+            .values()
+            .find(|(other, _)| other.location != attribute.location)
+        {
+            ensure!(
+                values.len() == other_values.len(),
+                "Mesh attribute {:?} has {} values, but {:?} has {}",
+                attribute.name,
+                values.len(),
+                other.name,
+                other_values.len()
+            );
+        }
+        self.set_attribute(attribute, values);
+        Ok(())
+    }
 
-        Self {
-            vertices,
-            indices,
-            aabb,
+    pub fn with_attribute(
+        mut self,
+        attribute: MeshAttribute,
+        values: impl Into<VertexAttributeValues>,
+    ) -> Result<Self> {
+        self.insert_attribute(attribute, values)?;
+        Ok(self)
+    }
+
+    /// Sets the values of an attribute that are known to fit the mesh.
+    fn set_attribute(
+        &mut self,
+        attribute: MeshAttribute,
+        values: impl Into<VertexAttributeValues>,
+    ) {
+        self.attributes
+            .insert(attribute.location, (attribute, values.into()));
+    }
+
+    pub fn remove_attribute(&mut self, attribute: &MeshAttribute) -> Option<VertexAttributeValues> {
+        self.attributes
+            .remove(&attribute.location)
+            .map(|(_, values)| values)
+    }
+
+    pub fn contains_attribute(&self, attribute: &MeshAttribute) -> bool {
+        self.attribute(attribute).is_some()
+    }
+
+    pub fn attribute(&self, attribute: &MeshAttribute) -> Option<&VertexAttributeValues> {
+        self.attributes
+            .get(&attribute.location)
+            .filter(|(other, _)| other == attribute)
+            .map(|(_, values)| values)
+    }
+
+    pub fn attribute_mut(
+        &mut self,
+        attribute: &MeshAttribute,
+    ) -> Option<&mut VertexAttributeValues> {
+        self.attributes
+            .get_mut(&attribute.location)
+            .filter(|(other, _)| other == attribute)
+            .map(|(_, values)| values)
+    }
+
+    /// All attributes in increasing location order.
+    pub fn attributes(&self) -> impl Iterator<Item = (&MeshAttribute, &VertexAttributeValues)> {
+        self.attributes
+            .values()
+            .map(|(attribute, values)| (attribute, values))
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.attributes
+            .values()
+            .map(|(_, values)| values.len())
+            .next()
+            .unwrap_or(0)
+    }
+
+    pub fn positions(&self) -> &[Vec3] {
+        self.vec3_attribute(&MeshAttribute::POSITION)
+    }
+
+    pub fn normals(&self) -> &[Vec3] {
+        self.vec3_attribute(&MeshAttribute::NORMAL)
+    }
+
+    pub fn tangents(&self) -> &[Vec3] {
+        self.vec3_attribute(&MeshAttribute::TANGENT)
+    }
+
+    pub fn uvs(&self) -> &[Vec2] {
+        match self.attribute(&MeshAttribute::UV_0) {
+            Some(VertexAttributeValues::Float32x2(values)) => bytemuck::cast_slice(values),
+            _ => &[],
+        }
+    }
+
+    fn vec3_attribute(&self, attribute: &MeshAttribute) -> &[Vec3] {
+        match self.attribute(attribute) {
+            Some(VertexAttributeValues::Float32x3(values)) => bytemuck::cast_slice(values),
+            _ => &[],
         }
     }
 
+    fn vec3_attribute_mut(&mut self, attribute: &MeshAttribute) -> &mut [Vec3] {
+        match self.attribute_mut(attribute) {
+            Some(VertexAttributeValues::Float32x3(values)) => bytemuck::cast_slice_mut(values),
+            _ => &mut [],
+        }
+    }
+
+    /// Appends the vertices and triangles of another mesh. Attributes that only one of the meshes has are filled with
+    /// zeros (white for colors) for the other mesh's vertices.
+    ///
+    /// Fails without changing the mesh if the meshes have different attributes at the same location.
+    pub fn append(&mut self, other: &Mesh) -> Result<()> {
+        for (attribute, _) in other.attributes() {
+            if let Some((own, _)) = self.attributes.get(&attribute.location) {
+                ensure!(
+                    own == attribute,
+                    "Cannot append mesh attribute {:?} to {:?} at location {}",
+                    attribute.name,
+                    own.name,
+                    attribute.location
+                );
+            }
+        }
+
+        let offset = self.vertex_count();
+        let len = offset + other.vertex_count();
+        for (attribute, values) in other.attributes() {
+            let (_, own) = self
+                .attributes
+                .entry(attribute.location)
+                .or_insert_with(|| {
+                    (
+                        attribute.clone(),
+                        VertexAttributeValues::zeroed(attribute.format, 0),
+                    )
+                });
+            own.pad(&attribute.default_value(), offset);
+            own.extend(values);
+        }
+        for (attribute, values) in self.attributes.values_mut() {
+            values.pad(&attribute.default_value(), len);
+        }
+        self.indices
+            .extend(other.indices.iter().map(|index| index + offset as u32));
+        self.regenerate_aabb();
+        Ok(())
+    }
+
+    /// The layout of the interleaved vertex buffer built by [`Mesh::vertex_buffer_data`].
+    pub fn vertex_layout(&self) -> MeshVertexLayout {
+        let mut stride = 0;
+        let attributes = self
+            .attributes
+            .values()
+            .map(|(attribute, _)| {
+                let offset = stride;
+                stride += attribute.format.size();
+                (attribute.clone(), offset)
+            })
+            .collect();
+        MeshVertexLayout { attributes, stride }
+    }
+
+    /// Interleaves the attributes into a vertex buffer, in increasing location order.
+    pub fn vertex_buffer_data(&self) -> Vec<u8> {
+        let layout = self.vertex_layout();
+        let mut data = vec![0; layout.stride as usize * self.vertex_count()];
+        for ((_, values), (attribute, offset)) in
+            self.attributes.values().zip(layout.attributes.iter())
+        {
+            let size = attribute.format.size() as usize;
+            for (vertex, value) in values.as_bytes().chunks_exact(size).enumerate() {
+                let start = vertex * layout.stride as usize + *offset as usize;
+                data[start..start + size].copy_from_slice(value);
+            }
+        }
+        data
+    }
+
     pub fn transformed(&self, transform: Transform) -> Self {
-        let mut vertices = self.vertices.clone();
+        let mut mesh = self.clone();
         let matrix = transform.matrix();
-        for vertex in &mut vertices {
-            vertex.position = matrix.transform_point3(vertex.position);
-            vertex.normal = matrix.transform_vector3(vertex.normal);
-            vertex.tangent = matrix.transform_vector3(vertex.tangent);
+        // normals stay perpendicular to the surface under non-uniform scaling with the inverse transpose
+        let normal_matrix = matrix.inverse().transpose();
+        for position in mesh.vec3_attribute_mut(&MeshAttribute::POSITION) {
+            *position = matrix.transform_point3(*position);
         }
-
-        Self {
-            vertices,
-            indices: self.indices.clone(),
-            aabb: self.aabb.transformed(transform),
+        for normal in mesh.vec3_attribute_mut(&MeshAttribute::NORMAL) {
+            *normal = normal_matrix.transform_vector3(*normal).normalize_or_zero();
+        }
+        for tangent in mesh.vec3_attribute_mut(&MeshAttribute::TANGENT) {
+            *tangent = matrix.transform_vector3(*tangent).normalize_or_zero();
         }
+        mesh.aabb = self.aabb.transformed(transform);
+        mesh
     }
 
     pub fn regenerate_aabb(&mut self) {
         let mut min = Vec3::splat(f32::INFINITY);
         let mut max = Vec3::splat(f32::NEG_INFINITY);
 
-        for vertex in &self.vertices {
-            min = min.min(vertex.position);
-            max = max.max(vertex.position);
+        for position in self.positions() {
+            min = min.min(*position);
+            max = max.max(*position);
         }
 
         self.aabb = Aabb::new(min, max);
     }
 
     pub fn recalculate_normals(&mut self) {
-        calculate_normals(&mut self.vertices, &self.indices);
+        let normals = compute_normals(self.positions(), &self.indices);
+        self.set_attribute(MeshAttribute::NORMAL, normals);
     }
 
+    /// Recalculates the tangents from the texture coordinates, or picks a tangent perpendicular to each normal if the
+    /// mesh has none.
     pub fn recalculate_tangents(&mut self) {
-        calculate_tangents(&mut self.vertices, &self.indices);
+        let tangents = if self.uvs().is_empty() {
+            fallback_tangents(self.normals())
+        } else {
+            compute_tangents(self.positions(), self.uvs(), &self.indices)
+        };
+        self.set_attribute(MeshAttribute::TANGENT, tangents);
     }
 }
 
@@ -92,10 +583,18 @@ impl<S: LoadSource> Default for ObjMeshLoader<S> {
 
 impl LoadFrom<PathAndFilesystem> for ObjMeshLoader<PathAndFilesystem> {
     type Asset = Mesh;
-
-    async fn load(&self, source: PathAndFilesystem, _commands: &Commands) -> Result<Mesh> {
-        let bytes = source.read()?;
-        let meshes = load_obj(&bytes)?;
+    type Settings = MeshSettings;
+
+    async fn load(
+        &self,
+        source: PathAndFilesystem,
+        settings: &MeshSettings,
+        _commands: &Commands,
+    ) -> Result<Mesh> {
+        let meshes = match source.read_processed_async::<ObjMeshProcessor>().await? {
+            AssetBytes::Processed(bytes) => read_processed_meshes(&bytes)?,
+            AssetBytes::Source(bytes) => settings.apply_all(load_obj(&bytes)?),
+        };
         if meshes.len() != 1 {
             bail!("expected exactly one mesh in OBJ file: {:?}", source.path);
         }
@@ -105,9 +604,16 @@ impl LoadFrom<PathAndFilesystem> for ObjMeshLoader<PathAndFilesystem> {
 
 impl LoadFrom<Vec<u8>> for ObjMeshLoader<Vec<u8>> {
     type Asset = Mesh;
-
-    async fn load(&self, source: Vec<u8>, _commands: &Commands) -> Result<Mesh> {
-        load_obj(&source)?
+    type Settings = MeshSettings;
+
+    async fn load(
+        &self,
+        source: Vec<u8>,
+        settings: &MeshSettings,
+        _commands: &Commands,
+    ) -> Result<Mesh> {
+        settings
+            .apply_all(load_obj(&source)?)
             .into_iter()
             .next()
             .ok_or_else(|| anyhow!("expected exactly one mesh in OBJ file"))
@@ -133,51 +639,48 @@ pub fn load_obj(bytes: &[u8]) -> Result<Vec<Mesh>> {
     let mut meshes = Vec::with_capacity(models.len());
 
     for model in &models {
-        let mesh = &model.mesh;
-
-        let mut vertices = Vec::with_capacity(mesh.positions.len() / 3);
-        let mut indices = Vec::with_capacity(mesh.indices.len());
-        let has_normals = !mesh.normals.is_empty();
-
-        for i in 0..mesh.positions.len() / 3 {
-            let position = [
-                mesh.positions[i * 3],
-                mesh.positions[i * 3 + 1],
-                mesh.positions[i * 3 + 2],
-            ];
-            let normal = if has_normals {
-                [
-                    mesh.normals[i * 3],
-                    mesh.normals[i * 3 + 1],
-                    mesh.normals[i * 3 + 2],
-                ]
-            } else {
-                [0.0, 0.0, 0.0]
-            };
-            let uv = [mesh.texcoords[i * 2], 1.0 - mesh.texcoords[i * 2 + 1]];
-
-            vertices.push(Vertex {
-                position: Vec3::from(position),
-                normal: Vec3::from(normal).normalize(),
-                tex_coords: Vec2::from(uv),
-                tangent: Vec3::ZERO,
-            });
-        }
-
-        for index in &mesh.indices {
-            indices.push(*index);
-        }
+        meshes.push(read_obj_mesh(&model.mesh)?);
+    }
 
-        if !has_normals {
-            calculate_normals(&mut vertices, &indices);
-        }
+    Ok(meshes)
+}
 
-        calculate_tangents(&mut vertices, &indices);
+/// Builds a mesh from an OBJ model, computing the normals and tangents that it doesn't have.
+pub fn read_obj_mesh(mesh: &tobj::Mesh) -> Result<Mesh> {
+    let positions = mesh
+        .positions
+        .chunks_exact(3)
+        .map(Vec3::from_slice)
+        .collect();
+    let normals = (!mesh.normals.is_empty()).then(|| {
+        mesh.normals
+            .chunks_exact(3)
+            .map(|normal| Vec3::from_slice(normal).normalize())
+            .collect()
+    });
+    let uvs = (!mesh.texcoords.is_empty()).then(|| {
+        mesh.texcoords
+            .chunks_exact(2)
+            .map(|uv| Vec2::new(uv[0], 1.0 - uv[1]))
+            .collect()
+    });
+
+    let mut primitive = Mesh::from_streams(positions, normals, uvs, None, mesh.indices.clone())?;
+    read_obj_colors(mesh, &mut primitive)?;
+    Ok(primitive)
+}
 
-        meshes.push(Mesh::new(vertices, indices));
+/// Reads the vertex colors that some OBJ exporters append to the vertex positions.
+pub fn read_obj_colors(mesh: &tobj::Mesh, primitive: &mut Mesh) -> Result<()> {
+    if mesh.vertex_color.is_empty() {
+        return Ok(());
     }
-
-    Ok(meshes)
+    let colors = mesh
+        .vertex_color
+        .chunks_exact(3)
+        .map(|color| [color[0], color[1], color[2], 1.0])
+        .collect::<Vec<_>>();
+    primitive.insert_attribute(MeshAttribute::COLOR, colors)
 }
 
 #[derive(Default)]
@@ -185,10 +688,18 @@ pub struct GltfMeshLoader<S: LoadSource>(std::marker::PhantomData<S>);
 
 impl LoadFrom<PathAndFilesystem> for GltfMeshLoader<PathBuf> {
     type Asset = Mesh;
-
-    async fn load(&self, source: PathAndFilesystem, _commands: &Commands) -> Result<Mesh> {
-        let bytes = source.read()?;
-        let meshes = load_gltf(&bytes)?;
+    type Settings = MeshSettings;
+
+    async fn load(
+        &self,
+        source: PathAndFilesystem,
+        settings: &MeshSettings,
+        _commands: &Commands,
+    ) -> Result<Mesh> {
+        let meshes = match source.read_processed_async::<GltfMeshProcessor>().await? {
+            AssetBytes::Processed(bytes) => read_processed_meshes(&bytes)?,
+            AssetBytes::Source(bytes) => settings.apply_all(load_gltf(&bytes)?),
+        };
         if meshes.len() != 1 {
             bail!("expected exactly one mesh in GLTF file: {:?}", source.path);
         }
@@ -198,9 +709,16 @@ impl LoadFrom<PathAndFilesystem> for GltfMeshLoader<PathBuf> {
 
 impl LoadFrom<Vec<u8>> for GltfMeshLoader<Vec<u8>> {
     type Asset = Mesh;
-
-    async fn load(&self, source: Vec<u8>, _commands: &Commands) -> Result<Mesh> {
-        load_gltf(&source)?
+    type Settings = MeshSettings;
+
+    async fn load(
+        &self,
+        source: Vec<u8>,
+        settings: &MeshSettings,
+        _commands: &Commands,
+    ) -> Result<Mesh> {
+        settings
+            .apply_all(load_gltf(&source)?)
             .into_iter()
             .next()
             .ok_or_else(|| anyhow!("expected exactly one mesh in GLTF file"))
@@ -217,102 +735,343 @@ pub fn load_gltf(bytes: &[u8]) -> Result<Vec<Mesh>> {
     let mut meshes = Vec::new();
 
     for mesh in gltf.meshes() {
-        let mut vertices = Vec::new();
-        let mut indices = Vec::new();
+        let mut combined = Mesh::default();
 
         for primitive in mesh.primitives() {
-            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
-
-            let mut positions = reader.read_positions().ok_or_else(|| {
-                anyhow!("mesh primitive does not have positions: {:?}", primitive)
-            })?;
-
-            let mut normals = reader
-                .read_normals()
-                .ok_or_else(|| anyhow!("mesh primitive does not have normals: {:?}", primitive))?;
-
-            let mut tex_coords = reader
-                .read_tex_coords(0)
-                .ok_or_else(|| anyhow!("mesh primitive does not have tex coords: {:?}", primitive))?
-                .into_f32();
-
-            let mut tangents = reader
-                .read_tangents()
-                .ok_or_else(|| anyhow!("mesh primitive does not have tangents: {:?}", primitive))?;
-
-            let indices_iter = reader
-                .read_indices()
-                .ok_or_else(|| anyhow!("mesh primitive does not have indices: {:?}", primitive))?
-                .into_u32();
-
-            let vertices_iter = positions
-                .by_ref()
-                .zip(normals.by_ref())
-                .zip(tex_coords.by_ref())
-                .zip(tangents.by_ref())
-                .map(|(((position, normal), tex_coord), tangent)| Vertex {
-                    position: Vec3::from(position),
-                    normal: Vec3::from(normal),
-                    tex_coords: Vec2::from(tex_coord),
-                    tangent: Vec4::from(tangent).truncate(),
-                });
+            combined.append(&read_gltf_primitive(&primitive, &buffers)?)?;
+        }
+
+        meshes.push(combined);
+    }
 
-            let vertex_offset = vertices.len() as u32;
+    Ok(meshes)
+}
+
+/// Builds a mesh from a glTF primitive. Only the positions are required: missing normals and tangents are computed,
+/// and primitives without indices are drawn in vertex order.
+pub fn read_gltf_primitive(
+    primitive: &gltf::Primitive<'_>,
+    buffers: &[gltf::buffer::Data],
+) -> Result<Mesh> {
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+    let positions = reader
+        .read_positions()
+        .ok_or_else(|| anyhow!("mesh primitive does not have positions: {:?}", primitive))?
+        .map(Vec3::from)
+        .collect::<Vec<_>>();
+    let normals = reader
+        .read_normals()
+        .map(|normals| normals.map(Vec3::from).collect());
+    let uvs = reader
+        .read_tex_coords(0)
+        .map(|uvs| uvs.into_f32().map(Vec2::from).collect());
+    let tangents = reader.read_tangents().map(|tangents| {
+        tangents
+            .map(|tangent| Vec4::from(tangent).truncate())
+            .collect()
+    });
+    let indices = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect(),
+        None => (0..positions.len() as u32).collect(),
+    };
+
+    let mut mesh = Mesh::from_streams(positions, normals, uvs, tangents, indices)?;
+    read_gltf_attributes(&reader, &mut mesh)?;
+    Ok(mesh)
+}
+
+/// Reads the optional attributes of a glTF primitive into its mesh: vertex colors, the second set of texture
+/// coordinates, and the joint indices and weights of skinned primitives.
+pub fn read_gltf_attributes<'a, 's, F>(
+    reader: &gltf::mesh::Reader<'a, 's, F>,
+    mesh: &mut Mesh,
+) -> Result<()>
+where
+    F: Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>,
+{
+    if let Some(colors) = reader.read_colors(0) {
+        mesh.insert_attribute(
+            MeshAttribute::COLOR,
+            colors.into_rgba_f32().collect::<Vec<_>>(),
+        )?;
+    }
+    if let Some(tex_coords) = reader.read_tex_coords(1) {
+        mesh.insert_attribute(
+            MeshAttribute::UV_1,
+            tex_coords.into_f32().collect::<Vec<_>>(),
+        )?;
+    }
+    if let (Some(joints), Some(weights)) = (reader.read_joints(0), reader.read_weights(0)) {
+        mesh.insert_attribute(
+            MeshAttribute::JOINT_INDICES,
+            joints.into_u16().collect::<Vec<_>>(),
+        )?;
+        mesh.insert_attribute(
+            MeshAttribute::JOINT_WEIGHTS,
+            weights.into_f32().collect::<Vec<_>>(),
+        )?;
+    }
+    Ok(())
+}
+
+const MESH_MAGIC: [u8; 4] = *b"WMSH";
+
+/// Packs the meshes of an OBJ file into vertex and index buffers that are loaded as they are.
+#[derive(Default)]
+pub struct ObjMeshProcessor;
+
+impl Process for ObjMeshProcessor {
+    const KIND: &'static str = "mesh";
+    const VERSION: u32 = 3;
 
-            vertices.extend(vertices_iter);
-            indices.extend(indices_iter.map(|index| index + vertex_offset));
+    type Settings = MeshSettings;
+
+    fn process(&self, source: &[u8], settings: &MeshSettings) -> Result<Vec<u8>> {
+        let meshes = settings.apply_all(load_obj(source)?);
+        Ok(write_processed_meshes(&meshes))
+    }
+}
+
+/// Packs the meshes of a glTF file into vertex and index buffers that are loaded as they are.
+#[derive(Default)]
+pub struct GltfMeshProcessor;
+
+impl Process for GltfMeshProcessor {
+    const KIND: &'static str = "mesh";
+    const VERSION: u32 = 3;
+
+    type Settings = MeshSettings;
+
+    fn process(&self, source: &[u8], settings: &MeshSettings) -> Result<Vec<u8>> {
+        let meshes = settings.apply_all(load_gltf(source)?);
+        Ok(write_processed_meshes(&meshes))
+    }
+}
+
+/// Import settings of meshes, read from the `[mesh]` section of their meta file.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct MeshSettings {
+    /// Uniform scale applied to the vertex positions, e.g. `0.01` for files authored in centimeters.
+    pub scale: f32,
+    /// Flip the V texture coordinate, for files authored with the texture origin in the bottom left corner.
+    pub flip_uvs: bool,
+}
+
+impl Default for MeshSettings {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            flip_uvs: false,
         }
+    }
+}
+
+impl LoaderSettings for MeshSettings {
+    const NAME: &'static str = "mesh";
+}
 
-        meshes.push(Mesh::new(vertices, indices));
+impl MeshSettings {
+    /// Applies the settings to a mesh loaded from its source file.
+    pub fn apply(&self, mesh: &mut Mesh) {
+        if self.scale != 1.0 {
+            for position in mesh.vec3_attribute_mut(&MeshAttribute::POSITION) {
+                *position *= self.scale;
+            }
+            mesh.regenerate_aabb();
+        }
+        if self.flip_uvs {
+            for attribute in [MeshAttribute::UV_0, MeshAttribute::UV_1] {
+                if let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute_mut(&attribute)
+                {
+                    for uv in uvs.iter_mut() {
+                        uv[1] = 1.0 - uv[1];
+                    }
+                }
+            }
+            mesh.recalculate_tangents();
+        }
     }
 
-    Ok(meshes)
+    pub fn apply_all(&self, mut meshes: Vec<Mesh>) -> Vec<Mesh> {
+        for mesh in meshes.iter_mut() {
+            self.apply(mesh);
+        }
+        meshes
+    }
+}
+
+fn write_processed_meshes(meshes: &[Mesh]) -> Vec<u8> {
+    let mut writer = ArtifactWriter::new(MESH_MAGIC);
+    writer.write_u32(meshes.len() as u32);
+    for mesh in meshes {
+        write_mesh(&mut writer, mesh);
+    }
+    writer.finish()
+}
+
+pub fn read_processed_meshes(bytes: &[u8]) -> Result<Vec<Mesh>> {
+    let mut reader = ArtifactReader::new(bytes, MESH_MAGIC)?;
+    let count = reader.read_u32()?;
+    (0..count).map(|_| read_mesh(&mut reader)).collect()
+}
+
+/// Writes the vertex attributes and indices of a mesh, e.g. as part of a larger artifact.
+pub fn write_mesh(writer: &mut ArtifactWriter, mesh: &Mesh) {
+    writer.write_u32(mesh.attributes.len() as u32);
+    for (attribute, values) in mesh.attributes() {
+        writer.write_bytes(attribute.name.as_bytes());
+        writer.write_u32(attribute.location);
+        writer.write_u8(
+            VertexFormat::ALL
+                .iter()
+                .position(|format| *format == attribute.format)
+                .unwrap() as u8,
+        );
+        writer.write_bytes(values.as_bytes());
+    }
+    writer.write_bytes(bytemuck::cast_slice(&mesh.indices));
+    for value in mesh
+        .aabb
+        .min
+        .to_array()
+        .into_iter()
+        .chain(mesh.aabb.max.to_array())
+    {
+        writer.write_f32(value);
+    }
+}
+
+pub fn read_mesh(reader: &mut ArtifactReader) -> Result<Mesh> {
+    let mut attributes = BTreeMap::new();
+    for _ in 0..reader.read_u32()? {
+        let name = String::from_utf8(reader.read_bytes()?.to_vec())?;
+        let location = reader.read_u32()?;
+        let format = *VertexFormat::ALL
+            .get(reader.read_u8()? as usize)
+            .ok_or_else(|| anyhow!("Invalid vertex format in mesh artifact"))?;
+        let values = VertexAttributeValues::from_bytes(format, reader.read_bytes()?)?;
+        let attribute = MeshAttribute {
+            name: Cow::Owned(name),
+            location,
+            format,
+        };
+        attributes.insert(location, (attribute, values));
+    }
+    let indices = read_buffer::<u32>(reader)?;
+    let mut aabb = [0.0; 6];
+    for value in aabb.iter_mut() {
+        *value = reader.read_f32()?;
+    }
+
+    Ok(Mesh {
+        attributes,
+        indices,
+        aabb: Aabb::new(Vec3::from_slice(&aabb[..3]), Vec3::from_slice(&aabb[3..])),
+    })
+}
+
+fn read_buffer<T: bytemuck::Pod>(reader: &mut ArtifactReader) -> Result<Vec<T>> {
+    let bytes = reader.read_bytes()?;
+    ensure!(
+        bytes.len() % std::mem::size_of::<T>() == 0,
+        "Invalid buffer size in mesh artifact"
+    );
+    // the buffer isn't necessarily aligned, so it's copied rather than cast in place
+    Ok(bytemuck::pod_collect_to_vec(bytes))
 }
 
 pub fn calculate_normals(vertices: &mut [Vertex], indices: &[u32]) {
-    for vertex in vertices.iter_mut() {
-        vertex.normal = Vec3::ZERO;
+    let positions = vertices
+        .iter()
+        .map(|vertex| vertex.position)
+        .collect::<Vec<_>>();
+    for (vertex, normal) in vertices
+        .iter_mut()
+        .zip(compute_normals(&positions, indices))
+    {
+        vertex.normal = normal;
+    }
+}
+
+pub fn calculate_tangents(vertices: &mut [Vertex], indices: &[u32]) {
+    let positions = vertices
+        .iter()
+        .map(|vertex| vertex.position)
+        .collect::<Vec<_>>();
+    let uvs = vertices
+        .iter()
+        .map(|vertex| vertex.tex_coords)
+        .collect::<Vec<_>>();
+    for (vertex, tangent) in vertices
+        .iter_mut()
+        .zip(compute_tangents(&positions, &uvs, indices))
+    {
+        vertex.tangent = tangent;
     }
+}
+
+fn compute_normals(positions: &[Vec3], indices: &[u32]) -> Vec<Vec3> {
+    let mut normals = vec![Vec3::ZERO; positions.len()];
 
     for c in indices.chunks_exact(3) {
         let i0 = c[0] as usize;
         let i1 = c[1] as usize;
         let i2 = c[2] as usize;
 
-        let v0 = vertices[i0].position;
-        let v1 = vertices[i1].position;
-        let v2 = vertices[i2].position;
+        let v0 = positions[i0];
+        let v1 = positions[i1];
+        let v2 = positions[i2];
 
         let normal = (v1 - v0).cross(v2 - v0).normalize();
 
-        vertices[i0].normal += normal;
-        vertices[i1].normal += normal;
-        vertices[i2].normal += normal;
+        normals[i0] += normal;
+        normals[i1] += normal;
+        normals[i2] += normal;
     }
 
-    for vertex in vertices.iter_mut() {
-        vertex.normal = vertex.normal.normalize();
+    for normal in normals.iter_mut() {
+        *normal = normal.normalize();
     }
+    normals
 }
 
-pub fn calculate_tangents(vertices: &mut [Vertex], indices: &[u32]) {
-    for vertex in vertices.iter_mut() {
-        vertex.tangent = Vec3::ZERO;
+/// A tangent perpendicular to each normal, for meshes without texture coordinates to derive the tangents from.
+fn fallback_tangents(normals: &[Vec3]) -> Vec<Vec3> {
+    normals
+        .iter()
+        .map(|normal| {
+            let axis = if normal.x.abs() < 0.9 {
+                Vec3::X
+            } else {
+                Vec3::Y
+            };
+            normal.cross(axis).normalize_or_zero()
+        })
+        .collect()
+}
+
+/// Averages the tangents of the triangles around every vertex. Meshes without texture coordinates get zero tangents.
+fn compute_tangents(positions: &[Vec3], uvs: &[Vec2], indices: &[u32]) -> Vec<Vec3> {
+    let mut tangents = vec![Vec3::ZERO; positions.len()];
+    if uvs.len() != positions.len() {
+        return tangents;
     }
 
-    let mut num_triangles = vec![0; vertices.len()];
+    let mut num_triangles = vec![0; positions.len()];
     for c in indices.chunks_exact(3) {
         let i0 = c[0] as usize;
         let i1 = c[1] as usize;
         let i2 = c[2] as usize;
 
-        let v0 = vertices[i0].position;
-        let v1 = vertices[i1].position;
-        let v2 = vertices[i2].position;
+        let v0 = positions[i0];
+        let v1 = positions[i1];
+        let v2 = positions[i2];
 
-        let uv0 = vertices[i0].tex_coords;
-        let uv1 = vertices[i1].tex_coords;
-        let uv2 = vertices[i2].tex_coords;
+        let uv0 = uvs[i0];
+        let uv1 = uvs[i1];
+        let uv2 = uvs[i2];
 
         let delta_pos1 = v1 - v0;
         let delta_pos2 = v2 - v0;
@@ -323,16 +1082,179 @@ pub fn calculate_tangents(vertices: &mut [Vertex], indices: &[u32]) {
         let r = 1.0 / (delta_uv1.x * delta_uv2.y - delta_uv1.y * delta_uv2.x);
         let tangent = (delta_pos1 * delta_uv2.y - delta_pos2 * delta_uv1.y) * r;
 
-        vertices[i0].tangent += tangent;
-        vertices[i1].tangent += tangent;
-        vertices[i2].tangent += tangent;
+        tangents[i0] += tangent;
+        tangents[i1] += tangent;
+        tangents[i2] += tangent;
 
         num_triangles[i0] += 1;
         num_triangles[i1] += 1;
         num_triangles[i2] += 1;
     }
 
-    for (vertex, num_triangles) in vertices.iter_mut().zip(num_triangles) {
-        vertex.tangent /= num_triangles as f32;
+    for (tangent, num_triangles) in tangents.iter_mut().zip(num_triangles) {
+        *tangent /= num_triangles as f32;
+    }
+    tangents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_and_interleave_attributes() {
+        let vertex = |x: f32| Vertex {
+            position: Vec3::new(x, 0.0, 0.0),
+            ..Default::default()
+        };
+        let temperature = MeshAttribute::new(
+            "temperature",
+            MeshAttribute::FIRST_CUSTOM_LOCATION,
+            VertexFormat::Float32,
+        );
+
+        let mut mesh = Mesh::new(vec![vertex(0.0), vertex(1.0), vertex(2.0)], vec![0, 1, 2])
+            .with_attribute(temperature.clone(), vec![10.0f32, 20.0, 30.0])
+            .unwrap();
+        let colored = Mesh::new(vec![vertex(3.0), vertex(4.0), vertex(5.0)], vec![0, 1, 2])
+            .with_attribute(MeshAttribute::COLOR, vec![[1.0, 0.0, 0.0, 1.0]; 3])
+            .unwrap();
+        mesh.append(&colored).unwrap();
+
+        // attributes that only one of the meshes had are padded with their defaults
+        assert_eq!(mesh.vertex_count(), 6);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(
+            mesh.attribute(&MeshAttribute::COLOR),
+            Some(&VertexAttributeValues::Float32x4(vec![
+                [1.0; 4],
+                [1.0; 4],
+                [1.0; 4],
+                [1.0, 0.0, 0.0, 1.0],
+                [1.0, 0.0, 0.0, 1.0],
+                [1.0, 0.0, 0.0, 1.0],
+            ]))
+        );
+        assert_eq!(
+            mesh.attribute(&temperature),
+            Some(&VertexAttributeValues::Float32(vec![
+                10.0, 20.0, 30.0, 0.0, 0.0, 0.0
+            ]))
+        );
+
+        let layout = mesh.vertex_layout();
+        assert_eq!(layout.stride, 64);
+        assert_eq!(layout.offset(&MeshAttribute::COLOR), Some(44));
+        assert_eq!(layout.offset(&temperature), Some(60));
+        let data = mesh.vertex_buffer_data();
+        assert_eq!(data.len(), 64 * 6);
+        let second_temperature: f32 = bytemuck::pod_read_unaligned(&data[64 + 60..64 + 64]);
+        assert_eq!(second_temperature, 20.0);
+
+        let read = read_processed_meshes(&write_processed_meshes(&[mesh.clone()])).unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].vertex_layout(), layout);
+        assert_eq!(
+            read[0].attributes().collect::<Vec<_>>(),
+            mesh.attributes().collect::<Vec<_>>()
+        );
+        assert_eq!(read[0].indices, mesh.indices);
+    }
+
+    #[test]
+    fn test_reject_mismatched_attributes() {
+        let mut mesh = Mesh::new(vec![Vertex::default(); 3], vec![0, 1, 2]);
+        // wrong format
+        assert!(
+            mesh.insert_attribute(MeshAttribute::COLOR, vec![[1.0f32; 3]; 3])
+                .is_err()
+        );
+        // wrong number of values
+        assert!(
+            mesh.insert_attribute(MeshAttribute::COLOR, vec![[1.0f32; 4]; 2])
+                .is_err()
+        );
+        assert!(!mesh.contains_attribute(&MeshAttribute::COLOR));
+
+        // different attributes at the same location leave the mesh as it was
+        let other = Mesh::new(vec![Vertex::default(); 3], vec![0, 1, 2])
+            .with_attribute(
+                MeshAttribute::new("heat", MeshAttribute::COLOR.location, VertexFormat::Float32),
+                vec![0.0f32; 3],
+            )
+            .unwrap();
+        let colored = mesh
+            .clone()
+            .with_attribute(MeshAttribute::COLOR, vec![[1.0f32; 4]; 3])
+            .unwrap();
+        let mut appended = colored.clone();
+        assert!(appended.append(&other).is_err());
+        assert_eq!(appended.vertex_count(), 3);
+        assert_eq!(appended.indices, colored.indices);
+        assert_eq!(
+            appended.attributes().collect::<Vec<_>>(),
+            colored.attributes().collect::<Vec<_>>()
+        );
+    }
+
+    /// Checks that a mesh without texture coordinates got unit normals pointing along +Z and tangents perpendicular
+    /// to them.
+    fn assert_computed_basis(mesh: &Mesh) {
+        assert!(!mesh.contains_attribute(&MeshAttribute::UV_0));
+        assert_eq!(mesh.normals().len(), 3);
+        for (normal, tangent) in mesh.normals().iter().zip(mesh.tangents()) {
+            assert!(normal.abs_diff_eq(Vec3::Z, 1e-6));
+            assert!((tangent.length() - 1.0).abs() < 1e-6);
+            assert!(tangent.dot(*normal).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_load_meshes_without_optional_attributes() {
+        // a triangle with only positions and indices
+        let gltf = r#"{
+            "asset": { "version": "2.0" },
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1 }] }],
+            "accessors": [
+                {
+                    "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                    "min": [0, 0, 0], "max": [1, 1, 0]
+                },
+                { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }
+            ],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 36, "byteLength": 6 }
+            ],
+            "buffers": [{
+                "byteLength": 44,
+                "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAA="
+            }]
+        }"#;
+        let meshes = load_gltf(gltf.as_bytes()).unwrap();
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].indices, vec![0, 1, 2]);
+        assert_computed_basis(&meshes[0]);
+
+        // the same triangle without texture coordinates or normals
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+        let meshes = load_obj(obj.as_bytes()).unwrap();
+        assert_eq!(meshes.len(), 1);
+        assert_computed_basis(&meshes[0]);
+
+        // texture coordinates without normals
+        let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nf 1/1 2/2 3/3\n";
+        let mesh = &load_obj(obj.as_bytes()).unwrap()[0];
+        assert_eq!(mesh.uvs(), &[Vec2::Y, Vec2::ONE, Vec2::ZERO]);
+        assert!(
+            mesh.normals()
+                .iter()
+                .all(|normal| normal.abs_diff_eq(Vec3::Z, 1e-6))
+        );
+        assert!(
+            mesh.tangents()
+                .iter()
+                .all(|tangent| tangent.abs_diff_eq(Vec3::X, 1e-6))
+        );
     }
 }
@@ -0,0 +1,950 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use glam::{DVec3, Vec3};
+use weaver_util::prelude::*;
+
+use crate::mesh::{Mesh, VertexAttributeValues, VertexFormat};
+
+/// The size of the FIFO cache that [`Mesh::optimize_overdraw`] and [`Mesh::vertex_cache_miss_ratio`] simulate, a
+/// conservative guess for current GPUs.
+const SIMULATED_CACHE_SIZE: usize = 16;
+
+/// The size of the LRU cache that [`Mesh::optimize_vertex_cache`] optimizes for.
+const OPTIMIZED_CACHE_SIZE: usize = 32;
+
+/// Collapses that would turn a triangle by more than this (as the cosine between its old and new normal) are rejected.
+const MIN_FLIP_COSINE: f64 = 0.25;
+
+/// How much more moving along the border or a seam of a mesh costs than moving across its surface.
+const BORDER_WEIGHT: f64 = 10.0;
+
+impl Mesh {
+    /// Merges vertices whose attributes are all equal, which meshes loaded with per-face attributes often have a lot
+    /// of. With a `tolerance` above zero, vertices are merged if every component of their floating point attributes
+    /// differs by at most that much. Vertices that no triangle uses are dropped.
+    pub fn weld_vertices(&mut self, tolerance: f32) {
+        let attributes = self
+            .attributes()
+            .map(|(_, values)| values.clone())
+            .collect::<Vec<_>>();
+        let positions = self.positions().to_vec();
+        let components = |vertex: usize| {
+            attributes
+                .iter()
+                .flat_map(|values| vertex_components(values, vertex))
+                .collect::<Vec<_>>()
+        };
+        let cell = |position: Vec3| {
+            if tolerance > 0.0 {
+                (position / tolerance).floor().as_i64vec3().to_array()
+            } else {
+                (position + Vec3::ZERO)
+                    .to_array()
+                    .map(|x| x.to_bits() as i64)
+            }
+        };
+        let neighbour_cells: &[i64] = if tolerance > 0.0 { &[-1, 0, 1] } else { &[0] };
+
+        // the kept vertices in every cell of a grid as large as the tolerance, with their components
+        let mut grid: FxHashMap<[i64; 3], Vec<(u32, Vec<Component>)>> = FxHashMap::default();
+        let mut kept = Vec::new();
+        let mut remap = Vec::with_capacity(self.vertex_count());
+        for vertex in 0..self.vertex_count() {
+            let own = components(vertex);
+            let [x, y, z] = cell(positions.get(vertex).copied().unwrap_or_default());
+            let mut found = None;
+            'search: for dx in neighbour_cells {
+                for dy in neighbour_cells {
+                    for dz in neighbour_cells {
+                        let Some(candidates) = grid.get(&[x + dx, y + dy, z + dz]) else {
+                            continue;
+                        };
+                        if let Some((index, _)) = candidates.iter().find(|(_, other)| {
+                            own.iter().zip(other).all(|(a, b)| a.is_near(b, tolerance))
+                        }) {
+                            found = Some(*index);
+                            break 'search;
+                        }
+                    }
+                }
+            }
+            let index = found.unwrap_or_else(|| {
+                kept.push(vertex as u32);
+                let index = kept.len() as u32 - 1;
+                grid.entry([x, y, z]).or_default().push((index, own));
+                index
+            });
+            remap.push(index);
+        }
+
+        for index in self.indices.iter_mut() {
+            *index = remap[*index as usize];
+        }
+        self.gather_vertices(&kept);
+        self.optimize_vertex_fetch();
+    }
+
+    /// Reorders the triangles so that consecutive triangles share vertices, which the GPU then only transforms once.
+    ///
+    /// Uses Tom Forsyth's "Linear-Speed Vertex Cache Optimisation".
+    pub fn optimize_vertex_cache(&mut self) {
+        self.indices = optimize_vertex_cache(&self.indices, self.vertex_count());
+    }
+
+    /// Reorders the triangles to draw the outside of the mesh first, so that more fragments behind it fail the depth
+    /// test. Call this after [`Mesh::optimize_vertex_cache`], whose ordering is kept within clusters of triangles.
+    ///
+    /// `threshold` is how much worse the vertex cache may get in exchange for smaller clusters, e.g. 1.05 for 5%.
+    pub fn optimize_overdraw(&mut self, threshold: f32) {
+        self.indices = optimize_overdraw(&self.indices, self.positions(), threshold);
+    }
+
+    /// Reorders the vertices by their first use in the index buffer, so that they're read from memory in order, and
+    /// drops the ones that no triangle uses.
+    pub fn optimize_vertex_fetch(&mut self) {
+        let mut remap = vec![u32::MAX; self.vertex_count()];
+        let mut order = Vec::with_capacity(self.vertex_count());
+        for index in self.indices.iter_mut() {
+            let new = &mut remap[*index as usize];
+            if *new == u32::MAX {
+                *new = order.len() as u32;
+                order.push(*index);
+            }
+            *index = *new;
+        }
+        self.gather_vertices(&order);
+    }
+
+    /// Runs [`Mesh::optimize_vertex_cache`], [`Mesh::optimize_overdraw`] and [`Mesh::optimize_vertex_fetch`], in the
+    /// order that lets each keep the gains of the previous one.
+    pub fn optimize(&mut self) {
+        self.optimize_vertex_cache();
+        self.optimize_overdraw(1.05);
+        self.optimize_vertex_fetch();
+    }
+
+    /// The average number of vertices that miss a FIFO cache of `cache_size` vertices per triangle, between 0.5 for
+    /// very regular meshes and 3 for the worst order.
+    pub fn vertex_cache_miss_ratio(&self, cache_size: usize) -> f32 {
+        let triangles = self.indices.len() / 3;
+        if triangles == 0 {
+            return 0.0;
+        }
+        let mut cache = FifoCache::new(self.vertex_count(), cache_size);
+        let misses = self
+            .indices
+            .iter()
+            .filter(|&&index| cache.access(index))
+            .count();
+        misses as f32 / triangles as f32
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Simplifies the mesh down to at most `target_triangle_count` triangles by collapsing the edges whose removal
+    /// changes its shape the least, measured with quadric error metrics (Garland and Heckbert, "Surface
+    /// Simplification Using Quadric Error Metrics").
+    ///
+    /// Edges collapse into one of their vertices, so the remaining vertices keep their attributes. Texture seams and
+    /// open borders are preserved where possible, and the mesh may end up with more triangles than the target if no
+    /// collapse is left that wouldn't fold it over itself.
+    pub fn simplify(&self, target_triangle_count: usize) -> Mesh {
+        let mut mesh = self.clone();
+        mesh.indices = Simplifier::new(self).run(target_triangle_count);
+        mesh.optimize_vertex_fetch();
+        mesh.regenerate_aabb();
+        mesh
+    }
+
+    /// Generates a chain of up to `levels` levels of detail, each simplified to `ratio` of the triangles of the
+    /// previous one, starting with the mesh itself. The chain ends early once simplification stops making progress.
+    /// Every level is [optimized](Mesh::optimize) for rendering.
+    pub fn generate_lods(&self, levels: usize, ratio: f32) -> Vec<Mesh> {
+        let mut lods: Vec<Mesh> = Vec::with_capacity(levels);
+        let mut first = self.clone();
+        first.optimize();
+        lods.push(first);
+        while lods.len() < levels {
+            let previous = lods.last().unwrap();
+            let previous_count = previous.triangle_count();
+            let target = (previous_count as f32 * ratio) as usize;
+            let mut lod = previous.simplify(target);
+            if lod.triangle_count() as f32 > previous_count as f32 * (1.0 + ratio) / 2.0 {
+                break;
+            }
+            lod.optimize();
+            lods.push(lod);
+        }
+        lods
+    }
+
+    /// Keeps only the given vertices, in the given order.
+    fn gather_vertices(&mut self, order: &[u32]) {
+        let attributes = self
+            .attributes()
+            .map(|(attribute, values)| (attribute.clone(), gather(values, order)))
+            .collect::<Vec<_>>();
+        // the old values are removed first, since every attribute must have as many values as the others
+        for (attribute, _) in attributes.iter() {
+            self.remove_attribute(attribute);
+        }
+        for (attribute, values) in attributes {
+            self.insert_attribute(attribute, values)
+                .expect("gathered attributes have the same length");
+        }
+    }
+}
+
+fn gather(values: &VertexAttributeValues, order: &[u32]) -> VertexAttributeValues {
+    let size = values.format().size() as usize;
+    let bytes = values.as_bytes();
+    let mut gathered = Vec::with_capacity(order.len() * size);
+    for &vertex in order {
+        let start = vertex as usize * size;
+        gathered.extend_from_slice(&bytes[start..start + size]);
+    }
+    VertexAttributeValues::from_bytes(values.format(), &gathered).unwrap()
+}
+
+#[derive(Clone, Copy)]
+enum Component {
+    Float(f32),
+    Integer(u32),
+}
+
+impl Component {
+    fn is_near(&self, other: &Self, tolerance: f32) -> bool {
+        match (self, other) {
+            (Self::Float(a), Self::Float(b)) => (a - b).abs() <= tolerance,
+            (Self::Integer(a), Self::Integer(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// The components of one vertex's value.
+fn vertex_components(values: &VertexAttributeValues, vertex: usize) -> Vec<Component> {
+    let size = values.format().size() as usize;
+    let bytes = &values.as_bytes()[vertex * size..(vertex + 1) * size];
+    match values.format() {
+        VertexFormat::Uint16x4 => bytes
+            .chunks_exact(2)
+            .map(|component| {
+                Component::Integer(u16::from_le_bytes([component[0], component[1]]) as u32)
+            })
+            .collect(),
+        VertexFormat::Uint32 => bytes
+            .chunks_exact(4)
+            .map(|component| Component::Integer(u32::from_le_bytes(component.try_into().unwrap())))
+            .collect(),
+        _ => bytes
+            .chunks_exact(4)
+            .map(|component| Component::Float(f32::from_le_bytes(component.try_into().unwrap())))
+            .collect(),
+    }
+}
+
+struct FifoCache {
+    timestamps: Vec<usize>,
+    time: usize,
+    size: usize,
+}
+
+impl FifoCache {
+    fn new(vertex_count: usize, size: usize) -> Self {
+        Self {
+            timestamps: vec![0; vertex_count],
+            // every vertex starts out evicted
+            time: size + 1,
+            size,
+        }
+    }
+
+    /// Returns true on a cache miss.
+    fn access(&mut self, vertex: u32) -> bool {
+        let timestamp = &mut self.timestamps[vertex as usize];
+        if self.time - *timestamp > self.size {
+            *timestamp = self.time;
+            self.time += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn clear(&mut self) {
+        self.time += self.size + 1;
+    }
+}
+
+fn vertex_score(cache_position: Option<usize>, remaining_triangles: u32) -> f32 {
+    if remaining_triangles == 0 {
+        return -1.0;
+    }
+    let mut score = match cache_position {
+        // the last triangle's vertices are scored lower, so that its neighbours don't all fan out from one vertex
+        Some(position) if position < 3 => 0.75,
+        Some(position) => {
+            (1.0 - (position - 3) as f32 / (OPTIMIZED_CACHE_SIZE - 3) as f32).powf(1.5)
+        }
+        None => 0.0,
+    };
+    // vertices with few triangles left are finished first, so they don't have to be loaded again later
+    score += 2.0 * (remaining_triangles as f32).powf(-0.5);
+    score
+}
+
+fn optimize_vertex_cache(indices: &[u32], vertex_count: usize) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 {
+        return indices.to_vec();
+    }
+
+    // the triangles of every vertex; the ones that haven't been emitted yet come first
+    let mut remaining = vec![0u32; vertex_count];
+    for &index in indices {
+        remaining[index as usize] += 1;
+    }
+    let mut offsets = Vec::with_capacity(vertex_count + 1);
+    offsets.push(0);
+    for &count in remaining.iter() {
+        offsets.push(offsets.last().unwrap() + count as usize);
+    }
+    let mut vertex_triangles = vec![0u32; indices.len()];
+    let mut filled = offsets.clone();
+    for (triangle, corners) in indices.chunks_exact(3).enumerate() {
+        for &vertex in corners {
+            vertex_triangles[filled[vertex as usize]] = triangle as u32;
+            filled[vertex as usize] += 1;
+        }
+    }
+
+    let mut cache_positions: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut vertex_scores = remaining
+        .iter()
+        .map(|&count| vertex_score(None, count))
+        .collect::<Vec<_>>();
+    let triangle_score = |corners: &[u32], vertex_scores: &[f32]| -> f32 {
+        corners
+            .iter()
+            .map(|&vertex| vertex_scores[vertex as usize])
+            .sum()
+    };
+    let mut triangle_scores = indices
+        .chunks_exact(3)
+        .map(|corners| triangle_score(corners, &vertex_scores))
+        .collect::<Vec<_>>();
+    let mut emitted = vec![false; triangle_count];
+
+    let mut output = Vec::with_capacity(indices.len());
+    let mut cache: Vec<u32> = Vec::with_capacity(OPTIMIZED_CACHE_SIZE + 3);
+    let mut next_unemitted = 0;
+    let mut best = None;
+    while output.len() < indices.len() {
+        let triangle = match best {
+            Some(triangle) => triangle,
+            // dead end: none of the cached vertices has triangles left
+            None => {
+                while emitted[next_unemitted] {
+                    next_unemitted += 1;
+                }
+                next_unemitted
+            }
+        };
+        emitted[triangle] = true;
+        let corners = &indices[triangle * 3..triangle * 3 + 3];
+        output.extend_from_slice(corners);
+
+        for &vertex in corners {
+            let vertex = vertex as usize;
+            let start = offsets[vertex];
+            let end = start + remaining[vertex] as usize;
+            let position = vertex_triangles[start..end]
+                .iter()
+                .position(|&other| other as usize == triangle)
+                .unwrap();
+            vertex_triangles.swap(start + position, end - 1);
+            remaining[vertex] -= 1;
+        }
+
+        // the triangle's vertices move to the front of the cache
+        let mut new_cache = corners.to_vec();
+        new_cache.extend(cache.iter().filter(|vertex| !corners.contains(vertex)));
+        for &evicted in new_cache.iter().skip(OPTIMIZED_CACHE_SIZE) {
+            cache_positions[evicted as usize] = None;
+            vertex_scores[evicted as usize] = vertex_score(None, remaining[evicted as usize]);
+        }
+        let touched = new_cache.clone();
+        new_cache.truncate(OPTIMIZED_CACHE_SIZE);
+        for (position, &vertex) in new_cache.iter().enumerate() {
+            cache_positions[vertex as usize] = Some(position);
+            vertex_scores[vertex as usize] =
+                vertex_score(Some(position), remaining[vertex as usize]);
+        }
+        cache = new_cache;
+
+        best = None;
+        let mut best_score = f32::MIN;
+        for vertex in touched {
+            let start = offsets[vertex as usize];
+            let end = start + remaining[vertex as usize] as usize;
+            for &other in &vertex_triangles[start..end] {
+                let other = other as usize;
+                let score = triangle_score(&indices[other * 3..other * 3 + 3], &vertex_scores);
+                triangle_scores[other] = score;
+                if score > best_score {
+                    best_score = score;
+                    best = Some(other);
+                }
+            }
+        }
+    }
+    output
+}
+
+fn optimize_overdraw(indices: &[u32], positions: &[Vec3], threshold: f32) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 {
+        return indices.to_vec();
+    }
+    let triangles = indices.chunks_exact(3).collect::<Vec<_>>();
+
+    // hard boundaries are where the cache is cold anyway: all three vertices of a triangle miss
+    let mut cache = FifoCache::new(positions.len(), SIMULATED_CACHE_SIZE);
+    let mut hard_boundaries = Vec::new();
+    for (triangle, corners) in triangles.iter().enumerate() {
+        let misses = corners
+            .iter()
+            .filter(|&&vertex| cache.access(vertex))
+            .count();
+        if misses == 3 {
+            hard_boundaries.push(triangle);
+        }
+    }
+    hard_boundaries.push(triangle_count);
+
+    // soft boundaries split hard clusters where restarting with a cold cache costs little
+    let mut cluster_starts = Vec::new();
+    let cluster_misses = |range: std::ops::Range<usize>, cache: &mut FifoCache| {
+        cache.clear();
+        range
+            .flat_map(|triangle| triangles[triangle].iter())
+            .filter(|&&vertex| cache.access(vertex))
+            .count()
+    };
+    for window in hard_boundaries.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        let cluster_ratio = cluster_misses(start..end, &mut cache) as f32 / (end - start) as f32;
+        let mut soft_start = start;
+        let mut misses = 0;
+        cache.clear();
+        cluster_starts.push(start);
+        for (offset, corners) in triangles[start..end].iter().enumerate() {
+            misses += corners
+                .iter()
+                .filter(|&&vertex| cache.access(vertex))
+                .count();
+            let next = start + offset + 1;
+            if next < end && misses as f32 / (next - soft_start) as f32 <= cluster_ratio * threshold
+            {
+                soft_start = next;
+                misses = 0;
+                cache.clear();
+                cluster_starts.push(soft_start);
+            }
+        }
+    }
+    cluster_starts.push(triangle_count);
+
+    // clusters that face away from the center of the mesh are on its outside, and likely to occlude the others
+    let face = |corners: &[u32]| {
+        let [a, b, c] = [0, 1, 2].map(|corner| positions[corners[corner] as usize]);
+        ((a + b + c) / 3.0, (b - a).cross(c - a))
+    };
+    let mut mesh_centroid = Vec3::ZERO;
+    let mut mesh_area = 0.0;
+    for corners in triangles.iter() {
+        let (centroid, normal) = face(corners);
+        let area = normal.length();
+        mesh_centroid += centroid * area;
+        mesh_area += area;
+    }
+    if mesh_area > 0.0 {
+        mesh_centroid /= mesh_area;
+    }
+
+    let mut clusters = cluster_starts
+        .windows(2)
+        .map(|window| {
+            let mut centroid = Vec3::ZERO;
+            let mut normal = Vec3::ZERO;
+            let mut area = 0.0;
+            for corners in &triangles[window[0]..window[1]] {
+                let (triangle_centroid, triangle_normal) = face(corners);
+                let triangle_area = triangle_normal.length();
+                centroid += triangle_centroid * triangle_area;
+                area += triangle_area;
+                normal += triangle_normal;
+            }
+            if area > 0.0 {
+                centroid /= area;
+            }
+            let sort_key = (centroid - mesh_centroid).dot(normal.normalize_or_zero());
+            (window[0]..window[1], sort_key)
+        })
+        .collect::<Vec<_>>();
+    clusters.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    clusters
+        .into_iter()
+        .flat_map(|(range, _)| range.flat_map(|triangle| triangles[triangle].iter().copied()))
+        .collect()
+}
+
+/// The sum of the squared distances to a set of planes, as a symmetric 4x4 matrix.
+#[derive(Clone, Copy, Default)]
+struct Quadric {
+    a2: f64,
+    ab: f64,
+    ac: f64,
+    ad: f64,
+    b2: f64,
+    bc: f64,
+    bd: f64,
+    c2: f64,
+    cd: f64,
+    d2: f64,
+}
+
+impl Quadric {
+    fn from_plane(normal: DVec3, point: DVec3, weight: f64) -> Self {
+        let (a, b, c) = (normal.x, normal.y, normal.z);
+        let d = -normal.dot(point);
+        Self {
+            a2: a * a * weight,
+            ab: a * b * weight,
+            ac: a * c * weight,
+            ad: a * d * weight,
+            b2: b * b * weight,
+            bc: b * c * weight,
+            bd: b * d * weight,
+            c2: c * c * weight,
+            cd: c * d * weight,
+            d2: d * d * weight,
+        }
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.a2 += other.a2;
+        self.ab += other.ab;
+        self.ac += other.ac;
+        self.ad += other.ad;
+        self.b2 += other.b2;
+        self.bc += other.bc;
+        self.bd += other.bd;
+        self.c2 += other.c2;
+        self.cd += other.cd;
+        self.d2 += other.d2;
+    }
+
+    fn error(&self, p: DVec3) -> f64 {
+        let (x, y, z) = (p.x, p.y, p.z);
+        self.a2 * x * x
+            + 2.0 * self.ab * x * y
+            + 2.0 * self.ac * x * z
+            + 2.0 * self.ad * x
+            + self.b2 * y * y
+            + 2.0 * self.bc * y * z
+            + 2.0 * self.bd * y
+            + self.c2 * z * z
+            + 2.0 * self.cd * z
+            + self.d2
+    }
+}
+
+/// A candidate collapse of one position into another, ordered by increasing cost.
+struct Collapse {
+    cost: f64,
+    from: u32,
+    to: u32,
+    /// The versions of both positions when the cost was computed, the collapse is stale once either changed.
+    versions: (u32, u32),
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// Edge collapse simplification on the positions of a mesh. Vertices with the same position, e.g. on both sides of
+/// a texture seam, collapse together.
+struct Simplifier {
+    triangles: Vec<[u32; 3]>,
+    removed: Vec<bool>,
+    live_triangles: usize,
+    /// The position of every vertex.
+    vertex_positions: Vec<u32>,
+    /// The location of every position.
+    points: Vec<DVec3>,
+    /// The triangles that use each position, including removed ones that haven't been cleaned up yet.
+    position_triangles: Vec<Vec<u32>>,
+    quadrics: Vec<Quadric>,
+    alive: Vec<bool>,
+    versions: Vec<u32>,
+}
+
+impl Simplifier {
+    fn new(mesh: &Mesh) -> Self {
+        let positions = mesh.positions();
+        let mut unique = FxHashMap::default();
+        let mut points = Vec::new();
+        let vertex_positions = positions
+            .iter()
+            .map(|position| {
+                let key = (*position + Vec3::ZERO).to_array().map(f32::to_bits);
+                *unique.entry(key).or_insert_with(|| {
+                    points.push(position.as_dvec3());
+                    points.len() as u32 - 1
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let triangles = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|corners| [corners[0], corners[1], corners[2]])
+            .collect::<Vec<_>>();
+        let mut this = Self {
+            removed: vec![false; triangles.len()],
+            live_triangles: triangles.len(),
+            position_triangles: vec![Vec::new(); points.len()],
+            quadrics: vec![Quadric::default(); points.len()],
+            alive: vec![true; points.len()],
+            versions: vec![0; points.len()],
+            triangles,
+            vertex_positions,
+            points,
+        };
+
+        let mut position_edges: FxHashMap<(u32, u32), u32> = FxHashMap::default();
+        let mut vertex_edges: FxHashMap<(u32, u32), u32> = FxHashMap::default();
+        for (triangle, corners) in this.triangles.iter().enumerate() {
+            let [a, b, c] = corners.map(|vertex| this.vertex_positions[vertex as usize]);
+            if a == b || b == c || c == a {
+                this.removed[triangle] = true;
+                this.live_triangles -= 1;
+                continue;
+            }
+            let (normal, area) = this.face_normal([a, b, c]);
+            let quadric = Quadric::from_plane(normal, this.points[a as usize], area);
+            for position in [a, b, c] {
+                this.position_triangles[position as usize].push(triangle as u32);
+                this.quadrics[position as usize].add(&quadric);
+            }
+            for (from, to) in [(0, 1), (1, 2), (2, 0)] {
+                let key = |a: u32, b: u32| (a.min(b), a.max(b));
+                *position_edges
+                    .entry(key([a, b, c][from], [a, b, c][to]))
+                    .or_default() += 1;
+                *vertex_edges
+                    .entry(key(corners[from], corners[to]))
+                    .or_default() += 1;
+            }
+        }
+
+        // borders and seams get planes perpendicular to the surface, so that collapses along them are cheaper than
+        // collapses that move them
+        for (triangle, corners) in this.triangles.iter().enumerate() {
+            if this.removed[triangle] {
+                continue;
+            }
+            let positions = corners.map(|vertex| this.vertex_positions[vertex as usize]);
+            let (normal, _) = this.face_normal(positions);
+            for (from, to) in [(0, 1), (1, 2), (2, 0)] {
+                let key = |a: u32, b: u32| (a.min(b), a.max(b));
+                let border = position_edges[&key(positions[from], positions[to])] == 1;
+                let seam = vertex_edges[&key(corners[from], corners[to])] == 1;
+                if !border && !seam {
+                    continue;
+                }
+                let start = this.points[positions[from] as usize];
+                let edge = this.points[positions[to] as usize] - start;
+                let plane_normal = edge.cross(normal).normalize_or_zero();
+                let quadric =
+                    Quadric::from_plane(plane_normal, start, edge.length_squared() * BORDER_WEIGHT);
+                this.quadrics[positions[from] as usize].add(&quadric);
+                this.quadrics[positions[to] as usize].add(&quadric);
+            }
+        }
+        this
+    }
+
+    fn face_normal(&self, positions: [u32; 3]) -> (DVec3, f64) {
+        let [a, b, c] = positions.map(|position| self.points[position as usize]);
+        let cross = (b - a).cross(c - a);
+        let length = cross.length();
+        if length > 0.0 {
+            (cross / length, length / 2.0)
+        } else {
+            (DVec3::ZERO, 0.0)
+        }
+    }
+
+    fn position_of(&self, vertex: u32) -> u32 {
+        self.vertex_positions[vertex as usize]
+    }
+
+    fn live_triangles_of(&self, position: u32) -> impl Iterator<Item = u32> + '_ {
+        self.position_triangles[position as usize]
+            .iter()
+            .copied()
+            .filter(|&triangle| !self.removed[triangle as usize])
+    }
+
+    fn neighbours(&self, position: u32) -> FxHashSet<u32> {
+        self.live_triangles_of(position)
+            .flat_map(|triangle| self.triangles[triangle as usize])
+            .map(|vertex| self.position_of(vertex))
+            .filter(|&other| other != position)
+            .collect()
+    }
+
+    fn push_collapses(&self, heap: &mut BinaryHeap<Collapse>, position: u32) {
+        for neighbour in self.neighbours(position) {
+            for (from, to) in [(position, neighbour), (neighbour, position)] {
+                let mut quadric = self.quadrics[from as usize];
+                quadric.add(&self.quadrics[to as usize]);
+                heap.push(Collapse {
+                    cost: quadric.error(self.points[to as usize]),
+                    from,
+                    to,
+                    versions: (self.versions[from as usize], self.versions[to as usize]),
+                });
+            }
+        }
+    }
+
+    /// Maps every vertex at `from` that's still used to the vertex at `to` it collapses into: the one it shares a
+    /// triangle with. Returns `None` if some vertex doesn't share a triangle with `to`, e.g. because the edge crosses
+    /// a texture seam.
+    fn vertex_mapping(&self, from: u32, to: u32) -> Option<FxHashMap<u32, u32>> {
+        let mut mapping = FxHashMap::default();
+        let mut used = FxHashSet::default();
+        for triangle in self.live_triangles_of(from) {
+            let corners = self.triangles[triangle as usize];
+            let from_vertex = corners
+                .into_iter()
+                .find(|&vertex| self.position_of(vertex) == from)
+                .unwrap();
+            used.insert(from_vertex);
+            if let Some(to_vertex) = corners
+                .into_iter()
+                .find(|&vertex| self.position_of(vertex) == to)
+            {
+                mapping.entry(from_vertex).or_insert(to_vertex);
+            }
+        }
+        (mapping.len() == used.len()).then_some(mapping)
+    }
+
+    fn is_valid(&self, from: u32, to: u32) -> bool {
+        // the positions both edges share must be the tips of the triangles on the edge, or the mesh gets pinched
+        let from_neighbours = self.neighbours(from);
+        let to_neighbours = self.neighbours(to);
+        let mut tips = FxHashSet::default();
+        for triangle in self.live_triangles_of(from) {
+            let positions =
+                self.triangles[triangle as usize].map(|vertex| self.position_of(vertex));
+            if positions.contains(&to) {
+                tips.extend(positions.into_iter().filter(|&p| p != from && p != to));
+            }
+        }
+        if from_neighbours
+            .intersection(&to_neighbours)
+            .any(|common| !tips.contains(common))
+        {
+            return false;
+        }
+
+        // the triangles that move must not fold over
+        for triangle in self.live_triangles_of(from) {
+            let positions =
+                self.triangles[triangle as usize].map(|vertex| self.position_of(vertex));
+            if positions.contains(&to) {
+                continue;
+            }
+            let (before, _) = self.face_normal(positions);
+            let (after, area) = self.face_normal(positions.map(|p| if p == from { to } else { p }));
+            if area == 0.0 || before.dot(after) < MIN_FLIP_COSINE {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn collapse(&mut self, from: u32, to: u32, mapping: &FxHashMap<u32, u32>) {
+        let triangles = self.live_triangles_of(from).collect::<Vec<_>>();
+        for triangle in triangles {
+            let corners = &mut self.triangles[triangle as usize];
+            for vertex in corners.iter_mut() {
+                if let Some(&mapped) = mapping.get(vertex) {
+                    *vertex = mapped;
+                }
+            }
+            let positions = corners.map(|vertex| self.vertex_positions[vertex as usize]);
+            if positions[0] == positions[1]
+                || positions[1] == positions[2]
+                || positions[2] == positions[0]
+            {
+                self.removed[triangle as usize] = true;
+                self.live_triangles -= 1;
+            } else {
+                self.position_triangles[to as usize].push(triangle);
+            }
+        }
+
+        let quadric = self.quadrics[from as usize];
+        self.quadrics[to as usize].add(&quadric);
+        self.alive[from as usize] = false;
+        self.position_triangles[from as usize] = Vec::new();
+        let removed = &self.removed;
+        self.position_triangles[to as usize].retain(|&triangle| !removed[triangle as usize]);
+        self.versions[to as usize] += 1;
+    }
+
+    fn run(mut self, target_triangle_count: usize) -> Vec<u32> {
+        let mut heap = BinaryHeap::new();
+        for position in 0..self.points.len() as u32 {
+            self.push_collapses(&mut heap, position);
+        }
+
+        while self.live_triangles > target_triangle_count {
+            let Some(Collapse {
+                from, to, versions, ..
+            }) = heap.pop()
+            else {
+                break;
+            };
+            if !self.alive[from as usize]
+                || !self.alive[to as usize]
+                || versions != (self.versions[from as usize], self.versions[to as usize])
+            {
+                continue;
+            }
+            let Some(mapping) = self.vertex_mapping(from, to) else {
+                continue;
+            };
+            if !self.is_valid(from, to) {
+                continue;
+            }
+            self.collapse(from, to, &mapping);
+            self.push_collapses(&mut heap, to);
+        }
+
+        self.triangles
+            .iter()
+            .zip(self.removed.iter())
+            .filter(|(_, removed)| !**removed)
+            .flat_map(|(corners, _)| *corners)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::*;
+    use crate::{
+        mesh::MeshAttribute,
+        primitive::{PlanePrimitive, Primitive, UvSpherePrimitive},
+    };
+
+    #[test]
+    fn test_weld_and_optimize() {
+        let mut plane = PlanePrimitive::new(Vec2::ONE)
+            .with_subdivisions(15, 15)
+            .generate_mesh();
+        let vertex_count = plane.vertex_count();
+
+        // unweld every triangle, then shuffle the triangles
+        let mut unwelded = Mesh::new(Vec::new(), Vec::new());
+        let triangles = plane.indices.chunks_exact(3).collect::<Vec<_>>();
+        for step in 0..triangles.len() {
+            let corners = triangles[step * 97 % triangles.len()];
+            let mut triangle = plane.clone();
+            triangle.indices = corners.to_vec();
+            triangle.optimize_vertex_fetch();
+            unwelded.append(&triangle).unwrap();
+        }
+        assert_eq!(unwelded.vertex_count(), triangles.len() * 3);
+
+        unwelded.weld_vertices(0.0);
+        assert_eq!(unwelded.vertex_count(), vertex_count);
+        let shuffled_ratio = unwelded.vertex_cache_miss_ratio(16);
+        unwelded.optimize();
+        assert!(unwelded.vertex_cache_miss_ratio(16) < shuffled_ratio * 0.6);
+        assert_eq!(unwelded.triangle_count(), triangles.len());
+
+        // nearly equal positions are merged with a tolerance
+        let mut nudged = plane.clone();
+        nudged
+            .insert_attribute(
+                MeshAttribute::POSITION,
+                plane
+                    .positions()
+                    .iter()
+                    .map(|position| *position + Vec3::X * 0.001)
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap();
+        plane.append(&nudged).unwrap();
+        plane.weld_vertices(0.0);
+        assert_eq!(plane.vertex_count(), vertex_count * 2);
+        plane.weld_vertices(0.01);
+        assert_eq!(plane.vertex_count(), vertex_count);
+    }
+
+    #[test]
+    fn test_simplify() {
+        // a flat plane can lose all but its corners
+        let plane = PlanePrimitive::new(Vec2::splat(2.0))
+            .with_subdivisions(9, 9)
+            .generate_mesh();
+        let simplified = plane.simplify(2);
+        assert_eq!(simplified.triangle_count(), 2);
+        assert_eq!(simplified.aabb, plane.aabb);
+
+        let sphere = UvSpherePrimitive::new(1.0, 32, 16).generate_mesh();
+        let lods = sphere.generate_lods(4, 0.5);
+        assert_eq!(lods.len(), 4);
+        for pair in lods.windows(2) {
+            assert!(pair[1].triangle_count() <= pair[0].triangle_count() / 2);
+        }
+        let last = lods.last().unwrap();
+        for position in last.positions() {
+            assert!((position.length() - 1.0).abs() < 1e-4);
+        }
+        // the seam keeps its texture coordinates on both sides
+        assert!(last.uvs().iter().any(|uv| uv.x == 0.0));
+        assert!(last.uvs().iter().any(|uv| uv.x == 1.0));
+        assert!((last.aabb.max - Vec3::ONE).max_element() < 0.2);
+    }
+}
@@ -0,0 +1,903 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use glam::{Vec2, Vec3};
+use weaver_util::prelude::*;
+
+use crate::{
+    mesh::{Mesh, Vertex},
+    transform::Transform,
+};
+
+/// A shape that can be turned into a [`Mesh`].
+///
+/// Triangles wind counter-clockwise when seen from outside, and the texture coordinates of the generated meshes run
+/// from the top left corner of a texture (`v` pointing down), as in glTF. Tangents point along increasing `u`.
+pub trait Primitive {
+    fn generate_mesh(&self) -> Mesh;
+}
+
+pub struct CubePrimitive {
+    pub side_length: f32,
+    pub wireframe: bool,
+}
+
+impl CubePrimitive {
+    pub fn new(side_length: f32, wireframe: bool) -> Self {
+        Self {
+            side_length,
+            wireframe,
+        }
+    }
+}
+
+pub fn create_unit_cube(wireframe: bool) -> Mesh {
+    let vertices = vec![
+        // Front face
+        Vertex {
+            position: Vec3::new(-1.0, -1.0, 1.0),
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(0.0, 1.0),
+        },
+        Vertex {
+            position: Vec3::new(1.0, -1.0, 1.0),
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(1.0, 1.0),
+        },
+        Vertex {
+            position: Vec3::new(1.0, 1.0, 1.0),
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(1.0, 0.0),
+        },
+        Vertex {
+            position: Vec3::new(-1.0, 1.0, 1.0),
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(0.0, 0.0),
+        },
+        // Back face
+        Vertex {
+            position: Vec3::new(-1.0, -1.0, -1.0),
+            normal: Vec3::new(0.0, 0.0, -1.0),
+            tangent: Vec3::new(-1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(1.0, 1.0),
+        },
+        Vertex {
+            position: Vec3::new(1.0, -1.0, -1.0),
+            normal: Vec3::new(0.0, 0.0, -1.0),
+            tangent: Vec3::new(-1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(0.0, 1.0),
+        },
+        Vertex {
+            position: Vec3::new(1.0, 1.0, -1.0),
+            normal: Vec3::new(0.0, 0.0, -1.0),
+            tangent: Vec3::new(-1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(0.0, 0.0),
+        },
+        Vertex {
+            position: Vec3::new(-1.0, 1.0, -1.0),
+            normal: Vec3::new(0.0, 0.0, -1.0),
+            tangent: Vec3::new(-1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(1.0, 0.0),
+        },
+        // Top face
+        Vertex {
+            position: Vec3::new(-1.0, 1.0, -1.0),
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(0.0, 0.0),
+        },
+        Vertex {
+            position: Vec3::new(1.0, 1.0, -1.0),
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(1.0, 0.0),
+        },
+        Vertex {
+            position: Vec3::new(1.0, 1.0, 1.0),
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(1.0, 1.0),
+        },
+        Vertex {
+            position: Vec3::new(-1.0, 1.0, 1.0),
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(0.0, 1.0),
+        },
+        // Bottom face
+        Vertex {
+            position: Vec3::new(-1.0, -1.0, -1.0),
+            normal: Vec3::new(0.0, -1.0, 0.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(0.0, 1.0),
+        },
+        Vertex {
+            position: Vec3::new(1.0, -1.0, -1.0),
+            normal: Vec3::new(0.0, -1.0, 0.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(1.0, 1.0),
+        },
+        Vertex {
+            position: Vec3::new(1.0, -1.0, 1.0),
+            normal: Vec3::new(0.0, -1.0, 0.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(1.0, 0.0),
+        },
+        Vertex {
+            position: Vec3::new(-1.0, -1.0, 1.0),
+            normal: Vec3::new(0.0, -1.0, 0.0),
+            tangent: Vec3::new(1.0, 0.0, 0.0),
+            tex_coords: Vec2::new(0.0, 0.0),
+        },
+        // Right face
+        Vertex {
+            position: Vec3::new(1.0, -1.0, -1.0),
+            normal: Vec3::new(1.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 0.0, -1.0),
+            tex_coords: Vec2::new(1.0, 1.0),
+        },
+        Vertex {
+            position: Vec3::new(1.0, 1.0, -1.0),
+            normal: Vec3::new(1.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 0.0, -1.0),
+            tex_coords: Vec2::new(1.0, 0.0),
+        },
+        Vertex {
+            position: Vec3::new(1.0, 1.0, 1.0),
+            normal: Vec3::new(1.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 0.0, -1.0),
+            tex_coords: Vec2::new(0.0, 0.0),
+        },
+        Vertex {
+            position: Vec3::new(1.0, -1.0, 1.0),
+            normal: Vec3::new(1.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 0.0, -1.0),
+            tex_coords: Vec2::new(0.0, 1.0),
+        },
+        // Left face
+        Vertex {
+            position: Vec3::new(-1.0, -1.0, -1.0),
+            normal: Vec3::new(-1.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 0.0, 1.0),
+            tex_coords: Vec2::new(0.0, 1.0),
+        },
+        Vertex {
+            position: Vec3::new(-1.0, 1.0, -1.0),
+            normal: Vec3::new(-1.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 0.0, 1.0),
+            tex_coords: Vec2::new(0.0, 0.0),
+        },
+        Vertex {
+            position: Vec3::new(-1.0, 1.0, 1.0),
+            normal: Vec3::new(-1.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 0.0, 1.0),
+            tex_coords: Vec2::new(1.0, 0.0),
+        },
+        Vertex {
+            position: Vec3::new(-1.0, -1.0, 1.0),
+            normal: Vec3::new(-1.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 0.0, 1.0),
+            tex_coords: Vec2::new(1.0, 1.0),
+        },
+    ];
+
+    #[rustfmt::skip]
+    let indices = if wireframe {
+        vec![
+            // Front face
+            0, 1, 1, 2, 2, 3, 3, 0, 
+            // Back face
+            4, 5, 5, 6, 6, 7, 7, 4, 
+            // Top face
+            8, 9, 9, 10, 10, 11, 11, 8, 
+            // Bottom face
+            12, 13, 13, 14, 14, 15, 15, 12, 
+            // Right face
+            16, 17, 17, 18, 18, 19, 19, 16, 
+            // Left face
+            20, 21, 21, 22, 22, 23, 23, 20,
+        ]
+    } else {
+        vec![
+            // Front face
+            0, 1, 2, 2, 3, 0, 
+            // Back face
+            4, 6, 5, 6, 4, 7, 
+            // Top face
+            8, 10, 9, 10, 8, 11, 
+            // Bottom face
+            12, 13, 14, 14, 15, 12, 
+            // Right face
+            16, 17, 18, 18, 19, 16, 
+            // Left face
+            20, 22, 21, 22, 20, 23,
+        ]
+    };
+
+    Mesh::new(vertices, indices)
+}
+
+impl Primitive for CubePrimitive {
+    fn generate_mesh(&self) -> Mesh {
+        create_unit_cube(self.wireframe)
+            .transformed(Transform::from_scale(Vec3::splat(self.side_length / 2.0)))
+    }
+}
+
+/// A sphere made of rings of latitude and longitude, with the poles on the Y axis.
+///
+/// The texture wraps around the sphere once, with its top row at the north pole.
+pub struct UvSpherePrimitive {
+    pub radius: f32,
+    /// The number of segments around the Y axis.
+    pub sectors: u32,
+    /// The number of segments from pole to pole.
+    pub stacks: u32,
+}
+
+impl UvSpherePrimitive {
+    pub fn new(radius: f32, sectors: u32, stacks: u32) -> Self {
+        Self {
+            radius,
+            sectors,
+            stacks,
+        }
+    }
+}
+
+impl Primitive for UvSpherePrimitive {
+    fn generate_mesh(&self) -> Mesh {
+        let stacks = self.stacks.max(2);
+        let profile = (0..=stacks)
+            .map(|stack| {
+                let v = stack as f32 / stacks as f32;
+                let normal = Vec2::from_angle(FRAC_PI_2 - v * PI);
+                ProfilePoint {
+                    radius: normal.x * self.radius,
+                    y: normal.y * self.radius,
+                    normal,
+                    v,
+                }
+            })
+            .collect::<Vec<_>>();
+        revolve(&profile, self.sectors)
+    }
+}
+
+/// A sphere made by subdividing the faces of an icosahedron, which spreads its vertices more evenly than a
+/// [`UvSpherePrimitive`].
+///
+/// Texture coordinates are the same as a [`UvSpherePrimitive`]'s; vertices on the seam and at the poles are
+/// duplicated so that no triangle stretches across the whole texture.
+pub struct IcospherePrimitive {
+    pub radius: f32,
+    /// How often the faces are split into four. Every subdivision quadruples the number of triangles.
+    pub subdivisions: u32,
+}
+
+impl IcospherePrimitive {
+    pub fn new(radius: f32, subdivisions: u32) -> Self {
+        Self {
+            radius,
+            subdivisions,
+        }
+    }
+}
+
+impl Primitive for IcospherePrimitive {
+    fn generate_mesh(&self) -> Mesh {
+        let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+        let mut directions = vec![
+            Vec3::new(-1.0, t, 0.0),
+            Vec3::new(1.0, t, 0.0),
+            Vec3::new(-1.0, -t, 0.0),
+            Vec3::new(1.0, -t, 0.0),
+            Vec3::new(0.0, -1.0, t),
+            Vec3::new(0.0, 1.0, t),
+            Vec3::new(0.0, -1.0, -t),
+            Vec3::new(0.0, 1.0, -t),
+            Vec3::new(t, 0.0, -1.0),
+            Vec3::new(t, 0.0, 1.0),
+            Vec3::new(-t, 0.0, -1.0),
+            Vec3::new(-t, 0.0, 1.0),
+        ]
+        .into_iter()
+        .map(Vec3::normalize)
+        .collect::<Vec<_>>();
+        #[rustfmt::skip]
+        let mut triangles: Vec<[u32; 3]> = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        for _ in 0..self.subdivisions {
+            // edges are shared by two triangles, which must share their midpoint too
+            let mut midpoints = FxHashMap::default();
+            let mut midpoint = |a: u32, b: u32| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    directions.push((directions[a as usize] + directions[b as usize]).normalize());
+                    directions.len() as u32 - 1
+                })
+            };
+            triangles = triangles
+                .into_iter()
+                .flat_map(|[a, b, c]| {
+                    let ab = midpoint(a, b);
+                    let bc = midpoint(b, c);
+                    let ca = midpoint(c, a);
+                    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        let uv = |direction: Vec3| {
+            let u = (-direction.z).atan2(direction.x).rem_euclid(TAU) / TAU;
+            let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+            Vec2::new(u, v)
+        };
+        let vertex = |direction: Vec3, uv: Vec2| {
+            let angle = uv.x * TAU;
+            Vertex {
+                position: direction * self.radius,
+                normal: direction,
+                tangent: Vec3::new(-angle.sin(), 0.0, -angle.cos()),
+                tex_coords: uv,
+            }
+        };
+
+        let mut vertices = directions
+            .iter()
+            .map(|&direction| vertex(direction, uv(direction)))
+            .collect::<Vec<_>>();
+        // the copies of vertices that needed different texture coordinates for some triangles
+        let mut copies = FxHashMap::default();
+        let mut indices = Vec::with_capacity(triangles.len() * 3);
+        for triangle in triangles {
+            let mut uvs = triangle.map(|index| vertices[index as usize].tex_coords);
+            // triangles that cross the seam get the `u` of their vertices on the far side shifted past 1
+            let max_u = uvs.iter().map(|uv| uv.x).fold(0.0, f32::max);
+            for uv in uvs.iter_mut() {
+                if max_u - uv.x > 0.5 {
+                    uv.x += 1.0;
+                }
+            }
+            // the `u` of a pole is meaningless, so it takes the one between its neighbours
+            for corner in 0..3 {
+                if directions[triangle[corner] as usize].y.abs() > 1.0 - 1e-6 {
+                    uvs[corner].x = (uvs[(corner + 1) % 3].x + uvs[(corner + 2) % 3].x) / 2.0;
+                }
+            }
+
+            for (index, uv) in triangle.into_iter().zip(uvs) {
+                if vertices[index as usize].tex_coords == uv {
+                    indices.push(index);
+                    continue;
+                }
+                let copy = *copies
+                    .entry((index, uv.x.to_bits(), uv.y.to_bits()))
+                    .or_insert_with(|| {
+                        vertices.push(vertex(directions[index as usize], uv));
+                        vertices.len() as u32 - 1
+                    });
+                indices.push(copy);
+            }
+        }
+
+        Mesh::new(vertices, indices)
+    }
+}
+
+/// A flat rectangle on the XZ plane, facing up, split into a grid of quads.
+///
+/// The texture covers the whole plane once, with its top row along the -Z edge.
+pub struct PlanePrimitive {
+    /// The extent along the X and Z axes.
+    pub size: Vec2,
+    /// How many cuts split the plane along the X and Z axes. Without any, the plane is a single quad.
+    pub subdivisions: [u32; 2],
+}
+
+impl PlanePrimitive {
+    pub fn new(size: Vec2) -> Self {
+        Self {
+            size,
+            subdivisions: [0, 0],
+        }
+    }
+
+    pub fn with_subdivisions(mut self, x: u32, z: u32) -> Self {
+        self.subdivisions = [x, z];
+        self
+    }
+}
+
+impl Primitive for PlanePrimitive {
+    fn generate_mesh(&self) -> Mesh {
+        let columns = self.subdivisions[0] + 1;
+        let rows = self.subdivisions[1] + 1;
+        let mut vertices = Vec::with_capacity(((columns + 1) * (rows + 1)) as usize);
+        for row in 0..=rows {
+            for column in 0..=columns {
+                let uv = Vec2::new(column as f32 / columns as f32, row as f32 / rows as f32);
+                let position = (uv - 0.5) * self.size;
+                vertices.push(Vertex {
+                    position: Vec3::new(position.x, 0.0, position.y),
+                    normal: Vec3::Y,
+                    tangent: Vec3::X,
+                    tex_coords: uv,
+                });
+            }
+        }
+
+        let mut indices = Vec::with_capacity((columns * rows * 6) as usize);
+        for row in 0..rows {
+            for column in 0..columns {
+                let top_left = row * (columns + 1) + column;
+                let bottom_left = top_left + columns + 1;
+                indices.extend([
+                    top_left,
+                    bottom_left,
+                    bottom_left + 1,
+                    top_left,
+                    bottom_left + 1,
+                    top_left + 1,
+                ]);
+            }
+        }
+
+        Mesh::new(vertices, indices)
+    }
+}
+
+/// A cylinder around the Y axis, centered on the origin, with flat caps.
+///
+/// The texture wraps around the side once; each cap maps the texture's inscribed circle.
+pub struct CylinderPrimitive {
+    pub radius: f32,
+    pub height: f32,
+    /// The number of segments around the Y axis.
+    pub sectors: u32,
+    /// The number of segments along the side, from top to bottom.
+    pub stacks: u32,
+}
+
+impl CylinderPrimitive {
+    pub fn new(radius: f32, height: f32, sectors: u32) -> Self {
+        Self {
+            radius,
+            height,
+            sectors,
+            stacks: 1,
+        }
+    }
+}
+
+impl Primitive for CylinderPrimitive {
+    fn generate_mesh(&self) -> Mesh {
+        let stacks = self.stacks.max(1);
+        let half_height = self.height / 2.0;
+        let profile = (0..=stacks)
+            .map(|stack| {
+                let v = stack as f32 / stacks as f32;
+                ProfilePoint {
+                    radius: self.radius,
+                    y: half_height - v * self.height,
+                    normal: Vec2::X,
+                    v,
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut mesh = revolve(&profile, self.sectors);
+        // the caps have the same standard attributes as the side
+        mesh.append(&disc(self.radius, half_height, self.sectors, true))
+            .unwrap();
+        mesh.append(&disc(self.radius, -half_height, self.sectors, false))
+            .unwrap();
+        mesh
+    }
+}
+
+/// A cone around the Y axis, centered on the origin, with its tip at the top and a flat base.
+///
+/// The texture wraps around the side once, with its top row at the tip; the base maps the texture's inscribed circle.
+pub struct ConePrimitive {
+    pub radius: f32,
+    pub height: f32,
+    /// The number of segments around the Y axis.
+    pub sectors: u32,
+}
+
+impl ConePrimitive {
+    pub fn new(radius: f32, height: f32, sectors: u32) -> Self {
+        Self {
+            radius,
+            height,
+            sectors,
+        }
+    }
+}
+
+impl Primitive for ConePrimitive {
+    fn generate_mesh(&self) -> Mesh {
+        let half_height = self.height / 2.0;
+        // the side leans inwards, so its normal leans upwards
+        let normal = Vec2::new(self.height, self.radius).normalize();
+        let profile = [
+            ProfilePoint {
+                radius: 0.0,
+                y: half_height,
+                normal,
+                v: 0.0,
+            },
+            ProfilePoint {
+                radius: self.radius,
+                y: -half_height,
+                normal,
+                v: 1.0,
+            },
+        ];
+        let mut mesh = revolve(&profile, self.sectors);
+        mesh.append(&disc(self.radius, -half_height, self.sectors, false))
+            .unwrap();
+        mesh
+    }
+}
+
+/// A cylinder around the Y axis with hemispheres instead of caps, centered on the origin.
+///
+/// The texture wraps around the capsule once, from the top pole to the bottom pole.
+pub struct CapsulePrimitive {
+    pub radius: f32,
+    /// The length of the cylindrical middle, i.e. the distance between the centers of the hemispheres.
+    pub length: f32,
+    /// The number of segments around the Y axis.
+    pub sectors: u32,
+    /// The number of segments of each hemisphere, from its pole to the cylinder.
+    pub rings: u32,
+}
+
+impl CapsulePrimitive {
+    pub fn new(radius: f32, length: f32, sectors: u32, rings: u32) -> Self {
+        Self {
+            radius,
+            length,
+            sectors,
+            rings,
+        }
+    }
+}
+
+impl Primitive for CapsulePrimitive {
+    fn generate_mesh(&self) -> Mesh {
+        let rings = self.rings.max(1);
+        let half_length = self.length / 2.0;
+        // `v` grows with the distance along the outline, so that the texture isn't stretched on the cylinder
+        let outline_length = PI * self.radius + self.length;
+        let hemisphere = |offset: f32, from: f32, distance: f32| {
+            (0..=rings).map(move |ring| {
+                let angle = from + FRAC_PI_2 * ring as f32 / rings as f32;
+                let normal = Vec2::from_angle(FRAC_PI_2 - angle);
+                ProfilePoint {
+                    radius: normal.x * self.radius,
+                    y: normal.y * self.radius + offset,
+                    normal,
+                    v: (distance + (angle - from) * self.radius) / outline_length,
+                }
+            })
+        };
+        let profile = hemisphere(half_length, 0.0, 0.0)
+            .chain(hemisphere(
+                -half_length,
+                FRAC_PI_2,
+                FRAC_PI_2 * self.radius + self.length,
+            ))
+            .collect::<Vec<_>>();
+        revolve(&profile, self.sectors)
+    }
+}
+
+/// A ring around the Y axis, centered on the origin.
+///
+/// The texture wraps around the ring once along `u`, and around its tube once along `v`, starting at the top.
+pub struct TorusPrimitive {
+    /// The distance from the center of the torus to the center of its tube.
+    pub major_radius: f32,
+    /// The radius of the tube.
+    pub minor_radius: f32,
+    /// The number of segments around the Y axis.
+    pub major_segments: u32,
+    /// The number of segments around the tube.
+    pub minor_segments: u32,
+}
+
+impl TorusPrimitive {
+    pub fn new(
+        major_radius: f32,
+        minor_radius: f32,
+        major_segments: u32,
+        minor_segments: u32,
+    ) -> Self {
+        Self {
+            major_radius,
+            minor_radius,
+            major_segments,
+            minor_segments,
+        }
+    }
+}
+
+impl Primitive for TorusPrimitive {
+    fn generate_mesh(&self) -> Mesh {
+        let segments = self.minor_segments.max(3);
+        let profile = (0..=segments)
+            .map(|segment| {
+                let v = segment as f32 / segments as f32;
+                // starting at the top of the tube and going outwards first
+                let normal = Vec2::from_angle(FRAC_PI_2 - v * TAU);
+                ProfilePoint {
+                    radius: self.major_radius + normal.x * self.minor_radius,
+                    y: normal.y * self.minor_radius,
+                    normal,
+                    v,
+                }
+            })
+            .collect::<Vec<_>>();
+        revolve(&profile, self.major_segments)
+    }
+}
+
+/// Connected line segments through a list of points, drawn with a line list topology: every pair of indices is a
+/// segment.
+///
+/// `u` runs along the line by distance, from 0 at the first point to 1 at the last (or back at the first point of a
+/// closed line). Tangents point along the line, normals in an arbitrary direction perpendicular to it.
+pub struct PolylinePrimitive {
+    pub points: Vec<Vec3>,
+    /// Whether the last point is connected back to the first one.
+    pub closed: bool,
+}
+
+impl PolylinePrimitive {
+    pub fn new(points: Vec<Vec3>, closed: bool) -> Self {
+        Self { points, closed }
+    }
+
+    /// A single segment.
+    pub fn line(start: Vec3, end: Vec3) -> Self {
+        Self::new(vec![start, end], false)
+    }
+}
+
+impl Primitive for PolylinePrimitive {
+    fn generate_mesh(&self) -> Mesh {
+        let count = self.points.len();
+        let segment_count = match (self.closed, count) {
+            (_, 0 | 1) => 0,
+            (true, _) => count,
+            (false, _) => count - 1,
+        };
+        let segment = |index: usize| self.points[(index + 1) % count] - self.points[index];
+
+        let mut vertices = Vec::with_capacity(count + self.closed as usize);
+        let mut distance = 0.0;
+        for (index, &position) in self.points.iter().enumerate() {
+            // the direction at a corner is the average of its segments'
+            let incoming = (index > 0 || self.closed) && segment_count > 0;
+            let outgoing = index < segment_count;
+            let mut direction = Vec3::ZERO;
+            if incoming {
+                direction += segment((index + count - 1) % count).normalize_or_zero();
+            }
+            if outgoing {
+                direction += segment(index).normalize_or_zero();
+            }
+            let tangent = direction.try_normalize().unwrap_or(Vec3::X);
+            vertices.push(Vertex {
+                position,
+                normal: tangent.any_orthonormal_vector(),
+                tangent,
+                tex_coords: Vec2::new(distance, 0.0),
+            });
+            if outgoing {
+                distance += segment(index).length();
+            }
+        }
+        // closed lines end on a copy of their first point, so that `u` can reach 1
+        if self.closed && segment_count > 0 {
+            let first = vertices[0];
+            vertices.push(Vertex {
+                tex_coords: Vec2::new(distance, 0.0),
+                ..first
+            });
+        }
+        if distance > 0.0 {
+            for vertex in vertices.iter_mut() {
+                vertex.tex_coords.x /= distance;
+            }
+        }
+
+        let indices = (0..segment_count as u32)
+            .flat_map(|index| [index, index + 1])
+            .collect();
+        Mesh::new(vertices, indices)
+    }
+}
+
+/// A point of the outline that [`revolve`] turns around the Y axis.
+struct ProfilePoint {
+    /// The distance from the Y axis.
+    radius: f32,
+    y: f32,
+    /// The normal in the plane of the outline: `x` points away from the Y axis.
+    normal: Vec2,
+    v: f32,
+}
+
+/// Sweeps an outline, given from top to bottom, around the Y axis. Quads that collapse into triangles where the
+/// outline touches the axis lose their degenerate half.
+fn revolve(profile: &[ProfilePoint], sectors: u32) -> Mesh {
+    let sectors = sectors.max(3);
+    let columns = sectors + 1;
+    let mut vertices = Vec::with_capacity(profile.len() * columns as usize);
+    for point in profile {
+        for sector in 0..=sectors {
+            let u = sector as f32 / sectors as f32;
+            let (sin, cos) = (u * TAU).sin_cos();
+            vertices.push(Vertex {
+                position: Vec3::new(point.radius * cos, point.y, -point.radius * sin),
+                normal: Vec3::new(point.normal.x * cos, point.normal.y, -point.normal.x * sin),
+                tangent: Vec3::new(-sin, 0.0, -cos),
+                tex_coords: Vec2::new(u, point.v),
+            });
+        }
+    }
+
+    let mut indices = Vec::new();
+    for (row, pair) in profile.windows(2).enumerate() {
+        let [top, bottom] = pair else { unreachable!() };
+        for sector in 0..sectors {
+            let top_left = row as u32 * columns + sector;
+            let bottom_left = top_left + columns;
+            if bottom.radius > 0.0 {
+                indices.extend([top_left, bottom_left, bottom_left + 1]);
+            }
+            if top.radius > 0.0 {
+                indices.extend([top_left, bottom_left + 1, top_left + 1]);
+            }
+        }
+    }
+
+    Mesh::new(vertices, indices)
+}
+
+/// A flat disc at the given height, facing up or down.
+fn disc(radius: f32, y: f32, sectors: u32, up: bool) -> Mesh {
+    let sectors = sectors.max(3);
+    let normal = if up { Vec3::Y } else { Vec3::NEG_Y };
+    // seen from below, the texture's top is towards +Z
+    let v_sign = if up { 1.0 } else { -1.0 };
+    let vertex = |offset: Vec2| Vertex {
+        position: Vec3::new(offset.x * radius, y, offset.y * radius),
+        normal,
+        tangent: Vec3::X,
+        tex_coords: Vec2::new(0.5 + offset.x / 2.0, 0.5 + v_sign * offset.y / 2.0),
+    };
+
+    let mut vertices = vec![vertex(Vec2::ZERO)];
+    vertices.extend((0..=sectors).map(|sector| {
+        let (sin, cos) = (sector as f32 / sectors as f32 * TAU).sin_cos();
+        vertex(Vec2::new(cos, -sin))
+    }));
+    let indices = (1..=sectors)
+        .flat_map(|sector| {
+            if up {
+                [0, sector, sector + 1]
+            } else {
+                [0, sector + 1, sector]
+            }
+        })
+        .collect();
+    Mesh::new(vertices, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the triangles face the way their vertex normals do, that the normals and tangents are
+    /// perpendicular unit vectors, and that textures are mapped upright with the tangents along increasing `u`.
+    fn assert_consistent(mesh: &Mesh) {
+        let positions = mesh.positions();
+        let normals = mesh.normals();
+        let tangents = mesh.tangents();
+        let uvs = mesh.uvs();
+        for (normal, tangent) in normals.iter().zip(tangents) {
+            assert!((normal.length() - 1.0).abs() < 1e-4, "{normal}");
+            assert!((tangent.length() - 1.0).abs() < 1e-4, "{tangent}");
+            assert!(normal.dot(*tangent).abs() < 1e-4, "{normal} {tangent}");
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|corner| triangle[corner] as usize);
+            let face = (positions[b] - positions[a]).cross(positions[c] - positions[a]);
+            assert!(face.length() > 0.0, "degenerate triangle {triangle:?}");
+            let normal = normals[a] + normals[b] + normals[c];
+            assert!(
+                face.dot(normal) > 0.0,
+                "triangle {triangle:?} faces inwards"
+            );
+
+            // with `v` pointing down, triangles that wind counter-clockwise wind clockwise on the texture
+            let (uv_b, uv_c) = (uvs[b] - uvs[a], uvs[c] - uvs[a]);
+            let uv_area = uv_b.perp_dot(uv_c);
+            if uv_area.abs() < 1e-6 {
+                continue;
+            }
+            assert!(uv_area < 0.0, "triangle {triangle:?} mirrors the texture");
+            let along_u = ((positions[b] - positions[a]) * uv_c.y
+                - (positions[c] - positions[a]) * uv_b.y)
+                / uv_area;
+            let tangent = tangents[a] + tangents[b] + tangents[c];
+            assert!(
+                along_u.dot(tangent) > 0.0,
+                "triangle {triangle:?} has tangents against increasing u"
+            );
+        }
+    }
+
+    #[test]
+    fn test_primitives_face_outwards() {
+        let sphere = UvSpherePrimitive::new(2.0, 16, 8).generate_mesh();
+        assert_consistent(&sphere);
+        assert!((sphere.aabb.max - Vec3::splat(2.0)).abs().max_element() < 1e-4);
+
+        let icosphere = IcospherePrimitive::new(1.0, 2).generate_mesh();
+        assert_consistent(&icosphere);
+        assert_eq!(icosphere.indices.len(), 20 * 16 * 3);
+        // no triangle spans more than a quarter of the texture
+        let uvs = icosphere.uvs();
+        for triangle in icosphere.indices.chunks_exact(3) {
+            let us = [0, 1, 2].map(|corner| uvs[triangle[corner] as usize].x);
+            let span = us.iter().copied().fold(f32::MIN, f32::max)
+                - us.iter().copied().fold(f32::MAX, f32::min);
+            assert!(span < 0.25, "{us:?}");
+        }
+
+        let plane = PlanePrimitive::new(Vec2::new(4.0, 2.0))
+            .with_subdivisions(3, 1)
+            .generate_mesh();
+        assert_consistent(&plane);
+        assert_eq!(plane.vertex_count(), 5 * 3);
+        assert_eq!(plane.indices.len(), 4 * 2 * 6);
+
+        assert_consistent(&CylinderPrimitive::new(1.0, 2.0, 12).generate_mesh());
+        assert_consistent(&ConePrimitive::new(1.0, 2.0, 12).generate_mesh());
+        let capsule = CapsulePrimitive::new(0.5, 1.0, 12, 4).generate_mesh();
+        assert_consistent(&capsule);
+        assert!((capsule.aabb.max.y - 1.0).abs() < 1e-4);
+        assert_consistent(&TorusPrimitive::new(1.0, 0.25, 16, 8).generate_mesh());
+        assert_consistent(&CubePrimitive::new(1.0, false).generate_mesh());
+    }
+
+    #[test]
+    fn test_polyline() {
+        let square = PolylinePrimitive::new(
+            vec![Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0), Vec3::Y],
+            true,
+        )
+        .generate_mesh();
+        assert_eq!(square.indices, vec![0, 1, 1, 2, 2, 3, 3, 4]);
+        assert_eq!(square.vertex_count(), 5);
+        assert_eq!(square.uvs()[2], Vec2::new(0.5, 0.0));
+        assert_eq!(square.uvs()[4], Vec2::new(1.0, 0.0));
+
+        let line = PolylinePrimitive::line(Vec3::ZERO, Vec3::Z).generate_mesh();
+        assert_eq!(line.indices, vec![0, 1]);
+        assert_eq!(line.tangents()[1], Vec3::Z);
+        assert_eq!(line.normals()[0].dot(Vec3::Z), 0.0);
+    }
+}
@@ -0,0 +1,214 @@
+use basis_universal::{
+    BasisTextureType, DecodeFlags, LowLevelUastcTranscoder, SliceParametersUastc,
+    TranscodeParameters, Transcoder, TranscoderBlockFormat, TranscoderTextureFormat,
+};
+use weaver_util::prelude::*;
+
+use super::{BasisTarget, ColorSpace, Texture, TextureDimension, TextureFormat, TextureSettings};
+
+pub(super) const MAGIC: [u8; 2] = *b"sB";
+
+/// UASTC blocks are always 4x4 texels of 16 bytes.
+const UASTC_BLOCK_SIZE: usize = 16;
+
+fn target_formats(
+    target: BasisTarget,
+) -> (
+    TextureFormat,
+    TranscoderBlockFormat,
+    TranscoderTextureFormat,
+) {
+    match target {
+        BasisTarget::Rgba8 => (
+            TextureFormat::Rgba8Unorm,
+            TranscoderBlockFormat::RGBA32,
+            TranscoderTextureFormat::RGBA32,
+        ),
+        BasisTarget::Bc7 => (
+            TextureFormat::Bc7RgbaUnorm,
+            TranscoderBlockFormat::BC7,
+            TranscoderTextureFormat::BC7_RGBA,
+        ),
+        BasisTarget::Etc2 => (
+            TextureFormat::Etc2Rgba8Unorm,
+            TranscoderBlockFormat::ETC2_RGBA,
+            TranscoderTextureFormat::ETC2_RGBA,
+        ),
+        BasisTarget::Astc4x4 => (
+            TextureFormat::Astc4x4Unorm,
+            TranscoderBlockFormat::ASTC_4x4,
+            TranscoderTextureFormat::ASTC_4x4_RGBA,
+        ),
+    }
+}
+
+/// The shape of a UASTC encoded KTX2 texture.
+pub(super) struct UastcShape {
+    pub dimension: TextureDimension,
+    pub width: u32,
+    pub height: u32,
+    pub depth_or_array_layers: u32,
+    pub has_alpha: bool,
+}
+
+/// Transcodes the levels of a UASTC encoded KTX2 texture, each holding its layers, faces or depth slices in turn.
+pub(super) fn transcode_uastc(
+    shape: UastcShape,
+    levels: Vec<Vec<u8>>,
+    target: BasisTarget,
+) -> Result<Texture> {
+    let (format, block_format, _) = target_formats(target);
+    let transcoder = LowLevelUastcTranscoder::new();
+
+    let levels = levels
+        .into_iter()
+        .enumerate()
+        .map(|(level, texels)| {
+            let width = (shape.width >> level).max(1);
+            let height = (shape.height >> level).max(1);
+            let slices = match shape.dimension {
+                TextureDimension::D3 => (shape.depth_or_array_layers >> level).max(1),
+                _ => shape.depth_or_array_layers,
+            };
+            let (blocks_x, blocks_y) = (width.div_ceil(4), height.div_ceil(4));
+            let slice_size = (blocks_x * blocks_y) as usize * UASTC_BLOCK_SIZE;
+            ensure!(
+                texels.len() == slice_size * slices as usize,
+                "UASTC level {level} has {} bytes, expected {}",
+                texels.len(),
+                slice_size * slices as usize
+            );
+
+            let mut transcoded =
+                Vec::with_capacity(format.image_size(width, height) * slices as usize);
+            for slice in texels.chunks_exact(slice_size) {
+                let parameters = SliceParametersUastc {
+                    num_blocks_x: blocks_x,
+                    num_blocks_y: blocks_y,
+                    has_alpha: shape.has_alpha,
+                    original_width: width,
+                    original_height: height,
+                };
+                let slice = transcoder
+                    .transcode_slice(slice, parameters, DecodeFlags::HIGH_QUALITY, block_format)
+                    .map_err(|err| anyhow!("Failed to transcode UASTC texture: {err:?}"))?;
+                transcoded.extend(slice);
+            }
+            Ok(transcoded)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Texture::from_levels(
+        format,
+        shape.dimension,
+        shape.width,
+        shape.height,
+        shape.depth_or_array_layers,
+        levels,
+    )
+}
+
+/// Transcodes a `.basis` file, which may be ETC1S or UASTC encoded.
+pub(super) fn decode_basis(bytes: &[u8], settings: &TextureSettings) -> Result<Texture> {
+    let (format, _, texture_format) = target_formats(settings.basis_target);
+    let mut transcoder = Transcoder::new();
+    ensure!(
+        transcoder.validate_header(bytes),
+        "Invalid Basis Universal file"
+    );
+
+    let image_count = transcoder.image_count(bytes);
+    ensure!(image_count > 0, "Basis Universal file has no images");
+    let (dimension, depth_or_array_layers) = match transcoder.basis_texture_type(bytes) {
+        BasisTextureType::TextureType2D if image_count == 1 => (TextureDimension::D2, 1),
+        BasisTextureType::TextureTypeCubemapArray if image_count == 6 => {
+            (TextureDimension::Cube, 6)
+        }
+        BasisTextureType::TextureTypeCubemapArray => (TextureDimension::CubeArray, image_count),
+        // volumes keep every slice in every level, so they're loaded as arrays like video frames
+        _ => (TextureDimension::D2Array, image_count),
+    };
+    let level_count = transcoder.image_level_count(bytes, 0);
+    let base = transcoder
+        .image_level_description(bytes, 0, 0)
+        .ok_or_else(|| anyhow!("Basis Universal file has no levels"))?;
+
+    transcoder
+        .prepare_transcoding(bytes)
+        .map_err(|_| anyhow!("Failed to prepare Basis Universal file for transcoding"))?;
+    let levels = (0..level_count)
+        .map(|level_index| {
+            let mut level = Vec::new();
+            for image_index in 0..image_count {
+                let parameters = TranscodeParameters {
+                    image_index,
+                    level_index,
+                    ..Default::default()
+                };
+                let image = transcoder
+                    .transcode_image_level(bytes, texture_format, parameters)
+                    .map_err(|err| anyhow!("Failed to transcode Basis Universal file: {err:?}"))?;
+                level.extend(image);
+            }
+            Ok(level)
+        })
+        .collect::<Result<Vec<_>>>();
+    transcoder.end_transcoding();
+
+    // Basis Universal encodes color images as sRGB unless told otherwise, and the file doesn't say
+    Ok(Texture::from_levels(
+        format,
+        dimension,
+        base.original_width,
+        base.original_height,
+        depth_or_array_layers,
+        levels?,
+    )?
+    .with_color_space(ColorSpace::Srgb))
+}
+
+#[cfg(test)]
+mod tests {
+    use basis_universal::{BasisTextureFormat, Compressor, CompressorParams};
+
+    use super::*;
+
+    #[test]
+    fn test_transcode_basis_file() {
+        let image = image::RgbaImage::from_fn(16, 8, |x, y| {
+            image::Rgba([x as u8 * 16, y as u8 * 32, 128, 255])
+        });
+        let mut params = CompressorParams::new();
+        params.set_basis_format(BasisTextureFormat::UASTC4x4);
+        params.set_generate_mipmaps(true);
+        params
+            .source_image_mut(0)
+            .init(image.as_raw(), image.width(), image.height(), 4);
+        let mut compressor = Compressor::default();
+        // SAFETY: the params outlive initialization, and processing only reads the source image they were given
+        let bytes = unsafe {
+            compressor.init(&params);
+            compressor.process().unwrap();
+            compressor.basis_file().to_vec()
+        };
+
+        let rgba = Texture::decode(&bytes, &TextureSettings::default()).unwrap();
+        assert_eq!(rgba.format(), TextureFormat::Rgba8Unorm);
+        assert_eq!(rgba.level_dimensions(0), (16, 8, 1));
+        assert_eq!(rgba.mip_level_count(), 5);
+        let decoded = rgba.to_image(0, 0).unwrap().into_rgba8();
+        for (decoded, original) in decoded.pixels().zip(image.pixels()) {
+            for (decoded, original) in decoded.0.iter().zip(original.0) {
+                assert!(decoded.abs_diff(original) <= 8, "{decoded} != {original}");
+            }
+        }
+
+        let settings = TextureSettings {
+            basis_target: BasisTarget::Bc7,
+            ..Default::default()
+        };
+        let bc7 = Texture::decode(&bytes, &settings).unwrap();
+        assert_eq!(bc7.format(), TextureFormat::Bc7RgbaUnorm);
+        assert_eq!(bc7.levels()[0].len(), 4 * 2 * 16);
+    }
+}
@@ -0,0 +1,418 @@
+use weaver_util::prelude::*;
+
+use super::{ColorSpace, Texture, TextureDimension, TextureFormat, expand_to_rgba, swap_red_blue};
+
+pub(super) const MAGIC: [u8; 4] = *b"DDS ";
+
+const HEADER_SIZE: usize = 4 + 124;
+const DX10_HEADER_SIZE: usize = 20;
+
+const DDSD_DEPTH: u32 = 0x80_0000;
+const DDSD_MIPMAPCOUNT: u32 = 0x2_0000;
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x2_0000;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_CUBEMAP_ALL_FACES: u32 = 0xFC00;
+const DDSCAPS2_VOLUME: u32 = 0x20_0000;
+const D3D10_RESOURCE_DIMENSION_TEXTURE3D: u32 = 4;
+const D3D10_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+
+/// How the texels of a DDS format are turned into those of a [`TextureFormat`].
+#[derive(Clone, Copy)]
+enum Conversion {
+    None,
+    /// Appends an alpha channel of the given value to 3-channel texels whose channels are as large as it.
+    ExpandToRgba(&'static [u8]),
+    Bgr,
+    Bgra,
+    /// BGRA texels whose alpha channel is unused.
+    Bgrx,
+    /// RGBA texels whose alpha channel is unused.
+    Rgbx,
+    Luminance,
+    LuminanceAlpha,
+}
+
+impl Conversion {
+    fn apply(self, texels: Vec<u8>) -> Vec<u8> {
+        match self {
+            Conversion::None => texels,
+            Conversion::ExpandToRgba(one) => expand_to_rgba(&texels, one.len(), one),
+            Conversion::Bgr => {
+                let mut texels = expand_to_rgba(&texels, 1, &[u8::MAX]);
+                swap_red_blue(&mut texels, 1);
+                texels
+            }
+            Conversion::Bgra | Conversion::Bgrx | Conversion::Rgbx => {
+                let mut texels = texels;
+                if !matches!(self, Conversion::Rgbx) {
+                    swap_red_blue(&mut texels, 1);
+                }
+                if !matches!(self, Conversion::Bgra) {
+                    texels
+                        .chunks_exact_mut(4)
+                        .for_each(|texel| texel[3] = u8::MAX);
+                }
+                texels
+            }
+            Conversion::Luminance => texels
+                .iter()
+                .flat_map(|&luma| [luma, luma, luma, u8::MAX])
+                .collect(),
+            Conversion::LuminanceAlpha => texels
+                .chunks_exact(2)
+                .flat_map(|texel| [texel[0], texel[0], texel[0], texel[1]])
+                .collect(),
+        }
+    }
+}
+
+const fn four_cc(code: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*code)
+}
+
+fn dxgi_format(dxgi_format: u32) -> Result<(TextureFormat, Conversion)> {
+    use TextureFormat::*;
+    let converted = match dxgi_format {
+        2 => (Rgba32Float, Conversion::None),
+        6 => (Rgba32Float, Conversion::ExpandToRgba(&[0, 0, 0x80, 0x3F])),
+        10 => (Rgba16Float, Conversion::None),
+        11 => (Rgba16Unorm, Conversion::None),
+        16 => (Rg32Float, Conversion::None),
+        26 => (Rg11b10Ufloat, Conversion::None),
+        28 | 29 => (Rgba8Unorm, Conversion::None),
+        34 => (Rg16Float, Conversion::None),
+        35 => (Rg16Unorm, Conversion::None),
+        41 => (R32Float, Conversion::None),
+        49 => (Rg8Unorm, Conversion::None),
+        54 => (R16Float, Conversion::None),
+        56 => (R16Unorm, Conversion::None),
+        61 => (R8Unorm, Conversion::None),
+        67 => (Rgb9e5Ufloat, Conversion::None),
+        71 | 72 => (Bc1RgbaUnorm, Conversion::None),
+        74 | 75 => (Bc2RgbaUnorm, Conversion::None),
+        77 | 78 => (Bc3RgbaUnorm, Conversion::None),
+        80 => (Bc4RUnorm, Conversion::None),
+        81 => (Bc4RSnorm, Conversion::None),
+        83 => (Bc5RgUnorm, Conversion::None),
+        84 => (Bc5RgSnorm, Conversion::None),
+        87 | 91 => (Rgba8Unorm, Conversion::Bgra),
+        88 | 93 => (Rgba8Unorm, Conversion::Bgrx),
+        95 => (Bc6hRgbUfloat, Conversion::None),
+        96 => (Bc6hRgbFloat, Conversion::None),
+        98 | 99 => (Bc7RgbaUnorm, Conversion::None),
+        format => bail!("Unsupported DXGI format {format} in DDS file"),
+    };
+    Ok(converted)
+}
+
+/// The format of DDS files without a DX10 header, described by a four character code or by bit masks.
+fn legacy_format(
+    flags: u32,
+    four_cc_code: u32,
+    bit_count: u32,
+    masks: [u32; 4],
+) -> Result<(TextureFormat, Conversion)> {
+    use TextureFormat::*;
+    if flags & DDPF_FOURCC != 0 {
+        let converted = match four_cc_code {
+            code if code == four_cc(b"DXT1") => (Bc1RgbaUnorm, Conversion::None),
+            code if code == four_cc(b"DXT2") || code == four_cc(b"DXT3") => {
+                (Bc2RgbaUnorm, Conversion::None)
+            }
+            code if code == four_cc(b"DXT4") || code == four_cc(b"DXT5") => {
+                (Bc3RgbaUnorm, Conversion::None)
+            }
+            code if code == four_cc(b"ATI1") || code == four_cc(b"BC4U") => {
+                (Bc4RUnorm, Conversion::None)
+            }
+            code if code == four_cc(b"BC4S") => (Bc4RSnorm, Conversion::None),
+            code if code == four_cc(b"ATI2") || code == four_cc(b"BC5U") => {
+                (Bc5RgUnorm, Conversion::None)
+            }
+            code if code == four_cc(b"BC5S") => (Bc5RgSnorm, Conversion::None),
+            // Direct3D 9 format numbers
+            36 => (Rgba16Unorm, Conversion::None),
+            111 => (R16Float, Conversion::None),
+            112 => (Rg16Float, Conversion::None),
+            113 => (Rgba16Float, Conversion::None),
+            114 => (R32Float, Conversion::None),
+            115 => (Rg32Float, Conversion::None),
+            116 => (Rgba32Float, Conversion::None),
+            code => bail!(
+                "Unsupported four character code {:?} in DDS file",
+                String::from_utf8_lossy(&code.to_le_bytes())
+            ),
+        };
+        return Ok(converted);
+    }
+
+    let has_alpha = flags & DDPF_ALPHAPIXELS != 0;
+    let red_first = masks[0] == 0xFF;
+    let converted = match (flags & (DDPF_RGB | DDPF_LUMINANCE), bit_count) {
+        (DDPF_RGB, 32) if red_first && has_alpha => (Rgba8Unorm, Conversion::None),
+        (DDPF_RGB, 32) if red_first => (Rgba8Unorm, Conversion::Rgbx),
+        (DDPF_RGB, 32) if has_alpha => (Rgba8Unorm, Conversion::Bgra),
+        (DDPF_RGB, 32) => (Rgba8Unorm, Conversion::Bgrx),
+        (DDPF_RGB, 24) if red_first => (Rgba8Unorm, Conversion::ExpandToRgba(&[u8::MAX])),
+        (DDPF_RGB, 24) => (Rgba8Unorm, Conversion::Bgr),
+        (DDPF_LUMINANCE, 8) => (Rgba8Unorm, Conversion::Luminance),
+        (DDPF_LUMINANCE, 16) if has_alpha => (Rgba8Unorm, Conversion::LuminanceAlpha),
+        _ => bail!("Unsupported {bit_count}-bit uncompressed DDS format with masks {masks:x?}"),
+    };
+    Ok(converted)
+}
+
+/// Decodes a DDS file with all of its mip levels, array layers and cube faces.
+pub(super) fn decode_dds(bytes: &[u8]) -> Result<Texture> {
+    ensure!(bytes.len() >= HEADER_SIZE, "DDS file is too short");
+    let word = |index: usize| {
+        let offset = MAGIC.len() + index * 4;
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    };
+
+    let flags = word(1);
+    let height = word(2);
+    let width = word(3);
+    let depth = word(5).max(1);
+    let mip_level_count = match flags & DDSD_MIPMAPCOUNT {
+        0 => 1,
+        _ => word(6).max(1),
+    };
+    let pixel_format_flags = word(19);
+    let four_cc_code = word(20);
+    let caps2 = word(27);
+
+    let mut offset = HEADER_SIZE;
+    let (format, conversion, color_space, dimension, depth_or_array_layers) =
+        if pixel_format_flags & DDPF_FOURCC != 0 && four_cc_code == four_cc(b"DX10") {
+            ensure!(
+                bytes.len() >= HEADER_SIZE + DX10_HEADER_SIZE,
+                "DDS file is too short"
+            );
+            offset += DX10_HEADER_SIZE;
+            let (format, conversion) = dxgi_format(word(31))?;
+            let color_space = match word(31) {
+                29 | 72 | 75 | 78 | 91 | 93 | 99 => ColorSpace::Srgb,
+                _ => ColorSpace::Linear,
+            };
+            let resource_dimension = word(32);
+            let misc_flags = word(33);
+            let array_size = word(34).max(1);
+            let (dimension, depth_or_array_layers) =
+                if resource_dimension == D3D10_RESOURCE_DIMENSION_TEXTURE3D {
+                    (TextureDimension::D3, depth)
+                } else if misc_flags & D3D10_RESOURCE_MISC_TEXTURECUBE != 0 {
+                    match array_size {
+                        1 => (TextureDimension::Cube, 6),
+                        _ => (
+                            TextureDimension::CubeArray,
+                            array_size
+                                .checked_mul(6)
+                                .ok_or_else(|| anyhow!("DDS cube array has {array_size} cubes"))?,
+                        ),
+                    }
+                } else {
+                    match array_size {
+                        1 => (TextureDimension::D2, 1),
+                        _ => (TextureDimension::D2Array, array_size),
+                    }
+                };
+            (
+                format,
+                conversion,
+                color_space,
+                dimension,
+                depth_or_array_layers,
+            )
+        } else {
+            let masks = [word(22), word(23), word(24), word(25)];
+            let (format, conversion) =
+                legacy_format(pixel_format_flags, four_cc_code, word(21), masks)?;
+            // legacy files don't tell, but their color formats almost always hold sRGB colors
+            let color_space = match format {
+                TextureFormat::Rgba8Unorm
+                | TextureFormat::Bc1RgbaUnorm
+                | TextureFormat::Bc2RgbaUnorm
+                | TextureFormat::Bc3RgbaUnorm => ColorSpace::Srgb,
+                _ => ColorSpace::Linear,
+            };
+            let (dimension, depth_or_array_layers) = if caps2 & DDSCAPS2_CUBEMAP != 0 {
+                ensure!(
+                    caps2 & DDSCAPS2_CUBEMAP_ALL_FACES == DDSCAPS2_CUBEMAP_ALL_FACES,
+                    "DDS cube map is missing faces"
+                );
+                (TextureDimension::Cube, 6)
+            } else if caps2 & DDSCAPS2_VOLUME != 0 && flags & DDSD_DEPTH != 0 {
+                (TextureDimension::D3, depth)
+            } else {
+                (TextureDimension::D2, 1)
+            };
+            (
+                format,
+                conversion,
+                color_space,
+                dimension,
+                depth_or_array_layers,
+            )
+        };
+
+    // the file stores the mip chain of each layer in turn, and the texture all layers of each level in turn
+    let layer_count = match dimension {
+        TextureDimension::D3 => 1,
+        _ => depth_or_array_layers,
+    };
+    // validate the header before sizing anything from it, this also keeps the mip level shifts below 32
+    super::check_shape(
+        dimension,
+        width,
+        height,
+        depth_or_array_layers,
+        mip_level_count as usize,
+    )?;
+    let mut levels = vec![Vec::new(); mip_level_count as usize];
+    for _ in 0..layer_count {
+        for (level, texels) in levels.iter_mut().enumerate() {
+            let size = format
+                .checked_image_size((width >> level).max(1), (height >> level).max(1))
+                .and_then(|size| match dimension {
+                    TextureDimension::D3 => size.checked_mul((depth >> level).max(1) as usize),
+                    _ => Some(size),
+                })
+                .ok_or_else(|| anyhow!("DDS texture of {width}x{height} is too large"))?;
+            let source_size = match conversion {
+                Conversion::ExpandToRgba(one) => size / (one.len() * 4) * (one.len() * 3),
+                Conversion::Bgr => size / 4 * 3,
+                Conversion::Luminance => size / 4,
+                Conversion::LuminanceAlpha => size / 2,
+                _ => size,
+            };
+            let layer = offset
+                .checked_add(source_size)
+                .and_then(|end| bytes.get(offset..end))
+                .ok_or_else(|| anyhow!("DDS file is missing texels"))?;
+            texels.extend_from_slice(layer);
+            offset += source_size;
+        }
+    }
+    let levels = levels
+        .into_iter()
+        .map(|texels| conversion.apply(texels))
+        .collect();
+
+    Ok(Texture::from_levels(
+        format,
+        dimension,
+        width,
+        height,
+        depth_or_array_layers,
+        levels,
+    )?
+    .with_color_space(color_space))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dds_file(words: &[(usize, u32)], dx10: Option<[u32; 5]>, texels: &[u8]) -> Vec<u8> {
+        let mut header = [0u32; 31];
+        header[0] = 124;
+        for &(index, value) in words {
+            header[index] = value;
+        }
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(header.iter().flat_map(|word| word.to_le_bytes()));
+        bytes.extend(dx10.iter().flatten().flat_map(|word| word.to_le_bytes()));
+        bytes.extend_from_slice(texels);
+        bytes
+    }
+
+    #[test]
+    fn test_decode_bgra() {
+        let texels = [
+            [1, 2, 3, 4],
+            [5, 6, 7, 8],
+            [9, 10, 11, 12],
+            [13, 14, 15, 16],
+        ]
+        .concat();
+        let bytes = dds_file(
+            &[
+                (2, 2),
+                (3, 2),
+                (19, DDPF_RGB | DDPF_ALPHAPIXELS),
+                (21, 32),
+                (22, 0xFF_0000),
+                (23, 0xFF00),
+                (24, 0xFF),
+                (25, 0xFF00_0000),
+            ],
+            None,
+            &texels,
+        );
+        let texture = decode_dds(&bytes).unwrap();
+        assert_eq!(texture.format(), TextureFormat::Rgba8Unorm);
+        assert_eq!(texture.color_space(), ColorSpace::Srgb);
+        assert_eq!(texture.levels()[0][..8], [3, 2, 1, 4, 7, 6, 5, 8]);
+    }
+
+    #[test]
+    fn test_decode_block_compressed_array_with_mips() {
+        // two layers of 8x8 BC1 with two levels each, stored layer by layer
+        let layer = |layer: u8| [vec![layer; 4 * 8], vec![layer + 10; 8]].concat();
+        let texels = [layer(1), layer(2)].concat();
+        let bytes = dds_file(
+            &[
+                (1, DDSD_MIPMAPCOUNT),
+                (2, 8),
+                (3, 8),
+                (6, 2),
+                (19, DDPF_FOURCC),
+                (20, four_cc(b"DX10")),
+            ],
+            Some([71, 3, 0, 2, 0]),
+            &texels,
+        );
+        let texture = decode_dds(&bytes).unwrap();
+        assert_eq!(texture.format(), TextureFormat::Bc1RgbaUnorm);
+        assert_eq!(texture.color_space(), ColorSpace::Linear);
+        assert_eq!(texture.dimension(), TextureDimension::D2Array);
+        assert_eq!(texture.mip_level_count(), 2);
+        assert_eq!(texture.levels()[0], [[1; 32], [2; 32]].concat());
+        assert_eq!(texture.levels()[1], [[11; 8], [12; 8]].concat());
+    }
+
+    #[test]
+    fn test_reject_malformed_header() {
+        let header = |words: &[(usize, u32)]| {
+            let mut all = vec![(19, DDPF_FOURCC), (20, four_cc(b"DX10"))];
+            all.extend_from_slice(words);
+            all
+        };
+        // more mip levels than an 8x8 texture has, and more than a shift can take
+        for mip_level_count in [5, 40, u32::MAX] {
+            let bytes = dds_file(
+                &header(&[(1, DDSD_MIPMAPCOUNT), (2, 8), (3, 8), (6, mip_level_count)]),
+                Some([71, 3, 0, 1, 0]),
+                &[0; 64],
+            );
+            assert!(decode_dds(&bytes).is_err());
+        }
+        // a huge texture in a short file, and one whose cube array has too many faces to count
+        let huge = dds_file(
+            &header(&[(1, DDSD_MIPMAPCOUNT), (2, u32::MAX), (3, u32::MAX), (6, 32)]),
+            Some([2, 3, 0, 1, 0]),
+            &[0; 64],
+        );
+        assert!(decode_dds(&huge).is_err());
+        let cubes = dds_file(
+            &header(&[(2, 4), (3, 4)]),
+            Some([71, 3, D3D10_RESOURCE_MISC_TEXTURECUBE, u32::MAX, 0]),
+            &[0; 64],
+        );
+        assert!(decode_dds(&cubes).is_err());
+    }
+}
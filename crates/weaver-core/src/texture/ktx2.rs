@@ -0,0 +1,530 @@
+use std::io::Read;
+
+use ::ktx2::{
+    ColorModel, Format, Header, Index, LevelIndex, Reader, SupercompressionScheme,
+    TransferFunction, dfd,
+};
+use half::f16;
+use weaver_util::prelude::*;
+
+use super::{
+    ColorSpace, Texture, TextureDimension, TextureFormat, TextureSettings, expand_to_rgba,
+    swap_red_blue,
+};
+
+pub(super) const MAGIC: [u8; 12] = [
+    0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n',
+];
+
+/// How the texels of a Vulkan format are turned into those of a [`TextureFormat`].
+enum Conversion {
+    None,
+    /// Appends an alpha channel of the given value to 3-channel texels whose channels are as large as it.
+    ExpandToRgba(&'static [u8]),
+    /// Turns BGRA texels into RGBA, after expanding BGR texels.
+    SwapRedBlue(Option<&'static [u8]>),
+}
+
+const ONE_UNORM8: &[u8] = &[u8::MAX];
+const ONE_UNORM16: &[u8] = &[u8::MAX, u8::MAX];
+const ONE_FLOAT16: &[u8] = &f16::ONE.to_le_bytes();
+const ONE_FLOAT32: &[u8] = &1f32.to_le_bytes();
+
+/// The sRGB and UNORM variants of a format map to the same [`TextureFormat`], the color space comes from the transfer
+/// function of the file.
+fn convert_format(format: Format) -> Result<(TextureFormat, Conversion)> {
+    use TextureFormat::*;
+    let converted = match format {
+        Format::R8_UNORM | Format::R8_SRGB => (R8Unorm, Conversion::None),
+        Format::R8G8_UNORM | Format::R8G8_SRGB => (Rg8Unorm, Conversion::None),
+        Format::R8G8B8_UNORM | Format::R8G8B8_SRGB => {
+            (Rgba8Unorm, Conversion::ExpandToRgba(ONE_UNORM8))
+        }
+        Format::B8G8R8_UNORM | Format::B8G8R8_SRGB => {
+            (Rgba8Unorm, Conversion::SwapRedBlue(Some(ONE_UNORM8)))
+        }
+        Format::R8G8B8A8_UNORM | Format::R8G8B8A8_SRGB => (Rgba8Unorm, Conversion::None),
+        Format::B8G8R8A8_UNORM | Format::B8G8R8A8_SRGB => {
+            (Rgba8Unorm, Conversion::SwapRedBlue(None))
+        }
+        Format::R16_UNORM => (R16Unorm, Conversion::None),
+        Format::R16G16_UNORM => (Rg16Unorm, Conversion::None),
+        Format::R16G16B16_UNORM => (Rgba16Unorm, Conversion::ExpandToRgba(ONE_UNORM16)),
+        Format::R16G16B16A16_UNORM => (Rgba16Unorm, Conversion::None),
+        Format::R16_SFLOAT => (R16Float, Conversion::None),
+        Format::R16G16_SFLOAT => (Rg16Float, Conversion::None),
+        Format::R16G16B16_SFLOAT => (Rgba16Float, Conversion::ExpandToRgba(ONE_FLOAT16)),
+        Format::R16G16B16A16_SFLOAT => (Rgba16Float, Conversion::None),
+        Format::R32_SFLOAT => (R32Float, Conversion::None),
+        Format::R32G32_SFLOAT => (Rg32Float, Conversion::None),
+        Format::R32G32B32_SFLOAT => (Rgba32Float, Conversion::ExpandToRgba(ONE_FLOAT32)),
+        Format::R32G32B32A32_SFLOAT => (Rgba32Float, Conversion::None),
+        Format::E5B9G9R9_UFLOAT_PACK32 => (Rgb9e5Ufloat, Conversion::None),
+        Format::B10G11R11_UFLOAT_PACK32 => (Rg11b10Ufloat, Conversion::None),
+        Format::BC1_RGB_UNORM_BLOCK
+        | Format::BC1_RGB_SRGB_BLOCK
+        | Format::BC1_RGBA_UNORM_BLOCK
+        | Format::BC1_RGBA_SRGB_BLOCK => (Bc1RgbaUnorm, Conversion::None),
+        Format::BC2_UNORM_BLOCK | Format::BC2_SRGB_BLOCK => (Bc2RgbaUnorm, Conversion::None),
+        Format::BC3_UNORM_BLOCK | Format::BC3_SRGB_BLOCK => (Bc3RgbaUnorm, Conversion::None),
+        Format::BC4_UNORM_BLOCK => (Bc4RUnorm, Conversion::None),
+        Format::BC4_SNORM_BLOCK => (Bc4RSnorm, Conversion::None),
+        Format::BC5_UNORM_BLOCK => (Bc5RgUnorm, Conversion::None),
+        Format::BC5_SNORM_BLOCK => (Bc5RgSnorm, Conversion::None),
+        Format::BC6H_UFLOAT_BLOCK => (Bc6hRgbUfloat, Conversion::None),
+        Format::BC6H_SFLOAT_BLOCK => (Bc6hRgbFloat, Conversion::None),
+        Format::BC7_UNORM_BLOCK | Format::BC7_SRGB_BLOCK => (Bc7RgbaUnorm, Conversion::None),
+        Format::ETC2_R8G8B8_UNORM_BLOCK | Format::ETC2_R8G8B8_SRGB_BLOCK => {
+            (Etc2Rgb8Unorm, Conversion::None)
+        }
+        Format::ETC2_R8G8B8A1_UNORM_BLOCK | Format::ETC2_R8G8B8A1_SRGB_BLOCK => {
+            (Etc2Rgb8A1Unorm, Conversion::None)
+        }
+        Format::ETC2_R8G8B8A8_UNORM_BLOCK | Format::ETC2_R8G8B8A8_SRGB_BLOCK => {
+            (Etc2Rgba8Unorm, Conversion::None)
+        }
+        Format::EAC_R11_UNORM_BLOCK => (EacR11Unorm, Conversion::None),
+        Format::EAC_R11G11_UNORM_BLOCK => (EacRg11Unorm, Conversion::None),
+        Format::ASTC_4x4_UNORM_BLOCK | Format::ASTC_4x4_SRGB_BLOCK => {
+            (Astc4x4Unorm, Conversion::None)
+        }
+        Format::ASTC_5x4_UNORM_BLOCK | Format::ASTC_5x4_SRGB_BLOCK => {
+            (Astc5x4Unorm, Conversion::None)
+        }
+        Format::ASTC_5x5_UNORM_BLOCK | Format::ASTC_5x5_SRGB_BLOCK => {
+            (Astc5x5Unorm, Conversion::None)
+        }
+        Format::ASTC_6x5_UNORM_BLOCK | Format::ASTC_6x5_SRGB_BLOCK => {
+            (Astc6x5Unorm, Conversion::None)
+        }
+        Format::ASTC_6x6_UNORM_BLOCK | Format::ASTC_6x6_SRGB_BLOCK => {
+            (Astc6x6Unorm, Conversion::None)
+        }
+        Format::ASTC_8x5_UNORM_BLOCK | Format::ASTC_8x5_SRGB_BLOCK => {
+            (Astc8x5Unorm, Conversion::None)
+        }
+        Format::ASTC_8x6_UNORM_BLOCK | Format::ASTC_8x6_SRGB_BLOCK => {
+            (Astc8x6Unorm, Conversion::None)
+        }
+        Format::ASTC_8x8_UNORM_BLOCK | Format::ASTC_8x8_SRGB_BLOCK => {
+            (Astc8x8Unorm, Conversion::None)
+        }
+        Format::ASTC_10x5_UNORM_BLOCK | Format::ASTC_10x5_SRGB_BLOCK => {
+            (Astc10x5Unorm, Conversion::None)
+        }
+        Format::ASTC_10x6_UNORM_BLOCK | Format::ASTC_10x6_SRGB_BLOCK => {
+            (Astc10x6Unorm, Conversion::None)
+        }
+        Format::ASTC_10x8_UNORM_BLOCK | Format::ASTC_10x8_SRGB_BLOCK => {
+            (Astc10x8Unorm, Conversion::None)
+        }
+        Format::ASTC_10x10_UNORM_BLOCK | Format::ASTC_10x10_SRGB_BLOCK => {
+            (Astc10x10Unorm, Conversion::None)
+        }
+        Format::ASTC_12x10_UNORM_BLOCK | Format::ASTC_12x10_SRGB_BLOCK => {
+            (Astc12x10Unorm, Conversion::None)
+        }
+        Format::ASTC_12x12_UNORM_BLOCK | Format::ASTC_12x12_SRGB_BLOCK => {
+            (Astc12x12Unorm, Conversion::None)
+        }
+        format => bail!("Unsupported KTX2 format {format:?}"),
+    };
+    Ok(converted)
+}
+
+/// Decodes a KTX2 file with all of its mip levels, layers and faces. Levels may be Zstandard or ZLIB
+/// supercompressed. UASTC encoded Basis Universal files are transcoded with the `basis-universal` feature.
+pub(super) fn decode_ktx2(bytes: &[u8], settings: &TextureSettings) -> Result<Texture> {
+    let reader = Reader::new(bytes)?;
+    let header = reader.header();
+
+    let (dimension, depth_or_array_layers) =
+        match (header.face_count, header.layer_count, header.pixel_depth) {
+            (6, 0, _) => (TextureDimension::Cube, 6),
+            (6, layers, _) => (TextureDimension::CubeArray, layers * 6),
+            (_, 0, 0) => (TextureDimension::D2, 1),
+            (_, 0, depth) => (TextureDimension::D3, depth),
+            (_, layers, _) => (TextureDimension::D2Array, layers),
+        };
+    // 1D textures are stored as a single row
+    let (width, height) = (header.pixel_width, header.pixel_height.max(1));
+    let color_space = match reader.transfer_function() {
+        Some(TransferFunction::SRGB) => ColorSpace::Srgb,
+        _ => ColorSpace::Linear,
+    };
+
+    // validate the header before sizing anything from it, the level index can claim any length
+    super::check_shape(
+        dimension,
+        width,
+        height,
+        depth_or_array_layers,
+        header.level_count.max(1) as usize,
+    )?;
+    let converted = header.format.map(convert_format).transpose()?;
+    // UASTC blocks are as large as those of ASTC 4x4
+    let (level_format, expansion) = match &converted {
+        Some((format, Conversion::ExpandToRgba(one) | Conversion::SwapRedBlue(Some(one)))) => {
+            (*format, Some(one.len()))
+        }
+        Some((format, _)) => (*format, None),
+        None => (TextureFormat::Astc4x4Unorm, None),
+    };
+    let level_size = |level: usize| {
+        let layers = match dimension {
+            TextureDimension::D3 => (depth_or_array_layers >> level).max(1),
+            _ => depth_or_array_layers,
+        };
+        let size = level_format
+            .checked_image_size((width >> level).max(1), (height >> level).max(1))?
+            .checked_mul(layers as usize)?;
+        Some(match expansion {
+            Some(channel_size) => size / (channel_size * 4) * (channel_size * 3),
+            None => size,
+        })
+    };
+
+    let levels = reader
+        .levels()
+        .enumerate()
+        .map(|(index, level)| {
+            let size = level_size(index)
+                .ok_or_else(|| anyhow!("KTX2 texture of {width}x{height} is too large"))?;
+            let texels = match header.supercompression_scheme {
+                None => level.data.to_vec(),
+                Some(SupercompressionScheme::Zstandard) => {
+                    read_level(zstd::stream::read::Decoder::new(level.data)?, size)?
+                }
+                Some(SupercompressionScheme::ZLIB) => {
+                    read_level(flate2::read::ZlibDecoder::new(level.data), size)?
+                }
+                Some(SupercompressionScheme::BasisLZ) => bail!(
+                    "BasisLZ (ETC1S) supercompressed KTX2 files aren't supported, encode them with UASTC instead"
+                ),
+                Some(scheme) => bail!("Unsupported KTX2 supercompression scheme {scheme:?}"),
+            };
+            ensure!(
+                texels.len() == size,
+                "KTX2 level {index} has {} bytes instead of {size}",
+                texels.len()
+            );
+            Ok(texels)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let Some((format, conversion)) = converted else {
+        ensure!(
+            reader.color_model() == Some(ColorModel::UASTC),
+            "KTX2 file has no format and isn't UASTC encoded"
+        );
+        #[cfg(feature = "basis-universal")]
+        {
+            // the channel of the single UASTC sample is RGB, RGBA, RRR, RRRG or RG, in that order from 0, 3, 4, 5 and 6
+            let has_alpha = reader.basic_dfd().is_some_and(|dfd| {
+                dfd.sample_information
+                    .first()
+                    .is_some_and(|sample| matches!(sample.channel_type, 3 | 5 | 6))
+            });
+            let shape = super::basis::UastcShape {
+                dimension,
+                width,
+                height,
+                depth_or_array_layers,
+                has_alpha,
+            };
+            let texture = super::basis::transcode_uastc(shape, levels, settings.basis_target)?;
+            return Ok(texture.with_color_space(color_space));
+        }
+        #[cfg(not(feature = "basis-universal"))]
+        {
+            let _ = settings;
+            bail!("Loading UASTC encoded KTX2 files requires the basis-universal feature");
+        }
+    };
+
+    let levels = levels
+        .into_iter()
+        .map(|texels| match conversion {
+            Conversion::None => texels,
+            Conversion::ExpandToRgba(one) => expand_to_rgba(&texels, one.len(), one),
+            Conversion::SwapRedBlue(expand) => {
+                let mut texels = match expand {
+                    Some(one) => expand_to_rgba(&texels, one.len(), one),
+                    None => texels,
+                };
+                swap_red_blue(&mut texels, 1);
+                texels
+            }
+        })
+        .collect();
+
+    Ok(Texture::from_levels(
+        format,
+        dimension,
+        width,
+        height,
+        depth_or_array_layers,
+        levels,
+    )?
+    .with_color_space(color_space))
+}
+
+/// Decompresses a level, reading no more than one byte past its expected size so that a file can't make it grow
+/// without bounds.
+fn read_level(decoder: impl Read, size: usize) -> Result<Vec<u8>> {
+    let mut texels = Vec::new();
+    decoder.take(size as u64 + 1).read_to_end(&mut texels)?;
+    Ok(texels)
+}
+
+/// The Vulkan format of a [`TextureFormat`], the sRGB variant for sRGB textures of formats that have one. The
+/// second value tells whether the format is an sRGB variant.
+fn vulkan_format(format: TextureFormat, color_space: ColorSpace) -> (Format, bool) {
+    use TextureFormat::*;
+    let srgb = color_space == ColorSpace::Srgb;
+    let pick = |unorm: Format, srgb_format: Format| match srgb {
+        true => (srgb_format, true),
+        false => (unorm, false),
+    };
+    match format {
+        R8Unorm => pick(Format::R8_UNORM, Format::R8_SRGB),
+        Rg8Unorm => pick(Format::R8G8_UNORM, Format::R8G8_SRGB),
+        Rgba8Unorm => pick(Format::R8G8B8A8_UNORM, Format::R8G8B8A8_SRGB),
+        R16Unorm => (Format::R16_UNORM, false),
+        Rg16Unorm => (Format::R16G16_UNORM, false),
+        Rgba16Unorm => (Format::R16G16B16A16_UNORM, false),
+        R16Float => (Format::R16_SFLOAT, false),
+        Rg16Float => (Format::R16G16_SFLOAT, false),
+        Rgba16Float => (Format::R16G16B16A16_SFLOAT, false),
+        R32Float => (Format::R32_SFLOAT, false),
+        Rg32Float => (Format::R32G32_SFLOAT, false),
+        Rgba32Float => (Format::R32G32B32A32_SFLOAT, false),
+        Rgb9e5Ufloat => (Format::E5B9G9R9_UFLOAT_PACK32, false),
+        Rg11b10Ufloat => (Format::B10G11R11_UFLOAT_PACK32, false),
+        Bc1RgbaUnorm => pick(Format::BC1_RGBA_UNORM_BLOCK, Format::BC1_RGBA_SRGB_BLOCK),
+        Bc2RgbaUnorm => pick(Format::BC2_UNORM_BLOCK, Format::BC2_SRGB_BLOCK),
+        Bc3RgbaUnorm => pick(Format::BC3_UNORM_BLOCK, Format::BC3_SRGB_BLOCK),
+        Bc4RUnorm => (Format::BC4_UNORM_BLOCK, false),
+        Bc4RSnorm => (Format::BC4_SNORM_BLOCK, false),
+        Bc5RgUnorm => (Format::BC5_UNORM_BLOCK, false),
+        Bc5RgSnorm => (Format::BC5_SNORM_BLOCK, false),
+        Bc6hRgbUfloat => (Format::BC6H_UFLOAT_BLOCK, false),
+        Bc6hRgbFloat => (Format::BC6H_SFLOAT_BLOCK, false),
+        Bc7RgbaUnorm => pick(Format::BC7_UNORM_BLOCK, Format::BC7_SRGB_BLOCK),
+        Etc2Rgb8Unorm => pick(
+            Format::ETC2_R8G8B8_UNORM_BLOCK,
+            Format::ETC2_R8G8B8_SRGB_BLOCK,
+        ),
+        Etc2Rgb8A1Unorm => pick(
+            Format::ETC2_R8G8B8A1_UNORM_BLOCK,
+            Format::ETC2_R8G8B8A1_SRGB_BLOCK,
+        ),
+        Etc2Rgba8Unorm => pick(
+            Format::ETC2_R8G8B8A8_UNORM_BLOCK,
+            Format::ETC2_R8G8B8A8_SRGB_BLOCK,
+        ),
+        EacR11Unorm => (Format::EAC_R11_UNORM_BLOCK, false),
+        EacRg11Unorm => (Format::EAC_R11G11_UNORM_BLOCK, false),
+        Astc4x4Unorm => pick(Format::ASTC_4x4_UNORM_BLOCK, Format::ASTC_4x4_SRGB_BLOCK),
+        Astc5x4Unorm => pick(Format::ASTC_5x4_UNORM_BLOCK, Format::ASTC_5x4_SRGB_BLOCK),
+        Astc5x5Unorm => pick(Format::ASTC_5x5_UNORM_BLOCK, Format::ASTC_5x5_SRGB_BLOCK),
+        Astc6x5Unorm => pick(Format::ASTC_6x5_UNORM_BLOCK, Format::ASTC_6x5_SRGB_BLOCK),
+        Astc6x6Unorm => pick(Format::ASTC_6x6_UNORM_BLOCK, Format::ASTC_6x6_SRGB_BLOCK),
+        Astc8x5Unorm => pick(Format::ASTC_8x5_UNORM_BLOCK, Format::ASTC_8x5_SRGB_BLOCK),
+        Astc8x6Unorm => pick(Format::ASTC_8x6_UNORM_BLOCK, Format::ASTC_8x6_SRGB_BLOCK),
+        Astc8x8Unorm => pick(Format::ASTC_8x8_UNORM_BLOCK, Format::ASTC_8x8_SRGB_BLOCK),
+        Astc10x5Unorm => pick(Format::ASTC_10x5_UNORM_BLOCK, Format::ASTC_10x5_SRGB_BLOCK),
+        Astc10x6Unorm => pick(Format::ASTC_10x6_UNORM_BLOCK, Format::ASTC_10x6_SRGB_BLOCK),
+        Astc10x8Unorm => pick(Format::ASTC_10x8_UNORM_BLOCK, Format::ASTC_10x8_SRGB_BLOCK),
+        Astc10x10Unorm => pick(
+            Format::ASTC_10x10_UNORM_BLOCK,
+            Format::ASTC_10x10_SRGB_BLOCK,
+        ),
+        Astc12x10Unorm => pick(
+            Format::ASTC_12x10_UNORM_BLOCK,
+            Format::ASTC_12x10_SRGB_BLOCK,
+        ),
+        Astc12x12Unorm => pick(
+            Format::ASTC_12x12_UNORM_BLOCK,
+            Format::ASTC_12x12_SRGB_BLOCK,
+        ),
+    }
+}
+
+/// Encodes a texture as a KTX2 file, with its levels Zstandard supercompressed if `supercompress` is set.
+pub(super) fn encode_ktx2(texture: &Texture, supercompress: bool) -> Result<Vec<u8>> {
+    let (format, srgb_variant) = vulkan_format(texture.format(), texture.color_space());
+    // formats without an sRGB variant declare sRGB colors through the transfer function alone
+    let transfer_function = match texture.color_space() {
+        ColorSpace::Srgb if !srgb_variant => Some(TransferFunction::SRGB),
+        _ => None,
+    };
+    let (basic, type_size) =
+        dfd::Basic::from_format_with(format, false, transfer_function, None, None)
+            .map_err(|err| anyhow!("Can't describe {format:?} in a KTX2 file: {err:?}"))?;
+    let dfd_block = dfd::Block::Basic(basic).to_vec();
+
+    let (pixel_depth, layer_count, face_count) = match texture.dimension() {
+        TextureDimension::D2 => (0, 0, 1),
+        TextureDimension::D2Array => (0, texture.depth_or_array_layers(), 1),
+        TextureDimension::Cube => (0, 0, 6),
+        TextureDimension::CubeArray => (0, texture.depth_or_array_layers() / 6, 6),
+        TextureDimension::D3 => (texture.depth_or_array_layers(), 0, 1),
+    };
+    let levels = texture
+        .levels()
+        .iter()
+        .map(|texels| match supercompress {
+            true => Ok(zstd::encode_all(texels.as_slice(), 0)?),
+            false => Ok(texels.clone()),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let level_count = levels.len();
+    let dfd_byte_offset = Header::LENGTH + level_count * LevelIndex::LENGTH;
+    let dfd_byte_length = 4 + dfd_block.len();
+    // levels start at a multiple of the block size and of 4, unless they're supercompressed
+    let alignment = match supercompress {
+        true => 1,
+        false => texture.format().block_size().max(4) as usize,
+    };
+
+    // levels are stored smallest first, so that streaming can show small levels early
+    let mut offset = dfd_byte_offset + dfd_byte_length;
+    let mut level_indices = levels
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(level, texels)| {
+            offset = offset.next_multiple_of(alignment);
+            let level_index = LevelIndex {
+                byte_offset: offset as u64,
+                byte_length: texels.len() as u64,
+                uncompressed_byte_length: texture.levels()[level].len() as u64,
+            };
+            offset += texels.len();
+            level_index
+        })
+        .collect::<Vec<_>>();
+    level_indices.reverse();
+
+    let header = Header {
+        format: Some(format),
+        type_size,
+        pixel_width: texture.width(),
+        pixel_height: texture.height(),
+        pixel_depth,
+        layer_count,
+        face_count,
+        level_count: level_count as u32,
+        supercompression_scheme: supercompress.then_some(SupercompressionScheme::Zstandard),
+        index: Index {
+            dfd_byte_offset: dfd_byte_offset as u32,
+            dfd_byte_length: dfd_byte_length as u32,
+            kvd_byte_offset: 0,
+            kvd_byte_length: 0,
+            sgd_byte_offset: 0,
+            sgd_byte_length: 0,
+        },
+    };
+
+    let mut bytes = Vec::with_capacity(offset);
+    bytes.extend_from_slice(&header.as_bytes());
+    for level_index in &level_indices {
+        bytes.extend_from_slice(&level_index.as_bytes());
+    }
+    bytes.extend_from_slice(&(dfd_byte_length as u32).to_le_bytes());
+    bytes.extend_from_slice(&dfd_block);
+    for (level_index, texels) in level_indices.iter().zip(&levels).rev() {
+        bytes.resize(level_index.byte_offset as usize, 0);
+        bytes.extend_from_slice(texels);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::MipFilter;
+    use super::*;
+
+    #[test]
+    fn test_decode_skybox_cubemap() {
+        let bytes = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../assets/skyboxes/clear_sky_diffuse.ktx2"
+        ))
+        .unwrap();
+        let texture = Texture::decode(&bytes, &TextureSettings::default()).unwrap();
+        assert_eq!(texture.format(), TextureFormat::Rgba16Float);
+        assert_eq!(texture.dimension(), TextureDimension::Cube);
+        assert_eq!(texture.color_space(), ColorSpace::Linear);
+        assert_eq!(texture.level_dimensions(0), (64, 64, 6));
+        assert!(texture.to_image(0, 5).is_some());
+    }
+
+    #[test]
+    fn test_encode_roundtrip() {
+        let texture = Texture::from_rgba8(
+            &(0..5 * 3 * 4).map(|byte| byte as u8).collect::<Vec<_>>(),
+            5,
+            3,
+        );
+        let mut cube = Texture::from_levels(
+            TextureFormat::Rgba8Unorm,
+            TextureDimension::Cube,
+            4,
+            4,
+            6,
+            vec![[10, 20, 30, 40].repeat(6 * 4 * 4)],
+        )
+        .unwrap()
+        .with_color_space(ColorSpace::Srgb);
+        cube.generate_mips(MipFilter::Box).unwrap();
+
+        let half = Texture::from_levels(
+            TextureFormat::Rg16Float,
+            TextureDimension::D2Array,
+            3,
+            2,
+            2,
+            vec![(0..3 * 2 * 2 * 4).map(|byte| byte as u8).collect()],
+        )
+        .unwrap()
+        .with_color_space(ColorSpace::Srgb);
+
+        for original in [texture, cube, half] {
+            for supercompress in [false, true] {
+                let bytes = encode_ktx2(&original, supercompress).unwrap();
+                let decoded = Texture::decode(&bytes, &TextureSettings::default()).unwrap();
+                assert_eq!(decoded.format(), original.format());
+                assert_eq!(decoded.dimension(), original.dimension());
+                assert_eq!(decoded.color_space(), original.color_space());
+                assert_eq!(decoded.level_dimensions(0), original.level_dimensions(0));
+                assert_eq!(decoded.levels(), original.levels());
+            }
+        }
+    }
+
+    #[test]
+    fn test_reject_malformed_header() {
+        let texture = Texture::from_rgba8(&[0; 4 * 4 * 4], 4, 4);
+        for supercompress in [false, true] {
+            let bytes = encode_ktx2(&texture, supercompress).unwrap();
+            let patched = |offset: usize, value: u32| {
+                let mut bytes = bytes.clone();
+                bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+                bytes
+            };
+            // pixel width and height, which the level no longer fits
+            for (offset, value) in [(20, u32::MAX), (24, 1 << 20)] {
+                let result = Texture::decode(&patched(offset, value), &TextureSettings::default());
+                assert!(result.is_err());
+            }
+            // the uncompressed length of the level, which is ignored for the size the dimensions give
+            let level_index = Header::LENGTH + 16;
+            let decoded =
+                Texture::decode(&patched(level_index, u32::MAX), &TextureSettings::default());
+            assert_eq!(decoded.unwrap().levels(), texture.levels());
+        }
+    }
+}
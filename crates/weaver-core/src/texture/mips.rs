@@ -0,0 +1,184 @@
+use std::f32::consts::PI;
+
+use super::MipFilter;
+
+/// Half the width of the Kaiser filter in texels of the downsampled image.
+const KAISER_RADIUS: f32 = 3.0;
+/// How quickly the Kaiser window falls off, trading sharpness for ringing.
+const KAISER_ALPHA: f32 = 4.0;
+
+pub(super) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub(super) fn linear_to_srgb(value: f32) -> f32 {
+    let value = value.clamp(0.0, 1.0);
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// The zeroth order modified Bessel function of the first kind, which shapes the Kaiser window.
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1.0;
+    while term > sum * 1e-7 {
+        term *= (x / (2.0 * k)).powi(2);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+impl MipFilter {
+    /// Half the width of the filter, in texels of the downsampled image.
+    fn radius(self) -> f32 {
+        match self {
+            MipFilter::Box => 0.5,
+            MipFilter::Kaiser => KAISER_RADIUS,
+        }
+    }
+
+    /// The weight of a texel `x` texels of the downsampled image away from the center of the filter.
+    fn weight(self, x: f32) -> f32 {
+        match self {
+            MipFilter::Box if x.abs() <= 0.5 => 1.0,
+            MipFilter::Box => 0.0,
+            MipFilter::Kaiser if x.abs() < KAISER_RADIUS => {
+                let window = (1.0 - (x / KAISER_RADIUS).powi(2)).sqrt();
+                sinc(x) * bessel_i0(KAISER_ALPHA * window) / bessel_i0(KAISER_ALPHA)
+            }
+            MipFilter::Kaiser => 0.0,
+        }
+    }
+
+    /// The first source texel and the normalized weights of the source texels each target texel is made of, with
+    /// texels past the edges folded into the edge texels.
+    fn weights(self, source: u32, target: u32) -> Vec<(usize, Vec<f32>)> {
+        let scale = source as f32 / target as f32;
+        let radius = self.radius() * scale;
+        (0..target)
+            .map(|texel| {
+                let center = (texel as f32 + 0.5) * scale;
+                let first = (center - radius).floor() as i64;
+                let last = (center + radius).ceil() as i64;
+                let start = first.clamp(0, source as i64 - 1) as usize;
+                let end = last.clamp(0, source as i64 - 1) as usize;
+
+                let mut weights = vec![0.0; end - start + 1];
+                for source_texel in first..=last {
+                    let x = (source_texel as f32 + 0.5 - center) / scale;
+                    let index = source_texel.clamp(0, source as i64 - 1) as usize - start;
+                    weights[index] += self.weight(x);
+                }
+                let sum = weights.iter().sum::<f32>();
+                weights.iter_mut().for_each(|weight| *weight /= sum);
+
+                // the support of the filter can end on texels it gives no weight
+                let skipped = weights.iter().take_while(|weight| **weight == 0.0).count();
+                let kept = weights.len()
+                    - weights
+                        .iter()
+                        .rev()
+                        .take_while(|weight| **weight == 0.0)
+                        .count();
+                (start + skipped, weights[skipped..kept].to_vec())
+            })
+            .collect()
+    }
+}
+
+/// Resamples an image of RGBA float texels to a smaller size, filtering rows and then columns.
+pub(super) fn downsample(
+    rgba: &[f32],
+    (width, height): (u32, u32),
+    (target_width, target_height): (u32, u32),
+    filter: MipFilter,
+) -> Vec<f32> {
+    let row_size = target_width as usize * 4;
+
+    let column_weights = filter.weights(width, target_width);
+    let mut rows = Vec::with_capacity(row_size * height as usize);
+    for row in rgba.chunks_exact(width as usize * 4) {
+        for (start, weights) in &column_weights {
+            let mut texel = [0.0; 4];
+            for (offset, weight) in weights.iter().enumerate() {
+                let source = &row[(start + offset) * 4..][..4];
+                for (value, source) in texel.iter_mut().zip(source) {
+                    *value += source * weight;
+                }
+            }
+            rows.extend_from_slice(&texel);
+        }
+    }
+
+    let row_weights = filter.weights(height, target_height);
+    let mut resampled = Vec::with_capacity(row_size * target_height as usize);
+    for (start, weights) in &row_weights {
+        let mut row = vec![0.0; row_size];
+        for (offset, weight) in weights.iter().enumerate() {
+            let source = &rows[(start + offset) * row_size..][..row_size];
+            for (value, source) in row.iter_mut().zip(source) {
+                *value += source * weight;
+            }
+        }
+        resampled.extend(row);
+    }
+    resampled
+}
+
+/// Scales the normal encoded in the color channels of a texel back to unit length.
+pub(super) fn renormalize(texel: &mut [f32]) {
+    let normal = [texel[0], texel[1], texel[2]].map(|value| value * 2.0 - 1.0);
+    let length = normal.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if length > 1e-6 {
+        for (value, normal) in texel.iter_mut().zip(normal) {
+            *value = (normal / length) * 0.5 + 0.5;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_weights() {
+        for filter in [MipFilter::Box, MipFilter::Kaiser] {
+            for (source, target) in [(8, 4), (5, 2), (1, 1), (3, 1)] {
+                for (_, weights) in filter.weights(source, target) {
+                    assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+                }
+            }
+        }
+        assert_eq!(
+            MipFilter::Box.weights(4, 2),
+            vec![(0, vec![0.5, 0.5]), (2, vec![0.5, 0.5])]
+        );
+
+        // a constant image stays constant, even with the negative lobes of the Kaiser filter
+        let constant = [0.25, 0.5, 0.75, 1.0].repeat(7 * 5);
+        let resampled = downsample(&constant, (7, 5), (3, 2), MipFilter::Kaiser);
+        assert_eq!(resampled.len(), 3 * 2 * 4);
+        for texel in resampled.chunks_exact(4) {
+            for (value, expected) in texel.iter().zip([0.25, 0.5, 0.75, 1.0]) {
+                assert!((value - expected).abs() < 1e-5);
+            }
+        }
+    }
+}
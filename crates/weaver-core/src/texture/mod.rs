@@ -0,0 +1,1197 @@
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+use half::f16;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use weaver_asset::{
+    LoadSource, PathAndFilesystem,
+    meta::LoaderSettings,
+    prelude::{Asset, LoadFrom},
+    processor::{ArtifactReader, ArtifactWriter, AssetBytes, Process},
+};
+use weaver_ecs::prelude::Commands;
+use weaver_util::prelude::*;
+
+#[cfg(feature = "basis-universal")]
+mod basis;
+mod dds;
+mod ktx2;
+mod mips;
+
+/// File extensions of the texture files [`TextureLoader`] can decode.
+pub const TEXTURE_EXTENSIONS: &[&str] = &[
+    "png",
+    "jpg",
+    "jpeg",
+    "tga",
+    "bmp",
+    "hdr",
+    "exr",
+    "ktx2",
+    "dds",
+    #[cfg(feature = "basis-universal")]
+    "basis",
+];
+
+/// How the texels of a [`Texture`] are stored. Uncompressed formats store their channels in order, each in little
+/// endian. Compressed formats store blocks of texels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    R8Unorm,
+    Rg8Unorm,
+    Rgba8Unorm,
+    R16Unorm,
+    Rg16Unorm,
+    Rgba16Unorm,
+    R16Float,
+    Rg16Float,
+    Rgba16Float,
+    R32Float,
+    Rg32Float,
+    Rgba32Float,
+    Rgb9e5Ufloat,
+    Rg11b10Ufloat,
+    Bc1RgbaUnorm,
+    Bc2RgbaUnorm,
+    Bc3RgbaUnorm,
+    Bc4RUnorm,
+    Bc4RSnorm,
+    Bc5RgUnorm,
+    Bc5RgSnorm,
+    Bc6hRgbUfloat,
+    Bc6hRgbFloat,
+    Bc7RgbaUnorm,
+    Etc2Rgb8Unorm,
+    Etc2Rgb8A1Unorm,
+    Etc2Rgba8Unorm,
+    EacR11Unorm,
+    EacRg11Unorm,
+    Astc4x4Unorm,
+    Astc5x4Unorm,
+    Astc5x5Unorm,
+    Astc6x5Unorm,
+    Astc6x6Unorm,
+    Astc8x5Unorm,
+    Astc8x6Unorm,
+    Astc8x8Unorm,
+    Astc10x5Unorm,
+    Astc10x6Unorm,
+    Astc10x8Unorm,
+    Astc10x10Unorm,
+    Astc12x10Unorm,
+    Astc12x12Unorm,
+}
+
+/// The type of the channels of an uncompressed format.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ChannelType {
+    Unorm8,
+    Unorm16,
+    Float16,
+    Float32,
+}
+
+impl TextureFormat {
+    const ALL: [TextureFormat; 43] = {
+        use TextureFormat::*;
+        [
+            R8Unorm,
+            Rg8Unorm,
+            Rgba8Unorm,
+            R16Unorm,
+            Rg16Unorm,
+            Rgba16Unorm,
+            R16Float,
+            Rg16Float,
+            Rgba16Float,
+            R32Float,
+            Rg32Float,
+            Rgba32Float,
+            Rgb9e5Ufloat,
+            Rg11b10Ufloat,
+            Bc1RgbaUnorm,
+            Bc2RgbaUnorm,
+            Bc3RgbaUnorm,
+            Bc4RUnorm,
+            Bc4RSnorm,
+            Bc5RgUnorm,
+            Bc5RgSnorm,
+            Bc6hRgbUfloat,
+            Bc6hRgbFloat,
+            Bc7RgbaUnorm,
+            Etc2Rgb8Unorm,
+            Etc2Rgb8A1Unorm,
+            Etc2Rgba8Unorm,
+            EacR11Unorm,
+            EacRg11Unorm,
+            Astc4x4Unorm,
+            Astc5x4Unorm,
+            Astc5x5Unorm,
+            Astc6x5Unorm,
+            Astc6x6Unorm,
+            Astc8x5Unorm,
+            Astc8x6Unorm,
+            Astc8x8Unorm,
+            Astc10x5Unorm,
+            Astc10x6Unorm,
+            Astc10x8Unorm,
+            Astc10x10Unorm,
+            Astc12x10Unorm,
+            Astc12x12Unorm,
+        ]
+    };
+
+    /// Width and height of the blocks of compressed formats, 1x1 for uncompressed formats.
+    pub fn block_dimensions(self) -> (u32, u32) {
+        use TextureFormat::*;
+        match self {
+            Bc1RgbaUnorm | Bc2RgbaUnorm | Bc3RgbaUnorm | Bc4RUnorm | Bc4RSnorm | Bc5RgUnorm
+            | Bc5RgSnorm | Bc6hRgbUfloat | Bc6hRgbFloat | Bc7RgbaUnorm | Etc2Rgb8Unorm
+            | Etc2Rgb8A1Unorm | Etc2Rgba8Unorm | EacR11Unorm | EacRg11Unorm | Astc4x4Unorm => {
+                (4, 4)
+            }
+            Astc5x4Unorm => (5, 4),
+            Astc5x5Unorm => (5, 5),
+            Astc6x5Unorm => (6, 5),
+            Astc6x6Unorm => (6, 6),
+            Astc8x5Unorm => (8, 5),
+            Astc8x6Unorm => (8, 6),
+            Astc8x8Unorm => (8, 8),
+            Astc10x5Unorm => (10, 5),
+            Astc10x6Unorm => (10, 6),
+            Astc10x8Unorm => (10, 8),
+            Astc10x10Unorm => (10, 10),
+            Astc12x10Unorm => (12, 10),
+            Astc12x12Unorm => (12, 12),
+            _ => (1, 1),
+        }
+    }
+
+    /// Size of a block of compressed formats, or of a texel of uncompressed formats, in bytes.
+    pub fn block_size(self) -> u32 {
+        use TextureFormat::*;
+        match self {
+            R8Unorm => 1,
+            Rg8Unorm | R16Unorm | R16Float => 2,
+            Rgba8Unorm | Rg16Unorm | Rg16Float | R32Float | Rgb9e5Ufloat | Rg11b10Ufloat => 4,
+            Rgba16Unorm | Rgba16Float | Rg32Float => 8,
+            Bc1RgbaUnorm | Bc4RUnorm | Bc4RSnorm | Etc2Rgb8Unorm | Etc2Rgb8A1Unorm
+            | EacR11Unorm => 8,
+            _ => 16,
+        }
+    }
+
+    pub fn is_compressed(self) -> bool {
+        self.block_dimensions() != (1, 1)
+    }
+
+    /// Size of an image of the given dimensions in this format, in bytes.
+    pub fn image_size(self, width: u32, height: u32) -> usize {
+        self.checked_image_size(width, height)
+            .expect("Texture image size overflows")
+    }
+
+    /// Size of an image of the given dimensions in this format, in bytes, or `None` if it overflows.
+    pub fn checked_image_size(self, width: u32, height: u32) -> Option<usize> {
+        let (block_width, block_height) = self.block_dimensions();
+        (width.div_ceil(block_width) as usize)
+            .checked_mul(height.div_ceil(block_height) as usize)?
+            .checked_mul(self.block_size() as usize)
+    }
+
+    /// Channel count and type of uncompressed formats whose texels can be converted to and from floats.
+    fn channels(self) -> Option<(usize, ChannelType)> {
+        use TextureFormat::*;
+        match self {
+            R8Unorm => Some((1, ChannelType::Unorm8)),
+            Rg8Unorm => Some((2, ChannelType::Unorm8)),
+            Rgba8Unorm => Some((4, ChannelType::Unorm8)),
+            R16Unorm => Some((1, ChannelType::Unorm16)),
+            Rg16Unorm => Some((2, ChannelType::Unorm16)),
+            Rgba16Unorm => Some((4, ChannelType::Unorm16)),
+            R16Float => Some((1, ChannelType::Float16)),
+            Rg16Float => Some((2, ChannelType::Float16)),
+            Rgba16Float => Some((4, ChannelType::Float16)),
+            R32Float => Some((1, ChannelType::Float32)),
+            Rg32Float => Some((2, ChannelType::Float32)),
+            Rgba32Float => Some((4, ChannelType::Float32)),
+            _ => None,
+        }
+    }
+}
+
+/// The shape of a [`Texture`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TextureDimension {
+    #[default]
+    D2,
+    D2Array,
+    /// Six layers, the faces in the order +X, -X, +Y, -Y, +Z, -Z.
+    Cube,
+    /// Six layers per cube.
+    CubeArray,
+    D3,
+}
+
+impl TextureDimension {
+    const ALL: [TextureDimension; 5] = [
+        TextureDimension::D2,
+        TextureDimension::D2Array,
+        TextureDimension::Cube,
+        TextureDimension::CubeArray,
+        TextureDimension::D3,
+    ];
+}
+
+/// How the color channels of a [`Texture`] are encoded, which selects whether the GPU decodes them when sampling.
+/// Alpha is always linear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorSpace {
+    /// Colors encoded with the sRGB transfer function, like those of most color images.
+    Srgb,
+    /// Linear values, like those of normal maps, roughness maps and HDR images.
+    Linear,
+}
+
+impl ColorSpace {
+    const ALL: [ColorSpace; 2] = [ColorSpace::Srgb, ColorSpace::Linear];
+}
+
+/// The filter mipmaps are downsampled with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MipFilter {
+    /// Averages the texels each texel covers. Blurrier, but never rings.
+    Box,
+    /// A Kaiser windowed sinc, which keeps mipmaps sharper at the cost of slight ringing around hard edges.
+    #[default]
+    Kaiser,
+}
+
+#[derive(Debug, Clone, Asset)]
+pub struct Texture {
+    format: TextureFormat,
+    dimension: TextureDimension,
+    color_space: ColorSpace,
+    width: u32,
+    height: u32,
+    depth_or_array_layers: u32,
+    /// Texels of each mip level, largest first. Each level holds its array layers, cube faces or depth slices one
+    /// after another.
+    levels: Vec<Vec<u8>>,
+}
+
+impl Texture {
+    pub fn new(image: image::RgbaImage) -> Self {
+        Self {
+            format: TextureFormat::Rgba8Unorm,
+            dimension: TextureDimension::D2,
+            color_space: ColorSpace::Srgb,
+            width: image.width(),
+            height: image.height(),
+            depth_or_array_layers: 1,
+            levels: vec![image.into_raw()],
+        }
+    }
+
+    /// Creates a linear texture from the texels of each mip level, largest first, checking that every level has the
+    /// size the format and dimensions call for.
+    pub fn from_levels(
+        format: TextureFormat,
+        dimension: TextureDimension,
+        width: u32,
+        height: u32,
+        depth_or_array_layers: u32,
+        levels: Vec<Vec<u8>>,
+    ) -> Result<Self> {
+        check_shape(
+            dimension,
+            width,
+            height,
+            depth_or_array_layers,
+            levels.len(),
+        )?;
+
+        let texture = Self {
+            format,
+            dimension,
+            color_space: ColorSpace::Linear,
+            width,
+            height,
+            depth_or_array_layers,
+            levels,
+        };
+        for (level, texels) in texture.levels.iter().enumerate() {
+            let expected = texture
+                .checked_level_size(level)
+                .ok_or_else(|| anyhow!("Size of mip level {level} of texture overflows"))?;
+            ensure!(
+                texels.len() == expected,
+                "Mip level {level} of texture has {} bytes, expected {expected}",
+                texels.len()
+            );
+        }
+        Ok(texture)
+    }
+
+    pub fn from_rgba8(rgba8: &[u8], width: u32, height: u32) -> Self {
+        let image = image::RgbaImage::from_raw(width, height, rgba8.to_vec()).unwrap();
+        Self::new(image)
+    }
+
+    pub fn from_rgb8(rgb8: &[u8], width: u32, height: u32) -> Self {
+        Self::from_rgba8(&expand_to_rgba(rgb8, 3, &[255]), width, height)
+    }
+
+    /// Creates a 2D texture from a decoded image. 16-bit images are stored as [`TextureFormat::Rgba16Unorm`] and
+    /// float images as [`TextureFormat::Rgba16Float`], which unlike 32-bit floats can be filtered on every GPU.
+    /// Everything else is stored as [`TextureFormat::Rgba8Unorm`]. Float images are linear, others sRGB.
+    pub fn from_image(image: DynamicImage) -> Self {
+        let (format, color_space) = match image {
+            DynamicImage::ImageLuma16(_)
+            | DynamicImage::ImageLumaA16(_)
+            | DynamicImage::ImageRgb16(_)
+            | DynamicImage::ImageRgba16(_) => (TextureFormat::Rgba16Unorm, ColorSpace::Srgb),
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+                (TextureFormat::Rgba16Float, ColorSpace::Linear)
+            }
+            _ => (TextureFormat::Rgba8Unorm, ColorSpace::Srgb),
+        };
+        Self {
+            format,
+            dimension: TextureDimension::D2,
+            color_space,
+            width: image.width(),
+            height: image.height(),
+            depth_or_array_layers: 1,
+            levels: vec![image_to_texels(format, image)],
+        }
+    }
+
+    /// Decodes a PNG, JPEG, TGA, BMP, Radiance HDR, OpenEXR, KTX2 or DDS file, or with the `basis-universal`
+    /// feature a `.basis` file, telling them apart by their contents. The other settings aren't applied.
+    pub fn decode(bytes: &[u8], settings: &TextureSettings) -> Result<Self> {
+        if bytes.starts_with(&ktx2::MAGIC) {
+            return ktx2::decode_ktx2(bytes, settings);
+        }
+        if bytes.starts_with(&dds::MAGIC) {
+            return dds::decode_dds(bytes);
+        }
+        #[cfg(feature = "basis-universal")]
+        if bytes.starts_with(&basis::MAGIC) {
+            return basis::decode_basis(bytes, settings);
+        }
+
+        // TGA files have no magic bytes, so they're tried first
+        let image = match image::load_from_memory_with_format(bytes, image::ImageFormat::Tga) {
+            Ok(image) => image,
+            Err(_) => image::load_from_memory(bytes)?,
+        };
+        log::trace!(
+            "Successfully loaded texture with dimensions {}x{}",
+            image.width(),
+            image.height()
+        );
+        Ok(Self::from_image(image))
+    }
+
+    /// Encodes the texture with all its levels as a KTX2 file, Zstandard supercompressed if `supercompress` is set.
+    pub fn encode_ktx2(&self, supercompress: bool) -> Result<Vec<u8>> {
+        ktx2::encode_ktx2(self, supercompress)
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    pub fn dimension(&self) -> TextureDimension {
+        self.dimension
+    }
+
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Marks the texels as encoded in another color space, without changing them.
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.color_space = color_space;
+    }
+
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The depth of 3D textures, or the number of layers of others. Cube textures have a layer per face.
+    pub fn depth_or_array_layers(&self) -> u32 {
+        self.depth_or_array_layers
+    }
+
+    pub fn mip_level_count(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    /// The texels of each mip level, largest first.
+    pub fn levels(&self) -> &[Vec<u8>] {
+        &self.levels
+    }
+
+    /// Width, height and depth or layer count of a mip level.
+    pub fn level_dimensions(&self, level: usize) -> (u32, u32, u32) {
+        let depth_or_array_layers = match self.dimension {
+            TextureDimension::D3 => (self.depth_or_array_layers >> level).max(1),
+            _ => self.depth_or_array_layers,
+        };
+        (
+            (self.width >> level).max(1),
+            (self.height >> level).max(1),
+            depth_or_array_layers,
+        )
+    }
+
+    /// Size of a mip level in bytes.
+    pub fn level_size(&self, level: usize) -> usize {
+        self.checked_level_size(level)
+            .expect("Texture level size overflows")
+    }
+
+    /// Size of a mip level in bytes, or `None` if it overflows.
+    fn checked_level_size(&self, level: usize) -> Option<usize> {
+        let (width, height, depth_or_array_layers) = self.level_dimensions(level);
+        self.format
+            .checked_image_size(width, height)?
+            .checked_mul(depth_or_array_layers as usize)
+    }
+
+    /// The texels of all mip levels in order, as the GPU expects them.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.levels.concat()
+    }
+
+    /// A layer, cube face or depth slice of a mip level as an image. 8 and 16-bit RGBA formats keep their channel
+    /// type, other uncompressed formats are converted to 32-bit float RGBA with missing color channels set to 0 and
+    /// missing alpha to 1. Compressed and packed formats can't be converted.
+    pub fn to_image(&self, level: usize, layer: u32) -> Option<DynamicImage> {
+        let (width, height, depth_or_array_layers) = self.level_dimensions(level);
+        if layer >= depth_or_array_layers {
+            return None;
+        }
+        let (channels, ty) = self.format.channels()?;
+        let size = self.format.image_size(width, height);
+        let texels = self.levels.get(level)?[size * layer as usize..][..size].to_vec();
+
+        match (channels, ty) {
+            (4, ChannelType::Unorm8) => {
+                image::RgbaImage::from_raw(width, height, texels).map(DynamicImage::ImageRgba8)
+            }
+            (4, ChannelType::Unorm16) => {
+                let texels = texels
+                    .chunks_exact(2)
+                    .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+                    .collect();
+                image::ImageBuffer::from_raw(width, height, texels).map(DynamicImage::ImageRgba16)
+            }
+            _ => {
+                let texels = texels_to_rgba32f(&texels, channels, ty);
+                image::Rgba32FImage::from_raw(width, height, texels).map(DynamicImage::ImageRgba32F)
+            }
+        }
+    }
+
+    /// Converts the texels of every level to another uncompressed format, mapping channels like
+    /// [`Texture::to_image`].
+    pub fn convert(&self, format: TextureFormat) -> Result<Texture> {
+        self.convert_texels(format, false)
+    }
+
+    /// Converts the texels of every level to another uncompressed format like [`Texture::convert`], decoding sRGB
+    /// colors to linear ones. Meant for formats the GPU can't decode sRGB colors of.
+    pub fn to_linear(&self, format: TextureFormat) -> Result<Texture> {
+        self.convert_texels(format, self.color_space == ColorSpace::Srgb)
+    }
+
+    fn convert_texels(&self, format: TextureFormat, decode_srgb: bool) -> Result<Texture> {
+        let (Some((from_channels, from_ty)), Some((channels, ty))) =
+            (self.format.channels(), format.channels())
+        else {
+            bail!(
+                "Can't convert textures from {:?} to {format:?}",
+                self.format
+            );
+        };
+        let levels = self
+            .levels
+            .iter()
+            .map(|texels| {
+                let mut rgba = texels_to_rgba32f(texels, from_channels, from_ty);
+                if decode_srgb {
+                    for texel in rgba.chunks_exact_mut(4) {
+                        texel[..3]
+                            .iter_mut()
+                            .for_each(|value| *value = mips::srgb_to_linear(*value));
+                    }
+                }
+                rgba32f_to_texels(&rgba, channels, ty)
+            })
+            .collect();
+        let color_space = match decode_srgb {
+            true => ColorSpace::Linear,
+            false => self.color_space,
+        };
+        Ok(Texture {
+            format,
+            color_space,
+            levels,
+            ..*self
+        })
+    }
+
+    /// Replaces each layer of the largest mip level with the result of `f`. All layers must end up with the same
+    /// size. Any other mip levels are discarded.
+    pub fn map_layers(&mut self, mut f: impl FnMut(DynamicImage) -> DynamicImage) -> Result<()> {
+        ensure!(
+            self.dimension != TextureDimension::D3,
+            "Can't edit the layers of 3D textures"
+        );
+
+        let mut texels = Vec::new();
+        let mut size = None;
+        for layer in 0..self.depth_or_array_layers {
+            let image = self
+                .to_image(0, layer)
+                .ok_or_else(|| anyhow!("Can't edit textures of format {:?}", self.format))?;
+            let image = f(image);
+            let dimensions = (image.width(), image.height());
+            ensure!(
+                *size.get_or_insert(dimensions) == dimensions,
+                "Layers of texture were resized to different sizes"
+            );
+            texels.extend(image_to_texels(self.format, image));
+        }
+
+        let (width, height) = size.unwrap();
+        self.width = width;
+        self.height = height;
+        self.levels = vec![texels];
+        Ok(())
+    }
+
+    /// Generates the full chain of mipmaps down to 1x1, replacing any existing ones. Each level is filtered from the
+    /// previous one, with sRGB colors filtered as linear ones.
+    pub fn generate_mips(&mut self, filter: MipFilter) -> Result<()> {
+        self.generate_mips_with(filter, false)
+    }
+
+    /// Generates mipmaps of a tangent space normal map like [`Texture::generate_mips`], scaling the filtered normals
+    /// back to unit length. Normal maps with less than three channels are left as filtered, as their Z is
+    /// reconstructed when they're sampled.
+    pub fn generate_normal_mips(&mut self, filter: MipFilter) -> Result<()> {
+        self.generate_mips_with(filter, true)
+    }
+
+    fn generate_mips_with(&mut self, filter: MipFilter, normal_map: bool) -> Result<()> {
+        ensure!(
+            self.dimension != TextureDimension::D3,
+            "Can't generate mipmaps of 3D textures"
+        );
+        let (channels, ty) = self
+            .format
+            .channels()
+            .ok_or_else(|| anyhow!("Can't generate mipmaps of format {:?}", self.format))?;
+        let srgb = self.color_space == ColorSpace::Srgb && !normal_map;
+
+        let layer_size = self.format.image_size(self.width, self.height);
+        let mut layers = self.levels[0]
+            .chunks_exact(layer_size)
+            .map(|texels| {
+                let mut rgba = texels_to_rgba32f(texels, channels, ty);
+                if srgb {
+                    for texel in rgba.chunks_exact_mut(4) {
+                        texel[..3]
+                            .iter_mut()
+                            .for_each(|value| *value = mips::srgb_to_linear(*value));
+                    }
+                }
+                rgba
+            })
+            .collect::<Vec<_>>();
+
+        self.levels.truncate(1);
+        let (mut width, mut height) = (self.width, self.height);
+        while width > 1 || height > 1 {
+            let target = ((width / 2).max(1), (height / 2).max(1));
+            let mut level = Vec::with_capacity(self.format.image_size(target.0, target.1));
+            for layer in layers.iter_mut() {
+                *layer = mips::downsample(layer, (width, height), target, filter);
+
+                let mut rgba = layer.clone();
+                for texel in rgba.chunks_exact_mut(4) {
+                    if normal_map {
+                        if channels >= 3 {
+                            mips::renormalize(texel);
+                        }
+                    } else {
+                        // the negative lobes of the Kaiser filter can undershoot dark texels
+                        texel.iter_mut().for_each(|value| *value = value.max(0.0));
+                        if srgb {
+                            texel[..3]
+                                .iter_mut()
+                                .for_each(|value| *value = mips::linear_to_srgb(*value));
+                        }
+                    }
+                }
+                level.extend(rgba32f_to_texels(&rgba, channels, ty));
+            }
+            self.levels.push(level);
+            (width, height) = target;
+        }
+        Ok(())
+    }
+
+    /// Resizes every layer. Any mipmaps are discarded.
+    pub fn resize(
+        &mut self,
+        width: u32,
+        height: u32,
+        filter: image::imageops::FilterType,
+    ) -> Result<()> {
+        self.map_layers(|image| image.resize_exact(width, height, filter))
+    }
+}
+
+/// Appends an opaque alpha channel to each texel of 3-channel data whose channels are `channel_size` bytes.
+pub(crate) fn expand_to_rgba(texels: &[u8], channel_size: usize, one: &[u8]) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity(texels.len() / 3 * 4);
+    for texel in texels.chunks_exact(channel_size * 3) {
+        rgba.extend_from_slice(texel);
+        rgba.extend_from_slice(one);
+    }
+    rgba
+}
+
+/// Swaps the first and third channel of each texel, turning BGRA into RGBA.
+pub(crate) fn swap_red_blue(texels: &mut [u8], channel_size: usize) {
+    for texel in texels.chunks_exact_mut(channel_size * 4) {
+        let (red, rest) = texel.split_at_mut(channel_size);
+        red.swap_with_slice(&mut rest[channel_size..][..channel_size]);
+    }
+}
+
+fn texels_to_rgba32f(texels: &[u8], channels: usize, ty: ChannelType) -> Vec<f32> {
+    let channel_size = match ty {
+        ChannelType::Unorm8 => 1,
+        ChannelType::Unorm16 | ChannelType::Float16 => 2,
+        ChannelType::Float32 => 4,
+    };
+    let read = |bytes: &[u8]| match ty {
+        ChannelType::Unorm8 => bytes[0] as f32 / 255.0,
+        ChannelType::Unorm16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 65535.0,
+        ChannelType::Float16 => f16::from_le_bytes([bytes[0], bytes[1]]).to_f32(),
+        ChannelType::Float32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+    };
+
+    let mut rgba = Vec::with_capacity(texels.len() / channel_size / channels * 4);
+    for texel in texels.chunks_exact(channel_size * channels) {
+        let mut values = [0.0, 0.0, 0.0, 1.0];
+        for (value, bytes) in values.iter_mut().zip(texel.chunks_exact(channel_size)) {
+            *value = read(bytes);
+        }
+        rgba.extend_from_slice(&values);
+    }
+    rgba
+}
+
+/// Converts an image to the texels of an uncompressed format, dropping channels the format doesn't have.
+fn image_to_texels(format: TextureFormat, image: DynamicImage) -> Vec<u8> {
+    let (channels, ty) = format
+        .channels()
+        .unwrap_or_else(|| panic!("Images can't be converted to {format:?}"));
+    match ty {
+        ChannelType::Unorm8 => image
+            .into_rgba8()
+            .pixels()
+            .flat_map(|pixel| pixel.0.into_iter().take(channels))
+            .collect(),
+        ChannelType::Unorm16 => image
+            .into_rgba16()
+            .pixels()
+            .flat_map(|pixel| pixel.0.into_iter().take(channels))
+            .flat_map(u16::to_le_bytes)
+            .collect(),
+        ChannelType::Float16 | ChannelType::Float32 => {
+            rgba32f_to_texels(image.into_rgba32f().as_raw(), channels, ty)
+        }
+    }
+}
+
+/// Converts 32-bit float RGBA texels to those of an uncompressed format, dropping channels the format doesn't have.
+fn rgba32f_to_texels(rgba: &[f32], channels: usize, ty: ChannelType) -> Vec<u8> {
+    let values = rgba
+        .chunks_exact(4)
+        .flat_map(|texel| &texel[..channels])
+        .copied();
+    match ty {
+        ChannelType::Unorm8 => values
+            .map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect(),
+        ChannelType::Unorm16 => values
+            .map(|value| (value.clamp(0.0, 1.0) * 65535.0).round() as u16)
+            .flat_map(u16::to_le_bytes)
+            .collect(),
+        // values beyond the range of half floats, e.g. the sun in an HDR, become the largest one
+        ChannelType::Float16 => values
+            .map(|value| f16::from_f32(value.clamp(f16::MIN.to_f32(), f16::MAX.to_f32())))
+            .flat_map(f16::to_le_bytes)
+            .collect(),
+        ChannelType::Float32 => values.flat_map(f32::to_le_bytes).collect(),
+    }
+}
+
+#[derive(Default)]
+pub struct TextureLoader<S: LoadSource>(std::marker::PhantomData<S>);
+
+impl LoadFrom<PathAndFilesystem> for TextureLoader<PathBuf> {
+    type Asset = Texture;
+    type Settings = TextureSettings;
+
+    async fn load(
+        &self,
+        source: PathAndFilesystem,
+        settings: &TextureSettings,
+        _commands: &Commands,
+    ) -> Result<Texture> {
+        match source.read_processed_async::<TextureProcessor>().await? {
+            AssetBytes::Processed(bytes) => read_processed_texture(&bytes),
+            AssetBytes::Source(bytes) => {
+                let mut texture = Texture::decode(&bytes, settings)?;
+                settings.apply(&mut texture)?;
+                Ok(texture)
+            }
+        }
+    }
+}
+
+impl LoadFrom<Vec<u8>> for TextureLoader<Vec<u8>> {
+    type Asset = Texture;
+    type Settings = TextureSettings;
+
+    async fn load(
+        &self,
+        source: Vec<u8>,
+        settings: &TextureSettings,
+        _commands: &Commands,
+    ) -> Result<Texture> {
+        let mut texture = Texture::decode(&source, settings)?;
+        settings.apply(&mut texture)?;
+        Ok(texture)
+    }
+}
+
+const TEXTURE_MAGIC: [u8; 4] = *b"WTEX";
+
+/// The format Basis Universal textures are transcoded to when they're loaded. Only used with the
+/// `basis-universal` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BasisTarget {
+    /// Uncompressed RGBA8, which every GPU supports.
+    #[default]
+    Rgba8,
+    /// BC7, supported by desktop GPUs.
+    Bc7,
+    /// ETC2 RGBA8, supported by most mobile GPUs.
+    Etc2,
+    /// ASTC 4x4, supported by most mobile GPUs.
+    Astc4x4,
+}
+
+/// Import settings of textures, read from the `[texture]` section of their meta file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextureSettings {
+    /// Generate mipmaps for textures without them. Textures in compressed formats and 3D textures are left as is.
+    pub generate_mips: bool,
+    pub mip_filter: MipFilter,
+    /// Overrides the color space the file declares, or that images are assumed to have.
+    pub color_space: Option<ColorSpace>,
+    /// Treat the texture as a tangent space normal map, which is linear and has its mipmaps renormalized.
+    pub normal_map: bool,
+    /// Flip the image vertically, for textures authored with the origin in the bottom left corner.
+    pub flip_y: bool,
+    /// Deflate the pixels of the processed artifact, trading load time for disk space.
+    pub compress: bool,
+    pub basis_target: BasisTarget,
+}
+
+impl Default for TextureSettings {
+    fn default() -> Self {
+        Self {
+            generate_mips: true,
+            mip_filter: MipFilter::default(),
+            color_space: None,
+            normal_map: false,
+            flip_y: false,
+            compress: false,
+            basis_target: BasisTarget::default(),
+        }
+    }
+}
+
+impl LoaderSettings for TextureSettings {
+    const NAME: &'static str = "texture";
+}
+
+impl TextureSettings {
+    /// Applies the settings to a texture loaded from its source file.
+    pub fn apply(&self, texture: &mut Texture) -> Result<()> {
+        if let Some(color_space) = self.color_space {
+            texture.set_color_space(color_space);
+        }
+        if self.normal_map {
+            texture.set_color_space(ColorSpace::Linear);
+        }
+        if self.flip_y {
+            texture.map_layers(|image| image.flipv())?;
+        }
+        if self.generate_mips
+            && texture.mip_level_count() == 1
+            && !texture.format().is_compressed()
+            && texture.dimension() != TextureDimension::D3
+        {
+            if self.normal_map {
+                texture.generate_normal_mips(self.mip_filter)?;
+            } else {
+                texture.generate_mips(self.mip_filter)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decodes image files into raw texels, optionally with mipmaps.
+#[derive(Default)]
+pub struct TextureProcessor;
+
+impl Process for TextureProcessor {
+    const KIND: &'static str = "texture";
+    const VERSION: u32 = 3;
+
+    type Settings = TextureSettings;
+
+    fn process(&self, source: &[u8], settings: &TextureSettings) -> Result<Vec<u8>> {
+        let mut texture = Texture::decode(source, settings)?;
+        settings.apply(&mut texture)?;
+
+        let mut writer = ArtifactWriter::new(TEXTURE_MAGIC);
+        write_texture(&mut writer, &texture, settings)?;
+        Ok(writer.finish())
+    }
+}
+
+pub fn read_processed_texture(bytes: &[u8]) -> Result<Texture> {
+    read_texture(&mut ArtifactReader::new(bytes, TEXTURE_MAGIC)?)
+}
+
+/// Writes a texture and its mipmaps, e.g. as part of a larger artifact.
+pub fn write_texture(
+    writer: &mut ArtifactWriter,
+    texture: &Texture,
+    settings: &TextureSettings,
+) -> Result<()> {
+    let format = TextureFormat::ALL
+        .iter()
+        .position(|format| *format == texture.format)
+        .unwrap();
+    let dimension = TextureDimension::ALL
+        .iter()
+        .position(|dimension| *dimension == texture.dimension)
+        .unwrap();
+    let color_space = ColorSpace::ALL
+        .iter()
+        .position(|color_space| *color_space == texture.color_space)
+        .unwrap();
+    writer.write_u32(format as u32);
+    writer.write_u32(dimension as u32);
+    writer.write_u8(color_space as u8);
+    writer.write_u32(texture.width);
+    writer.write_u32(texture.height);
+    writer.write_u32(texture.depth_or_array_layers);
+    writer.write_u32(texture.mip_level_count());
+    writer.write_u8(settings.compress as u8);
+
+    let texels = texture.to_bytes();
+    if settings.compress {
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&texels)?;
+        writer.write_bytes(&encoder.finish()?);
+    } else {
+        writer.write_bytes(&texels);
+    }
+    Ok(())
+}
+
+/// Checks that the dimensions of a texture fit its shape, and that it has at least one mip level but no more than its
+/// largest side can be halved into.
+fn check_shape(
+    dimension: TextureDimension,
+    width: u32,
+    height: u32,
+    depth_or_array_layers: u32,
+    mip_level_count: usize,
+) -> Result<()> {
+    ensure!(
+        width > 0 && height > 0 && depth_or_array_layers > 0,
+        "Texture has a size of {width}x{height}x{depth_or_array_layers}"
+    );
+    match dimension {
+        TextureDimension::D2 => ensure!(
+            depth_or_array_layers == 1,
+            "2D texture has {depth_or_array_layers} layers"
+        ),
+        TextureDimension::Cube => ensure!(
+            depth_or_array_layers == 6,
+            "Cube texture has {depth_or_array_layers} faces"
+        ),
+        TextureDimension::CubeArray => ensure!(
+            depth_or_array_layers.is_multiple_of(6),
+            "Cube array texture has {depth_or_array_layers} faces"
+        ),
+        TextureDimension::D2Array | TextureDimension::D3 => {}
+    }
+    ensure!(mip_level_count > 0, "Texture has no mip levels");
+    let largest = match dimension {
+        TextureDimension::D3 => width.max(height).max(depth_or_array_layers),
+        _ => width.max(height),
+    };
+    ensure!(
+        mip_level_count <= (largest.ilog2() + 1) as usize,
+        "Texture of {width}x{height}x{depth_or_array_layers} has {} mip levels",
+        mip_level_count
+    );
+    Ok(())
+}
+
+pub fn read_texture(reader: &mut ArtifactReader) -> Result<Texture> {
+    let format = *TextureFormat::ALL
+        .get(reader.read_u32()? as usize)
+        .ok_or_else(|| anyhow!("Texture artifact has an unknown format"))?;
+    let dimension = *TextureDimension::ALL
+        .get(reader.read_u32()? as usize)
+        .ok_or_else(|| anyhow!("Texture artifact has an unknown dimension"))?;
+    let color_space = *ColorSpace::ALL
+        .get(reader.read_u8()? as usize)
+        .ok_or_else(|| anyhow!("Texture artifact has an unknown color space"))?;
+    let width = reader.read_u32()?;
+    let height = reader.read_u32()?;
+    let depth_or_array_layers = reader.read_u32()?;
+    let mip_level_count = reader.read_u32()?;
+    let compressed = reader.read_u8()? != 0;
+
+    // the header is checked before anything is sized from it
+    check_shape(
+        dimension,
+        width,
+        height,
+        depth_or_array_layers,
+        mip_level_count as usize,
+    )?;
+    // an empty texture of the right shape tells the size of each level
+    let shape = Texture {
+        format,
+        dimension,
+        color_space,
+        width,
+        height,
+        depth_or_array_layers,
+        levels: Vec::new(),
+    };
+    let level_sizes = (0..mip_level_count as usize)
+        .map(|level| shape.checked_level_size(level))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| anyhow!("Texture artifact size overflows"))?;
+    let total_size = level_sizes
+        .iter()
+        .try_fold(0usize, |total, &size| total.checked_add(size))
+        .ok_or_else(|| anyhow!("Texture artifact size overflows"))?;
+
+    let texels = if compressed {
+        // never inflate past the size the header calls for
+        let mut texels = Vec::new();
+        flate2::read::DeflateDecoder::new(reader.read_bytes()?)
+            .take(total_size as u64)
+            .read_to_end(&mut texels)?;
+        texels
+    } else {
+        reader.read_bytes()?.to_vec()
+    };
+
+    let mut texels = texels.as_slice();
+    let mut levels = Vec::with_capacity(level_sizes.len());
+    for size in level_sizes {
+        if texels.len() < size {
+            bail!("Texture artifact is missing texels");
+        }
+        let (level, rest) = texels.split_at(size);
+        levels.push(level.to_vec());
+        texels = rest;
+    }
+    Ok(Texture::from_levels(
+        format,
+        dimension,
+        width,
+        height,
+        depth_or_array_layers,
+        levels,
+    )?
+    .with_color_space(color_space))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_processed_texture_roundtrip() {
+        let mut texture = Texture::new(image::RgbaImage::from_fn(8, 4, |x, y| {
+            image::Rgba([x as u8 * 30, y as u8 * 60, 0, 255])
+        }));
+        texture.generate_mips(MipFilter::Box).unwrap();
+        assert_eq!(texture.mip_level_count(), 4);
+        assert_eq!(texture.level_dimensions(3), (1, 1, 1));
+
+        for compress in [false, true] {
+            let settings = TextureSettings {
+                compress,
+                ..Default::default()
+            };
+            let mut writer = ArtifactWriter::new(TEXTURE_MAGIC);
+            write_texture(&mut writer, &texture, &settings).unwrap();
+            let read = read_processed_texture(&writer.finish()).unwrap();
+            assert_eq!(read.levels, texture.levels);
+            assert_eq!(read.color_space(), ColorSpace::Srgb);
+        }
+    }
+
+    #[test]
+    fn test_malformed_texture_artifact() {
+        let artifact = |width: u32, height: u32, mip_level_count: u32| {
+            let mut writer = ArtifactWriter::new(TEXTURE_MAGIC);
+            writer.write_u32(0);
+            writer.write_u32(0);
+            writer.write_u8(0);
+            writer.write_u32(width);
+            writer.write_u32(height);
+            writer.write_u32(1);
+            writer.write_u32(mip_level_count);
+            writer.write_u8(0);
+            writer.write_bytes(&[0; 4]);
+            writer.finish()
+        };
+
+        assert!(read_processed_texture(&artifact(1, 1, 1)).is_ok());
+        assert!(read_processed_texture(&artifact(1, 1, 2)).is_err());
+        assert!(read_processed_texture(&artifact(1, 1, u32::MAX)).is_err());
+        assert!(read_processed_texture(&artifact(0, 1, 1)).is_err());
+        assert!(read_processed_texture(&artifact(u32::MAX, u32::MAX, 1)).is_err());
+    }
+
+    #[test]
+    fn test_float_and_16_bit_images() {
+        let hdr = image::Rgb32FImage::from_fn(4, 4, |x, _| image::Rgb([x as f32 * 10.0, 0.5, 1e6]));
+        let mut texture = Texture::from_image(hdr.into());
+        assert_eq!(texture.format(), TextureFormat::Rgba16Float);
+        assert_eq!(texture.levels()[0].len(), 4 * 4 * 8);
+        let image = texture.to_image(0, 0).unwrap().into_rgba32f();
+        assert_eq!(image.get_pixel(3, 0).0, [30.0, 0.5, 65504.0, 1.0]);
+
+        assert_eq!(texture.color_space(), ColorSpace::Linear);
+        texture.generate_mips(MipFilter::Kaiser).unwrap();
+        assert_eq!(texture.mip_level_count(), 3);
+        let image = texture.to_image(2, 0).unwrap().into_rgba32f();
+        assert!((image.get_pixel(0, 0).0[1] - 0.5).abs() < 1e-3);
+
+        let deep =
+            image::ImageBuffer::<image::Rgba<u16>, _>::from_pixel(2, 2, image::Rgba([1000; 4]));
+        let texture = Texture::from_image(deep.into());
+        assert_eq!(texture.format(), TextureFormat::Rgba16Unorm);
+        let DynamicImage::ImageRgba16(image) = texture.to_image(0, 0).unwrap() else {
+            panic!("16-bit texture converted to a different image type");
+        };
+        assert_eq!(image.get_pixel(1, 1).0, [1000; 4]);
+
+        let half = texture.convert(TextureFormat::R16Float).unwrap();
+        assert_eq!(half.levels()[0].len(), 2 * 2 * 2);
+        let image = half.to_image(0, 0).unwrap().into_rgba32f();
+        let [red, green, _, alpha] = image.get_pixel(0, 0).0;
+        assert!((red - 1000.0 / 65535.0).abs() < 1e-4);
+        assert_eq!((green, alpha), (0.0, 1.0));
+    }
+
+    #[test]
+    fn test_cube_layers() {
+        let faces = (0..6u8)
+            .flat_map(|face| [face, face + 10].repeat(16))
+            .collect::<Vec<_>>();
+        let mut texture = Texture::from_levels(
+            TextureFormat::Rg8Unorm,
+            TextureDimension::Cube,
+            4,
+            4,
+            6,
+            vec![faces],
+        )
+        .unwrap();
+        texture.generate_mips(MipFilter::Box).unwrap();
+        assert_eq!(texture.mip_level_count(), 3);
+        assert_eq!(texture.level_size(2), 6 * 2);
+        assert_eq!(texture.levels()[2][8..10], [4, 14]);
+
+        assert!(
+            Texture::from_levels(
+                TextureFormat::Bc1RgbaUnorm,
+                TextureDimension::D2,
+                8,
+                8,
+                1,
+                vec![vec![0; 32], vec![0; 8], vec![0; 7]],
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_srgb_and_normal_map_mips() {
+        // black and white average to half the light, which is brighter than half the sRGB value
+        let mut texture = Texture::from_rgba8(&[0, 0, 0, 0, 255, 255, 255, 255], 2, 1);
+        texture.generate_mips(MipFilter::Box).unwrap();
+        assert_eq!(texture.levels()[1][..3], [188, 188, 188]);
+        assert_eq!(texture.levels()[1][3], 128);
+
+        let linear = texture.to_linear(TextureFormat::R16Float).unwrap();
+        assert_eq!(linear.color_space(), ColorSpace::Linear);
+        let red = f16::from_le_bytes([linear.levels()[1][0], linear.levels()[1][1]]).to_f32();
+        assert!((red - 0.5).abs() < 1e-2);
+
+        // +X and +Z average to a normal halfway between them, rather than one of length 0.7
+        let mut normals = Texture::from_rgba8(&[255, 128, 128, 255, 128, 128, 255, 255], 2, 1)
+            .with_color_space(ColorSpace::Linear);
+        normals.generate_normal_mips(MipFilter::Box).unwrap();
+        let [x, y, z, _] = normals.levels()[1][..] else {
+            panic!("Normal map mip has the wrong size");
+        };
+        assert!(x.abs_diff(218) <= 1 && y.abs_diff(128) <= 1 && z.abs_diff(218) <= 1);
+    }
+}
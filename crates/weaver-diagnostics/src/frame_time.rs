@@ -1,4 +1,4 @@
-use weaver_app::{plugin::Plugin, App, AppStage};
+use weaver_app::{plugin::Plugin, App, AppStage, AppTimings};
 use weaver_ecs::{
     component::{Res, ResMut},
     system::IntoSystemConfig,
@@ -39,7 +39,9 @@ pub struct LogFrameTimePlugin {
 
 impl Plugin for LogFrameTimePlugin {
     fn build(&self, app: &mut App) -> Result<()> {
-        app.add_plugin(FrameTimePlugin)?;
+        if !app.has_plugin::<FrameTimePlugin>() {
+            app.add_plugin(FrameTimePlugin)?;
+        }
         app.insert_resource(FrameTimeLogger {
             log_interval: self.log_interval,
             last_log: std::time::Instant::now(),
@@ -58,7 +60,11 @@ async fn update_frame_time(mut frame_time: ResMut<FrameTime>) {
     frame_time.frame_count += 1;
 }
 
-async fn log_frame_time(frame_time: Res<FrameTime>, mut logger: ResMut<FrameTimeLogger>) {
+async fn log_frame_time(
+    frame_time: Res<FrameTime>,
+    app_timings: Option<Res<AppTimings>>,
+    mut logger: ResMut<FrameTimeLogger>,
+) {
     let now = std::time::Instant::now();
     if now.duration_since(logger.last_log) >= logger.log_interval {
         log::info!(
@@ -66,6 +72,17 @@ async fn log_frame_time(frame_time: Res<FrameTime>, mut logger: ResMut<FrameTime
             frame_time.frame_time * 1000.0,
             frame_time.fps
         );
+        if let Some(app_timings) = app_timings {
+            log::info!("  Main: {:.4}ms", app_timings.main.as_secs_f32() * 1000.0);
+            for timing in app_timings.sub_apps.iter() {
+                log::info!(
+                    "  {}: extract {:.4}ms, update {:.4}ms",
+                    timing.name,
+                    timing.extract.as_secs_f32() * 1000.0,
+                    timing.update.as_secs_f32() * 1000.0
+                );
+            }
+        }
         logger.last_log = now;
     }
 }
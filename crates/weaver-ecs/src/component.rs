@@ -235,6 +235,45 @@ impl ComponentMap {
     }
 }
 
+/// A resource that was removed from a [`ComponentMap`] without knowing its concrete type.
+pub struct ErasedResource {
+    type_id: TypeId,
+    value: LoanStorage<BoxedComponent>,
+    ticks: LoanStorage<ComponentTicks>,
+}
+
+impl ErasedResource {
+    pub fn id(&self) -> TypeId {
+        self.type_id
+    }
+}
+
+impl ComponentMap {
+    pub fn remove_erased(&mut self, type_id: TypeId) -> Option<ErasedResource> {
+        let value = self.map.remove(&type_id)?;
+        let ticks = self
+            .ticks
+            .remove(&type_id)
+            .unwrap_or_else(|| LoanStorage::new(ComponentTicks::new(Tick::default())));
+        Some(ErasedResource {
+            type_id,
+            value,
+            ticks,
+        })
+    }
+
+    /// Inserts a previously removed resource, replacing any resource of the same type.
+    pub fn insert_erased(&mut self, resource: ErasedResource) {
+        let ErasedResource {
+            type_id,
+            value,
+            ticks,
+        } = resource;
+        self.map.insert(type_id, value);
+        self.ticks.insert(type_id, ticks);
+    }
+}
+
 pub struct Res<T: Component> {
     loan: Loan<BoxedComponent>,
     last_run: Tick,
@@ -106,6 +106,15 @@ impl<T> LoanStorage<T> {
         }
     }
 
+    /// The value, unless it's vacant or mutably loaned out.
+    pub fn peek(&self) -> Option<&T> {
+        match self {
+            Self::Owned(value) => Some(value),
+            Self::Loan(value) => Some(value),
+            _ => None,
+        }
+    }
+
     pub fn as_owned_mut(&mut self) -> Option<&mut T> {
         match self {
             Self::Owned(value) => Some(value),
@@ -620,6 +620,9 @@ impl IntoSystemConfig<()> for SystemConfig {
     }
 }
 
+/// Identifies a system within the [`SystemGraph`] of its stage.
+pub type SystemIndex = NodeIndex;
+
 #[derive(Default)]
 pub struct SystemGraph {
     systems: StableDiGraph<SharedLock<Box<dyn System<Input = (), Output = ()>>>, ()>,
@@ -680,6 +683,31 @@ impl SystemGraph {
         self.index_cache.contains_key(&TypeId::of::<S>())
     }
 
+    /// Returns the indices of every system in the graph.
+    pub fn system_indices(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.systems.node_indices()
+    }
+
+    /// Removes a system and all of its ordering constraints from the graph.
+    pub fn remove_system(&mut self, index: NodeIndex) -> bool {
+        if self.systems.remove_node(index).is_none() {
+            return false;
+        }
+        self.index_cache.retain(|_, node| *node != index);
+        true
+    }
+
+    /// Initializes only the given systems, leaving the state of all other systems untouched.
+    pub fn initialize_systems(&mut self, world: &mut World, indices: &[NodeIndex]) {
+        self.resolve_dependencies().unwrap();
+
+        for &index in indices {
+            if let Some(system) = self.systems.node_weight(index) {
+                system.write().initialize(world);
+            }
+        }
+    }
+
     /// Sorts the graph based on system dependencies, returning a list of layers where each layer contains systems that can be run in parallel.
     /// This will respect existing system dependencies, and will not add any new ones.
     fn get_batches(&self) -> Vec<Vec<NodeIndex>> {
@@ -1,3 +1,4 @@
+use petgraph::graph::NodeIndex;
 use weaver_util::prelude::*;
 
 use crate::{
@@ -126,6 +127,38 @@ impl Systems {
         self.get_stage(stage).has_system(system)
     }
 
+    /// Returns every system in every stage, identified by its stage and index within the stage.
+    pub fn system_indices(&self) -> Vec<(InternedSystemStage, NodeIndex)> {
+        self.systems
+            .iter()
+            .flat_map(|(stage, graph)| graph.system_indices().map(|index| (*stage, index)))
+            .collect()
+    }
+
+    pub fn remove_system(&mut self, stage: impl SystemStage, index: NodeIndex) -> bool {
+        self.systems
+            .get_mut(&stage.intern())
+            .is_some_and(|graph| graph.remove_system(index))
+    }
+
+    /// Initializes only the given systems, e.g. systems added after the world has started running.
+    pub fn initialize_systems(
+        &mut self,
+        world: &mut World,
+        systems: &[(InternedSystemStage, NodeIndex)],
+    ) {
+        for (stage, graph) in self.systems.iter_mut() {
+            let indices = systems
+                .iter()
+                .filter(|(s, _)| s == stage)
+                .map(|(_, index)| *index)
+                .collect::<Vec<_>>();
+            if !indices.is_empty() {
+                graph.initialize_systems(world, &indices);
+            }
+        }
+    }
+
     pub fn initialize_stage(&mut self, world: &mut World, stage: impl SystemStage) {
         self.get_stage_mut(stage).initialize(world);
     }
@@ -1,19 +1,23 @@
 use std::{
+    any::TypeId,
     ops::{Deref, DerefMut},
     sync::atomic::AtomicU64,
 };
 
 use weaver_util::{prelude::*, span};
 
+use petgraph::graph::NodeIndex;
+
 use crate::{
     change_detection::Tick,
-    component::Component,
+    component::{Component, ErasedResource},
     prelude::{
         Bundle, Command, Commands, ComponentMap, Entities, IntoSystem, Res, ResMut, System,
         SystemAccess, SystemStage, Systems,
     },
     query::{Query, Queryable},
     system::{IntoSystemConfig, SystemParam},
+    system_schedule::InternedSystemStage,
 };
 
 use super::{entity::Entity, storage::Components};
@@ -171,6 +175,19 @@ impl World {
         self.resources.write().remove_component::<T>().unwrap()
     }
 
+    /// Returns the type ids of every resource in the world.
+    pub fn resource_ids(&self) -> Vec<TypeId> {
+        self.resources.read().keys().copied().collect()
+    }
+
+    pub fn remove_resource_by_id(&self, type_id: TypeId) -> Option<ErasedResource> {
+        self.resources.write().remove_erased(type_id)
+    }
+
+    pub fn insert_erased_resource(&self, resource: ErasedResource) {
+        self.resources.write().insert_erased(resource);
+    }
+
     pub fn has_system_stage(&self, stage: impl SystemStage) -> bool {
         self.systems.has_stage(stage)
     }
@@ -244,6 +261,22 @@ impl World {
         self.systems = systems;
     }
 
+    /// Returns every system in the world, identified by its stage and index within the stage.
+    pub fn system_indices(&self) -> Vec<(InternedSystemStage, NodeIndex)> {
+        self.systems.system_indices()
+    }
+
+    pub fn remove_system(&mut self, stage: impl SystemStage, index: NodeIndex) -> bool {
+        self.systems.remove_system(stage, index)
+    }
+
+    /// Initializes only the given systems, leaving the state of all other systems untouched.
+    pub fn initialize_systems_by_index(&mut self, systems: &[(InternedSystemStage, NodeIndex)]) {
+        let mut world_systems = std::mem::take(&mut self.systems);
+        world_systems.initialize_systems(self, systems);
+        self.systems = world_systems;
+    }
+
     pub fn initialize_system_stage(&mut self, stage: impl SystemStage) {
         let mut systems = std::mem::take(&mut self.systems);
         systems.initialize_stage(self, stage);
@@ -1,7 +1,10 @@
 use egui::{Context, FullOutput};
 use egui_wgpu::{Renderer, ScreenDescriptor};
 use egui_winit::{State, winit};
-use weaver_app::{App, AppStage, plugin::Plugin};
+use weaver_app::{
+    App, AppStage,
+    plugin::{Plugin, PluginId},
+};
 use weaver_ecs::{
     SystemStage,
     component::{Res, ResMut},
@@ -10,8 +13,8 @@ use weaver_ecs::{
 };
 use weaver_event::{EventRx, prelude::StreamExt};
 use weaver_renderer::{
-    CurrentFrame, MainWorld, RenderApp, RenderStage, WgpuDevice, WgpuQueue, prelude::wgpu,
-    texture::texture_format,
+    CurrentFrame, MainWorld, RenderApp, RenderStage, RendererPlugin, WgpuDevice, WgpuQueue,
+    prelude::wgpu, texture::texture_format,
 };
 use weaver_util::prelude::*;
 use weaver_winit::{Window, WinitEvent};
@@ -178,11 +181,15 @@ pub struct RenderUi;
 pub struct EguiPlugin;
 
 impl Plugin for EguiPlugin {
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RendererPlugin>()]
+    }
+
     fn build(&self, app: &mut App) -> Result<()> {
         app.add_system(begin_frame, AppStage::PreUpdate);
         app.add_system(end_frame, AppStage::PostUpdate);
         app.add_system(egui_events, AppStage::PostUpdate);
-        let render_app = app.get_sub_app_mut::<RenderApp>().unwrap();
+        let render_app = app.sub_app_mut::<RenderApp>()?;
         render_app
             .world_mut()
             .add_system(extract_egui_context, RenderStage::Extract);
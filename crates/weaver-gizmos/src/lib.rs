@@ -1,7 +1,16 @@
-use std::{path::Path, sync::Arc};
+use std::sync::Arc;
 
-use weaver_app::{App, AppStage, plugin::Plugin};
-use weaver_core::{color::Color, prelude::Mat4, transform::Transform};
+use weaver_app::{
+    App, AppStage,
+    plugin::{Plugin, PluginId},
+};
+use weaver_core::{
+    color::Color,
+    mesh::Vertex,
+    prelude::Mat4,
+    primitive::{CubePrimitive, Primitive},
+    transform::Transform,
+};
 use weaver_ecs::{
     component::Res,
     prelude::ResMut,
@@ -16,11 +25,10 @@ use weaver_renderer::{
     buffer::{GpuBuffer, GpuBufferVec},
     camera::{CameraBindGroup, ViewTarget},
     hdr::{HdrRenderTarget, render_hdr},
-    mesh::primitive::{CubePrimitive, Primitive},
     pipeline::{RenderPipeline, RenderPipelineLayout},
     prelude::*,
     resources::ActiveCommandEncoder,
-    shader::Shader,
+    shader::{Shader, ShaderDir},
     texture::{GpuTexture, texture_format},
 };
 use weaver_util::prelude::*;
@@ -51,7 +59,7 @@ impl ConstructFromWorld for SolidCubeGizmo {
 
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("CubeVertexBuffer"),
-            contents: bytemuck::cast_slice(&mesh.vertices),
+            contents: &mesh.vertex_buffer_data(),
             usage: wgpu::BufferUsages::VERTEX,
         });
 
@@ -84,7 +92,7 @@ impl ConstructFromWorld for WireCubeGizmo {
 
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("WireCubeVertexBuffer"),
-            contents: bytemuck::cast_slice(&mesh.vertices),
+            contents: &mesh.vertex_buffer_data(),
             usage: wgpu::BufferUsages::VERTEX,
         });
 
@@ -207,6 +215,8 @@ pub struct GizmoRenderable {
     color_buffer: FxHashMap<GizmoKey, GpuBufferVec<Color>>,
     bind_group: FxHashMap<GizmoKey, Arc<wgpu::BindGroup>>,
     pipelines: FxHashMap<GizmoKey, RenderPipeline>,
+    /// The [shader revision](ShaderDir::revision) that the pipelines were created from.
+    pipeline_revision: u64,
     gizmo_depth_texture: Option<GpuTexture>,
 }
 
@@ -308,8 +318,13 @@ impl GizmoRenderable {
     pub fn get_or_create_pipeline(
         &mut self,
         device: &wgpu::Device,
+        shader_dir: &ShaderDir,
         key: GizmoKey,
     ) -> RenderPipeline {
+        if self.pipeline_revision != shader_dir.revision() {
+            self.pipelines.clear();
+            self.pipeline_revision = shader_dir.revision();
+        }
         if let Some(pipeline) = self.pipelines.get(&key) {
             return pipeline.clone();
         }
@@ -317,7 +332,7 @@ impl GizmoRenderable {
         let layout = Self::create_render_pipeline_layout(device);
 
         const VERTEX_BUFFER_LAYOUT: wgpu::VertexBufferLayout = wgpu::VertexBufferLayout {
-            array_stride: 4 * (3 + 3 + 3 + 2) as u64,
+            array_stride: std::mem::size_of::<Vertex>() as u64,
             step_mode: wgpu::VertexStepMode::Vertex,
             attributes: &[
                 wgpu::VertexAttribute {
@@ -343,8 +358,7 @@ impl GizmoRenderable {
             ],
         };
 
-        let shader =
-            Shader::new(Path::new("assets/shaders/gizmos.wgsl")).create_shader_module(device);
+        let shader = Shader::new(shader_dir, "gizmos.wgsl").create_shader_module(device);
 
         let primitive_topology = match key.mode {
             GizmoMode::Solid => wgpu::PrimitiveTopology::TriangleList,
@@ -400,6 +414,7 @@ pub async fn prepare_gizmos(
     gizmos: Res<Gizmos>,
     device: Res<WgpuDevice>,
     queue: Res<WgpuQueue>,
+    shader_dir: Res<ShaderDir>,
     hdr_target: Res<HdrRenderTarget>,
     mut gizmo_renderable: ResMut<GizmoRenderable>,
 ) {
@@ -438,7 +453,7 @@ pub async fn prepare_gizmos(
         color_buffer.enqueue_update(&device, &queue);
 
         gizmo_renderable.get_or_create_bind_group(&device, *key);
-        gizmo_renderable.get_or_create_pipeline(&device, *key);
+        gizmo_renderable.get_or_create_pipeline(&device, &shader_dir, *key);
     }
 }
 
@@ -559,15 +574,17 @@ pub async fn render_gizmos(
 pub struct GizmoPlugin;
 
 impl Plugin for GizmoPlugin {
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RendererPlugin>()]
+    }
+
     fn build(&self, app: &mut App) -> Result<()> {
         let gizmos = Gizmos::default();
         app.insert_resource(gizmos.clone());
         app.add_system(clear_gizmos, AppStage::PrepareFrame);
 
-        let render_app = app.get_sub_app_mut::<RenderApp>().unwrap();
-        render_app
-            .add_plugin(GizmoRenderAppPlugin { gizmos })
-            .unwrap();
+        let render_app = app.sub_app_mut::<RenderApp>()?;
+        render_app.add_plugin(GizmoRenderAppPlugin { gizmos })?;
 
         Ok(())
     }
@@ -1,21 +1,39 @@
 use std::path::PathBuf;
 
-use weaver_asset::{AssetCommands, prelude::*};
+use serde::{Deserialize, Serialize};
+use weaver_asset::{
+    AssetCommands,
+    meta::LoaderSettings,
+    prelude::*,
+    processor::{ArtifactReader, ArtifactWriter, AssetBytes, Process},
+};
 use weaver_core::{
-    mesh::{Mesh, Vertex, calculate_normals, calculate_tangents},
-    prelude::{Vec2, Vec3, Vec4},
-    texture::{Texture, TextureLoader},
+    color::Color,
+    mesh::{Mesh, MeshSettings, read_gltf_primitive, read_mesh, read_obj_mesh, write_mesh},
+    prelude::Vec2,
+    texture::{ColorSpace, Texture, TextureLoader, TextureSettings, read_texture, write_texture},
 };
 use weaver_ecs::prelude::Commands;
+use weaver_task::unblock;
 use weaver_util::prelude::*;
 
-use crate::prelude::{BLACK_TEXTURE, Material, WHITE_TEXTURE};
+use weaver_renderer::prelude::wgpu;
+
+use crate::prelude::{
+    AlphaMode, BLACK_TEXTURE, Material, MaterialSampling, TextureSampling, TextureTransform,
+    WHITE_TEXTURE,
+};
 
-#[derive(Asset)]
 pub struct LoadedModelWithMaterials {
     pub primitives: Vec<LoadedMaterialMeshPrimitive>,
 }
 
+impl Asset for LoadedModelWithMaterials {
+    fn visit_dependencies(&self, visit: &mut dyn FnMut(UntypedHandle)) {
+        self.primitives.visit_dependencies(visit);
+    }
+}
+
 impl IntoIterator for LoadedModelWithMaterials {
     type Item = (Material, Mesh);
     type IntoIter = std::iter::Map<
@@ -28,12 +46,18 @@ impl IntoIterator for LoadedModelWithMaterials {
     }
 }
 
-#[derive(Asset, Clone)]
+#[derive(Clone)]
 pub struct LoadedMaterialMeshPrimitive {
     pub material: Material,
     pub mesh: Mesh,
 }
 
+impl Asset for LoadedMaterialMeshPrimitive {
+    fn visit_dependencies(&self, visit: &mut dyn FnMut(UntypedHandle)) {
+        self.material.visit_dependencies(visit);
+    }
+}
+
 impl LoadedMaterialMeshPrimitive {
     pub fn into_parts(self) -> (Material, Mesh) {
         (self.material, self.mesh)
@@ -43,23 +67,44 @@ impl LoadedMaterialMeshPrimitive {
 #[derive(Default)]
 pub struct ObjMaterialModelLoader;
 
+/// Import settings of models with materials, read from the `[model]` section of their meta file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelSettings {
+    pub mesh: MeshSettings,
+    /// Settings of the textures embedded in the model, and of the normal, metallic-roughness and AO textures of OBJ
+    /// materials. Other textures in separate files have their own meta files.
+    pub textures: TextureSettings,
+}
+
+impl LoaderSettings for ModelSettings {
+    const NAME: &'static str = "model";
+}
+
 impl LoadFrom<PathAndFilesystem> for ObjMaterialModelLoader {
     type Asset = LoadedModelWithMaterials;
+    type Settings = ModelSettings;
 
     async fn load(
         &self,
         source: PathAndFilesystem,
+        settings: &ModelSettings,
         commands: &Commands,
     ) -> Result<LoadedModelWithMaterials> {
-        load_obj_material_mesh(&source, commands).await
+        let mut model = load_obj_material_mesh(&source, &settings.textures, commands).await?;
+        for primitive in model.primitives.iter_mut() {
+            settings.mesh.apply(&mut primitive.mesh);
+        }
+        Ok(model)
     }
 }
 
 pub async fn load_obj_material_mesh(
     source: &PathAndFilesystem,
+    texture_settings: &TextureSettings,
     commands: &Commands,
 ) -> Result<LoadedModelWithMaterials> {
-    let bytes = source.read()?;
+    let bytes = source.read_async().await?;
     let (models, materials) = tobj::load_obj_buf(
         &mut std::io::Cursor::new(bytes),
         &tobj::LoadOptions {
@@ -81,49 +126,19 @@ pub async fn load_obj_material_mesh(
     )?;
     let materials = materials?;
 
+    let linear_settings = TextureSettings {
+        color_space: Some(ColorSpace::Linear),
+        ..texture_settings.clone()
+    };
+    let normal_map_settings = TextureSettings {
+        normal_map: true,
+        ..texture_settings.clone()
+    };
+
     let mut primitives = Vec::with_capacity(models.len());
 
     for model in &models {
-        let mesh = &model.mesh;
-
-        let mut vertices = Vec::with_capacity(mesh.positions.len() / 3);
-        let mut indices = Vec::with_capacity(mesh.indices.len());
-        let has_normals = !mesh.normals.is_empty();
-
-        for i in 0..mesh.positions.len() / 3 {
-            let position = [
-                mesh.positions[i * 3],
-                mesh.positions[i * 3 + 1],
-                mesh.positions[i * 3 + 2],
-            ];
-            let normal = if has_normals {
-                [
-                    mesh.normals[i * 3],
-                    mesh.normals[i * 3 + 1],
-                    mesh.normals[i * 3 + 2],
-                ]
-            } else {
-                [0.0, 0.0, 0.0]
-            };
-            let uv = [mesh.texcoords[i * 2], 1.0 - mesh.texcoords[i * 2 + 1]];
-
-            vertices.push(Vertex {
-                position: Vec3::from(position),
-                normal: Vec3::from(normal).normalize(),
-                tex_coords: Vec2::from(uv),
-                tangent: Vec3::ZERO,
-            });
-        }
-
-        for index in &mesh.indices {
-            indices.push(*index);
-        }
-
-        if !has_normals {
-            calculate_normals(&mut vertices, &indices);
-        }
-
-        calculate_tangents(&mut vertices, &indices);
+        let primitive_mesh = read_obj_mesh(&model.mesh)?;
 
         let material = materials.get(model.mesh.material_id.unwrap_or(0));
 
@@ -131,10 +146,7 @@ pub async fn load_obj_material_mesh(
             Some(material) => {
                 let diffuse = material.diffuse.unwrap_or([1.0, 1.0, 1.0]);
                 let diffuse_texture = match &material.diffuse_texture {
-                    Some(texture) => commands.lazy_load_asset::<TextureLoader<PathBuf>, _>((
-                        texture.into(),
-                        source.fs.clone(),
-                    )),
+                    Some(texture) => load_texture(source, texture, commands),
                     None => {
                         #[cfg(debug_assertions)]
                         log::warn!("Material does not have a diffuse texture");
@@ -142,10 +154,13 @@ pub async fn load_obj_material_mesh(
                     }
                 };
                 let normal_texture = match &material.normal_texture {
-                    Some(texture) => commands.lazy_load_asset::<TextureLoader<PathBuf>, _>((
-                        texture.into(),
-                        source.fs.clone(),
-                    )),
+                    Some(texture) => load_data_texture(
+                        source,
+                        texture,
+                        &normal_map_settings,
+                        BLACK_TEXTURE,
+                        commands,
+                    ),
                     None => {
                         #[cfg(debug_assertions)]
                         log::warn!("Material does not have a normal texture");
@@ -154,10 +169,13 @@ pub async fn load_obj_material_mesh(
                 };
                 let ao = material.ambient.unwrap_or([1.0, 1.0, 1.0]);
                 let ao_texture = match &material.ambient_texture {
-                    Some(texture) => commands.lazy_load_asset::<TextureLoader<PathBuf>, _>((
-                        texture.into(),
-                        source.fs.clone(),
-                    )),
+                    Some(texture) => load_data_texture(
+                        source,
+                        texture,
+                        &linear_settings,
+                        WHITE_TEXTURE,
+                        commands,
+                    ),
                     None => {
                         #[cfg(debug_assertions)]
                         log::warn!("Material does not have an AO texture");
@@ -167,10 +185,13 @@ pub async fn load_obj_material_mesh(
 
                 let metallic = material.shininess.unwrap_or(0.0);
                 let metallic_roughness_texture = match &material.shininess_texture {
-                    Some(texture) => commands.lazy_load_asset::<TextureLoader<PathBuf>, _>((
-                        texture.into(),
-                        source.fs.clone(),
-                    )),
+                    Some(texture) => load_data_texture(
+                        source,
+                        texture,
+                        &linear_settings,
+                        BLACK_TEXTURE,
+                        commands,
+                    ),
                     None => {
                         #[cfg(debug_assertions)]
                         log::warn!("Material does not have a metallic roughness texture");
@@ -187,12 +208,18 @@ pub async fn load_obj_material_mesh(
                     metallic_roughness_texture,
                     ao: ao.into_iter().sum::<f32>() / 3.0,
                     ao_texture,
+                    emissive: Color::BLACK,
+                    emissive_texture: WHITE_TEXTURE,
+                    alpha_mode: AlphaMode::default(),
+                    alpha_cutoff: 0.5,
+                    double_sided: false,
                     texture_scale: 1.0,
+                    sampling: MaterialSampling::default(),
                 };
 
                 primitives.push(LoadedMaterialMeshPrimitive {
                     material,
-                    mesh: Mesh::new(vertices, indices),
+                    mesh: primitive_mesh,
                 });
             }
             None => {
@@ -207,12 +234,18 @@ pub async fn load_obj_material_mesh(
                     metallic_roughness_texture: BLACK_TEXTURE,
                     ao: 1.0,
                     ao_texture: WHITE_TEXTURE,
+                    emissive: Color::BLACK,
+                    emissive_texture: WHITE_TEXTURE,
+                    alpha_mode: AlphaMode::default(),
+                    alpha_cutoff: 0.5,
+                    double_sided: false,
                     texture_scale: 1.0,
+                    sampling: MaterialSampling::default(),
                 };
 
                 primitives.push(LoadedMaterialMeshPrimitive {
                     material,
-                    mesh: Mesh::new(vertices, indices),
+                    mesh: primitive_mesh,
                 });
             }
         }
@@ -221,14 +254,84 @@ pub async fn load_obj_material_mesh(
     Ok(LoadedModelWithMaterials { primitives })
 }
 
+/// Loads a texture referenced by a model through the asset server, so that textures shared between models are only loaded once.
+fn load_texture(source: &PathAndFilesystem, texture: &str, commands: &Commands) -> Handle<Texture> {
+    let texture = PathAndFilesystem::new(texture, source.fs.clone());
+    match commands.load_asset_from(texture.clone()) {
+        Ok(handle) => handle,
+        Err(e) => {
+            log::warn!("Failed to load texture through the asset server: {}", e);
+            commands.lazy_load_asset::<TextureLoader<PathBuf>, _>(texture)
+        }
+    }
+}
+
+/// Decodes a texture that holds data rather than colors, such as normals or occlusion, with the settings of its
+/// material slot. The asset server would load the file with the settings of its own meta file, which treat images as
+/// sRGB colors unless told otherwise, so the texture is added as an asset of the model instead.
+fn load_data_texture(
+    source: &PathAndFilesystem,
+    texture: &str,
+    settings: &TextureSettings,
+    default: Handle<Texture>,
+    commands: &Commands,
+) -> Handle<Texture> {
+    let decoded = source.fs.read_sub_path(texture).and_then(|bytes| {
+        let mut decoded = Texture::decode(&bytes, settings)?;
+        settings.apply(&mut decoded)?;
+        Ok(decoded)
+    });
+    match decoded {
+        Ok(decoded) => commands.lazy_load_asset_direct(decoded),
+        Err(e) => {
+            log::warn!("Failed to load texture {:?}: {}", texture, e);
+            default
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct GltfMaterialModelLoader;
 
+impl LoadFrom<PathAndFilesystem> for GltfMaterialModelLoader {
+    type Asset = LoadedModelWithMaterials;
+    type Settings = ModelSettings;
+
+    async fn load(
+        &self,
+        source: PathAndFilesystem,
+        settings: &ModelSettings,
+        commands: &Commands,
+    ) -> Result<LoadedModelWithMaterials> {
+        let primitives = match source
+            .read_processed_async::<GltfMaterialModelProcessor>()
+            .await?
+        {
+            AssetBytes::Processed(bytes) => read_processed_gltf_primitives(&bytes)?,
+            AssetBytes::Source(bytes) => {
+                let mut primitives = read_gltf_primitives(&bytes)?;
+                apply_model_settings(&mut primitives, settings)?;
+                primitives
+            }
+        };
+        Ok(into_loaded_model(primitives, commands))
+    }
+}
+
 impl LoadFrom<PathBuf> for GltfMaterialModelLoader {
     type Asset = LoadedModelWithMaterials;
+    type Settings = ModelSettings;
 
-    async fn load(&self, source: PathBuf, commands: &Commands) -> Result<LoadedModelWithMaterials> {
-        load_gltf_material_mesh(&source, commands)
+    async fn load(
+        &self,
+        source: PathBuf,
+        settings: &ModelSettings,
+        commands: &Commands,
+    ) -> Result<LoadedModelWithMaterials> {
+        let bytes = unblock(move || std::fs::read(source)).await?;
+        let mut primitives = read_gltf_primitives(&bytes)?;
+        apply_model_settings(&mut primitives, settings)?;
+        Ok(into_loaded_model(primitives, commands))
     }
 }
 
@@ -237,169 +340,609 @@ pub fn load_gltf_material_mesh(
     commands: &Commands,
 ) -> Result<LoadedModelWithMaterials> {
     let bytes = std::fs::read(source)?;
+    load_gltf_material_mesh_from_bytes(&bytes, commands)
+}
+
+pub fn load_gltf_material_mesh_from_bytes(
+    bytes: &[u8],
+    commands: &Commands,
+) -> Result<LoadedModelWithMaterials> {
+    Ok(into_loaded_model(read_gltf_primitives(bytes)?, commands))
+}
+
+fn apply_model_settings(
+    primitives: &mut [(GltfMaterial, Mesh)],
+    settings: &ModelSettings,
+) -> Result<()> {
+    for (material, mesh) in primitives.iter_mut() {
+        settings.mesh.apply(mesh);
+        material.apply_texture_settings(&settings.textures)?;
+    }
+    Ok(())
+}
+
+fn into_loaded_model(
+    primitives: Vec<(GltfMaterial, Mesh)>,
+    commands: &Commands,
+) -> LoadedModelWithMaterials {
+    let primitives = primitives
+        .into_iter()
+        .map(|(material, mesh)| LoadedMaterialMeshPrimitive {
+            material: material.into_material(commands),
+            mesh,
+        })
+        .collect();
+    LoadedModelWithMaterials { primitives }
+}
+
+/// A glTF material with its textures decoded, before they're added as assets.
+pub(crate) struct GltfMaterial {
+    diffuse: [f32; 4],
+    metallic: f32,
+    roughness: f32,
+    ao: f32,
+    emissive: [f32; 3],
+    alpha_mode: AlphaMode,
+    alpha_cutoff: f32,
+    double_sided: bool,
+    sampling: MaterialSampling,
+    diffuse_texture: Option<Texture>,
+    normal_texture: Option<Texture>,
+    metallic_roughness_texture: Option<Texture>,
+    ao_texture: Option<Texture>,
+    emissive_texture: Option<Texture>,
+}
+
+impl GltfMaterial {
+    fn textures(&self) -> [Option<&Texture>; 5] {
+        [
+            self.diffuse_texture.as_ref(),
+            self.normal_texture.as_ref(),
+            self.metallic_roughness_texture.as_ref(),
+            self.ao_texture.as_ref(),
+            self.emissive_texture.as_ref(),
+        ]
+    }
+
+    /// Applies import settings to each texture, treating the normal texture as a normal map.
+    pub(crate) fn apply_texture_settings(&mut self, settings: &TextureSettings) -> Result<()> {
+        let normal_map_settings = TextureSettings {
+            normal_map: true,
+            ..settings.clone()
+        };
+        for (texture, settings) in [
+            (&mut self.diffuse_texture, settings),
+            (&mut self.normal_texture, &normal_map_settings),
+            (&mut self.metallic_roughness_texture, settings),
+            (&mut self.ao_texture, settings),
+            (&mut self.emissive_texture, settings),
+        ] {
+            if let Some(texture) = texture {
+                settings.apply(texture)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the textures as assets. Missing textures are replaced by ones that leave the material's factors as they are.
+    pub(crate) fn into_material(self, commands: &Commands) -> Material {
+        let load = |texture: Option<Texture>, default: Handle<Texture>| {
+            texture.map_or(default, |texture| commands.lazy_load_asset_direct(texture))
+        };
+        Material {
+            diffuse: self.diffuse.into(),
+            diffuse_texture: load(self.diffuse_texture, WHITE_TEXTURE),
+            // the shader uses the vertex normals if the normal texture is black
+            normal_texture: load(self.normal_texture, BLACK_TEXTURE),
+            metallic: self.metallic,
+            roughness: self.roughness,
+            metallic_roughness_texture: load(self.metallic_roughness_texture, WHITE_TEXTURE),
+            ao: self.ao,
+            ao_texture: load(self.ao_texture, WHITE_TEXTURE),
+            emissive: Color::from_rgb(self.emissive),
+            emissive_texture: load(self.emissive_texture, WHITE_TEXTURE),
+            alpha_mode: self.alpha_mode,
+            alpha_cutoff: self.alpha_cutoff,
+            double_sided: self.double_sided,
+            texture_scale: 1.0,
+            sampling: self.sampling,
+        }
+    }
+}
+
+fn read_gltf_primitives(bytes: &[u8]) -> Result<Vec<(GltfMaterial, Mesh)>> {
     let (document, buffers, images) = gltf::import_slice(bytes)?;
 
     let mut primitives = Vec::new();
 
     for mesh in document.meshes() {
         for primitive in mesh.primitives() {
-            let material = load_material(primitive.material(), &images, commands)?;
-
-            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
-
-            let mut positions = reader.read_positions().ok_or_else(|| {
-                anyhow!("mesh primitive does not have positions: {:?}", primitive)
-            })?;
-
-            let mut normals = reader
-                .read_normals()
-                .ok_or_else(|| anyhow!("mesh primitive does not have normals: {:?}", primitive))?;
-
-            let mut tex_coords = reader
-                .read_tex_coords(0)
-                .ok_or_else(|| anyhow!("mesh primitive does not have tex coords: {:?}", primitive))?
-                .into_f32();
-
-            let mut tangents = reader
-                .read_tangents()
-                .ok_or_else(|| anyhow!("mesh primitive does not have tangents: {:?}", primitive))?;
-
-            let indices_iter = reader
-                .read_indices()
-                .ok_or_else(|| anyhow!("mesh primitive does not have indices: {:?}", primitive))?
-                .into_u32();
-
-            let vertices_iter = positions
-                .by_ref()
-                .zip(normals.by_ref())
-                .zip(tex_coords.by_ref())
-                .zip(tangents.by_ref())
-                .map(|(((position, normal), tex_coord), tangent)| Vertex {
-                    position: Vec3::from(position),
-                    normal: Vec3::from(normal),
-                    tex_coords: Vec2::from(tex_coord),
-                    tangent: Vec4::from(tangent).truncate(),
-                });
-
-            let vertices = vertices_iter.collect();
-            let indices = indices_iter.collect();
-
-            primitives.push(LoadedMaterialMeshPrimitive {
-                material,
-                mesh: Mesh::new(vertices, indices),
-            });
+            let material = read_material(primitive.material(), &images)?;
+            primitives.push((material, read_gltf_primitive(&primitive, &buffers)?));
         }
     }
 
-    Ok(LoadedModelWithMaterials { primitives })
+    Ok(primitives)
 }
 
-fn load_material(
+pub(crate) fn read_material(
     material: gltf::Material<'_>,
     images: &[gltf::image::Data],
-    commands: &Commands,
-) -> Result<Material> {
-    let metallic = material.pbr_metallic_roughness().metallic_factor();
-    let roughness = material.pbr_metallic_roughness().roughness_factor();
-    let ao = material
-        .occlusion_texture()
-        .map_or(1.0, |info| info.strength());
-    let diffuse = material.pbr_metallic_roughness().base_color_factor();
-    let diffuse_texture = material
-        .pbr_metallic_roughness()
+) -> Result<GltfMaterial> {
+    let pbr = material.pbr_metallic_roughness();
+    let mut sampling = MaterialSampling::default();
+
+    let diffuse_texture = pbr
         .base_color_texture()
-        .map(|info| images[info.texture().index()].clone());
+        .map(|info| {
+            sampling.diffuse = read_sampling(&info.texture(), read_transform(&info));
+            read_image(&info.texture(), images)
+        })
+        .transpose()?;
+    let metallic_roughness_texture = pbr
+        .metallic_roughness_texture()
+        .map(|info| {
+            sampling.metallic_roughness = read_sampling(&info.texture(), read_transform(&info));
+            read_linear_image(&info.texture(), images)
+        })
+        .transpose()?;
+    let emissive_texture = material
+        .emissive_texture()
+        .map(|info| {
+            sampling.emissive = read_sampling(&info.texture(), read_transform(&info));
+            read_image(&info.texture(), images)
+        })
+        .transpose()?;
+    // the gltf crate only exposes the transforms of normal and occlusion textures as raw extension values
     let normal_texture = material
         .normal_texture()
-        .map(|info| images[info.texture().index()].clone());
-    let metallic_roughness_texture = material
-        .pbr_metallic_roughness()
-        .metallic_roughness_texture()
-        .map(|info| images[info.texture().index()].clone());
+        .map(|info| {
+            let transform = read_extension_transform(info.extension_value(TEXTURE_TRANSFORM));
+            sampling.normal = read_sampling(&info.texture(), transform);
+            read_linear_image(&info.texture(), images)
+        })
+        .transpose()?;
     let ao_texture = material
         .occlusion_texture()
-        .map(|info| images[info.texture().index()].clone());
-
-    let diffuse_texture =
-        diffuse_texture.ok_or_else(|| anyhow!("Material must have a diffuse texture"))?;
-    let normal_texture =
-        normal_texture.ok_or_else(|| anyhow!("Material must have a normal texture"))?;
-    let metallic_roughness_texture = metallic_roughness_texture
-        .ok_or_else(|| anyhow!("Material must have a metallic roughness texture"))?;
-    let ao_texture = ao_texture.ok_or_else(|| anyhow!("Material must have an AO texture"))?;
-
-    let diffuse_texture = match diffuse_texture.format {
-        gltf::image::Format::R8G8B8 => Texture::from_rgb8(
-            &diffuse_texture.pixels,
-            diffuse_texture.width,
-            diffuse_texture.height,
-        ),
-        gltf::image::Format::R8G8B8A8 => Texture::from_rgba8(
-            &diffuse_texture.pixels,
-            diffuse_texture.width,
-            diffuse_texture.height,
-        ),
-        format => bail!(
-            "Diffuse texture must be in RGB8 or RGBA8 format (got {:?})",
-            format
-        ),
+        .map(|info| {
+            let transform = read_extension_transform(info.extension_value(TEXTURE_TRANSFORM));
+            sampling.ao = read_sampling(&info.texture(), transform);
+            read_linear_image(&info.texture(), images)
+        })
+        .transpose()?;
+
+    let emissive_strength = material.emissive_strength().unwrap_or(1.0);
+    let alpha_mode = match material.alpha_mode() {
+        gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+        gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+        gltf::material::AlphaMode::Blend => AlphaMode::Blend,
     };
-    let normal_texture = match normal_texture.format {
-        gltf::image::Format::R8G8B8 => Texture::from_rgb8(
-            &normal_texture.pixels,
-            normal_texture.width,
-            normal_texture.height,
-        ),
-        gltf::image::Format::R8G8B8A8 => Texture::from_rgba8(
-            &normal_texture.pixels,
-            normal_texture.width,
-            normal_texture.height,
-        ),
-        format => bail!(
-            "Normal texture must be in RGB8 or RGBA8 format (got {:?})",
-            format
-        ),
+
+    Ok(GltfMaterial {
+        diffuse: pbr.base_color_factor(),
+        metallic: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+        ao: material
+            .occlusion_texture()
+            .map_or(1.0, |info| info.strength()),
+        emissive: material
+            .emissive_factor()
+            .map(|factor| factor * emissive_strength),
+        alpha_mode,
+        alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+        double_sided: material.double_sided(),
+        sampling,
+        diffuse_texture,
+        normal_texture,
+        metallic_roughness_texture,
+        ao_texture,
+        emissive_texture,
+    })
+}
+
+const TEXTURE_TRANSFORM: &str = "KHR_texture_transform";
+
+fn read_transform(info: &gltf::texture::Info<'_>) -> Option<TextureTransform> {
+    info.texture_transform().map(|transform| TextureTransform {
+        offset: Vec2::from(transform.offset()),
+        rotation: transform.rotation(),
+        scale: Vec2::from(transform.scale()),
+    })
+}
+
+fn read_extension_transform(value: Option<&gltf::json::Value>) -> Option<TextureTransform> {
+    let transform: gltf::json::extensions::texture::TextureTransform =
+        gltf::json::deserialize::from_value(value?.clone()).ok()?;
+    Some(TextureTransform {
+        offset: Vec2::from(transform.offset.0),
+        rotation: transform.rotation.0,
+        scale: Vec2::from(transform.scale.0),
+    })
+}
+
+fn read_sampling(
+    texture: &gltf::Texture<'_>,
+    transform: Option<TextureTransform>,
+) -> TextureSampling {
+    use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+    use wgpu::{AddressMode, FilterMode, MipmapFilterMode};
+
+    let sampler = texture.sampler();
+    let address_mode = |mode| match mode {
+        WrappingMode::ClampToEdge => AddressMode::ClampToEdge,
+        WrappingMode::MirroredRepeat => AddressMode::MirrorRepeat,
+        WrappingMode::Repeat => AddressMode::Repeat,
     };
-    let metallic_roughness_texture = match metallic_roughness_texture.format {
-        gltf::image::Format::R8G8B8 => Texture::from_rgb8(
-            &metallic_roughness_texture.pixels,
-            metallic_roughness_texture.width,
-            metallic_roughness_texture.height,
-        ),
-        gltf::image::Format::R8G8B8A8 => Texture::from_rgba8(
-            &metallic_roughness_texture.pixels,
-            metallic_roughness_texture.width,
-            metallic_roughness_texture.height,
-        ),
-        format => bail!(
-            "Metallic/Roughness texture must be in RGB8 or RGBA8 format (got {:?})",
-            format
-        ),
+    let mag_filter = match sampler.mag_filter() {
+        Some(MagFilter::Nearest) => FilterMode::Nearest,
+        Some(MagFilter::Linear) | None => FilterMode::Linear,
     };
-    let ao_texture = match ao_texture.format {
-        gltf::image::Format::R8G8B8 => {
-            Texture::from_rgb8(&ao_texture.pixels, ao_texture.width, ao_texture.height)
+    let (min_filter, mipmap_filter) = match sampler.min_filter() {
+        Some(MinFilter::Nearest | MinFilter::NearestMipmapNearest) => {
+            (FilterMode::Nearest, MipmapFilterMode::Nearest)
         }
-        gltf::image::Format::R8G8B8A8 => {
-            Texture::from_rgba8(&ao_texture.pixels, ao_texture.width, ao_texture.height)
+        Some(MinFilter::NearestMipmapLinear) => (FilterMode::Nearest, MipmapFilterMode::Linear),
+        Some(MinFilter::LinearMipmapNearest) => (FilterMode::Linear, MipmapFilterMode::Nearest),
+        Some(MinFilter::Linear | MinFilter::LinearMipmapLinear) | None => {
+            (FilterMode::Linear, MipmapFilterMode::Linear)
         }
-        format => bail!(
-            "Ambient Occlusion texture must be in RGB8 or RGBA8 format (got {:?})",
-            format
-        ),
     };
 
-    let material = Material {
-        diffuse: diffuse.into(),
-        diffuse_texture: commands.lazy_load_asset_direct(diffuse_texture),
-        normal_texture: commands.lazy_load_asset_direct(normal_texture),
-        metallic,
-        roughness,
-        metallic_roughness_texture: commands.lazy_load_asset_direct(metallic_roughness_texture),
-        ao,
-        ao_texture: commands.lazy_load_asset_direct(ao_texture),
-        texture_scale: 1.0,
+    TextureSampling {
+        address_mode_u: address_mode(sampler.wrap_s()),
+        address_mode_v: address_mode(sampler.wrap_t()),
+        mag_filter,
+        min_filter,
+        mipmap_filter,
+        transform: transform.unwrap_or_default(),
+    }
+}
+
+/// Reads the image of a glTF texture holding data rather than colors, like normals, roughness or occlusion.
+fn read_linear_image(texture: &gltf::Texture<'_>, images: &[gltf::image::Data]) -> Result<Texture> {
+    Ok(read_image(texture, images)?.with_color_space(ColorSpace::Linear))
+}
+
+/// Converts the image of a glTF texture to a texture. Grayscale images are spread over the color channels, 16-bit
+/// images keep their precision and float images are stored as 16-bit floats.
+fn read_image(texture: &gltf::Texture<'_>, images: &[gltf::image::Data]) -> Result<Texture> {
+    use gltf::image::Format;
+    use image::{DynamicImage, ImageBuffer};
+
+    let image = images
+        .get(texture.source().index())
+        .ok_or_else(|| anyhow!("Texture {} has no image", texture.index()))?;
+    let (width, height) = (image.width, image.height);
+    // the gltf crate stores 16-bit and float channels in native byte order
+    let pixels = image.pixels.clone();
+    let pixels16 = || {
+        image
+            .pixels
+            .chunks_exact(2)
+            .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]))
+            .collect::<Vec<_>>()
+    };
+    let pixels32 = || {
+        image
+            .pixels
+            .chunks_exact(4)
+            .map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect::<Vec<_>>()
+    };
+    let decoded = match image.format {
+        Format::R8 => ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8),
+        Format::R8G8 => ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageLumaA8),
+        Format::R8G8B8 => ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8),
+        Format::R8G8B8A8 => {
+            ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageRgba8)
+        }
+        Format::R16 => {
+            ImageBuffer::from_raw(width, height, pixels16()).map(DynamicImage::ImageLuma16)
+        }
+        Format::R16G16 => {
+            ImageBuffer::from_raw(width, height, pixels16()).map(DynamicImage::ImageLumaA16)
+        }
+        Format::R16G16B16 => {
+            ImageBuffer::from_raw(width, height, pixels16()).map(DynamicImage::ImageRgb16)
+        }
+        Format::R16G16B16A16 => {
+            ImageBuffer::from_raw(width, height, pixels16()).map(DynamicImage::ImageRgba16)
+        }
+        Format::R32G32B32FLOAT => {
+            ImageBuffer::from_raw(width, height, pixels32()).map(DynamicImage::ImageRgb32F)
+        }
+        Format::R32G32B32A32FLOAT => {
+            ImageBuffer::from_raw(width, height, pixels32()).map(DynamicImage::ImageRgba32F)
+        }
     };
+    let decoded = decoded.ok_or_else(|| {
+        anyhow!(
+            "Image of texture {} has {} bytes, too few for {width}x{height} {:?} texels",
+            texture.index(),
+            image.pixels.len(),
+            image.format
+        )
+    })?;
+
+    Ok(Texture::from_image(decoded))
+}
+
+const GLTF_MODEL_MAGIC: [u8; 4] = *b"WMDL";
+
+/// Decodes the meshes and material textures of glTF models, so that loading them doesn't parse glTF or decode images.
+#[derive(Default)]
+pub struct GltfMaterialModelProcessor;
 
-    Ok(material)
+impl Process for GltfMaterialModelProcessor {
+    const KIND: &'static str = "pbr-model";
+    const VERSION: u32 = 6;
+
+    type Settings = ModelSettings;
+
+    fn process(&self, source: &[u8], settings: &ModelSettings) -> Result<Vec<u8>> {
+        let mut primitives = read_gltf_primitives(source)?;
+        apply_model_settings(&mut primitives, settings)?;
+
+        let mut writer = ArtifactWriter::new(GLTF_MODEL_MAGIC);
+        writer.write_u32(primitives.len() as u32);
+        for (material, mesh) in primitives.iter() {
+            for value in material.diffuse {
+                writer.write_f32(value);
+            }
+            writer.write_f32(material.metallic);
+            writer.write_f32(material.roughness);
+            writer.write_f32(material.ao);
+            for value in material.emissive {
+                writer.write_f32(value);
+            }
+            write_setting(&mut writer, &ALPHA_MODES, &material.alpha_mode);
+            writer.write_f32(material.alpha_cutoff);
+            writer.write_u8(material.double_sided as u8);
+            for sampling in material.sampling.textures() {
+                write_sampling(&mut writer, sampling);
+            }
+            for texture in material.textures() {
+                writer.write_u8(texture.is_some() as u8);
+                if let Some(texture) = texture {
+                    write_texture(&mut writer, texture, &settings.textures)?;
+                }
+            }
+            write_mesh(&mut writer, mesh);
+        }
+        Ok(writer.finish())
+    }
+}
+
+fn read_processed_gltf_primitives(bytes: &[u8]) -> Result<Vec<(GltfMaterial, Mesh)>> {
+    let mut reader = ArtifactReader::new(bytes, GLTF_MODEL_MAGIC)?;
+    let count = reader.read_u32()?;
+    let mut primitives = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let mut diffuse = [0.0; 4];
+        for value in diffuse.iter_mut() {
+            *value = reader.read_f32()?;
+        }
+        let metallic = reader.read_f32()?;
+        let roughness = reader.read_f32()?;
+        let ao = reader.read_f32()?;
+        let mut emissive = [0.0; 3];
+        for value in emissive.iter_mut() {
+            *value = reader.read_f32()?;
+        }
+        let alpha_mode = read_setting(&mut reader, &ALPHA_MODES)?;
+        let alpha_cutoff = reader.read_f32()?;
+        let double_sided = reader.read_u8()? != 0;
+        let mut sampling = MaterialSampling::default();
+        for texture in sampling.textures_mut() {
+            *texture = read_sampling_artifact(&mut reader)?;
+        }
+        let mut read_optional_texture = || -> Result<Option<Texture>> {
+            match reader.read_u8()? {
+                0 => Ok(None),
+                _ => Ok(Some(read_texture(&mut reader)?)),
+            }
+        };
+        let material = GltfMaterial {
+            diffuse,
+            metallic,
+            roughness,
+            ao,
+            emissive,
+            alpha_mode,
+            alpha_cutoff,
+            double_sided,
+            sampling,
+            diffuse_texture: read_optional_texture()?,
+            normal_texture: read_optional_texture()?,
+            metallic_roughness_texture: read_optional_texture()?,
+            ao_texture: read_optional_texture()?,
+            emissive_texture: read_optional_texture()?,
+        };
+        primitives.push((material, read_mesh(&mut reader)?));
+    }
+    Ok(primitives)
+}
+
+// the settings are stored as their index in these tables
+const ALPHA_MODES: [AlphaMode; 3] = [AlphaMode::Opaque, AlphaMode::Mask, AlphaMode::Blend];
+const ADDRESS_MODES: [wgpu::AddressMode; 3] = [
+    wgpu::AddressMode::ClampToEdge,
+    wgpu::AddressMode::Repeat,
+    wgpu::AddressMode::MirrorRepeat,
+];
+const FILTER_MODES: [wgpu::FilterMode; 2] = [wgpu::FilterMode::Nearest, wgpu::FilterMode::Linear];
+const MIPMAP_FILTER_MODES: [wgpu::MipmapFilterMode; 2] = [
+    wgpu::MipmapFilterMode::Nearest,
+    wgpu::MipmapFilterMode::Linear,
+];
+
+fn write_setting<T: PartialEq>(writer: &mut ArtifactWriter, table: &[T], value: &T) {
+    let index = table.iter().position(|entry| entry == value).unwrap_or(0);
+    writer.write_u8(index as u8);
+}
+
+fn read_setting<T: Copy>(reader: &mut ArtifactReader, table: &[T]) -> Result<T> {
+    let index = reader.read_u8()?;
+    table
+        .get(index as usize)
+        .copied()
+        .ok_or_else(|| anyhow!("Invalid setting index {} in model artifact", index))
+}
+
+fn write_sampling(writer: &mut ArtifactWriter, sampling: &TextureSampling) {
+    write_setting(writer, &ADDRESS_MODES, &sampling.address_mode_u);
+    write_setting(writer, &ADDRESS_MODES, &sampling.address_mode_v);
+    write_setting(writer, &FILTER_MODES, &sampling.mag_filter);
+    write_setting(writer, &FILTER_MODES, &sampling.min_filter);
+    write_setting(writer, &MIPMAP_FILTER_MODES, &sampling.mipmap_filter);
+    let transform = &sampling.transform;
+    for value in [
+        transform.offset.x,
+        transform.offset.y,
+        transform.rotation,
+        transform.scale.x,
+        transform.scale.y,
+    ] {
+        writer.write_f32(value);
+    }
+}
+
+fn read_sampling_artifact(reader: &mut ArtifactReader) -> Result<TextureSampling> {
+    Ok(TextureSampling {
+        address_mode_u: read_setting(reader, &ADDRESS_MODES)?,
+        address_mode_v: read_setting(reader, &ADDRESS_MODES)?,
+        mag_filter: read_setting(reader, &FILTER_MODES)?,
+        min_filter: read_setting(reader, &FILTER_MODES)?,
+        mipmap_filter: read_setting(reader, &MIPMAP_FILTER_MODES)?,
+        transform: TextureTransform {
+            offset: Vec2::new(reader.read_f32()?, reader.read_f32()?),
+            rotation: reader.read_f32()?,
+            scale: Vec2::new(reader.read_f32()?, reader.read_f32()?),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use weaver_app::{App, settings::AppSettings};
+    use weaver_asset::{AssetApp, AssetPlugin, AssetSettings, server::AssetServer};
+    use weaver_task::{task_pool::TaskPool, usages::GlobalTaskPool};
+
+    use super::*;
+
+    #[test]
+    fn test_read_factor_only_material() {
+        let gltf = gltf::Gltf::from_slice(
+            br#"{
+                "asset": { "version": "2.0" },
+                "materials": [{
+                    "pbrMetallicRoughness": { "baseColorFactor": [1.0, 0.5, 0.25, 0.5], "metallicFactor": 0.0 },
+                    "emissiveFactor": [1.0, 1.0, 0.0],
+                    "alphaMode": "MASK",
+                    "alphaCutoff": 0.25,
+                    "doubleSided": true
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        let material = read_material(gltf.materials().next().unwrap(), &[]).unwrap();
+        assert_eq!(material.diffuse, [1.0, 0.5, 0.25, 0.5]);
+        assert_eq!(material.metallic, 0.0);
+        assert_eq!(material.roughness, 1.0);
+        assert_eq!(material.emissive, [1.0, 1.0, 0.0]);
+        assert_eq!(material.alpha_mode, AlphaMode::Mask);
+        assert_eq!(material.alpha_cutoff, 0.25);
+        assert!(material.double_sided);
+        assert!(material.textures().iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_obj_normal_map_mips_stay_unit_length() {
+        GlobalTaskPool::get_or_init(TaskPool::new);
+
+        // a normal map of alternating columns tilted along +X and facing +Z, whose averages are shorter than one
+        // unless they're renormalized
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let normal_map = image::RgbaImage::from_fn(4, 4, |x, _| {
+            if x % 2 == 0 {
+                image::Rgba([255, 128, 128, 255])
+            } else {
+                image::Rgba([128, 128, 255, 255])
+            }
+        });
+        normal_map.save(dir.join("normal.png")).unwrap();
+        std::fs::write(dir.join("quad.mtl"), "newmtl quad\nbump normal.png\n").unwrap();
+        std::fs::write(
+            dir.join("quad.obj"),
+            "mtllib quad.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl quad\nf 1 2 3\n",
+        )
+        .unwrap();
+
+        let mut settings = AppSettings::new();
+        settings
+            .set_section(&AssetSettings {
+                roots: vec![dir.to_path_buf()],
+                watch_for_changes: false,
+                write_meta_files: false,
+                ..Default::default()
+            })
+            .unwrap();
+        let mut app = App::new();
+        app.insert_resource(settings);
+        app.add_plugin(AssetPlugin).unwrap();
+        app.add_asset::<Texture>();
+        app.add_asset::<LoadedModelWithMaterials>();
+        app.add_file_asset_loader::<ObjMaterialModelLoader>(&["obj"]);
+        app.init();
+
+        let model = app
+            .main_app()
+            .world()
+            .get_resource::<AssetServer>()
+            .unwrap()
+            .load::<LoadedModelWithMaterials>("quad.obj")
+            .unwrap();
+
+        let start = Instant::now();
+        let normal_map = loop {
+            app.update();
+            let world = app.main_app().world();
+            let normal_texture = world
+                .get_resource_mut::<Assets<LoadedModelWithMaterials>>()
+                .unwrap()
+                .get(&model)
+                .map(|model| model.primitives[0].material.normal_texture.clone());
+            if let Some(normal_texture) = normal_texture
+                && let Some(texture) = world
+                    .get_resource_mut::<Assets<Texture>>()
+                    .unwrap()
+                    .get(&normal_texture)
+            {
+                break texture.clone();
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "normal map was never loaded"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        };
+
+        assert_eq!(normal_map.color_space(), ColorSpace::Linear);
+        assert_eq!(normal_map.mip_level_count(), 3);
+        for level in normal_map.levels() {
+            for texel in level.chunks_exact(4) {
+                let normal = texel[..3]
+                    .iter()
+                    .map(|&channel| channel as f32 / 255.0 * 2.0 - 1.0)
+                    .map(|channel| channel * channel)
+                    .sum::<f32>()
+                    .sqrt();
+                assert!((normal - 1.0).abs() < 0.02, "normal of length {normal}");
+            }
+        }
+    }
 }
@@ -1 +1,2 @@
 pub mod material_mesh;
+pub mod scene;
@@ -0,0 +1,349 @@
+use weaver_animation::clip::{AnimationChannel, AnimationClip, Interpolation, Keyframes};
+use weaver_asset::{AssetCommands, prelude::*};
+use weaver_core::{
+    color::Color,
+    mesh::{Mesh, read_gltf_primitive},
+    prelude::{Mat4, Quat, Vec3},
+    transform::Transform,
+};
+use weaver_ecs::prelude::Commands;
+use weaver_util::prelude::*;
+
+use super::material_mesh::{ModelSettings, read_material};
+use crate::prelude::Material;
+
+/// A glTF scene with its node hierarchy, spawned as entities by the [`SceneSpawner`](crate::scene::SceneSpawner).
+///
+/// Nodes that instance the same glTF mesh share its [`Mesh`] and [`Material`] handles. The channels of the
+/// [`AnimationClip`]s target nodes by their index in `nodes`.
+pub struct GltfScene {
+    pub nodes: Vec<GltfNode>,
+    /// The nodes at the top of the hierarchy, as indices into `nodes`.
+    pub roots: Vec<usize>,
+    /// The primitives of every glTF mesh.
+    pub meshes: Vec<Vec<GltfPrimitive>>,
+    pub materials: Vec<Handle<Material>>,
+    pub skins: Vec<GltfSkin>,
+    pub animations: Vec<Handle<AnimationClip>>,
+}
+
+impl Asset for GltfScene {
+    fn visit_dependencies(&self, visit: &mut dyn FnMut(UntypedHandle)) {
+        for primitive in self.meshes.iter().flatten() {
+            visit(primitive.mesh.untyped());
+        }
+        for material in self.materials.iter() {
+            visit(material.untyped());
+        }
+        for animation in self.animations.iter() {
+            visit(animation.untyped());
+        }
+    }
+}
+
+impl GltfScene {
+    /// Finds a node by name.
+    pub fn node(&self, name: &str) -> Option<&GltfNode> {
+        self.nodes
+            .iter()
+            .find(|node| node.name.as_deref() == Some(name))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GltfPrimitive {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<Material>,
+}
+
+/// The joints of a skinned mesh.
+#[derive(Debug, Clone)]
+pub struct GltfSkin {
+    /// Indices into [`GltfScene::nodes`].
+    pub joints: Vec<usize>,
+    pub inverse_bind_matrices: Vec<Mat4>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GltfNode {
+    pub name: Option<String>,
+    /// The transform relative to the parent node.
+    pub transform: Transform,
+    /// Indices into [`GltfScene::nodes`].
+    pub children: Vec<usize>,
+    /// Index into [`GltfScene::meshes`].
+    pub mesh: Option<usize>,
+    /// Index into [`GltfScene::skins`], for nodes whose mesh is skinned.
+    pub skin: Option<usize>,
+    /// The initial weights of the mesh's morph targets.
+    pub weights: Option<Vec<f32>>,
+    pub light: Option<GltfLight>,
+    pub camera: Option<GltfCamera>,
+}
+
+/// A light from the `KHR_lights_punctual` extension.
+#[derive(Debug, Clone, Copy)]
+pub struct GltfLight {
+    pub kind: GltfLightKind,
+    pub color: Color,
+    /// Candela for point and spot lights, lux for directional lights.
+    pub intensity: f32,
+    /// The distance at which the light reaches zero, or `None` for infinite range.
+    pub range: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GltfLightKind {
+    Directional,
+    Point,
+    Spot {
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GltfCamera {
+    Perspective {
+        yfov: f32,
+        aspect_ratio: Option<f32>,
+        znear: f32,
+        zfar: Option<f32>,
+    },
+    Orthographic {
+        xmag: f32,
+        ymag: f32,
+        znear: f32,
+        zfar: f32,
+    },
+}
+
+#[derive(Default)]
+pub struct GltfSceneLoader;
+
+impl LoadFrom<PathAndFilesystem> for GltfSceneLoader {
+    type Asset = GltfScene;
+    type Settings = ModelSettings;
+
+    async fn load(
+        &self,
+        source: PathAndFilesystem,
+        settings: &ModelSettings,
+        commands: &Commands,
+    ) -> Result<GltfScene> {
+        let bytes = source.read_async().await?;
+        load_gltf_scene(&bytes, settings, commands)
+    }
+}
+
+/// Reads the default scene of a glTF file (or the first one), adding its meshes, materials and animations as assets.
+pub fn load_gltf_scene(
+    bytes: &[u8],
+    settings: &ModelSettings,
+    commands: &Commands,
+) -> Result<GltfScene> {
+    let (document, buffers, images) = gltf::import_slice(bytes)?;
+
+    let materials = document
+        .materials()
+        .map(|material| {
+            let mut material = read_material(material, &images)?;
+            material.apply_texture_settings(&settings.textures)?;
+            Ok(commands.lazy_load_asset_direct(material.into_material(commands)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut default_material = None;
+    let mut meshes = Vec::with_capacity(document.meshes().len());
+    for mesh in document.meshes() {
+        let mut primitives = Vec::new();
+        for primitive in mesh.primitives() {
+            let mut primitive_mesh = read_gltf_primitive(&primitive, &buffers)?;
+            settings.mesh.apply(&mut primitive_mesh);
+            let material = match primitive.material().index() {
+                Some(index) => materials[index].clone(),
+                // primitives without a material use the glTF default material, which is only read if needed
+                None => match default_material.as_ref() {
+                    Some(material) => Handle::clone(material),
+                    None => {
+                        let material = read_material(primitive.material(), &images)?;
+                        let material =
+                            commands.lazy_load_asset_direct(material.into_material(commands));
+                        default_material.insert(material).clone()
+                    }
+                },
+            };
+            primitives.push(GltfPrimitive {
+                mesh: commands.lazy_load_asset_direct(primitive_mesh),
+                material,
+            });
+        }
+        meshes.push(primitives);
+    }
+
+    let nodes = document
+        .nodes()
+        .map(|node| {
+            let (translation, rotation, scale) = node.transform().decomposed();
+            GltfNode {
+                name: node.name().map(String::from),
+                // translations are scaled along with the meshes, so that the parts of a model stay together
+                transform: Transform::new(
+                    Vec3::from(translation) * settings.mesh.scale,
+                    Quat::from_array(rotation),
+                    Vec3::from(scale),
+                ),
+                children: node.children().map(|child| child.index()).collect(),
+                mesh: node.mesh().map(|mesh| mesh.index()),
+                skin: node.skin().map(|skin| skin.index()),
+                weights: node
+                    .weights()
+                    .or_else(|| node.mesh().and_then(|mesh| mesh.weights()))
+                    .map(Vec::from),
+                light: node.light().map(read_light),
+                camera: node.camera().map(read_camera),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let skins = document
+        .skins()
+        .map(|skin| read_skin(skin, &buffers, settings.mesh.scale))
+        .collect();
+
+    let animations = document
+        .animations()
+        .map(|animation| {
+            let clip = read_animation(animation, &buffers, settings.mesh.scale)?;
+            Ok(commands.lazy_load_asset_direct(clip))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let roots = match document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        Some(scene) => scene.nodes().map(|node| node.index()).collect(),
+        // files without scenes are spawned as all of their top-level nodes
+        None => {
+            let mut is_child = vec![false; nodes.len()];
+            for child in nodes.iter().flat_map(|node| node.children.iter()) {
+                is_child[*child] = true;
+            }
+            (0..nodes.len()).filter(|&index| !is_child[index]).collect()
+        }
+    };
+
+    Ok(GltfScene {
+        nodes,
+        roots,
+        meshes,
+        materials,
+        skins,
+        animations,
+    })
+}
+
+fn read_skin(skin: gltf::Skin<'_>, buffers: &[gltf::buffer::Data], scale: f32) -> GltfSkin {
+    let joints = skin.joints().map(|joint| joint.index()).collect::<Vec<_>>();
+    let reader = skin.reader(|buffer| Some(&buffers[buffer.index()]));
+    let inverse_bind_matrices = match reader.read_inverse_bind_matrices() {
+        Some(matrices) => matrices
+            .map(|matrix| {
+                let mut matrix = Mat4::from_cols_array_2d(&matrix);
+                // the joints' translations are scaled, so their inverses are too
+                matrix.w_axis = (matrix.w_axis.truncate() * scale).extend(matrix.w_axis.w);
+                matrix
+            })
+            .collect(),
+        None => vec![Mat4::IDENTITY; joints.len()],
+    };
+    GltfSkin {
+        joints,
+        inverse_bind_matrices,
+    }
+}
+
+fn read_animation(
+    animation: gltf::Animation<'_>,
+    buffers: &[gltf::buffer::Data],
+    scale: f32,
+) -> Result<AnimationClip> {
+    use gltf::animation::{Interpolation as GltfInterpolation, util::ReadOutputs};
+
+    let mut clip = AnimationClip::new(animation.name().map(String::from));
+    for channel in animation.channels() {
+        let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+        let times = reader
+            .read_inputs()
+            .ok_or_else(|| anyhow!("Animation channel has no keyframe times"))?
+            .collect();
+        let interpolation = match channel.sampler().interpolation() {
+            GltfInterpolation::Step => Interpolation::Step,
+            GltfInterpolation::Linear => Interpolation::Linear,
+            GltfInterpolation::CubicSpline => Interpolation::CubicSpline,
+        };
+        let keyframes = match reader
+            .read_outputs()
+            .ok_or_else(|| anyhow!("Animation channel has no keyframe values"))?
+        {
+            // translations are scaled along with the meshes, like the nodes' transforms
+            ReadOutputs::Translations(values) => {
+                Keyframes::Translation(values.map(|value| Vec3::from(value) * scale).collect())
+            }
+            ReadOutputs::Rotations(values) => {
+                Keyframes::Rotation(values.into_f32().map(Quat::from_array).collect())
+            }
+            ReadOutputs::Scales(values) => Keyframes::Scale(values.map(Vec3::from).collect()),
+            ReadOutputs::MorphTargetWeights(values) => {
+                Keyframes::Weights(values.into_f32().collect())
+            }
+        };
+        clip.add_channel(AnimationChannel::new(
+            channel.target().node().index(),
+            interpolation,
+            times,
+            keyframes,
+        )?);
+    }
+    Ok(clip)
+}
+
+fn read_light(light: gltf::khr_lights_punctual::Light<'_>) -> GltfLight {
+    use gltf::khr_lights_punctual::Kind;
+
+    let kind = match light.kind() {
+        Kind::Directional => GltfLightKind::Directional,
+        Kind::Point => GltfLightKind::Point,
+        Kind::Spot {
+            inner_cone_angle,
+            outer_cone_angle,
+        } => GltfLightKind::Spot {
+            inner_cone_angle,
+            outer_cone_angle,
+        },
+    };
+    GltfLight {
+        kind,
+        color: light.color().into(),
+        intensity: light.intensity(),
+        range: light.range(),
+    }
+}
+
+fn read_camera(camera: gltf::Camera<'_>) -> GltfCamera {
+    match camera.projection() {
+        gltf::camera::Projection::Perspective(perspective) => GltfCamera::Perspective {
+            yfov: perspective.yfov(),
+            aspect_ratio: perspective.aspect_ratio(),
+            znear: perspective.znear(),
+            zfar: perspective.zfar(),
+        },
+        gltf::camera::Projection::Orthographic(orthographic) => GltfCamera::Orthographic {
+            xmag: orthographic.xmag(),
+            ymag: orthographic.ymag(),
+            znear: orthographic.znear(),
+            zfar: orthographic.zfar(),
+        },
+    }
+}
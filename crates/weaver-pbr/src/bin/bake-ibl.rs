@@ -0,0 +1,68 @@
+//! Bakes the image based lighting textures of a skybox next to its equirectangular HDR image, the same way
+//! `Skybox` does on load when they're missing.
+//!
+//! ```text
+//! bake-ibl <image> [--cubemap-size <n>] [--irradiance-size <n>] [--specular-size <n>] [--specular-mips <n>]
+//!          [--specular-samples <n>] [--lut-size <n>] [--lut-samples <n>]
+//! ```
+
+use weaver_pbr::skybox::{Skybox, bake::IblBakeSettings};
+use weaver_util::prelude::*;
+
+const USAGE: &str = "usage: bake-ibl <image> [--cubemap-size <n>] [--irradiance-size <n>] [--specular-size <n>]
+                [--specular-mips <n>] [--specular-samples <n>] [--lut-size <n>] [--lut-samples <n>]";
+
+fn main() {
+    if let Err(e) = run(std::env::args().skip(1).collect()) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<()> {
+    let mut paths = Vec::new();
+    let mut settings = IblBakeSettings::default();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let setting = match arg.as_str() {
+            "--cubemap-size" => &mut settings.cubemap_size,
+            "--irradiance-size" => &mut settings.irradiance_size,
+            "--specular-size" => &mut settings.specular_size,
+            "--specular-mips" => &mut settings.specular_mip_levels,
+            "--specular-samples" => &mut settings.specular_sample_count,
+            "--lut-size" => &mut settings.brdf_lut_size,
+            "--lut-samples" => &mut settings.brdf_lut_sample_count,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if arg.starts_with("--") => bail!("unknown option {}\n{}", arg, USAGE),
+            _ => {
+                paths.push(arg);
+                continue;
+            }
+        };
+        let value = args
+            .next()
+            .ok_or_else(|| anyhow!("{} needs a number", arg))?;
+        *setting = value
+            .parse()
+            .map_err(|_| anyhow!("{} needs a number, got {}", arg, value))?;
+    }
+
+    let [path] = paths.as_slice() else {
+        bail!("{}", USAGE);
+    };
+    let skybox = Skybox::new(path).with_bake_settings(settings);
+    skybox.bake()?;
+    for path in [
+        &skybox.cubemap_path,
+        &skybox.diffuse_path,
+        &skybox.specular_path,
+        &skybox.brdf_lut_path,
+    ] {
+        println!("Wrote {:?}", path);
+    }
+    Ok(())
+}
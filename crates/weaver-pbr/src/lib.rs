@@ -1,12 +1,25 @@
-use assets::material_mesh::{LoadedModelWithMaterials, ObjMaterialModelLoader};
+use assets::{
+    material_mesh::{
+        GltfMaterialModelLoader, GltfMaterialModelProcessor, LoadedModelWithMaterials,
+        ObjMaterialModelLoader,
+    },
+    scene::{GltfScene, GltfSceneLoader},
+};
 use light::{PointLight, PointLightPlugin};
-use material::{BLACK_TEXTURE, ERROR_TEXTURE, MaterialPlugin, WHITE_TEXTURE};
+use material::{
+    BLACK_TEXTURE, ERROR_TEXTURE, MaterialPlugin, WHITE_TEXTURE,
+    mark_materials_with_modified_textures,
+};
 use prelude::Material;
 use render::{PbrLightingInformation, PbrRenderable, render_pbr};
+use scene::{SceneSpawner, spawn_scenes};
+use skinning::SkinningPlugin;
 use skybox::{Skybox, SkyboxPlugin, SkyboxRenderablePlugin, render_skybox};
+use weaver_animation::{AnimationPlugin, AssetTweenPlugin, TweenPlugin, clip::AnimationClip};
+use weaver_app::plugin::PluginId;
 use weaver_app::prelude::*;
 use weaver_asset::{AssetApp, Assets};
-use weaver_core::{texture::Texture, transform::Transform};
+use weaver_core::{CoreTypesPlugin, texture::Texture, transform::Transform};
 use weaver_ecs::{
     component::{Res, ResMut},
     prelude::Commands,
@@ -14,8 +27,8 @@ use weaver_ecs::{
     system::IntoSystemConfig,
 };
 use weaver_renderer::{
-    RenderApp, RenderStage, WgpuDevice, WgpuQueue, bind_group::ResourceBindGroupPlugin,
-    hdr::render_hdr, prelude::RenderPipelinePlugin,
+    RenderApp, RenderStage, RendererPlugin, WgpuDevice, WgpuQueue,
+    bind_group::ResourceBindGroupPlugin, hdr::render_hdr, prelude::MeshPipelinePlugin,
 };
 use weaver_util::prelude::*;
 
@@ -23,29 +36,62 @@ pub mod assets;
 pub mod light;
 pub mod material;
 pub mod render;
+pub mod scene;
+pub mod skinning;
 pub mod skybox;
 
 pub mod prelude {
     pub use crate::PbrPlugin;
     pub use crate::assets::material_mesh::*;
+    pub use crate::assets::scene::*;
     pub use crate::light::*;
     pub use crate::material::*;
+    pub use crate::scene::*;
+    pub use crate::skinning::*;
     pub use crate::skybox::*;
 }
 
+/// Renders meshes with PBR materials and lights, and loads and spawns glTF scenes.
+///
+/// If the [`AnimationPlugin`] was added before it, [`PointLight`] and [`Material`] tweens are animated too.
 pub struct PbrPlugin;
 
 impl Plugin for PbrPlugin {
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![
+            PluginId::of::<CoreTypesPlugin>(),
+            PluginId::of::<RendererPlugin>(),
+        ]
+    }
+
     fn build(&self, app: &mut App) -> Result<()> {
         app.add_asset::<Material>();
         app.add_asset::<LoadedModelWithMaterials>();
-        app.add_asset_loader::<ObjMaterialModelLoader, _>();
+        app.add_file_asset_loader::<ObjMaterialModelLoader>(&["obj"]);
+        app.add_file_asset_loader::<GltfMaterialModelLoader>(&["gltf", "glb"]);
+        app.add_asset_processor(
+            &["gltf", "glb"],
+            GltfMaterialModelProcessor,
+            Default::default(),
+        );
+        app.add_asset::<GltfScene>();
+        app.add_file_asset_loader::<GltfSceneLoader>(&["gltf", "glb"]);
+        app.init_resource::<SceneSpawner>();
+        app.add_system(spawn_scenes, AppStage::PreUpdate);
+        app.add_system(mark_materials_with_modified_textures, AppStage::Update);
+        // scenes hold their animation clips, whether or not they're played
+        app.add_asset::<AnimationClip>();
+        if app.has_plugin::<AnimationPlugin>() {
+            app.add_plugin(TweenPlugin::<PointLight>::default())?;
+            app.add_plugin(AssetTweenPlugin::<Material>::default())?;
+        }
 
-        let render_app = app.get_sub_app_mut::<RenderApp>().unwrap();
+        let render_app = app.sub_app_mut::<RenderApp>()?;
         render_app.add_plugin(MaterialPlugin)?;
         render_app.add_plugin(PointLightPlugin)?;
         render_app.add_plugin(SkyboxPlugin)?;
         render_app.add_plugin(SkyboxRenderablePlugin)?;
+        render_app.add_plugin(SkinningPlugin)?;
 
         render_app.add_plugin(ResourceBindGroupPlugin::<PbrLightingInformation>::default())?;
 
@@ -58,7 +104,7 @@ impl Plugin for PbrPlugin {
             RenderStage::InitRenderResources,
         );
 
-        render_app.add_plugin(RenderPipelinePlugin::<PbrRenderable>::default())?;
+        render_app.add_plugin(MeshPipelinePlugin::<PbrRenderable>::default())?;
 
         render_app.world_mut().add_system(
             render_pbr.after(render_skybox).before(render_hdr),
@@ -1,18 +1,25 @@
 use encase::ShaderType;
 use weaver_app::{App, plugin::Plugin};
-use weaver_asset::{Assets, Handle, prelude::Asset};
-use weaver_core::{color::Color, texture::Texture};
+use weaver_asset::{AssetEvent, Assets, Handle, UntypedHandle, prelude::Asset};
+use weaver_core::{
+    color::Color,
+    prelude::{Mat3, Vec2},
+    texture::Texture,
+};
 use weaver_ecs::prelude::ResMut;
+use weaver_event::{EventRx, prelude::StreamExt};
 use weaver_renderer::{
     asset::{ExtractRenderAssetPlugin, RenderAsset},
     bind_group::{AssetBindGroupPlugin, BindGroupLayout, CreateBindGroup},
     buffer::GpuBufferVec,
     extract::Extract,
     prelude::*,
-    texture::{GpuTexture, texture_format},
+    texture::GpuTexture,
 };
 use weaver_util::prelude::*;
 
+use crate::render::PbrPipelineKey;
+
 pub const WHITE_TEXTURE: Handle<Texture> =
     Handle::from_u128(171952135557955961317447623731106286307);
 pub const BLACK_TEXTURE: Handle<Texture> =
@@ -20,7 +27,7 @@ pub const BLACK_TEXTURE: Handle<Texture> =
 pub const ERROR_TEXTURE: Handle<Texture> =
     Handle::from_u128(288942464416563327199333453807837020723);
 
-#[derive(Asset, Clone)]
+#[derive(Clone)]
 pub struct Material {
     pub diffuse: Color,
     pub diffuse_texture: Handle<Texture>,
@@ -34,7 +41,186 @@ pub struct Material {
     pub ao: f32,
     pub ao_texture: Handle<Texture>,
 
+    pub emissive: Color,
+    pub emissive_texture: Handle<Texture>,
+
+    pub alpha_mode: AlphaMode,
+    /// Fragments with a lower alpha are discarded if `alpha_mode` is [`AlphaMode::Mask`].
+    pub alpha_cutoff: f32,
+    /// Whether back faces are rendered too, lit with their normals flipped.
+    pub double_sided: bool,
+
     pub texture_scale: f32,
+    pub sampling: MaterialSampling,
+}
+
+/// How the alpha of a material's diffuse color is interpreted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AlphaMode {
+    /// Alpha is ignored and the material is fully opaque.
+    #[default]
+    Opaque,
+    /// The material is either fully opaque or fully transparent, depending on [`Material::alpha_cutoff`].
+    Mask,
+    /// The material is blended with whatever is behind it.
+    Blend,
+}
+
+impl AlphaMode {
+    fn to_u32(self) -> u32 {
+        match self {
+            AlphaMode::Opaque => 0,
+            AlphaMode::Mask => 1,
+            AlphaMode::Blend => 2,
+        }
+    }
+}
+
+/// How each of a material's textures is sampled.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MaterialSampling {
+    pub diffuse: TextureSampling,
+    pub normal: TextureSampling,
+    pub metallic_roughness: TextureSampling,
+    pub ao: TextureSampling,
+    pub emissive: TextureSampling,
+}
+
+impl MaterialSampling {
+    /// The sampling of each texture, in the order of [`Material::textures`].
+    pub fn textures(&self) -> [&TextureSampling; 5] {
+        [
+            &self.diffuse,
+            &self.normal,
+            &self.metallic_roughness,
+            &self.ao,
+            &self.emissive,
+        ]
+    }
+
+    pub fn textures_mut(&mut self) -> [&mut TextureSampling; 5] {
+        [
+            &mut self.diffuse,
+            &mut self.normal,
+            &mut self.metallic_roughness,
+            &mut self.ao,
+            &mut self.emissive,
+        ]
+    }
+}
+
+/// The sampler settings and texture coordinate transform of one texture of a material.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureSampling {
+    pub address_mode_u: wgpu::AddressMode,
+    pub address_mode_v: wgpu::AddressMode,
+    pub mag_filter: wgpu::FilterMode,
+    pub min_filter: wgpu::FilterMode,
+    pub mipmap_filter: wgpu::MipmapFilterMode,
+    pub transform: TextureTransform,
+}
+
+impl Default for TextureSampling {
+    fn default() -> Self {
+        Self {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::MipmapFilterMode::Linear,
+            transform: TextureTransform::default(),
+        }
+    }
+}
+
+impl TextureSampling {
+    fn create_sampler(&self, device: &wgpu::Device, label: &str) -> wgpu::Sampler {
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(label),
+            address_mode_u: self.address_mode_u,
+            address_mode_v: self.address_mode_v,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            mipmap_filter: self.mipmap_filter,
+            ..Default::default()
+        })
+    }
+}
+
+/// An offset, rotation and scale of texture coordinates, as in glTF's `KHR_texture_transform`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureTransform {
+    pub offset: Vec2,
+    /// Counter-clockwise rotation in radians.
+    pub rotation: f32,
+    pub scale: Vec2,
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self {
+            offset: Vec2::ZERO,
+            rotation: 0.0,
+            scale: Vec2::ONE,
+        }
+    }
+}
+
+impl TextureTransform {
+    /// The matrix that transforms texture coordinates, scaling them first, then rotating and offsetting them.
+    pub fn matrix(&self) -> Mat3 {
+        // glTF rotates texture coordinates clockwise for a positive rotation, since v points down
+        Mat3::from_translation(self.offset)
+            * Mat3::from_angle(-self.rotation)
+            * Mat3::from_scale(self.scale)
+    }
+}
+
+impl Asset for Material {
+    fn visit_dependencies(&self, visit: &mut dyn FnMut(UntypedHandle)) {
+        for texture in self.textures() {
+            visit(texture.untyped());
+        }
+    }
+}
+
+impl Material {
+    pub fn textures(&self) -> [&Handle<Texture>; 5] {
+        [
+            &self.diffuse_texture,
+            &self.normal_texture,
+            &self.metallic_roughness_texture,
+            &self.ao_texture,
+            &self.emissive_texture,
+        ]
+    }
+}
+
+/// Marks materials as modified when one of their textures is reloaded, so their bind groups are recreated.
+pub(crate) async fn mark_materials_with_modified_textures(
+    mut events: EventRx<AssetEvent<Texture>>,
+    mut materials: ResMut<Assets<Material>>,
+) {
+    while let Some(event) = events.next().await {
+        let AssetEvent::Modified(texture) = event else {
+            continue;
+        };
+        let modified = materials
+            .ids()
+            .map(Handle::from_raw)
+            .collect::<Vec<Handle<Material>>>()
+            .into_iter()
+            .filter(|material| {
+                materials
+                    .get(material)
+                    .is_some_and(|material| material.textures().contains(&&texture))
+            })
+            .collect::<Vec<_>>();
+        for material in modified {
+            materials.mark_modified(&material);
+        }
+    }
 }
 
 impl From<Color> for Material {
@@ -48,7 +234,13 @@ impl From<Color> for Material {
             metallic_roughness_texture: WHITE_TEXTURE,
             ao: 1.0,
             ao_texture: WHITE_TEXTURE,
+            emissive: Color::BLACK,
+            emissive_texture: WHITE_TEXTURE,
+            alpha_mode: AlphaMode::default(),
+            alpha_cutoff: 0.5,
+            double_sided: false,
             texture_scale: 1.0,
+            sampling: MaterialSampling::default(),
         }
     }
 }
@@ -64,7 +256,13 @@ impl From<Handle<Texture>> for Material {
             metallic_roughness_texture: WHITE_TEXTURE,
             ao: 1.0,
             ao_texture: WHITE_TEXTURE,
+            emissive: Color::BLACK,
+            emissive_texture: WHITE_TEXTURE,
+            alpha_mode: AlphaMode::default(),
+            alpha_cutoff: 0.5,
+            double_sided: false,
             texture_scale: 1.0,
+            sampling: MaterialSampling::default(),
         }
     }
 }
@@ -77,6 +275,31 @@ pub struct MaterialMetaUniform {
     roughness: f32,
     ao: f32,
     texture_scale: f32,
+    emissive: Color,
+    alpha_cutoff: f32,
+    alpha_mode: u32,
+    double_sided: u32,
+    uv_transforms: [Mat3; 5],
+}
+
+impl MaterialMetaUniform {
+    fn new(material: &Material) -> Self {
+        Self {
+            diffuse: material.diffuse,
+            metallic: material.metallic,
+            roughness: material.roughness,
+            ao: material.ao,
+            texture_scale: material.texture_scale,
+            emissive: material.emissive,
+            alpha_cutoff: material.alpha_cutoff,
+            alpha_mode: material.alpha_mode.to_u32(),
+            double_sided: material.double_sided as u32,
+            uv_transforms: material
+                .sampling
+                .textures()
+                .map(|sampling| sampling.transform.matrix()),
+        }
+    }
 }
 
 #[derive(Asset)]
@@ -91,6 +314,11 @@ pub struct GpuMaterial {
     pub metallic_roughness_texture_sampler: wgpu::Sampler,
     pub ao_texture: GpuTexture,
     pub ao_texture_sampler: wgpu::Sampler,
+    pub emissive_texture: GpuTexture,
+    pub emissive_texture_sampler: wgpu::Sampler,
+
+    /// The pipeline state of the material, which can't be changed through its bind group.
+    pub pipeline_key: PbrPipelineKey,
 }
 
 impl RenderAsset for GpuMaterial {
@@ -106,85 +334,41 @@ impl RenderAsset for GpuMaterial {
     where
         Self: Sized,
     {
-        let diffuse_texture = textures.get(base_asset.diffuse_texture)?;
-        let diffuse_texture =
-            GpuTexture::from_image(device, queue, &diffuse_texture, texture_format::SDR_FORMAT)?;
-
-        let normal_texture = textures.get(base_asset.normal_texture)?;
-        let normal_texture = GpuTexture::from_image(
-            device,
-            queue,
-            &normal_texture,
-            texture_format::NORMAL_FORMAT,
-        )?;
-
-        let metallic_roughness_texture = textures.get(base_asset.metallic_roughness_texture)?;
-        let metallic_roughness_texture = GpuTexture::from_image(
-            device,
-            queue,
-            &metallic_roughness_texture,
-            texture_format::SDR_FORMAT,
-        )?;
-
-        let ao_texture = textures.get(base_asset.ao_texture)?;
-        let ao_texture =
-            GpuTexture::from_image(device, queue, &ao_texture, texture_format::SDR_FORMAT)?;
+        let diffuse_texture = textures.get(&base_asset.diffuse_texture)?;
+        let diffuse_texture = GpuTexture::from_image(device, queue, &diffuse_texture)?;
+
+        let normal_texture = textures.get(&base_asset.normal_texture)?;
+        let normal_texture = GpuTexture::from_image(device, queue, &normal_texture)?;
+
+        let metallic_roughness_texture = textures.get(&base_asset.metallic_roughness_texture)?;
+        let metallic_roughness_texture =
+            GpuTexture::from_image(device, queue, &metallic_roughness_texture)?;
+
+        let ao_texture = textures.get(&base_asset.ao_texture)?;
+        let ao_texture = GpuTexture::from_image(device, queue, &ao_texture)?;
 
         let mut meta =
             GpuBufferVec::new(wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST);
 
-        let diffuse_texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("Diffuse Texture Sampler"),
-            address_mode_u: wgpu::AddressMode::Repeat,
-            address_mode_v: wgpu::AddressMode::Repeat,
-            address_mode_w: wgpu::AddressMode::Repeat,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::MipmapFilterMode::Linear,
-            ..Default::default()
-        });
-
-        let normal_texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("Normal Texture Sampler"),
-            address_mode_u: wgpu::AddressMode::Repeat,
-            address_mode_v: wgpu::AddressMode::Repeat,
-            address_mode_w: wgpu::AddressMode::Repeat,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::MipmapFilterMode::Linear,
-            ..Default::default()
-        });
+        let emissive_texture = textures.get(&base_asset.emissive_texture)?;
+        let emissive_texture = GpuTexture::from_image(device, queue, &emissive_texture)?;
 
-        let metallic_roughness_texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("Metallic Roughness Texture Sampler"),
-            address_mode_u: wgpu::AddressMode::Repeat,
-            address_mode_v: wgpu::AddressMode::Repeat,
-            address_mode_w: wgpu::AddressMode::Repeat,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::MipmapFilterMode::Linear,
-            ..Default::default()
-        });
+        let sampling = &base_asset.sampling;
+        let diffuse_texture_sampler = sampling
+            .diffuse
+            .create_sampler(device, "Diffuse Texture Sampler");
+        let normal_texture_sampler = sampling
+            .normal
+            .create_sampler(device, "Normal Texture Sampler");
+        let metallic_roughness_texture_sampler = sampling
+            .metallic_roughness
+            .create_sampler(device, "Metallic Roughness Texture Sampler");
+        let ao_texture_sampler = sampling.ao.create_sampler(device, "AO Texture Sampler");
+        let emissive_texture_sampler = sampling
+            .emissive
+            .create_sampler(device, "Emissive Texture Sampler");
 
-        let ao_texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("AO Texture Sampler"),
-            address_mode_u: wgpu::AddressMode::Repeat,
-            address_mode_v: wgpu::AddressMode::Repeat,
-            address_mode_w: wgpu::AddressMode::Repeat,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::MipmapFilterMode::Linear,
-            ..Default::default()
-        });
-
-        let meta_uniform = MaterialMetaUniform {
-            diffuse: base_asset.diffuse,
-            metallic: base_asset.metallic,
-            roughness: base_asset.roughness,
-            ao: base_asset.ao,
-            texture_scale: base_asset.texture_scale,
-        };
-        meta.push(meta_uniform);
+        meta.push(MaterialMetaUniform::new(base_asset));
         meta.enqueue_update(device, queue);
 
         Some(Self {
@@ -197,6 +381,9 @@ impl RenderAsset for GpuMaterial {
             metallic_roughness_texture_sampler,
             ao_texture,
             ao_texture_sampler,
+            emissive_texture,
+            emissive_texture_sampler,
+            pipeline_key: PbrPipelineKey::new(base_asset),
         })
     }
 
@@ -210,21 +397,30 @@ impl RenderAsset for GpuMaterial {
     where
         Self: Sized,
     {
-        let meta = MaterialMetaUniform {
-            diffuse: base_asset.diffuse,
-            metallic: base_asset.metallic,
-            roughness: base_asset.roughness,
-            ao: base_asset.ao,
-            texture_scale: base_asset.texture_scale,
-        };
-
         self.meta.clear();
-        self.meta.push(meta);
+        self.meta.push(MaterialMetaUniform::new(base_asset));
+        self.pipeline_key = PbrPipelineKey::new(base_asset);
 
         self.meta.enqueue_update(device, queue);
 
         Ok(())
     }
+
+    fn reload_render_asset(
+        &mut self,
+        base_asset: &Self::Source,
+        textures: &mut Extract<ResMut<Assets<Texture>>>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<()>
+    where
+        Self: Sized,
+    {
+        // the textures or samplers may have changed along with the material
+        *self = Self::extract_render_asset(base_asset, textures, device, queue)
+            .ok_or_else(|| anyhow!("Material textures are not loaded"))?;
+        Ok(())
+    }
 }
 
 impl CreateBindGroup for GpuMaterial {
@@ -318,6 +514,24 @@ impl CreateBindGroup for GpuMaterial {
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                     count: None,
                 },
+                // Emissive texture
+                wgpu::BindGroupLayoutEntry {
+                    binding: 9,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // Emissive texture sampler
+                wgpu::BindGroupLayoutEntry {
+                    binding: 10,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
             ],
         })
     }
@@ -380,6 +594,16 @@ impl CreateBindGroup for GpuMaterial {
                     binding: 8,
                     resource: wgpu::BindingResource::Sampler(&self.ao_texture_sampler),
                 },
+                // Emissive texture
+                wgpu::BindGroupEntry {
+                    binding: 9,
+                    resource: wgpu::BindingResource::TextureView(&self.emissive_texture.view),
+                },
+                // Emissive texture sampler
+                wgpu::BindGroupEntry {
+                    binding: 10,
+                    resource: wgpu::BindingResource::Sampler(&self.emissive_texture_sampler),
+                },
             ],
         })
     }
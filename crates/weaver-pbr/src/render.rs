@@ -1,6 +1,5 @@
-use std::path::Path;
-
 use weaver_asset::{Assets, Handle};
+use weaver_core::mesh::{Mesh, MeshAttribute, MeshVertexLayout};
 use weaver_ecs::{
     prelude::{Res, ResMut, World},
     query::Query,
@@ -9,18 +8,20 @@ use weaver_ecs::{
 use weaver_renderer::{
     bind_group::{BindGroupLayout, CreateBindGroup},
     camera::{CameraBindGroup, ViewTarget},
-    mesh::GpuMesh,
-    pipeline::RenderPipelineCache,
+    mesh::{GpuMesh, GpuVertexLayout},
+    pipeline::{MeshPipelines, RenderPipelineCache, SpecializeMeshPipeline},
     prelude::*,
     resources::ActiveCommandEncoder,
-    shader::Shader,
+    shader::{Shader, ShaderDir},
     texture::texture_format,
     transform::TransformBindGroup,
 };
+use weaver_util::prelude::*;
 
 use crate::{
     light::GpuPointLightArray,
-    prelude::{GpuMaterial, irradiance::GpuSkyboxIrradiance},
+    prelude::{GpuMaterial, Material, irradiance::GpuSkyboxIrradiance},
+    skinning::GpuJointMatrices,
 };
 
 /// Combined bind group for PBR light arrays and environment maps.
@@ -140,6 +141,21 @@ impl CreateBindGroup for PbrLightingInformation {
 #[derive(Default)]
 pub struct PbrRenderable;
 
+/// The material state that [`PbrRenderable`] pipelines are specialized on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PbrPipelineKey {
+    /// Back faces are culled unless the material is double-sided.
+    pub double_sided: bool,
+}
+
+impl PbrPipelineKey {
+    pub fn new(material: &Material) -> Self {
+        Self {
+            double_sided: material.double_sided,
+        }
+    }
+}
+
 impl CreateRenderPipeline for PbrRenderable {
     fn create_render_pipeline_layout(
         device: &wgpu::Device,
@@ -155,6 +171,7 @@ impl CreateRenderPipeline for PbrRenderable {
                 Some(&bind_group_layout_cache.get_or_create::<CameraBindGroup>(device)),
                 Some(&bind_group_layout_cache.get_or_create::<TransformBindGroup>(device)),
                 Some(&bind_group_layout_cache.get_or_create::<PbrLightingInformation>(device)),
+                Some(&bind_group_layout_cache.get_or_create::<GpuJointMatrices>(device)),
             ],
             ..Default::default()
         });
@@ -164,12 +181,59 @@ impl CreateRenderPipeline for PbrRenderable {
 
     fn create_render_pipeline(
         device: &wgpu::Device,
+        shader_dir: &ShaderDir,
         cached_layout: &wgpu::PipelineLayout,
     ) -> RenderPipeline
     where
         Self: Sized,
     {
-        let shader = Shader::new(Path::new("assets/shaders/pbr.wgsl")).create_shader_module(device);
+        // meshes built from `Vertex`es have the standard attributes
+        let vertex_layout = Mesh::new(Vec::new(), Vec::new()).vertex_layout();
+        Self::specialize(
+            device,
+            shader_dir,
+            cached_layout,
+            &vertex_layout,
+            &PbrPipelineKey::default(),
+        )
+        .unwrap()
+    }
+}
+
+impl SpecializeMeshPipeline for PbrRenderable {
+    type Key = PbrPipelineKey;
+
+    fn specialize(
+        device: &wgpu::Device,
+        shader_dir: &ShaderDir,
+        cached_layout: &wgpu::PipelineLayout,
+        vertex_layout: &MeshVertexLayout,
+        key: &PbrPipelineKey,
+    ) -> Result<RenderPipeline> {
+        // meshes without texture coordinates sample their textures at the origin
+        let mut optional = vec![MeshAttribute::UV_0, MeshAttribute::COLOR];
+        // joints are only used together with their weights
+        if vertex_layout
+            .offset(&MeshAttribute::JOINT_INDICES)
+            .is_some()
+            && vertex_layout
+                .offset(&MeshAttribute::JOINT_WEIGHTS)
+                .is_some()
+        {
+            optional.extend([MeshAttribute::JOINT_INDICES, MeshAttribute::JOINT_WEIGHTS]);
+        }
+        let gpu_layout = GpuVertexLayout::new(
+            vertex_layout,
+            &[
+                MeshAttribute::POSITION,
+                MeshAttribute::NORMAL,
+                MeshAttribute::TANGENT,
+            ],
+            &optional,
+        )?;
+
+        let shader = Shader::with_defs(shader_dir, "pbr.wgsl", &gpu_layout.shader_defs)
+            .create_shader_module(device);
 
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("PBR Pipeline"),
@@ -178,32 +242,7 @@ impl CreateRenderPipeline for PbrRenderable {
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: Some("vs_main"),
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: 4 * (3 + 3 + 3 + 2) as u64,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[
-                        wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x3,
-                            offset: 0,
-                            shader_location: 0,
-                        },
-                        wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x3,
-                            offset: 4 * 3,
-                            shader_location: 1,
-                        },
-                        wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x3,
-                            offset: 4 * 6,
-                            shader_location: 2,
-                        },
-                        wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x2,
-                            offset: 4 * 9,
-                            shader_location: 3,
-                        },
-                    ],
-                }],
+                buffers: &[gpu_layout.buffer_layout()],
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             },
             fragment: Some(wgpu::FragmentState {
@@ -217,8 +256,11 @@ impl CreateRenderPipeline for PbrRenderable {
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             }),
             primitive: wgpu::PrimitiveState {
-                cull_mode: Some(wgpu::Face::Back),
-                // cull_mode: None,
+                cull_mode: if key.double_sided {
+                    None
+                } else {
+                    Some(wgpu::Face::Back)
+                },
                 ..Default::default()
             },
             depth_stencil: Some(wgpu::DepthStencilState {
@@ -232,7 +274,7 @@ impl CreateRenderPipeline for PbrRenderable {
             multiview_mask: None,
         });
 
-        RenderPipeline::new(pipeline)
+        Ok(RenderPipeline::new(pipeline))
     }
 }
 
@@ -240,17 +282,40 @@ impl CreateRenderPipeline for PbrRenderable {
 pub(crate) async fn render_pbr(
     mut mesh_assets: ResMut<Assets<GpuMesh>>,
     mut material_assets: ResMut<Assets<BindGroup<GpuMaterial>>>,
+    mut gpu_materials: ResMut<Assets<GpuMaterial>>,
     pipeline_cache: Res<RenderPipelineCache>,
+    mut pipelines: ResMut<MeshPipelines<PbrRenderable>>,
+    device: Res<WgpuDevice>,
+    shader_dir: Res<ShaderDir>,
     mut item_query: Query<(
         &Handle<GpuMesh>,
+        &Handle<GpuMaterial>,
         &Handle<BindGroup<GpuMaterial>>,
         &BindGroup<TransformBindGroup>,
+        Option<&BindGroup<GpuJointMatrices>>,
     )>,
     lights_bind_group: Res<BindGroup<PbrLightingInformation>>,
+    default_joint_matrices: Res<BindGroup<GpuJointMatrices>>,
     mut encoder: ResMut<ActiveCommandEncoder>,
     mut view_target: Query<(&ViewTarget, &BindGroup<CameraBindGroup>)>,
 ) {
-    let pipeline = pipeline_cache.get_pipeline_for::<PbrRenderable>().unwrap();
+    // meshes with a vertex layout or material state that hasn't been seen yet get their own pipeline
+    for (mesh_handle, gpu_material_handle, _, _, _) in item_query.iter() {
+        let Some(mesh) = mesh_assets.get(&mesh_handle) else {
+            continue;
+        };
+        let Some(gpu_material) = gpu_materials.get(&gpu_material_handle) else {
+            continue;
+        };
+        pipelines.specialize(
+            &device,
+            &shader_dir,
+            &pipeline_cache,
+            &mesh.layout,
+            &gpu_material.pipeline_key,
+        );
+    }
+
     let (view_target, camera_bind_group) = view_target.iter().next().unwrap();
     {
         let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -275,17 +340,40 @@ pub(crate) async fn render_pbr(
             ..Default::default()
         });
 
-        pass.set_pipeline(pipeline);
-
-        for (mesh_handle, material_handle, transform_bind_group) in item_query.iter() {
-            let mesh = mesh_assets.get(*mesh_handle).unwrap();
-
-            let material = material_assets.get(*material_handle).unwrap();
-
+        for (
+            mesh_handle,
+            gpu_material_handle,
+            material_handle,
+            transform_bind_group,
+            joint_matrices,
+        ) in item_query.iter()
+        {
+            // the assets may have been removed while their entity still holds a handle to them
+            let Some(mesh) = mesh_assets.get(&mesh_handle) else {
+                continue;
+            };
+
+            let Some(material) = material_assets.get(&material_handle) else {
+                continue;
+            };
+
+            let Some(gpu_material) = gpu_materials.get(&gpu_material_handle) else {
+                continue;
+            };
+
+            // meshes whose layout lacks attributes the shader needs aren't drawn
+            let Some(pipeline) = pipelines.get(&mesh.layout, &gpu_material.pipeline_key) else {
+                continue;
+            };
+
+            pass.set_pipeline(pipeline);
             pass.set_bind_group(0, &**material.bind_group(), &[]);
             pass.set_bind_group(1, &**camera_bind_group.bind_group(), &[]);
             pass.set_bind_group(2, &**transform_bind_group.bind_group(), &[]);
             pass.set_bind_group(3, &**lights_bind_group.bind_group(), &[]);
+            // unskinned vertices ignore the joint matrices, but the pipeline needs some bound
+            let joint_matrices = joint_matrices.as_deref().unwrap_or(&default_joint_matrices);
+            pass.set_bind_group(4, &**joint_matrices.bind_group(), &[]);
 
             pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
             pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
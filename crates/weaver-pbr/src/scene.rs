@@ -0,0 +1,487 @@
+use weaver_animation::{
+    player::{AnimationPlayer, MorphWeights},
+    skin::Skin,
+};
+use weaver_asset::prelude::*;
+use weaver_core::{
+    hierarchy::{Children, GlobalTransform, Name, Parent},
+    prelude::Mat4,
+    transform::Transform,
+};
+use weaver_ecs::{
+    entity::Entity,
+    prelude::{Commands, Res, ResMut},
+    query::Query,
+};
+use weaver_util::prelude::*;
+
+use crate::{
+    assets::scene::{GltfLightKind, GltfScene},
+    light::PointLight,
+};
+
+/// Radius of point lights spawned from glTF lights with infinite range.
+pub const DEFAULT_LIGHT_RADIUS: f32 = 1000.0;
+
+/// The root entity of a scene spawned by the [`SceneSpawner`].
+pub struct SceneRoot(pub Handle<GltfScene>);
+
+/// Spawns [`GltfScene`]s as entity hierarchies once they're loaded.
+///
+/// Every node becomes an entity with its [`Transform`], its [`GlobalTransform`] (so that it's rendered in place right
+/// away), a [`Parent`] and (if it's named) a [`Name`]. Nodes with a single mesh primitive get its `Handle<Mesh>` and
+/// `Handle<Material>`, nodes with several get one child entity per primitive. Point and spot lights become
+/// [`PointLight`]s; directional lights and cameras aren't supported and are skipped with a warning.
+///
+/// The entities of skinned meshes get a [`Skin`] pointing at their joints' entities, and those of meshes with morph
+/// targets get their initial [`MorphWeights`]. Scenes with animations get an [`AnimationPlayer`] on their root
+/// entity that targets their nodes' entities, ready to play the clips in [`GltfScene::animations`].
+#[derive(Default)]
+pub struct SceneSpawner {
+    pending: Lock<Vec<(Entity, Handle<GltfScene>)>>,
+}
+
+impl SceneSpawner {
+    /// Spawns the root entity of a scene right away, and the entities of its nodes under it once it's loaded.
+    pub fn spawn(
+        &self,
+        commands: &Commands,
+        scene: Handle<GltfScene>,
+        transform: Transform,
+    ) -> Entity {
+        let root = commands.spawn((
+            transform,
+            GlobalTransform(transform.matrix()),
+            SceneRoot(scene.clone()),
+        ));
+        self.pending.write().push((root, scene));
+        root
+    }
+
+    /// The number of scenes that are waiting to be loaded.
+    pub fn pending(&self) -> usize {
+        self.pending.read().len()
+    }
+}
+
+pub(crate) async fn spawn_scenes(
+    commands: Commands,
+    spawner: Res<SceneSpawner>,
+    mut scenes: ResMut<Assets<GltfScene>>,
+    load_status: Res<AssetLoadStatus>,
+    mut transforms: Query<&Transform>,
+    mut parents: Query<&Parent>,
+    mut globals: Query<&GlobalTransform>,
+) {
+    let pending = std::mem::take(&mut *spawner.pending.write());
+    let mut ready = Vec::new();
+    let mut waiting = Vec::new();
+    for (root, handle) in pending {
+        if scenes.contains(&handle) {
+            // roots that were given a parent have their global transform propagated, the others are where they are
+            let global = parents
+                .get(root)
+                .and_then(|_| globals.get(root).map(|global| global.matrix()));
+            let root_matrix = global
+                .or_else(|| transforms.get(root).map(|transform| transform.matrix()))
+                .unwrap_or(Mat4::IDENTITY);
+            ready.push((root, root_matrix, handle));
+        } else if let LoadState::Failed(error) = load_status.load_state(&handle) {
+            log::error!("Failed to spawn scene: {}", error);
+        } else {
+            waiting.push((root, handle));
+        }
+    }
+    spawner.pending.write().extend(waiting);
+
+    // the spawned entities have transforms too
+    drop((transforms, parents, globals));
+    for (root, root_matrix, handle) in ready {
+        if let Some(scene) = scenes.get(&handle) {
+            spawn_scene(&commands, root, root_matrix, &scene);
+        }
+    }
+}
+
+/// The entities spawned for a scene's nodes.
+struct SpawnedNodes {
+    /// The entity of every node, or `None` for nodes that aren't part of the scene.
+    entities: Vec<Option<Entity>>,
+    /// The node index and entities of every mesh that's skinned.
+    skinned_meshes: Vec<(usize, Vec<Entity>)>,
+}
+
+fn spawn_scene(commands: &Commands, root: Entity, root_matrix: Mat4, scene: &GltfScene) {
+    let mut spawned = SpawnedNodes {
+        entities: vec![None; scene.nodes.len()],
+        skinned_meshes: Vec::new(),
+    };
+    let children = scene
+        .roots
+        .iter()
+        .map(|&node| spawn_node(commands, scene, node, root, root_matrix, &mut spawned))
+        .collect();
+    commands.insert_component(root, Children(children));
+
+    // skins are added once all nodes are spawned, since their joints can be anywhere in the hierarchy
+    for (index, mesh_entities) in spawned.skinned_meshes {
+        let Some(skin) = scene.nodes[index].skin.map(|skin| &scene.skins[skin]) else {
+            continue;
+        };
+        let Some(joints) = skin
+            .joints
+            .iter()
+            .map(|&joint| spawned.entities[joint])
+            .collect::<Option<Vec<_>>>()
+        else {
+            log::warn!(
+                "Skipping skin of node {:?}, whose joints aren't all part of the scene",
+                scene.nodes[index].name
+            );
+            continue;
+        };
+        for entity in mesh_entities {
+            commands.insert_component(
+                entity,
+                Skin {
+                    joints: joints.clone(),
+                    inverse_bind_matrices: skin.inverse_bind_matrices.clone(),
+                },
+            );
+        }
+    }
+
+    if !scene.animations.is_empty() {
+        commands.insert_component(root, AnimationPlayer::new(spawned.entities));
+    }
+}
+
+fn spawn_node(
+    commands: &Commands,
+    scene: &GltfScene,
+    index: usize,
+    parent: Entity,
+    parent_matrix: Mat4,
+    spawned: &mut SpawnedNodes,
+) -> Entity {
+    let node = &scene.nodes[index];
+    let global = GlobalTransform(parent_matrix * node.transform.matrix());
+    let entity = commands.spawn((node.transform, global, Parent(parent)));
+    spawned.entities[index] = Some(entity);
+    if let Some(name) = node.name.as_ref() {
+        commands.insert_component(entity, Name::new(name));
+    }
+    if let Some(weights) = node.weights.as_ref() {
+        commands.insert_component(entity, MorphWeights(weights.clone()));
+    }
+
+    let mut children = Vec::new();
+    if let Some(mesh) = node.mesh {
+        match scene.meshes[mesh].as_slice() {
+            [primitive] => {
+                commands.insert_component(entity, primitive.mesh.clone());
+                commands.insert_component(entity, primitive.material.clone());
+            }
+            primitives => {
+                for primitive in primitives {
+                    children.push(commands.spawn((
+                        Transform::default(),
+                        global,
+                        Parent(entity),
+                        primitive.mesh.clone(),
+                        primitive.material.clone(),
+                    )));
+                }
+            }
+        }
+        if node.skin.is_some() {
+            let mesh_entities = if children.is_empty() {
+                vec![entity]
+            } else {
+                children.clone()
+            };
+            spawned.skinned_meshes.push((index, mesh_entities));
+        }
+    }
+    if let Some(light) = node.light {
+        match light.kind {
+            // spot lights are approximated as point lights
+            GltfLightKind::Point | GltfLightKind::Spot { .. } => {
+                commands.insert_component(
+                    entity,
+                    PointLight {
+                        color: light.color,
+                        intensity: light.intensity,
+                        radius: light.range.unwrap_or(DEFAULT_LIGHT_RADIUS),
+                        enabled: true,
+                    },
+                );
+            }
+            GltfLightKind::Directional => {
+                log::warn!(
+                    "Skipping directional light of node {:?}, directional lights aren't supported",
+                    node.name
+                );
+            }
+        }
+    }
+    if node.camera.is_some() {
+        log::warn!(
+            "Skipping camera of node {:?}, cameras aren't spawned from scenes",
+            node.name
+        );
+    }
+
+    children.extend(
+        node.children
+            .iter()
+            .map(|&child| spawn_node(commands, scene, child, entity, global.0, spawned)),
+    );
+    if !children.is_empty() {
+        commands.insert_component(entity, Children(children));
+    }
+    entity
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use weaver_animation::{
+        clip::{AnimationClip, ChannelValue, Interpolation, Keyframes},
+        skin::{JointMatrices, compute_joint_matrices},
+    };
+    use weaver_app::{App, AppStage, settings::AppSettings};
+    use weaver_asset::{AssetApp, AssetPlugin, AssetSettings};
+    use weaver_core::{
+        mesh::{Mesh, MeshAttribute, VertexAttributeValues},
+        prelude::{Quat, Vec3},
+        texture::Texture,
+    };
+    use weaver_task::{task_pool::TaskPool, usages::GlobalTaskPool};
+
+    use super::*;
+    use crate::{
+        assets::scene::{GltfCamera, GltfSceneLoader},
+        material::Material,
+    };
+
+    async fn spawn_fixture(
+        commands: Commands,
+        server: Res<AssetServer>,
+        spawner: Res<SceneSpawner>,
+    ) {
+        let scene = server.load::<GltfScene>("scene.gltf").unwrap();
+        spawner.spawn(
+            &commands,
+            scene,
+            Transform::from_translation(Vec3::new(0.0, 0.0, 10.0)),
+        );
+    }
+
+    async fn spawn_skinned_fixture(
+        commands: Commands,
+        server: Res<AssetServer>,
+        spawner: Res<SceneSpawner>,
+    ) {
+        let scene = server.load::<GltfScene>("skinned.gltf").unwrap();
+        spawner.spawn(
+            &commands,
+            scene,
+            Transform::from_translation(Vec3::new(0.0, 0.0, 10.0)),
+        );
+    }
+
+    /// An app that loads glTF scenes from the fixtures directory and spawns them, without transform propagation.
+    fn fixture_app() -> App {
+        GlobalTaskPool::get_or_init(TaskPool::new);
+
+        let mut settings = AppSettings::new();
+        settings
+            .set_section(&AssetSettings {
+                roots: vec![concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures").into()],
+                watch_for_changes: false,
+                write_meta_files: false,
+                ..Default::default()
+            })
+            .unwrap();
+        let mut app = App::new();
+        app.insert_resource(settings);
+        app.add_plugin(AssetPlugin).unwrap();
+        app.add_asset::<Mesh>();
+        app.add_asset::<Texture>();
+        app.add_asset::<AnimationClip>();
+        app.add_asset::<Material>();
+        app.add_asset::<GltfScene>();
+        app.add_file_asset_loader::<GltfSceneLoader>(&["gltf"]);
+        app.init_resource::<SceneSpawner>();
+        app.add_system(spawn_scenes, AppStage::PreUpdate);
+        app
+    }
+
+    fn update_until_spawned(app: &mut App) {
+        let start = Instant::now();
+        loop {
+            app.update();
+            let world = app.main_app().world();
+            if world.get_resource::<SceneSpawner>().unwrap().pending() == 0 {
+                break;
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "scene was never spawned"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn named_entity(app: &App, name: &str) -> Entity {
+        app.main_app()
+            .world()
+            .query::<(Entity, &Name)>()
+            .iter()
+            .find(|(_, node_name)| node_name.as_str() == name)
+            .map(|(entity, _)| entity)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_spawn_scene() {
+        let mut app = fixture_app();
+        // no transform propagation, so the global transforms must be there from the start
+        app.add_system(spawn_fixture, AppStage::Init);
+        app.init();
+        update_until_spawned(&mut app);
+
+        let world = app.main_app().world();
+        let mut scenes = world.get_resource_mut::<Assets<GltfScene>>().unwrap();
+        let root = world
+            .query::<(Entity, &SceneRoot, &Children)>()
+            .iter()
+            .map(|(entity, scene_root, children)| {
+                let scene = scenes.get(&scene_root.0).unwrap();
+                assert_eq!(scene.nodes.len(), 5);
+                assert_eq!(scene.roots, vec![0]);
+                assert_eq!(scene.meshes[0].len(), 1);
+                assert!(matches!(
+                    scene.nodes[4].camera,
+                    Some(GltfCamera::Perspective { .. })
+                ));
+                assert_eq!(children.0.len(), 1);
+                entity
+            })
+            .next()
+            .unwrap();
+
+        let node = |name: &str| {
+            world
+                .query::<(Entity, &Name, &Parent, &GlobalTransform)>()
+                .iter()
+                .find(|(_, node_name, _, _)| node_name.as_str() == name)
+                .map(|(entity, _, parent, global)| (entity, parent.0, global.to_transform()))
+                .unwrap()
+        };
+        let (scene_root, parent, global) = node("Root");
+        assert_eq!(parent, root);
+        assert_eq!(global.translation, Vec3::new(0.0, 1.0, 10.0));
+
+        let (triangle, parent, global) = node("Triangle");
+        assert_eq!(parent, scene_root);
+        assert_eq!(global.translation, Vec3::new(2.0, 1.0, 10.0));
+        assert_eq!(global.scale, Vec3::splat(2.0));
+        assert!(world.has_component::<Handle<Mesh>>(triangle));
+        assert!(world.has_component::<Handle<Material>>(triangle));
+
+        let (lamp, _, global) = node("Lamp");
+        assert_eq!(global.translation, Vec3::new(0.0, 4.0, 10.0));
+        let light = *world.query::<&PointLight>().get(lamp).unwrap();
+        assert_eq!((light.intensity, light.radius), (20.0, 5.0));
+
+        // directional lights and cameras are skipped
+        let (sun, _, _) = node("Sun");
+        assert!(!world.has_component::<PointLight>(sun));
+        let (camera, _, _) = node("Camera");
+        assert!(!world.has_component::<PointLight>(camera));
+    }
+
+    #[test]
+    fn test_import_skinned_scene() {
+        let mut app = fixture_app();
+        app.add_system(spawn_skinned_fixture, AppStage::Init);
+        app.add_system(compute_joint_matrices, AppStage::Update);
+        app.init();
+        update_until_spawned(&mut app);
+        // the joint matrices are inserted by the update after the skin
+        app.update();
+
+        let world = app.main_app().world();
+        let mut scenes = world.get_resource_mut::<Assets<GltfScene>>().unwrap();
+        let (root, scene) = world
+            .query::<(Entity, &SceneRoot)>()
+            .iter()
+            .map(|(entity, scene_root)| (entity, scene_root.0.clone()))
+            .next()
+            .unwrap();
+        let scene = scenes.get(&scene).unwrap();
+        assert_eq!(scene.roots, vec![0, 1]);
+        assert_eq!(scene.nodes[0].skin, Some(0));
+        assert_eq!(scene.skins[0].joints, vec![1, 2]);
+        assert_eq!(
+            scene.skins[0].inverse_bind_matrices,
+            vec![Mat4::IDENTITY, Mat4::from_translation(-Vec3::Y)]
+        );
+
+        let mut meshes = world.get_resource_mut::<Assets<Mesh>>().unwrap();
+        let mesh = meshes.get(&scene.meshes[0][0].mesh).unwrap();
+        assert_eq!(
+            mesh.attribute(&MeshAttribute::JOINT_INDICES),
+            Some(&VertexAttributeValues::Uint16x4(vec![
+                [0, 0, 0, 0],
+                [0, 1, 0, 0],
+                [1, 0, 0, 0]
+            ]))
+        );
+        assert_eq!(
+            mesh.attribute(&MeshAttribute::JOINT_WEIGHTS),
+            Some(&VertexAttributeValues::Float32x4(vec![
+                [1.0, 0.0, 0.0, 0.0],
+                [0.5, 0.5, 0.0, 0.0],
+                [1.0, 0.0, 0.0, 0.0]
+            ]))
+        );
+
+        let mut clips = world.get_resource_mut::<Assets<AnimationClip>>().unwrap();
+        let clip = clips.get(&scene.animations[0]).unwrap();
+        assert_eq!(clip.name.as_deref(), Some("Bend"));
+        assert_eq!(clip.duration(), 1.0);
+        let [bend, lift] = clip.channels() else {
+            panic!("expected two channels, got {}", clip.channels().len());
+        };
+        assert_eq!(bend.target, 2);
+        assert_eq!(bend.interpolation(), Interpolation::Linear);
+        assert!(matches!(bend.keyframes(), Keyframes::Rotation(values) if values.len() == 2));
+        let ChannelValue::Rotation(rotation) = bend.sample(0.5) else {
+            panic!("expected a rotation");
+        };
+        assert!(rotation.abs_diff_eq(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4), 1e-5));
+        assert_eq!(lift.target, 1);
+        assert_eq!(lift.interpolation(), Interpolation::Step);
+        assert_eq!(lift.sample(0.5), ChannelValue::Translation(Vec3::ZERO));
+
+        let body = named_entity(&app, "Body");
+        let hip = named_entity(&app, "Hip");
+        let knee = named_entity(&app, "Knee");
+        let skin = world.query::<&Skin>().get(body).unwrap().clone();
+        assert_eq!(skin.joints, vec![hip, knee]);
+        assert!(world.has_component::<AnimationPlayer>(root));
+
+        // the knee is bent by a quarter turn from its bind pose, around its own origin
+        let joint_matrices = world.query::<&JointMatrices>().get(body).unwrap().clone();
+        let bent = Mat4::from_translation(Vec3::Y)
+            * Mat4::from_rotation_z(std::f32::consts::FRAC_PI_2)
+            * Mat4::from_translation(-Vec3::Y);
+        assert_eq!(joint_matrices.0.len(), 2);
+        assert!(joint_matrices.0[0].abs_diff_eq(Mat4::IDENTITY, 1e-5));
+        assert!(joint_matrices.0[1].abs_diff_eq(bent, 1e-5));
+    }
+}
@@ -0,0 +1,151 @@
+use weaver_animation::skin::JointMatrices;
+use weaver_app::{App, plugin::Plugin};
+use weaver_core::prelude::Mat4;
+use weaver_ecs::{
+    entity::Entity,
+    prelude::{Commands, Res, ResMut, World},
+    query::Query,
+    system::IntoSystemConfig,
+    world::ConstructFromWorld,
+};
+use weaver_renderer::{
+    RenderStage, WgpuDevice, WgpuQueue,
+    bind_group::{
+        BindGroupLayout, ComponentBindGroupPlugin, ComponentBindGroupStaleness, CreateBindGroup,
+        ResourceBindGroupPlugin,
+    },
+    buffer::GpuBufferVec,
+    extract::Extract,
+    prelude::wgpu,
+};
+use weaver_util::prelude::*;
+
+/// The joint matrices of a skinned mesh on the GPU, extracted from its [`JointMatrices`].
+///
+/// The resource of this type holds a single identity matrix and is bound for meshes without a skin.
+pub struct GpuJointMatrices {
+    buffer: GpuBufferVec<Mat4>,
+}
+
+impl GpuJointMatrices {
+    fn new(matrices: &[Mat4], device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let mut this = Self {
+            buffer: GpuBufferVec::new(wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST),
+        };
+        this.update(matrices, device, queue);
+        this
+    }
+
+    /// Uploads the matrices, returning true if the buffer was recreated.
+    fn update(&mut self, matrices: &[Mat4], device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        self.buffer.clear();
+        for matrix in matrices {
+            self.buffer.push(*matrix);
+        }
+        // storage buffers can't be empty
+        if matrices.is_empty() {
+            self.buffer.push(Mat4::IDENTITY);
+        }
+        self.buffer.enqueue_update(device, queue)
+    }
+}
+
+impl ConstructFromWorld for GpuJointMatrices {
+    fn from_world(world: &World) -> Self {
+        let device = world.get_resource::<WgpuDevice>().unwrap();
+        let queue = world.get_resource::<WgpuQueue>().unwrap();
+        Self::new(&[Mat4::IDENTITY], &device, &queue)
+    }
+}
+
+impl CreateBindGroup for GpuJointMatrices {
+    fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout
+    where
+        Self: Sized,
+    {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Joint Matrices Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        cached_layout: &BindGroupLayout,
+    ) -> wgpu::BindGroup
+    where
+        Self: Sized,
+    {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Joint Matrices Bind Group"),
+            layout: cached_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: self.buffer.binding().unwrap(),
+            }],
+        })
+    }
+}
+
+async fn extract_joint_matrices(
+    commands: Commands,
+    mut main_query: Extract<Query<(Entity, &JointMatrices)>>,
+    mut query: Query<&mut GpuJointMatrices>,
+    mut staleness: ResMut<ComponentBindGroupStaleness>,
+    device: Res<WgpuDevice>,
+    queue: Res<WgpuQueue>,
+) {
+    let mut to_insert = Vec::new();
+    for (entity, joint_matrices) in main_query.iter() {
+        if let Some(mut gpu_joint_matrices) = query.get(entity) {
+            // skins that gain joints outgrow their buffer, which invalidates the bind group
+            if gpu_joint_matrices.update(&joint_matrices.0, &device, &queue) {
+                staleness.set_stale(entity, true);
+            }
+        } else {
+            to_insert.push((
+                entity,
+                GpuJointMatrices::new(&joint_matrices.0, &device, &queue),
+            ));
+        }
+    }
+
+    drop(query);
+
+    for (entity, gpu_joint_matrices) in to_insert {
+        commands.insert_component(entity, gpu_joint_matrices);
+    }
+}
+
+async fn init_default_joint_matrices(commands: Commands) {
+    if !commands.has_resource::<GpuJointMatrices>() {
+        commands.init_resource::<GpuJointMatrices>();
+    }
+}
+
+/// Uploads the [`JointMatrices`] of skinned meshes for the PBR pipeline to deform their vertices with.
+pub struct SkinningPlugin;
+
+impl Plugin for SkinningPlugin {
+    fn build(&self, render_app: &mut App) -> Result<()> {
+        render_app.add_plugin(ComponentBindGroupPlugin::<GpuJointMatrices>::default())?;
+        render_app.add_plugin(ResourceBindGroupPlugin::<GpuJointMatrices>::default())?;
+
+        render_app.add_system(extract_joint_matrices, RenderStage::Extract);
+        render_app.add_system(
+            init_default_joint_matrices.before(extract_joint_matrices),
+            RenderStage::Extract,
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,585 @@
+//! Bakes the image based lighting of a skybox on the CPU from an equirectangular HDR image: the cubemap the skybox
+//! is drawn from, the irradiance as spherical harmonics and a cubemap, the GGX prefiltered specular mips and the
+//! split-sum BRDF lookup table.
+
+use std::f32::consts::PI;
+
+use weaver_core::{
+    prelude::{Vec2, Vec3},
+    texture::{ColorSpace, Texture, TextureDimension, TextureFormat},
+};
+use weaver_util::prelude::*;
+
+use super::{
+    SKYBOX_CUBEMAP_SIZE,
+    irradiance::{SKYBOX_IRRADIANCE_SIZE, SKYBOX_SPECULAR_MIP_LEVELS, SKYBOX_SPECULAR_SIZE},
+};
+
+pub const SKYBOX_BRDF_LUT_SIZE: u32 = 128;
+
+/// The largest cubemap face the spherical harmonics are projected from. Irradiance has no detail a smaller mip of
+/// the cubemap loses.
+const SH_PROJECTION_SIZE: usize = 64;
+
+/// The largest value a half float holds, which brighter texels are clamped to rather than becoming infinite.
+const HALF_MAX: f32 = 65504.0;
+
+/// How much each band of spherical harmonics is scaled by convolving them with the clamped cosine lobe.
+const SH_BAND_CONVOLUTION: [f32; 9] = [
+    PI,
+    2.0 * PI / 3.0,
+    2.0 * PI / 3.0,
+    2.0 * PI / 3.0,
+    PI / 4.0,
+    PI / 4.0,
+    PI / 4.0,
+    PI / 4.0,
+    PI / 4.0,
+];
+
+#[derive(Clone, Debug)]
+pub struct IblBakeSettings {
+    /// The size of the faces of the cubemap the skybox is drawn from.
+    pub cubemap_size: u32,
+    /// The size of the faces of the irradiance cubemap.
+    pub irradiance_size: u32,
+    /// The size of the faces of the largest specular mip.
+    pub specular_size: u32,
+    /// The number of specular mips, going from a roughness of 0 to 1.
+    pub specular_mip_levels: u32,
+    /// The number of GGX samples each specular texel is filtered with.
+    pub specular_sample_count: u32,
+    /// The size of the BRDF lookup table.
+    pub brdf_lut_size: u32,
+    /// The number of GGX samples each texel of the BRDF lookup table is integrated with.
+    pub brdf_lut_sample_count: u32,
+}
+
+impl Default for IblBakeSettings {
+    fn default() -> Self {
+        Self {
+            cubemap_size: SKYBOX_CUBEMAP_SIZE,
+            irradiance_size: SKYBOX_IRRADIANCE_SIZE,
+            specular_size: SKYBOX_SPECULAR_SIZE,
+            specular_mip_levels: SKYBOX_SPECULAR_MIP_LEVELS,
+            specular_sample_count: 256,
+            brdf_lut_size: SKYBOX_BRDF_LUT_SIZE,
+            brdf_lut_sample_count: 512,
+        }
+    }
+}
+
+/// The image based lighting of a skybox. The cubemaps are linear `Rgba16Float` textures, and the BRDF lookup table
+/// is a linear `Rg16Float` texture with NdotV along its width and roughness along its height.
+pub struct BakedIbl {
+    pub cubemap: Texture,
+    /// The first three bands of spherical harmonics of the radiance a white Lambertian surface reflects.
+    pub irradiance_sh: [Vec3; 9],
+    pub diffuse: Texture,
+    pub specular: Texture,
+    pub brdf_lut: Texture,
+}
+
+impl BakedIbl {
+    /// The radiance a white Lambertian surface facing `normal` reflects, from the spherical harmonics.
+    pub fn irradiance(&self, normal: Vec3) -> Vec3 {
+        evaluate_sh(&self.irradiance_sh, normal.normalize())
+    }
+}
+
+/// Bakes the image based lighting of an equirectangular image, which is usually a Radiance HDR or OpenEXR file.
+pub fn bake_ibl(equirect: &Texture, settings: &IblBakeSettings) -> Result<BakedIbl> {
+    ensure!(
+        settings.cubemap_size > 0
+            && settings.irradiance_size > 0
+            && settings.specular_size > 0
+            && settings.brdf_lut_size > 0,
+        "IBL textures must have a size"
+    );
+    ensure!(
+        (1..=settings.specular_size.ilog2() + 1).contains(&settings.specular_mip_levels),
+        "Specular cubemap of {} can't have {} mip levels",
+        settings.specular_size,
+        settings.specular_mip_levels
+    );
+    ensure!(
+        settings.specular_sample_count > 0 && settings.brdf_lut_sample_count > 0,
+        "IBL textures must be baked with samples"
+    );
+
+    let equirect = Equirect::new(equirect)?;
+    let cubemap = bake_cube(settings.cubemap_size as usize, |direction| {
+        equirect.sample(direction)
+    });
+    let cubemap_mips = mip_chain(cubemap);
+
+    let projected = cubemap_mips
+        .iter()
+        .find(|level| level.size <= SH_PROJECTION_SIZE)
+        .unwrap_or(&cubemap_mips[cubemap_mips.len() - 1]);
+    let irradiance_sh = project_sh(projected);
+    let diffuse = bake_cube(settings.irradiance_size as usize, |direction| {
+        evaluate_sh(&irradiance_sh, direction).max(Vec3::ZERO)
+    });
+
+    let specular = prefilter_specular(&cubemap_mips, settings);
+
+    Ok(BakedIbl {
+        cubemap: cube_texture(&cubemap_mips[..1])?,
+        irradiance_sh,
+        diffuse: cube_texture(&[diffuse])?,
+        specular: cube_texture(&specular)?,
+        brdf_lut: bake_brdf_lut(settings.brdf_lut_size, settings.brdf_lut_sample_count)?,
+    })
+}
+
+/// The texels of an equirectangular image, with up at the top row.
+struct Equirect {
+    width: usize,
+    height: usize,
+    texels: Vec<Vec3>,
+}
+
+impl Equirect {
+    fn new(texture: &Texture) -> Result<Self> {
+        let image = texture
+            .to_linear(TextureFormat::Rgba32Float)?
+            .to_image(0, 0)
+            .ok_or_else(|| anyhow!("Equirectangular image has no texels"))?
+            .into_rgba32f();
+        Ok(Self {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            texels: image
+                .pixels()
+                .map(|texel| Vec3::new(texel[0], texel[1], texel[2]))
+                .collect(),
+        })
+    }
+
+    /// Bilinearly samples the image in a direction, wrapping around horizontally.
+    fn sample(&self, direction: Vec3) -> Vec3 {
+        let u = direction.z.atan2(direction.x) / (2.0 * PI) + 0.5;
+        let v = 0.5 - direction.y.clamp(-1.0, 1.0).asin() / PI;
+        let x = u * self.width as f32 - 0.5;
+        let y = (v * self.height as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let x0 = (x0 as i64).rem_euclid(self.width as i64) as usize;
+        let x1 = (x0 + 1) % self.width;
+        let y0 = y0 as usize;
+        let y1 = (y0 + 1).min(self.height - 1);
+        let texel = |x: usize, y: usize| self.texels[y * self.width + x];
+
+        let top = texel(x0, y0).lerp(texel(x1, y0), fx);
+        let bottom = texel(x0, y1).lerp(texel(x1, y1), fx);
+        top.lerp(bottom, fy)
+    }
+}
+
+/// A mip level of a cubemap, with the faces in the +X, -X, +Y, -Y, +Z, -Z order of the GPU and their rows top to
+/// bottom.
+struct CubeLevel {
+    size: usize,
+    texels: Vec<Vec3>,
+}
+
+impl CubeLevel {
+    /// Bilinearly samples the face a direction points at, clamping to its edges.
+    fn sample(&self, direction: Vec3) -> Vec3 {
+        let (face, u, v) = direction_face(direction);
+        let max = (self.size - 1) as f32;
+        let x = ((u + 1.0) * 0.5 * self.size as f32 - 0.5).clamp(0.0, max);
+        let y = ((v + 1.0) * 0.5 * self.size as f32 - 0.5).clamp(0.0, max);
+
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+        let (x1, y1) = ((x0 + 1).min(self.size - 1), (y0 + 1).min(self.size - 1));
+        let texel = |x: usize, y: usize| self.texels[(face * self.size + y) * self.size + x];
+
+        let top = texel(x0, y0).lerp(texel(x1, y0), fx);
+        let bottom = texel(x0, y1).lerp(texel(x1, y1), fx);
+        top.lerp(bottom, fy)
+    }
+
+    /// Averages each 2x2 block of texels of every face into the next mip level.
+    fn downsample(&self) -> CubeLevel {
+        let size = (self.size / 2).max(1);
+        let last = self.size - 1;
+        let texel = |face: usize, x: usize, y: usize| {
+            self.texels[(face * self.size + y.min(last)) * self.size + x.min(last)]
+        };
+        let mut texels = Vec::with_capacity(6 * size * size);
+        for face in 0..6 {
+            for y in 0..size {
+                for x in 0..size {
+                    let sum = texel(face, 2 * x, 2 * y)
+                        + texel(face, 2 * x + 1, 2 * y)
+                        + texel(face, 2 * x, 2 * y + 1)
+                        + texel(face, 2 * x + 1, 2 * y + 1);
+                    texels.push(sum / 4.0);
+                }
+            }
+        }
+        CubeLevel { size, texels }
+    }
+}
+
+/// The mip levels of a cubemap, down to faces of a single texel.
+fn mip_chain(level: CubeLevel) -> Vec<CubeLevel> {
+    let mut levels = vec![level];
+    while levels[levels.len() - 1].size > 1 {
+        let next = levels[levels.len() - 1].downsample();
+        levels.push(next);
+    }
+    levels
+}
+
+/// Trilinearly samples the mip levels of a cubemap, `lod` levels down from the largest.
+fn sample_lod(levels: &[CubeLevel], direction: Vec3, lod: f32) -> Vec3 {
+    let lod = lod.clamp(0.0, (levels.len() - 1) as f32);
+    let lower = lod.floor() as usize;
+    let upper = (lower + 1).min(levels.len() - 1);
+    let color = levels[lower].sample(direction);
+    match upper == lower {
+        true => color,
+        false => color.lerp(levels[upper].sample(direction), lod - lower as f32),
+    }
+}
+
+/// The direction a point of a face points at, with `u` and `v` going from -1 to 1 left to right and top to bottom.
+fn face_direction(face: usize, u: f32, v: f32) -> Vec3 {
+    match face {
+        0 => Vec3::new(1.0, -v, -u),
+        1 => Vec3::new(-1.0, -v, u),
+        2 => Vec3::new(u, 1.0, v),
+        3 => Vec3::new(u, -1.0, -v),
+        4 => Vec3::new(u, -v, 1.0),
+        _ => Vec3::new(-u, -v, -1.0),
+    }
+}
+
+/// The face a direction points at and where on it, the inverse of [`face_direction`].
+fn direction_face(direction: Vec3) -> (usize, f32, f32) {
+    let Vec3 { x, y, z } = direction;
+    let abs = direction.abs();
+    if abs.x >= abs.y && abs.x >= abs.z {
+        match x > 0.0 {
+            true => (0, -z / abs.x, -y / abs.x),
+            false => (1, z / abs.x, -y / abs.x),
+        }
+    } else if abs.y >= abs.z {
+        match y > 0.0 {
+            true => (2, x / abs.y, z / abs.y),
+            false => (3, x / abs.y, -z / abs.y),
+        }
+    } else {
+        match z > 0.0 {
+            true => (4, x / abs.z, -y / abs.z),
+            false => (5, -x / abs.z, -y / abs.z),
+        }
+    }
+}
+
+/// The face coordinates of the center of a texel of a face of `size`.
+fn texel_uv(x: usize, y: usize, size: usize) -> (f32, f32) {
+    (
+        2.0 * (x as f32 + 0.5) / size as f32 - 1.0,
+        2.0 * (y as f32 + 0.5) / size as f32 - 1.0,
+    )
+}
+
+/// Computes every texel of a cubemap of `size` from the direction it points at, baking each face on its own thread.
+fn bake_cube(size: usize, texel: impl Fn(Vec3) -> Vec3 + Sync) -> CubeLevel {
+    let mut texels = vec![Vec3::ZERO; 6 * size * size];
+    std::thread::scope(|scope| {
+        for (face, face_texels) in texels.chunks_exact_mut(size * size).enumerate() {
+            let texel = &texel;
+            scope.spawn(move || {
+                for (index, value) in face_texels.iter_mut().enumerate() {
+                    let (u, v) = texel_uv(index % size, index / size, size);
+                    *value = texel(face_direction(face, u, v).normalize());
+                }
+            });
+        }
+    });
+    CubeLevel { size, texels }
+}
+
+/// Makes an `Rgba16Float` cube texture of mip levels, largest first.
+fn cube_texture(levels: &[CubeLevel]) -> Result<Texture> {
+    let texels = levels
+        .iter()
+        .map(|level| {
+            level
+                .texels
+                .iter()
+                .flat_map(|texel| {
+                    let texel = texel.min(Vec3::splat(HALF_MAX));
+                    [texel.x, texel.y, texel.z, 1.0]
+                })
+                .flat_map(f32::to_le_bytes)
+                .collect()
+        })
+        .collect();
+    let size = levels[0].size as u32;
+    Texture::from_levels(
+        TextureFormat::Rgba32Float,
+        TextureDimension::Cube,
+        size,
+        size,
+        6,
+        texels,
+    )?
+    .with_color_space(ColorSpace::Linear)
+    .convert(TextureFormat::Rgba16Float)
+}
+
+/// The real spherical harmonics of the first three bands in a direction.
+fn sh_basis(direction: Vec3) -> [f32; 9] {
+    let Vec3 { x, y, z } = direction;
+    [
+        0.282_095,
+        0.488_603 * y,
+        0.488_603 * z,
+        0.488_603 * x,
+        1.092_548 * x * y,
+        1.092_548 * y * z,
+        0.315_392 * (3.0 * z * z - 1.0),
+        1.092_548 * x * z,
+        0.546_274 * (x * x - y * y),
+    ]
+}
+
+fn evaluate_sh(sh: &[Vec3; 9], direction: Vec3) -> Vec3 {
+    sh.iter()
+        .zip(sh_basis(direction))
+        .map(|(coefficient, basis)| coefficient * basis)
+        .sum()
+}
+
+/// Projects the radiance of a cubemap onto spherical harmonics and convolves them with the cosine lobe, scaled by
+/// 1/π so that they give the radiance a white Lambertian surface reflects.
+fn project_sh(level: &CubeLevel) -> [Vec3; 9] {
+    let mut sh = [Vec3::ZERO; 9];
+    let texel_area = (2.0 / level.size as f32).powi(2);
+    for (index, radiance) in level.texels.iter().enumerate() {
+        let face = index / (level.size * level.size);
+        let (u, v) = texel_uv(
+            index % level.size,
+            index / level.size % level.size,
+            level.size,
+        );
+        // texels toward the edges of a face cover less of the sphere
+        let solid_angle = texel_area / (1.0 + u * u + v * v).powf(1.5);
+        let basis = sh_basis(face_direction(face, u, v).normalize());
+        for (coefficient, basis) in sh.iter_mut().zip(basis) {
+            *coefficient += radiance * basis * solid_angle;
+        }
+    }
+    for (coefficient, convolution) in sh.iter_mut().zip(SH_BAND_CONVOLUTION) {
+        *coefficient *= convolution / PI;
+    }
+    sh
+}
+
+/// The `index`th of `count` points of the Hammersley sequence, which spreads samples evenly over the unit square.
+fn hammersley(index: u32, count: u32) -> Vec2 {
+    Vec2::new(
+        index as f32 / count as f32,
+        index.reverse_bits() as f32 / 4_294_967_296.0,
+    )
+}
+
+/// Maps a point of the unit square to a half vector around +Z, distributed like the GGX normal distribution.
+fn importance_sample_ggx(point: Vec2, alpha: f32) -> Vec3 {
+    let phi = 2.0 * PI * point.x;
+    let cos_theta = ((1.0 - point.y) / (1.0 + (alpha * alpha - 1.0) * point.y)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+fn ggx_distribution(n_dot_h: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * denominator * denominator)
+}
+
+/// Prefilters the cubemap with the GGX distribution of a roughness going from 0 at the largest mip to 1 at the
+/// smallest, assuming the view and normal are the reflected direction.
+fn prefilter_specular(cubemap_mips: &[CubeLevel], settings: &IblBakeSettings) -> Vec<CubeLevel> {
+    let source_size = cubemap_mips[0].size as f32;
+    let texel_solid_angle = 4.0 * PI / (6.0 * source_size * source_size);
+    let sample_count = settings.specular_sample_count;
+
+    (0..settings.specular_mip_levels)
+        .map(|mip| {
+            let size = (settings.specular_size >> mip).max(1) as usize;
+            if mip == 0 {
+                let lod = (source_size / size as f32).log2().max(0.0);
+                return bake_cube(size, |direction| sample_lod(cubemap_mips, direction, lod));
+            }
+
+            let roughness = mip as f32 / (settings.specular_mip_levels - 1) as f32;
+            let alpha = roughness * roughness;
+            bake_cube(size, |normal| {
+                let (tangent, bitangent) = normal.any_orthonormal_pair();
+                let mut color = Vec3::ZERO;
+                let mut weight = 0.0;
+                for index in 0..sample_count {
+                    let half = importance_sample_ggx(hammersley(index, sample_count), alpha);
+                    let half = tangent * half.x + bitangent * half.y + normal * half.z;
+                    let light = 2.0 * normal.dot(half) * half - normal;
+                    let n_dot_l = normal.dot(light);
+                    if n_dot_l <= 0.0 {
+                        continue;
+                    }
+
+                    // samples the mip whose texels cover about as much of the sphere as the sample does, to avoid
+                    // the aliasing of bright spots
+                    let pdf = ggx_distribution(normal.dot(half), alpha) / 4.0;
+                    let sample_solid_angle = 1.0 / (sample_count as f32 * pdf).max(f32::EPSILON);
+                    let lod = 0.5 * (sample_solid_angle / texel_solid_angle).log2() + 1.0;
+                    color += sample_lod(cubemap_mips, light, lod) * n_dot_l;
+                    weight += n_dot_l;
+                }
+                match weight > 0.0 {
+                    true => color / weight,
+                    false => cubemap_mips[0].sample(normal),
+                }
+            })
+        })
+        .collect()
+}
+
+/// The scale and bias applied to F0 by the split-sum approximation of the specular BRDF.
+fn integrate_brdf(n_dot_v: f32, roughness: f32, sample_count: u32) -> Vec2 {
+    let view = Vec3::new((1.0 - n_dot_v * n_dot_v).sqrt(), 0.0, n_dot_v);
+    let alpha = roughness * roughness;
+    // the Schlick approximation of Smith's geometry term, with the k of image based lighting
+    let k = alpha / 2.0;
+    let geometry = |n_dot_x: f32| n_dot_x / (n_dot_x * (1.0 - k) + k);
+
+    let mut integral = Vec2::ZERO;
+    for index in 0..sample_count {
+        let half = importance_sample_ggx(hammersley(index, sample_count), alpha);
+        let light = 2.0 * view.dot(half) * half - view;
+        let n_dot_l = light.z;
+        if n_dot_l <= 0.0 {
+            continue;
+        }
+
+        let v_dot_h = view.dot(half).max(0.0);
+        let visibility =
+            geometry(n_dot_v) * geometry(n_dot_l) * v_dot_h / (half.z * n_dot_v).max(f32::EPSILON);
+        let fresnel = (1.0 - v_dot_h).powi(5);
+        integral += Vec2::new(1.0 - fresnel, fresnel) * visibility;
+    }
+    integral / sample_count as f32
+}
+
+/// Bakes the BRDF lookup table, with NdotV going from 0 to 1 left to right and roughness top to bottom.
+fn bake_brdf_lut(size: u32, sample_count: u32) -> Result<Texture> {
+    let texels = (0..size * size)
+        .flat_map(|index| {
+            let n_dot_v = ((index % size) as f32 + 0.5) / size as f32;
+            let roughness = ((index / size) as f32 + 0.5) / size as f32;
+            integrate_brdf(n_dot_v, roughness, sample_count).to_array()
+        })
+        .flat_map(f32::to_le_bytes)
+        .collect();
+    Texture::from_levels(
+        TextureFormat::Rg32Float,
+        TextureDimension::D2,
+        size,
+        size,
+        1,
+        vec![texels],
+    )?
+    .convert(TextureFormat::Rg16Float)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cube_directions() {
+        for face in 0..6 {
+            for (u, v) in [(0.0, 0.0), (0.5, -0.25), (-0.9, 0.7)] {
+                let (found, found_u, found_v) = direction_face(face_direction(face, u, v) * 3.0);
+                assert_eq!(found, face);
+                assert!((found_u - u).abs() < 1e-6 && (found_v - v).abs() < 1e-6);
+            }
+        }
+        // +Y is the top of the side faces
+        assert_eq!(direction_face(Vec3::new(1.0, 0.5, 0.0)), (0, 0.0, -0.5));
+    }
+
+    #[test]
+    fn test_bake_constant_environment() {
+        let radiance = [0.5, 1.0, 2.0, 1.0];
+        let equirect = Texture::from_levels(
+            TextureFormat::Rgba32Float,
+            TextureDimension::D2,
+            16,
+            8,
+            1,
+            vec![
+                radiance
+                    .repeat(16 * 8)
+                    .into_iter()
+                    .flat_map(f32::to_le_bytes)
+                    .collect(),
+            ],
+        )
+        .unwrap();
+        let settings = IblBakeSettings {
+            cubemap_size: 16,
+            irradiance_size: 4,
+            specular_size: 8,
+            specular_mip_levels: 3,
+            specular_sample_count: 16,
+            brdf_lut_size: 4,
+            brdf_lut_sample_count: 16,
+        };
+        let baked = bake_ibl(&equirect, &settings).unwrap();
+
+        // a white surface under a uniform sky reflects the radiance of the sky in every direction
+        let expected = Vec3::new(0.5, 1.0, 2.0);
+        for normal in [Vec3::X, Vec3::NEG_Y, Vec3::new(1.0, 2.0, -3.0)] {
+            assert!(baked.irradiance(normal).abs_diff_eq(expected, 0.02));
+        }
+
+        assert_eq!(baked.cubemap.dimension(), TextureDimension::Cube);
+        assert_eq!(baked.cubemap.level_dimensions(0), (16, 16, 6));
+        assert_eq!(baked.diffuse.level_dimensions(0), (4, 4, 6));
+        assert_eq!(baked.specular.format(), TextureFormat::Rgba16Float);
+        assert_eq!(baked.specular.mip_level_count(), 3);
+        for texture in [&baked.cubemap, &baked.diffuse, &baked.specular] {
+            for level in 0..texture.mip_level_count() as usize {
+                let image = texture.to_image(level, 3).unwrap().into_rgba32f();
+                for texel in image.pixels() {
+                    assert!(Vec3::new(texel[0], texel[1], texel[2]).abs_diff_eq(expected, 0.02));
+                }
+            }
+        }
+        assert_eq!(baked.brdf_lut.format(), TextureFormat::Rg16Float);
+    }
+
+    #[test]
+    fn test_brdf_lut() {
+        // smooth surfaces seen head on reflect F0, and the scale and bias never add up to more than all the light
+        let smooth = integrate_brdf(0.99, 0.01, 64);
+        assert!((smooth.x - 1.0).abs() < 0.05 && smooth.y < 0.05);
+        for roughness in [0.1, 0.5, 0.9] {
+            for n_dot_v in [0.1, 0.5, 0.9] {
+                let brdf = integrate_brdf(n_dot_v, roughness, 256);
+                assert!(brdf.x >= 0.0 && brdf.y >= 0.0 && brdf.x + brdf.y <= 1.01);
+            }
+        }
+        // rough surfaces reflect less than smooth ones
+        assert!(integrate_brdf(0.5, 0.9, 256).x < integrate_brdf(0.5, 0.1, 256).x);
+    }
+}
@@ -1,11 +1,11 @@
 use std::{
-    fs::File,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, OnceLock},
 };
 
-use image::{ImageDecoder, codecs::hdr::HdrDecoder};
+use bake::{BakedIbl, IblBakeSettings, bake_ibl};
 use weaver_app::plugin::Plugin;
+use weaver_core::texture::{Texture, TextureSettings};
 use weaver_ecs::{
     component::Res,
     prelude::{Commands, ResMut, World},
@@ -23,100 +23,164 @@ use weaver_renderer::{
     extract::{ExtractResource, ExtractResourcePlugin},
     hdr::HdrRenderTarget,
     pipeline::{
-        ComputePipelineCache, ComputePipelinePlugin, CreateComputePipeline, CreateRenderPipeline,
-        RenderPipeline, RenderPipelineCache, RenderPipelineLayout, RenderPipelinePlugin,
+        CreateRenderPipeline, RenderPipeline, RenderPipelineCache, RenderPipelineLayout,
+        RenderPipelinePlugin,
     },
-    prelude::{ComputePipeline, ComputePipelineLayout, wgpu},
+    prelude::wgpu,
     resources::ActiveCommandEncoder,
-    shader::Shader,
+    shader::{Shader, ShaderDir},
     texture::{GpuTexture, texture_format},
 };
 use weaver_util::prelude::*;
 
+pub mod bake;
 pub mod irradiance;
 
 pub const SKYBOX_CUBEMAP_SIZE: u32 = 1024;
 
+/// A skybox drawn from an equirectangular HDR image, lit by the textures baked from it next to the image.
 #[derive(Clone)]
 pub struct Skybox {
     pub path: PathBuf,
+    pub cubemap_path: PathBuf,
     pub diffuse_path: PathBuf,
     pub specular_path: PathBuf,
     pub brdf_lut_path: PathBuf,
+    /// The settings the textures are baked with when any of them are missing.
+    pub bake_settings: IblBakeSettings,
+    /// Shared by the clones of the skybox, so that the skybox and its lighting are read or baked only once.
+    textures: Arc<OnceLock<SkyboxTextures>>,
+}
+
+/// The baked textures of a [`Skybox`].
+pub struct SkyboxTextures {
+    pub cubemap: Texture,
+    pub diffuse: Texture,
+    pub specular: Texture,
+    pub brdf_lut: Texture,
+}
+
+impl From<BakedIbl> for SkyboxTextures {
+    fn from(baked: BakedIbl) -> Self {
+        Self {
+            cubemap: baked.cubemap,
+            diffuse: baked.diffuse,
+            specular: baked.specular,
+            brdf_lut: baked.brdf_lut,
+        }
+    }
 }
 
 impl Skybox {
     pub fn new(path: impl AsRef<Path>) -> Self {
         let path = path.as_ref().to_path_buf();
         let file_stem = path.file_stem().unwrap().to_str().unwrap().to_owned();
-        let diffuse_path = path
-            .with_file_name(file_stem.clone() + "_diffuse")
-            .with_extension("ktx2");
-        let specular_path = path
-            .with_file_name(file_stem.clone() + "_specular")
-            .with_extension("ktx2");
-        let brdf_lut_path = path
-            .with_file_name(file_stem.clone() + "_LUT")
-            .with_extension("png");
+        let baked_path = |suffix: &str| {
+            path.with_file_name(file_stem.clone() + suffix)
+                .with_extension("ktx2")
+        };
 
         Self {
+            cubemap_path: baked_path("_cubemap"),
+            diffuse_path: baked_path("_diffuse"),
+            specular_path: baked_path("_specular"),
+            brdf_lut_path: baked_path("_LUT"),
             path,
-            diffuse_path,
-            specular_path,
-            brdf_lut_path,
+            bake_settings: IblBakeSettings::default(),
+            textures: Arc::default(),
         }
     }
-}
 
-impl ExtractResource for Skybox {
-    type Source = Skybox;
+    pub fn with_bake_settings(mut self, bake_settings: IblBakeSettings) -> Self {
+        self.bake_settings = bake_settings;
+        self
+    }
 
-    fn extract_render_resource(source: &Self::Source) -> Self {
-        source.clone()
+    fn baked_paths(&self) -> [&PathBuf; 4] {
+        [
+            &self.cubemap_path,
+            &self.diffuse_path,
+            &self.specular_path,
+            &self.brdf_lut_path,
+        ]
     }
-}
 
-pub(crate) struct GpuSkyboxSrc {
-    src_texture: GpuTexture,
-}
+    /// Bakes the cubemap, irradiance, specular and BRDF lookup table textures from the image, overwriting them.
+    pub fn bake(&self) -> Result<BakedIbl> {
+        let baked = self.bake_in_memory()?;
+        self.write_baked(&baked)?;
+        Ok(baked)
+    }
 
-impl CreateBindGroup for GpuSkyboxSrc {
-    fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout
-    where
-        Self: Sized,
-    {
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Skybox Bind Group Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                    multisampled: false,
-                    view_dimension: wgpu::TextureViewDimension::D2,
-                },
-                count: None,
-            }],
-        })
+    fn bake_in_memory(&self) -> Result<BakedIbl> {
+        log::info!("Baking image based lighting for {}", self.path.display());
+        let bytes = std::fs::read(&self.path)
+            .map_err(|err| anyhow!("Failed to read skybox {}: {err}", self.path.display()))?;
+        let equirect = Texture::decode(&bytes, &TextureSettings::default())?;
+        bake_ibl(&equirect, &self.bake_settings)
     }
 
-    fn create_bind_group(&self, device: &wgpu::Device, layout: &BindGroupLayout) -> wgpu::BindGroup
-    where
-        Self: Sized,
-    {
-        device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Skybox Bind Group"),
-            layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::TextureView(&self.src_texture.view),
-            }],
+    /// Writes each texture to a temporary file before renaming it, so that an interrupted bake doesn't leave a
+    /// truncated texture behind that would be read as baked.
+    fn write_baked(&self, baked: &BakedIbl) -> Result<()> {
+        for (path, texture) in self.baked_paths().into_iter().zip([
+            &baked.cubemap,
+            &baked.diffuse,
+            &baked.specular,
+            &baked.brdf_lut,
+        ]) {
+            let temp_path = path.with_extension("ktx2.tmp");
+            std::fs::write(&temp_path, texture.encode_ktx2(true)?)
+                .and_then(|()| std::fs::rename(&temp_path, path))
+                .map_err(|err| {
+                    let _ = std::fs::remove_file(&temp_path);
+                    anyhow!("Failed to write {}: {err}", path.display())
+                })?;
+        }
+        Ok(())
+    }
+
+    fn read_baked(&self) -> Result<SkyboxTextures> {
+        let read = |path: &PathBuf| {
+            let bytes = std::fs::read(path)
+                .map_err(|err| anyhow!("Failed to read {}: {err}", path.display()))?;
+            Texture::decode(&bytes, &TextureSettings::default())
+        };
+        Ok(SkyboxTextures {
+            cubemap: read(&self.cubemap_path)?,
+            diffuse: read(&self.diffuse_path)?,
+            specular: read(&self.specular_path)?,
+            brdf_lut: read(&self.brdf_lut_path)?,
         })
     }
+
+    /// The baked textures, read from their files or baked from the image if any of them are missing, so that
+    /// skyboxes can ship without the image. Textures that can't be saved, e.g. because the directory is read only, are
+    /// still used but baked again next time.
+    pub fn textures(&self) -> Result<&SkyboxTextures> {
+        if let Some(textures) = self.textures.get() {
+            return Ok(textures);
+        }
+        let textures = match self.baked_paths().iter().all(|path| path.exists()) {
+            true => self.read_baked()?,
+            false => {
+                let baked = self.bake_in_memory()?;
+                if let Err(err) = self.write_baked(&baked) {
+                    log::warn!("Failed to save the baked lighting of the skybox: {err}");
+                }
+                baked.into()
+            }
+        };
+        Ok(self.textures.get_or_init(|| textures))
+    }
 }
 
-pub(crate) struct GpuSkyboxDst {
-    dst_texture: GpuTexture,
+impl ExtractResource for Skybox {
+    type Source = Skybox;
+
+    fn extract_render_resource(source: &Self::Source) -> Self {
+        source.clone()
+    }
 }
 
 #[derive(Clone)]
@@ -132,141 +196,21 @@ impl ConstructFromWorld for GpuSkybox {
         let device = world.get_resource::<WgpuDevice>().unwrap();
         let queue = world.get_resource::<WgpuQueue>().unwrap();
         let skybox = world.get_resource::<Skybox>().unwrap();
-        let mut pipeline_cache = world.get_resource_mut::<ComputePipelineCache>().unwrap();
-        let mut bind_group_layout_cache = world.get_resource_mut::<BindGroupLayoutCache>().unwrap();
-
-        Self::new(
-            &skybox,
-            &device,
-            &queue,
-            &mut pipeline_cache,
-            &mut bind_group_layout_cache,
-        )
+
+        Self::new(&skybox, &device, &queue).unwrap()
     }
 }
 
 impl GpuSkybox {
-    pub fn new(
-        skybox: &Skybox,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        pipeline_cache: &mut ComputePipelineCache,
-        bind_group_layout_cache: &mut BindGroupLayoutCache,
-    ) -> Self {
-        let file = File::open(&skybox.path).unwrap();
-
-        let hdr_decoder = HdrDecoder::new(file).unwrap();
-        let meta = hdr_decoder.metadata();
-        // let mut pixels = vec![[0.0, 0.0, 0.0, 0.0]; (meta.width * meta.height) as usize];
-        let mut pixels_buf = vec![0; hdr_decoder.total_bytes() as usize];
-        hdr_decoder.read_image(&mut pixels_buf).unwrap();
-        let mut pixels = vec![[0.0, 0.0, 0.0, 0.0]; (meta.width * meta.height) as usize];
-        for (i, chunk) in pixels_buf.chunks_exact(3 * size_of::<f32>()).enumerate() {
-            let rgb = bytemuck::cast_slice::<u8, f32>(chunk);
-            pixels[i] = [rgb[0], rgb[1], rgb[2], 1.0];
-        }
-
-        let src = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Skybox Source Texture"),
-            size: wgpu::Extent3d {
-                width: meta.width,
-                height: meta.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: texture_format::HDR_CUBE_FORMAT,
-            usage: wgpu::TextureUsages::COPY_DST
-                | wgpu::TextureUsages::COPY_SRC
-                | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        });
-
-        let src_view = src.create_view(&wgpu::TextureViewDescriptor::default());
-        queue.write_texture(
-            src.as_image_copy(),
-            bytemuck::cast_slice(&pixels),
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(meta.width * std::mem::size_of::<[f32; 4]>() as u32),
-                rows_per_image: Some(meta.height),
-            },
-            wgpu::Extent3d {
-                width: meta.width,
-                height: meta.height,
-                depth_or_array_layers: 1,
-            },
-        );
-
-        let dst = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Skybox Destination Texture"),
-            size: wgpu::Extent3d {
-                width: SKYBOX_CUBEMAP_SIZE,
-                height: SKYBOX_CUBEMAP_SIZE,
-                depth_or_array_layers: 6,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: texture_format::HDR_FORMAT,
-            usage: wgpu::TextureUsages::STORAGE_BINDING
-                | wgpu::TextureUsages::TEXTURE_BINDING
-                | wgpu::TextureUsages::COPY_SRC
-                | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
-
-        let dst_view = dst.create_view(&wgpu::TextureViewDescriptor {
-            dimension: Some(wgpu::TextureViewDimension::D2Array),
-            array_layer_count: Some(6),
-            ..Default::default()
-        });
-
-        let skybox_src = GpuSkyboxSrc {
-            src_texture: GpuTexture {
-                texture: Arc::new(src),
-                view: Arc::new(src_view),
-            },
-        };
-
-        let src_bind_group = skybox_src.create_bind_group(
-            device,
-            &BindGroupLayout::get_or_create::<GpuSkyboxSrc>(device, bind_group_layout_cache),
-        );
-
-        let dst = GpuSkyboxDst {
-            dst_texture: GpuTexture {
-                texture: Arc::new(dst),
-                view: Arc::new(dst_view),
-            },
-        };
-
-        let dst_bind_group = dst.create_bind_group(
-            device,
-            &BindGroupLayout::get_or_create::<GpuSkyboxDst>(device, bind_group_layout_cache),
-        );
-
-        let pipeline =
-            pipeline_cache.get_or_create_pipeline::<GpuSkybox>(device, bind_group_layout_cache);
-
-        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Skybox Load Encoder"),
-        });
-        {
-            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Skybox Load Pass"),
-                timestamp_writes: None,
-            });
-            let num_workgroups = SKYBOX_CUBEMAP_SIZE.div_ceil(16);
-            cpass.set_pipeline(&pipeline);
-            cpass.set_bind_group(0, &src_bind_group, &[]);
-            cpass.set_bind_group(1, &dst_bind_group, &[]);
-            cpass.dispatch_workgroups(num_workgroups, num_workgroups, 6);
-        }
-        queue.submit(Some(encoder.finish()));
-
-        let GpuSkyboxDst { dst_texture } = dst;
+    /// Uploads the baked cubemap of a skybox, baking it first if it's missing.
+    pub fn new(skybox: &Skybox, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Self> {
+        let cubemap = &skybox.textures()?.cubemap;
+        let texture = GpuTexture::from_image(device, queue, cubemap).ok_or_else(|| {
+            anyhow!(
+                "Failed to upload skybox cubemap {}",
+                skybox.cubemap_path.display()
+            )
+        })?;
 
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("Skybox Sampler"),
@@ -279,96 +223,10 @@ impl GpuSkybox {
             ..Default::default()
         });
 
-        let cube_view = dst_texture
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor {
-                dimension: Some(wgpu::TextureViewDimension::Cube),
-                array_layer_count: Some(6),
-                ..Default::default()
-            });
-
-        Self {
-            texture: dst_texture,
-            cube_view: Arc::new(cube_view),
+        Ok(Self {
+            cube_view: texture.view.clone(),
+            texture,
             sampler: Arc::new(sampler),
-        }
-    }
-}
-
-impl CreateComputePipeline for GpuSkybox {
-    fn create_compute_pipeline_layout(
-        device: &wgpu::Device,
-        bind_group_layout_cache: &mut BindGroupLayoutCache,
-    ) -> ComputePipelineLayout
-    where
-        Self: Sized,
-    {
-        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Skybox Pipeline Layout"),
-            bind_group_layouts: &[
-                Some(&bind_group_layout_cache.get_or_create::<GpuSkyboxSrc>(device)),
-                Some(&bind_group_layout_cache.get_or_create::<GpuSkyboxDst>(device)),
-            ],
-            ..Default::default()
-        });
-
-        ComputePipelineLayout::new(layout)
-    }
-
-    fn create_compute_pipeline(
-        device: &wgpu::Device,
-        cached_layout: &wgpu::PipelineLayout,
-    ) -> ComputePipeline
-    where
-        Self: Sized,
-    {
-        let module = Shader::new(Path::new("assets/shaders/skybox_loader.wgsl"))
-            .create_shader_module(device);
-
-        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Skybox Compute Pipeline"),
-            layout: Some(cached_layout),
-            module: &module,
-            entry_point: Some("load"),
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        });
-
-        ComputePipeline::new(pipeline)
-    }
-}
-
-impl CreateBindGroup for GpuSkyboxDst {
-    fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout
-    where
-        Self: Sized,
-    {
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Skybox Bind Group Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::StorageTexture {
-                    access: wgpu::StorageTextureAccess::WriteOnly,
-                    format: texture_format::HDR_FORMAT,
-                    view_dimension: wgpu::TextureViewDimension::D2Array,
-                },
-                count: None,
-            }],
-        })
-    }
-
-    fn create_bind_group(&self, device: &wgpu::Device, layout: &BindGroupLayout) -> wgpu::BindGroup
-    where
-        Self: Sized,
-    {
-        device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Skybox Aux Bind Group"),
-            layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::TextureView(&self.dst_texture.view),
-            }],
         })
     }
 }
@@ -455,12 +313,13 @@ impl CreateRenderPipeline for SkyboxRenderable {
 
     fn create_render_pipeline(
         device: &wgpu::Device,
+        shader_dir: &ShaderDir,
         cached_layout: &wgpu::PipelineLayout,
     ) -> RenderPipeline
     where
         Self: Sized,
     {
-        let module = Shader::new(Path::new("assets/shaders/sky.wgsl")).create_shader_module(device);
+        let module = Shader::new(shader_dir, "sky.wgsl").create_shader_module(device);
 
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Skybox Render Pipeline"),
@@ -549,7 +408,6 @@ pub struct SkyboxPlugin;
 impl Plugin for SkyboxPlugin {
     fn build(&self, render_app: &mut weaver_app::App) -> Result<()> {
         render_app.add_plugin(ExtractResourcePlugin::<Skybox>::default())?;
-        render_app.add_plugin(ComputePipelinePlugin::<GpuSkybox>::default())?;
         render_app.add_plugin(ResourceBindGroupPlugin::<GpuSkybox>::default())?;
 
         render_app.add_system(init_gpu_skybox, RenderStage::InitRenderResources);
@@ -575,3 +433,75 @@ impl Plugin for SkyboxRenderablePlugin {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skybox(baked_dir: &Path) -> Skybox {
+        let mut skybox = Skybox::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../assets/skyboxes/clear_sky.hdr"
+        ))
+        .with_bake_settings(IblBakeSettings {
+            cubemap_size: 16,
+            irradiance_size: 4,
+            specular_size: 8,
+            specular_mip_levels: 2,
+            specular_sample_count: 4,
+            brdf_lut_size: 4,
+            brdf_lut_sample_count: 4,
+        });
+        for path in [
+            &mut skybox.cubemap_path,
+            &mut skybox.diffuse_path,
+            &mut skybox.specular_path,
+            &mut skybox.brdf_lut_path,
+        ] {
+            *path = baked_dir.join(path.file_name().unwrap());
+        }
+        skybox
+    }
+
+    #[test]
+    fn test_bake_missing_textures_once() {
+        let dir = std::env::temp_dir().join(format!("weaver-skybox-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // the textures are baked once for all clones of the skybox, and saved without leaving temporary files behind
+        let baked = skybox(&dir);
+        let textures = baked.textures().unwrap();
+        assert!(std::ptr::eq(textures, baked.clone().textures().unwrap()));
+        assert_eq!(textures.cubemap.level_dimensions(0), (16, 16, 6));
+        let mut files = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                "clear_sky_LUT.ktx2",
+                "clear_sky_cubemap.ktx2",
+                "clear_sky_diffuse.ktx2",
+                "clear_sky_specular.ktx2"
+            ]
+        );
+
+        // saved textures are read back as they were baked
+        let read = skybox(&dir);
+        assert_eq!(
+            read.textures().unwrap().specular.levels(),
+            textures.specular.levels()
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // textures that can't be saved are still used
+        let unwritable = skybox(&dir.join("missing"));
+        assert_eq!(
+            unwritable.textures().unwrap().diffuse.levels(),
+            textures.diffuse.levels()
+        );
+        assert!(!dir.exists());
+    }
+}
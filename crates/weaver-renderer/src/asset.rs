@@ -1,15 +1,18 @@
 use weaver_app::{App, plugin::Plugin};
-use weaver_asset::{Asset, AssetApp, Assets, Handle, UntypedHandle};
+use weaver_asset::{Asset, AssetApp, AssetEvent, AssetId, Assets, Handle, UntypedHandle};
 use weaver_ecs::{
     commands::Commands,
     component::{Res, ResMut},
     entity::Entity,
     query::Query,
-    system::{SystemParam, SystemParamItem, SystemParamWrapper},
+    system::{IntoSystemConfig, SystemParam, SystemParamItem, SystemParamWrapper},
 };
+use weaver_event::{EventRx, prelude::StreamExt};
 use weaver_util::prelude::*;
 
-use crate::{RenderStage, WgpuDevice, WgpuQueue, extract::Extract};
+use crate::{
+    RenderStage, WgpuDevice, WgpuQueue, bind_group::AssetBindGroupStaleness, extract::Extract,
+};
 
 pub trait RenderAsset: Asset {
     type Source: Asset;
@@ -33,11 +36,29 @@ pub trait RenderAsset: Asset {
     ) -> Result<()>
     where
         Self: Sized;
+
+    /// Called instead of [`update_render_asset`](RenderAsset::update_render_asset) when the source asset was replaced,
+    /// e.g. reloaded from disk. Render assets whose update doesn't pick up every change of their source, like the
+    /// geometry of a mesh, refresh their GPU resources here.
+    fn reload_render_asset(
+        &mut self,
+        source: &Self::Source,
+        param: &mut SystemParamItem<Self::Param>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<()>
+    where
+        Self: Sized,
+    {
+        self.update_render_asset(source, param, device, queue)
+    }
 }
 
 #[derive(Default)]
 pub struct ExtractedRenderAssets {
     assets: Lock<FxHashMap<UntypedHandle, UntypedHandle>>,
+    /// Version of each source asset at the time it was extracted, see [`Assets::version`].
+    versions: Lock<FxHashMap<UntypedHandle, u64>>,
 }
 
 impl ExtractedRenderAssets {
@@ -49,10 +70,28 @@ impl ExtractedRenderAssets {
         self.assets.write().insert(handle, render_handle);
     }
 
+    pub fn version(&self, handle: &UntypedHandle) -> u64 {
+        self.versions
+            .read()
+            .get(handle)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_version(&self, handle: UntypedHandle, version: u64) {
+        self.versions.write().insert(handle, version);
+    }
+
     pub fn contains(&self, handle: &UntypedHandle) -> bool {
         self.assets.read().contains_key(handle)
     }
 
+    /// Forgets an extracted asset, returning the handle of its render asset.
+    pub fn remove(&self, handle: &UntypedHandle) -> Option<UntypedHandle> {
+        self.versions.write().remove(handle);
+        self.assets.write().remove(handle)
+    }
+
     pub fn read(&'_ self) -> Read<'_, FxHashMap<UntypedHandle, UntypedHandle>> {
         self.assets.read()
     }
@@ -74,6 +113,10 @@ impl<T: RenderAsset> Plugin for ExtractRenderAssetPlugin<T> {
     fn build(&self, render_app: &mut App) -> Result<()> {
         render_app.add_asset::<T>();
         render_app.add_system(extract_render_asset::<T>, RenderStage::Extract);
+        render_app.add_system(
+            remove_render_assets::<T>.before(extract_render_asset::<T>),
+            RenderStage::Extract,
+        );
         Ok(())
     }
 }
@@ -86,42 +129,69 @@ async fn extract_render_asset<T: RenderAsset>(
     mut query: Extract<Query<(Entity, &Handle<T::Source>)>>,
     extracted_assets: Res<ExtractedRenderAssets>,
     mut render_assets: ResMut<Assets<T>>,
+    mut bind_group_staleness: ResMut<AssetBindGroupStaleness>,
     device: Res<WgpuDevice>,
     queue: Res<WgpuQueue>,
 ) {
     // let mut query = query.query();
     // query for handles to the base asset
     for (entity, handle) in query.iter() {
-        if !extracted_assets.contains(&handle.into_untyped()) {
+        let untyped_handle = handle.untyped();
+        // weak handles may outlive their asset
+        let Some(base_asset) = main_world_assets.get(&handle) else {
+            continue;
+        };
+
+        if !extracted_assets.contains(&untyped_handle) {
             // if the asset has not been extracted yet, extract it
-            let base_asset = main_world_assets.get(*handle).unwrap();
             if let Some(render_asset) =
                 T::extract_render_asset(&base_asset, param.item_mut(), &device, &queue)
             {
                 log::trace!("Extracted render asset: {:?}", T::type_name());
 
                 // insert the render asset into the asset storage
-                let render_handle = render_assets.insert(render_asset);
+                // (the handle is weak, the render asset is removed along with its source asset)
+                let render_handle = render_assets.insert_manual(render_asset, AssetId::new());
 
-                let untyped_handle = handle.into_untyped();
+                // mark the original asset as extracted
+                extracted_assets.insert(untyped_handle, render_handle.untyped());
+                extracted_assets.set_version(untyped_handle, main_world_assets.version(&handle));
 
                 // insert the render asset handle into the entity
                 commands.insert_component(entity, render_handle);
-
-                // mark the original asset as extracted
-                extracted_assets.insert(untyped_handle, render_handle.into_untyped());
             } else {
                 log::error!("Failed to extract render asset: {:?}", T::type_name());
             }
         } else {
             // if the asset has already been extracted, insert the render asset handle into the entity
-            let extracted_assets = extracted_assets.read();
-            let render_handle = extracted_assets.get(&handle.into_untyped()).unwrap();
-            let render_handle = Handle::<T>::try_from(*render_handle).unwrap();
+            let render_handle = *extracted_assets.read().get(&untyped_handle).unwrap();
+            let render_handle = Handle::<T>::try_from(render_handle).unwrap();
+
+            let mut render_asset = render_assets.get_mut(&render_handle).unwrap();
+
+            // the source asset was replaced (e.g. reloaded from disk), so the render asset is refreshed in place
+            let version = main_world_assets.version(&handle);
+            if version != extracted_assets.version(&untyped_handle) {
+                extracted_assets.set_version(untyped_handle, version);
+                match render_asset.reload_render_asset(
+                    &base_asset,
+                    param.item_mut(),
+                    &device,
+                    &queue,
+                ) {
+                    Ok(()) => {
+                        log::debug!("Reloaded render asset: {:?}", T::type_name());
+                        bind_group_staleness.set_stale(render_handle.id(), true);
+                    }
+                    Err(e) => {
+                        log::error!("Failed to reload render asset {:?}: {}", T::type_name(), e)
+                    }
+                }
+                commands.insert_component(entity, render_handle);
+                continue;
+            }
 
             // update the asset
-            let base_asset = main_world_assets.get(*handle).unwrap();
-            let mut render_asset = render_assets.get_mut(render_handle).unwrap();
             render_asset
                 .update_render_asset(&base_asset, param.item_mut(), &device, &queue)
                 .unwrap();
@@ -130,3 +200,22 @@ async fn extract_render_asset<T: RenderAsset>(
         }
     }
 }
+
+/// Removes the render assets whose source assets were removed from the main world.
+async fn remove_render_assets<T: RenderAsset>(
+    mut events: Extract<EventRx<AssetEvent<T::Source>>>,
+    extracted_assets: Res<ExtractedRenderAssets>,
+    mut render_assets: ResMut<Assets<T>>,
+) {
+    while let Some(event) = events.next().await {
+        let AssetEvent::Removed(handle) = event else {
+            continue;
+        };
+        let Some(render_handle) = extracted_assets.remove(&handle.untyped()) else {
+            continue;
+        };
+        let render_handle = Handle::<T>::try_from(render_handle).unwrap();
+        render_assets.remove(&render_handle);
+        log::trace!("Removed render asset: {:?}", T::type_name());
+    }
+}
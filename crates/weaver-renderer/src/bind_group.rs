@@ -8,6 +8,7 @@ use weaver_ecs::{
     entity::{Entity, EntityMap},
     prelude::Component,
     query::{Query, With},
+    system::IntoSystemConfig,
 };
 use weaver_util::prelude::*;
 
@@ -291,6 +292,10 @@ impl<T: CreateBindGroup + RenderAsset> Plugin for AssetBindGroupPlugin<T> {
     fn build(&self, app: &mut App) -> Result<()> {
         app.add_asset::<BindGroup<T>>();
         app.add_system(create_asset_bind_group::<T>, RenderStage::ExtractBindGroup);
+        app.add_system(
+            remove_unused_asset_bind_groups::<T>.before(create_asset_bind_group::<T>),
+            RenderStage::ExtractBindGroup,
+        );
         Ok(())
     }
 }
@@ -315,10 +320,14 @@ async fn create_asset_bind_group<T: CreateBindGroup + RenderAsset>(
         if staleness.is_stale(handle.id()) {
             to_remove.push(entity);
             // commands.remove_component::<Handle<BindGroup<T>>>(entity);
-            asset_bind_groups
+            if let Some(bind_group_handle) = asset_bind_groups
                 .bind_groups
                 .write()
-                .remove(&handle.into_untyped());
+                .remove(&handle.untyped())
+            {
+                bind_group_assets
+                    .remove(&Handle::<BindGroup<T>>::try_from(bind_group_handle).unwrap());
+            }
             stale = true;
         }
 
@@ -326,21 +335,22 @@ async fn create_asset_bind_group<T: CreateBindGroup + RenderAsset>(
             continue;
         }
 
-        if let Some(bind_group_handle) = asset_bind_groups
-            .bind_groups
-            .read()
-            .get(&handle.into_untyped())
+        if let Some(bind_group_handle) = asset_bind_groups.bind_groups.read().get(&handle.untyped())
         {
             let bind_group_handle = Handle::<BindGroup<T>>::try_from(*bind_group_handle).unwrap();
             to_add.push((entity, bind_group_handle));
             // commands.insert_component(entity, bind_group_handle);
         } else {
-            let asset = assets.get(*handle).unwrap();
+            // the render asset may have been removed along with its source asset
+            let Some(asset) = assets.get(&handle) else {
+                continue;
+            };
             staleness.set_stale(handle.id(), false);
             let bind_group = BindGroup::new(&device, &*asset, &mut layout_cache);
             log::trace!("Created bind group for asset: {:?}", T::type_name());
-            let bind_group_handle = bind_group_assets.insert(bind_group);
-            asset_bind_groups.insert(handle.into_untyped(), bind_group_handle.into_untyped());
+            // the handle is weak, the bind group is removed along with its render asset
+            let bind_group_handle = bind_group_assets.insert_manual(bind_group, AssetId::new());
+            asset_bind_groups.insert(handle.untyped(), bind_group_handle.untyped());
             // commands.insert_component(entity, bind_group_handle);
             to_add.push((entity, bind_group_handle));
         }
@@ -356,3 +366,29 @@ async fn create_asset_bind_group<T: CreateBindGroup + RenderAsset>(
         commands.insert_component(entity, bind_group_handle);
     }
 }
+
+/// Removes the bind groups of render assets that were removed.
+async fn remove_unused_asset_bind_groups<T: CreateBindGroup + RenderAsset>(
+    assets: Res<Assets<T>>,
+    mut bind_group_assets: ResMut<Assets<BindGroup<T>>>,
+    asset_bind_groups: Res<ExtractedAssetBindGroups>,
+    mut staleness: ResMut<AssetBindGroupStaleness>,
+) {
+    asset_bind_groups
+        .bind_groups
+        .write()
+        .retain(|handle, bind_group_handle| {
+            let Ok(handle) = Handle::<T>::try_from(*handle) else {
+                return true;
+            };
+            if assets.contains(&handle) {
+                return true;
+            }
+
+            bind_group_assets
+                .remove(&Handle::<BindGroup<T>>::try_from(*bind_group_handle).unwrap());
+            staleness.0.remove(&handle.id());
+            log::trace!("Removed bind group for asset: {:?}", T::type_name());
+            false
+        });
+}
@@ -1,10 +1,13 @@
-use weaver_app::{App, plugin::Plugin};
+use weaver_app::{
+    App,
+    plugin::{Plugin, PluginId},
+};
 use weaver_core::color::Color;
 use weaver_ecs::{component::Res, prelude::ResMut, query::Query};
 use weaver_util::prelude::*;
 
 use crate::{
-    RenderApp, RenderStage,
+    RenderApp, RenderStage, RendererPlugin,
     camera::ViewTarget,
     extract::{ExtractResource, ExtractResourcePlugin},
     hdr::HdrRenderTarget,
@@ -33,12 +36,14 @@ impl Default for ClearColorPlugin {
 }
 
 impl Plugin for ClearColorPlugin {
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<RendererPlugin>()]
+    }
+
     fn build(&self, app: &mut App) -> Result<()> {
         app.insert_resource(ClearColor(self.0));
-        let render_app = app.get_sub_app_mut::<RenderApp>().unwrap();
-        render_app
-            .add_plugin(ExtractResourcePlugin::<ClearColor>::default())
-            .unwrap();
+        let render_app = app.sub_app_mut::<RenderApp>()?;
+        render_app.add_plugin(ExtractResourcePlugin::<ClearColor>::default())?;
 
         render_app
             .world_mut()
@@ -17,7 +17,7 @@ use crate::{
         RenderPipelinePlugin,
     },
     resources::ActiveCommandEncoder,
-    shader::Shader,
+    shader::{Shader, ShaderDir},
     texture::{GpuTexture, texture_format},
 };
 
@@ -149,12 +149,13 @@ impl CreateRenderPipeline for HdrRenderable {
 
     fn create_render_pipeline(
         device: &wgpu::Device,
+        shader_dir: &ShaderDir,
         cached_layout: &wgpu::PipelineLayout,
     ) -> RenderPipeline
     where
         Self: Sized,
     {
-        let shader = Shader::new("assets/shaders/hdr.wgsl");
+        let shader = Shader::new(shader_dir, "hdr.wgsl");
         let shader_module = shader.create_shader_module(device);
 
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -4,6 +4,7 @@ use std::{
     hash::Hash,
     ops::{Deref, DerefMut},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use asset::ExtractedRenderAssets;
@@ -17,12 +18,19 @@ use hdr::{HdrPlugin, HdrRenderTarget};
 use mesh::MeshPlugin;
 use pipeline::RenderPipelineCache;
 use resources::ActiveCommandEncoder;
+use serde::{Deserialize, Serialize};
+use shader::{ShaderDir, ShaderWatcher};
 use texture::{
     TexturePlugin,
     texture_format::{DEPTH_FORMAT, VIEW_FORMAT},
 };
 use transform::TransformPlugin;
-use weaver_app::{App, AppLabel, SubApp, plugin::Plugin};
+use weaver_app::{
+    App, AppLabel, AppStage, SubApp,
+    plugin::{Plugin, PluginId},
+    settings::SettingsSection,
+};
+use weaver_asset::AssetSettings;
 use weaver_ecs::{
     SystemStage,
     commands::Commands,
@@ -35,7 +43,7 @@ use weaver_ecs::{
 };
 use weaver_event::{EventRx, EventTx, Events, prelude::StreamExt};
 use weaver_util::prelude::*;
-use weaver_winit::{Window, WindowResized, WindowSettings};
+use weaver_winit::{Window, WindowPlugin, WindowResized, WindowSettings};
 
 pub mod asset;
 pub mod bind_group;
@@ -53,15 +61,18 @@ pub mod transform;
 
 pub mod prelude {
     pub use super::{
-        Renderer, RendererPlugin, WgpuDevice, WgpuQueue,
+        PresentMode, RenderPipelining, RenderSettings, RenderTimings, Renderer, RendererPlugin,
+        WgpuDevice, WgpuQueue,
         bind_group::*,
         buffer::{GpuBuffer, GpuBufferVec},
         camera::{Camera, CameraPlugin, PrimaryCamera},
         clear_color::ClearColorPlugin,
         extract::ExtractComponent,
+        mesh::lod::{MeshLod, MeshLodLevel, MeshLodPlugin},
         pipeline::{
             ComputePipeline, ComputePipelineLayout, ComputePipelinePlugin, CreateComputePipeline,
-            CreateRenderPipeline, RenderPipeline, RenderPipelineLayout, RenderPipelinePlugin,
+            CreateRenderPipeline, MeshPipelinePlugin, MeshPipelines, RenderPipeline,
+            RenderPipelineLayout, RenderPipelinePlugin, SpecializeMeshPipeline,
         },
     };
     pub use encase;
@@ -199,7 +210,11 @@ pub struct Renderer {
     command_buffers: Vec<wgpu::CommandBuffer>,
 }
 
-fn create_surface(render_world: &mut World, window: &Window) -> Result<()> {
+fn create_surface(
+    render_world: &mut World,
+    window: &Window,
+    settings: &RenderSettings,
+) -> Result<()> {
     if render_world.has_resource::<WindowSurface>() {
         log::warn!("Surface already created");
         return Ok(());
@@ -228,10 +243,18 @@ fn create_surface(render_world: &mut World, window: &Window) -> Result<()> {
     let required_limits = wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits());
     // required_limits.max_push_constant_size = 256;
 
+    // formats of loaded textures, used when the adapter supports them
+    let texture_features = adapter.features()
+        & (wgpu::Features::TEXTURE_COMPRESSION_BC
+            | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+            | wgpu::Features::TEXTURE_COMPRESSION_ASTC
+            | wgpu::Features::TEXTURE_FORMAT_16BIT_NORM);
+
     let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
         required_features: wgpu::Features::MULTIVIEW
             | wgpu::Features::TEXTURE_BINDING_ARRAY
-            | wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING,
+            | wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING
+            | texture_features,
         required_limits,
         label: None,
         memory_hints: wgpu::MemoryHints::Performance,
@@ -249,8 +272,8 @@ fn create_surface(render_world: &mut World, window: &Window) -> Result<()> {
             format: VIEW_FORMAT,
             width: window.inner_size().width,
             height: window.inner_size().height,
-            present_mode: wgpu::PresentMode::AutoNoVsync,
-            desired_maximum_frame_latency: 1,
+            present_mode: settings.present_mode.into(),
+            desired_maximum_frame_latency: settings.max_frame_latency,
             alpha_mode: caps.alpha_modes[0],
             view_formats: vec![],
         },
@@ -300,9 +323,53 @@ impl Renderer {
 pub struct RenderExtractApp;
 impl AppLabel for RenderExtractApp {}
 
+/// Hands the render app back and forth between the main thread and the render thread when rendering is pipelined.
 pub struct RenderAppChannels {
     pub main_to_render_tx: crossbeam_channel::Sender<SubApp>,
     pub render_to_main_rx: crossbeam_channel::Receiver<SubApp>,
+    pub render_thread: Option<std::thread::JoinHandle<()>>,
+    max_frames_ahead: u32,
+    /// How many updates the main app has run since it last handed the render app to the render thread.
+    frames_ahead: u32,
+}
+
+impl RenderAppChannels {
+    pub fn new(
+        main_to_render_tx: crossbeam_channel::Sender<SubApp>,
+        render_to_main_rx: crossbeam_channel::Receiver<SubApp>,
+        render_thread: Option<std::thread::JoinHandle<()>>,
+        max_frames_ahead: u32,
+    ) -> Self {
+        Self {
+            main_to_render_tx,
+            render_to_main_rx,
+            render_thread,
+            max_frames_ahead,
+            frames_ahead: 0,
+        }
+    }
+
+    /// Takes the render app back from the render thread for the next extraction. Returns `None` without waiting if
+    /// the render thread is still busy and the main app may run another update ahead of it, otherwise waits for it.
+    /// The update that runs ahead isn't extracted, so it's never rendered.
+    fn receive(&mut self) -> Result<Option<SubApp>> {
+        let render_app = if self.frames_ahead < self.max_frames_ahead {
+            match self.render_to_main_rx.try_recv() {
+                Ok(render_app) => render_app,
+                Err(crossbeam_channel::TryRecvError::Empty) => {
+                    self.frames_ahead += 1;
+                    return Ok(None);
+                }
+                Err(crossbeam_channel::TryRecvError::Disconnected) => {
+                    bail!("Render thread has exited")
+                }
+            }
+        } else {
+            self.render_to_main_rx.recv()?
+        };
+        self.frames_ahead = 0;
+        Ok(Some(render_app))
+    }
 }
 
 #[derive(Default)]
@@ -324,15 +391,128 @@ impl DerefMut for MainWorld {
     }
 }
 
-pub struct RendererPlugin;
+/// How the render sub-app is scheduled relative to the main app.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderPipelining {
+    /// The render sub-app runs on its own thread: frame N is rendered while frame N+1 is simulated.
+    /// The main app only waits for the render thread when extracting the next frame, and only once it has run
+    /// [`RenderSettings::max_frames_ahead`] updates since the frame being rendered was extracted.
+    #[default]
+    Pipelined,
+    /// The render sub-app runs on the main thread right after extraction.
+    /// Slower, but deterministic and easier to debug.
+    Synchronous,
+}
+
+/// Presentation mode of the window surface, see [`wgpu::PresentMode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresentMode {
+    AutoVsync,
+    #[default]
+    AutoNoVsync,
+    Fifo,
+    FifoRelaxed,
+    Immediate,
+    Mailbox,
+}
+
+impl From<PresentMode> for wgpu::PresentMode {
+    fn from(mode: PresentMode) -> Self {
+        match mode {
+            PresentMode::AutoVsync => wgpu::PresentMode::AutoVsync,
+            PresentMode::AutoNoVsync => wgpu::PresentMode::AutoNoVsync,
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::FifoRelaxed => wgpu::PresentMode::FifoRelaxed,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+        }
+    }
+}
+
+/// Renderer configuration, available as a resource in both the main and render worlds.
+///
+/// This is the `[render]` section of the app settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderSettings {
+    pub pipelining: RenderPipelining,
+    /// When rendering is pipelined, how many updates the main app may run while the render thread is busy before it
+    /// waits for it. Those updates are skipped rather than queued: they aren't extracted or rendered, and the next
+    /// extraction picks up their latest state. 0 makes every update wait for the previous frame.
+    pub max_frames_ahead: u32,
+    /// How many frames the surface may queue for presentation, see
+    /// [`wgpu::SurfaceConfiguration::desired_maximum_frame_latency`].
+    pub max_frame_latency: u32,
+    pub present_mode: PresentMode,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            pipelining: RenderPipelining::Pipelined,
+            max_frames_ahead: 0,
+            max_frame_latency: 2,
+            present_mode: PresentMode::AutoNoVsync,
+        }
+    }
+}
+
+impl SettingsSection for RenderSettings {
+    const NAME: &'static str = "render";
+}
+
+/// Where the time of the last rendered frame went. Inserted into the main world after every extraction.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderTimings {
+    /// Time the main thread spent waiting for the render thread to finish the previous frame.
+    pub wait: Duration,
+    /// Time spent extracting the main world into the render world.
+    pub extract: Duration,
+    /// Time spent running the render stages of the last finished frame.
+    pub render: Duration,
+    /// How many updates the main app has run ahead of the frame being rendered without extracting them.
+    pub frames_ahead: u32,
+}
+
+/// Duration of the render stages of the last frame, recorded in the render world.
+#[derive(Default)]
+struct RenderFrameTime(Duration);
+
+/// The render sub-app when rendering synchronously (see [`RenderPipelining::Synchronous`]).
+pub struct SynchronousRenderApp(SubApp);
+
+/// Sets up the render sub-app. `settings` are the defaults for the `[render]` settings section.
+#[derive(Default)]
+pub struct RendererPlugin {
+    pub settings: RenderSettings,
+}
 
 impl Plugin for RendererPlugin {
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<WindowPlugin>()]
+    }
+
     fn build(&self, main_app: &mut App) -> Result<()> {
+        let app_settings = main_app.settings();
+        let settings = app_settings.section_or(self.settings)?;
+        let asset_settings = app_settings.section::<AssetSettings>()?;
+        let shader_dir = ShaderDir::new(&asset_settings.shader_dir);
+        let shader_watcher = asset_settings
+            .watch_for_changes
+            .then(|| ShaderWatcher::new(&shader_dir));
+
         main_app.insert_resource(ScratchMainWorld::default());
+        main_app.insert_resource(settings);
+        main_app.init_resource::<RenderTimings>();
 
         let mut render_app = SubApp::new();
 
+        render_app.world().insert_resource(settings);
+        render_app.world().insert_resource(shader_dir);
         render_app.world().init_resource::<CurrentFrame>();
+        render_app.world().init_resource::<RenderFrameTime>();
 
         render_app
             .world_mut()
@@ -367,6 +547,10 @@ impl Plugin for RendererPlugin {
             .world_mut()
             .push_manual_stage(RenderStage::PostRender);
 
+        render_app
+            .world_mut()
+            .push_shutdown_stage(AppStage::Shutdown);
+
         render_app
             .world()
             .insert_resource(RenderPipelineCache::new());
@@ -401,6 +585,20 @@ impl Plugin for RendererPlugin {
             .world_mut()
             .add_system(forward_resized_events, RenderStage::Extract);
 
+        match shader_watcher {
+            Some(Ok(watcher)) => {
+                render_app.world().insert_resource(watcher);
+                render_app
+                    .world_mut()
+                    .add_system(shader::reload_changed_shaders, RenderStage::Extract);
+            }
+            Some(Err(e)) => log::warn!(
+                "Failed to watch shaders, shader reloading is disabled: {}",
+                e
+            ),
+            None => {}
+        }
+
         render_app.set_extract(Box::new(extract::render_extract));
 
         main_app.add_sub_app::<RenderApp>(render_app);
@@ -409,6 +607,8 @@ impl Plugin for RendererPlugin {
         extract_app.set_extract(Box::new(renderer_extract));
         main_app.add_sub_app::<RenderExtractApp>(extract_app);
 
+        main_app.add_system(shutdown_render_app, AppStage::Shutdown);
+
         Ok(())
     }
 
@@ -421,102 +621,232 @@ impl Plugin for RendererPlugin {
     }
 
     fn finish(&self, main_app: &mut App) -> Result<()> {
-        let (main_to_render_tx, main_to_render_rx) = crossbeam_channel::bounded(1);
-        let (render_to_main_tx, render_to_main_rx) = crossbeam_channel::bounded(1);
-
         let window = main_app
             .main_app()
             .world()
             .get_resource::<Window>()
             .unwrap()
             .clone();
-        // let resized_events = main_app
-        //     .main_app()
-        //     .world()
-        //     .get_resource::<Events<WindowResized>>()
-        //     .unwrap();
 
-        // let resized_events = resized_events.clone();
+        let settings = *main_app
+            .main_app()
+            .world()
+            .get_resource::<RenderSettings>()
+            .unwrap();
 
         let mut render_app = main_app.remove_sub_app::<RenderApp>().unwrap();
 
-        // render_app.world().insert_resource(resized_events);
-
         render_app.world().insert_resource(WindowSettings {
             title: window.title(),
             width: window.inner_size().width,
             height: window.inner_size().height,
         });
 
-        create_surface(render_app.world_mut(), &window).unwrap();
+        create_surface(render_app.world_mut(), &window, &settings)?;
 
         render_app.world().insert_resource(window);
 
         render_app.finish_plugins();
         render_app.world_mut().initialize_systems();
 
-        render_to_main_tx.send(render_app).unwrap();
-
-        main_app.insert_resource(RenderAppChannels {
-            main_to_render_tx,
-            render_to_main_rx,
-        });
-
-        std::thread::spawn(move || {
-            log::trace!("Entering render task main loop");
-
-            loop {
-                let Ok(mut render_app) = main_to_render_rx.recv() else {
-                    break;
-                };
-                log::trace!("Received render app on render task");
+        match settings.pipelining {
+            RenderPipelining::Synchronous => {
+                log::debug!("Rendering synchronously on the main thread");
+                main_app.insert_resource(SynchronousRenderApp(render_app));
+            }
+            RenderPipelining::Pipelined => {
+                let (main_to_render_tx, main_to_render_rx) =
+                    crossbeam_channel::bounded::<SubApp>(1);
+                let (render_to_main_tx, render_to_main_rx) = crossbeam_channel::bounded(1);
+
+                render_to_main_tx.send(render_app).unwrap();
+
+                let render_thread = std::thread::Builder::new()
+                    .name("render".to_string())
+                    .spawn(move || {
+                        log::trace!("Entering render task main loop");
+
+                        loop {
+                            let Ok(mut render_app) = main_to_render_rx.recv() else {
+                                break;
+                            };
+                            log::trace!("Received render app on render task");
+
+                            render_frame(&mut render_app);
+
+                            log::trace!("Sending render app back to main task");
+
+                            if let Err(e) = render_to_main_tx.send(render_app) {
+                                // we're probably shutting down
+                                log::debug!("Failed to send render app back to main task: {}", e);
+                                break;
+                            }
+                        }
+
+                        log::trace!("Exiting render task main loop");
+                    })?;
+
+                main_app.insert_resource(RenderAppChannels::new(
+                    main_to_render_tx,
+                    render_to_main_rx,
+                    Some(render_thread),
+                    settings.max_frames_ahead,
+                ));
+            }
+        }
 
-                render_app.finish_plugins();
+        Ok(())
+    }
+}
 
-                log::trace!("Running render app stage: InitRenderResources");
-                render_app
-                    .world_mut()
-                    .run_stage(RenderStage::InitRenderResources)
-                    .unwrap();
-                log::trace!("Running render app stage: PreRender");
-                render_app
-                    .world_mut()
-                    .run_stage(RenderStage::PreRender)
-                    .unwrap();
-                log::trace!("Running render app stage: Render");
-                render_app
-                    .world_mut()
-                    .run_stage(RenderStage::Render)
-                    .unwrap();
-                log::trace!("Running render app stage: PostRender");
-                render_app
-                    .world_mut()
-                    .run_stage(RenderStage::PostRender)
-                    .unwrap();
+/// Runs the render stages of the render sub-app once, recording how long they took.
+fn render_frame(render_app: &mut SubApp) {
+    let start = Instant::now();
+
+    render_app.finish_plugins();
+
+    log::trace!("Running render app stage: InitRenderResources");
+    render_app
+        .world_mut()
+        .run_stage(RenderStage::InitRenderResources)
+        .unwrap();
+    log::trace!("Running render app stage: PreRender");
+    render_app
+        .world_mut()
+        .run_stage(RenderStage::PreRender)
+        .unwrap();
+    log::trace!("Running render app stage: Render");
+    render_app
+        .world_mut()
+        .run_stage(RenderStage::Render)
+        .unwrap();
+    log::trace!("Running render app stage: PostRender");
+    render_app
+        .world_mut()
+        .run_stage(RenderStage::PostRender)
+        .unwrap();
+
+    render_app
+        .world()
+        .insert_resource(RenderFrameTime(start.elapsed()));
+}
 
-                log::trace!("Sending render app back to main task");
+/// Takes the render app back from the render thread, runs its shutdown stages, and joins the render thread.
+async fn shutdown_render_app(commands: Commands) {
+    if let Some(SynchronousRenderApp(mut render_app)) =
+        commands.remove_resource::<SynchronousRenderApp>()
+    {
+        log::debug!("Shutting down render app");
+        if let Err(e) = render_app.world_mut().shutdown() {
+            log::error!("Failed to shut down render app: {}", e);
+        }
+        return;
+    }
 
-                if let Err(e) = render_to_main_tx.send(render_app) {
-                    // we're probably shutting down
-                    log::debug!("Failed to send render app back to main task: {}", e);
-                    break;
-                }
+    let Some(channels) = commands.remove_resource::<RenderAppChannels>() else {
+        return;
+    };
+    let RenderAppChannels {
+        main_to_render_tx,
+        render_to_main_rx,
+        render_thread,
+        ..
+    } = channels;
+
+    match render_to_main_rx.recv() {
+        Ok(mut render_app) => {
+            log::debug!("Shutting down render app");
+            if let Err(e) = render_app.world_mut().shutdown() {
+                log::error!("Failed to shut down render app: {}", e);
             }
+        }
+        Err(e) => log::error!("Failed to receive render app for shutdown: {}", e),
+    }
 
-            log::trace!("Exiting render task main loop");
-        });
+    // closing the channel ends the render thread's main loop
+    drop(main_to_render_tx);
 
-        Ok(())
+    if let Some(render_thread) = render_thread
+        && render_thread.join().is_err()
+    {
+        log::error!("Render thread panicked");
     }
 }
 
 fn renderer_extract(main_world: &mut World, _world: &mut World) -> Result<()> {
-    let channels = main_world.remove_resource::<RenderAppChannels>().unwrap();
-    let mut render_app = channels.render_to_main_rx.recv().unwrap();
+    if let Some(SynchronousRenderApp(mut render_app)) =
+        main_world.remove_resource::<SynchronousRenderApp>()
+    {
+        let extract_start = Instant::now();
+        render_app.extract_from(main_world)?;
+        let extract = extract_start.elapsed();
+
+        render_frame(&mut render_app);
+        let render = render_app
+            .world()
+            .get_resource::<RenderFrameTime>()
+            .unwrap()
+            .0;
+
+        main_world.insert_resource(RenderTimings {
+            wait: Duration::ZERO,
+            extract,
+            render,
+            frames_ahead: 0,
+        });
+        main_world.insert_resource(SynchronousRenderApp(render_app));
+        return Ok(());
+    }
+
+    let mut channels = main_world
+        .remove_resource::<RenderAppChannels>()
+        .ok_or_else(|| anyhow!("Render app channels not found"))?;
+
+    let wait_start = Instant::now();
+    let received = channels.receive();
+    let wait = wait_start.elapsed();
+    let render_app = match received {
+        Ok(render_app) => render_app,
+        Err(e) => {
+            main_world.insert_resource(channels);
+            return Err(e);
+        }
+    };
+    let Some(mut render_app) = render_app else {
+        log::trace!("Render thread is busy, running ahead of it");
+        let render = main_world
+            .get_resource::<RenderTimings>()
+            .map(|timings| timings.render)
+            .unwrap_or_default();
+        main_world.insert_resource(RenderTimings {
+            wait,
+            extract: Duration::ZERO,
+            render,
+            frames_ahead: channels.frames_ahead,
+        });
+        main_world.insert_resource(channels);
+        return Ok(());
+    };
     log::trace!("Received render app on main thread");
-    render_app.extract_from(main_world).unwrap();
+
+    let extract_start = Instant::now();
+    render_app.extract_from(main_world)?;
+    let extract = extract_start.elapsed();
+
+    let render = render_app
+        .world()
+        .get_resource::<RenderFrameTime>()
+        .unwrap()
+        .0;
+    main_world.insert_resource(RenderTimings {
+        wait,
+        extract,
+        render,
+        frames_ahead: 0,
+    });
+
     log::trace!("Sending render app back to render thread");
-    channels.main_to_render_tx.send(render_app).unwrap();
+    channels.main_to_render_tx.send(render_app)?;
     main_world.insert_resource(channels);
 
     Ok(())
@@ -664,6 +994,7 @@ async fn resize_surface(
     device: Res<WgpuDevice>,
     surface: Res<WindowSurface>,
     mut hdr_target: ResMut<HdrRenderTarget>,
+    settings: Res<RenderSettings>,
 ) {
     if let Some(event) = events.next().await {
         let mut has_current_frame = false;
@@ -695,8 +1026,8 @@ async fn resize_surface(
                 format: VIEW_FORMAT,
                 width,
                 height,
-                present_mode: wgpu::PresentMode::AutoNoVsync,
-                desired_maximum_frame_latency: 1,
+                present_mode: settings.present_mode.into(),
+                desired_maximum_frame_latency: settings.max_frame_latency,
                 alpha_mode: wgpu::CompositeAlphaMode::Opaque,
                 view_formats: vec![],
             },
@@ -758,3 +1089,127 @@ async fn resize_surface(
         hdr_target.resize(&device, width, height);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use weaver_task::{task_pool::TaskPool, usages::GlobalTaskPool};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct RenderedFrames(u32);
+
+    async fn count_frame(mut frames: ResMut<RenderedFrames>) {
+        frames.0 += 1;
+    }
+
+    fn test_render_app() -> SubApp {
+        GlobalTaskPool::get_or_init(TaskPool::new);
+
+        let mut render_app = SubApp::new();
+        render_app.world().init_resource::<RenderFrameTime>();
+        render_app.world().init_resource::<RenderedFrames>();
+        for stage in [
+            RenderStage::InitRenderResources,
+            RenderStage::PreRender,
+            RenderStage::Render,
+            RenderStage::PostRender,
+        ] {
+            render_app.world_mut().push_manual_stage(stage);
+        }
+        render_app
+            .world_mut()
+            .add_system(count_frame, RenderStage::Render);
+        render_app.world_mut().initialize_systems();
+        render_app
+    }
+
+    fn rendered_frames(render_app: &SubApp) -> u32 {
+        render_app
+            .world()
+            .get_resource::<RenderedFrames>()
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn test_synchronous_rendering() {
+        let mut main_world = World::new();
+        main_world.insert_resource(SynchronousRenderApp(test_render_app()));
+
+        for _ in 0..3 {
+            renderer_extract(&mut main_world, &mut World::new()).unwrap();
+        }
+
+        // every extraction renders a frame right away on the calling thread
+        let timings = *main_world.get_resource::<RenderTimings>().unwrap();
+        assert_eq!(timings.wait, Duration::ZERO);
+        assert_eq!(timings.frames_ahead, 0);
+        let render_app = main_world
+            .remove_resource::<SynchronousRenderApp>()
+            .unwrap();
+        assert_eq!(rendered_frames(&render_app.0), 3);
+    }
+
+    #[test]
+    fn test_pipelined_frames_ahead() {
+        let (main_to_render_tx, main_to_render_rx) = crossbeam_channel::bounded::<SubApp>(1);
+        let (render_to_main_tx, render_to_main_rx) = crossbeam_channel::bounded(1);
+        let (release_tx, release_rx) = crossbeam_channel::unbounded::<()>();
+        render_to_main_tx.send(test_render_app()).unwrap();
+
+        // a render thread that only finishes a frame when the test releases it
+        let render_thread = std::thread::spawn(move || {
+            while let Ok(mut render_app) = main_to_render_rx.recv() {
+                render_frame(&mut render_app);
+                if release_rx.recv().is_err() || render_to_main_tx.send(render_app).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut main_world = World::new();
+        main_world.insert_resource(RenderAppChannels::new(
+            main_to_render_tx,
+            render_to_main_rx,
+            None,
+            2,
+        ));
+        let frames_ahead = |main_world: &World| {
+            main_world
+                .get_resource::<RenderTimings>()
+                .unwrap()
+                .frames_ahead
+        };
+
+        // the first frame is handed to the render thread, and the next two updates run ahead of it
+        renderer_extract(&mut main_world, &mut World::new()).unwrap();
+        assert_eq!(frames_ahead(&main_world), 0);
+        renderer_extract(&mut main_world, &mut World::new()).unwrap();
+        assert_eq!(frames_ahead(&main_world), 1);
+        renderer_extract(&mut main_world, &mut World::new()).unwrap();
+        assert_eq!(frames_ahead(&main_world), 2);
+
+        // with two updates ahead, the next one waits for the render thread
+        let release = std::thread::spawn({
+            let release_tx = release_tx.clone();
+            move || {
+                std::thread::sleep(Duration::from_millis(50));
+                release_tx.send(()).unwrap();
+            }
+        });
+        renderer_extract(&mut main_world, &mut World::new()).unwrap();
+        assert_eq!(frames_ahead(&main_world), 0);
+        assert!(main_world.get_resource::<RenderTimings>().unwrap().wait > Duration::ZERO);
+        release.join().unwrap();
+
+        // take the render app back and stop the render thread
+        release_tx.send(()).unwrap();
+        let channels = main_world.remove_resource::<RenderAppChannels>().unwrap();
+        let render_app = channels.render_to_main_rx.recv().unwrap();
+        assert_eq!(rendered_frames(&render_app), 2);
+        drop(channels);
+        drop(release_tx);
+        render_thread.join().unwrap();
+    }
+}
@@ -0,0 +1,281 @@
+use weaver_app::{
+    App, AppStage,
+    plugin::{Plugin, PluginId},
+};
+use weaver_asset::{Assets, Handle};
+use weaver_core::{
+    CoreTypesPlugin,
+    geometry::Sphere,
+    hierarchy::{GlobalTransform, propagate_transforms},
+    mesh::Mesh,
+    prelude::Vec2,
+    transform::Transform,
+};
+use weaver_ecs::{
+    commands::Commands, component::Res, entity::Entity, query::Query, system::IntoSystemConfig,
+};
+use weaver_util::prelude::*;
+use weaver_winit::WindowSettings;
+
+use crate::camera::Camera;
+
+/// One version of the mesh of a [`MeshLod`].
+#[derive(Debug, Clone)]
+pub struct MeshLodLevel {
+    pub mesh: Handle<Mesh>,
+    /// The smallest size on screen, in pixels, at which this level is used.
+    pub min_screen_size: f32,
+}
+
+/// Versions of an entity's mesh from most to least detailed, e.g. from [`Mesh::generate_lods`], of which
+/// [`select_mesh_lods`] picks one by how large the entity appears on screen and sets it as the entity's
+/// `Handle<Mesh>`.
+///
+/// The size on screen is the projected diameter of the bounding sphere of the first level's mesh.
+#[derive(Debug, Clone)]
+pub struct MeshLod {
+    pub levels: Vec<MeshLodLevel>,
+}
+
+impl MeshLod {
+    pub fn new(levels: Vec<MeshLodLevel>) -> Self {
+        Self { levels }
+    }
+
+    /// Uses the first mesh at `full_detail_size` pixels and above, and each following mesh below half the size of
+    /// the previous one. That keeps the size of the triangles on screen about the same if every level has a quarter
+    /// of the triangles of the previous one.
+    pub fn from_chain(meshes: Vec<Handle<Mesh>>, full_detail_size: f32) -> Self {
+        let mut min_screen_size = full_detail_size;
+        let levels = meshes
+            .into_iter()
+            .map(|mesh| {
+                let level = MeshLodLevel {
+                    mesh,
+                    min_screen_size,
+                };
+                min_screen_size /= 2.0;
+                level
+            })
+            .collect();
+        Self { levels }
+    }
+
+    /// The first level that may be used at a size on screen, or the least detailed one if it's smaller than all of
+    /// them.
+    pub fn select(&self, screen_size: f32) -> Option<&MeshLodLevel> {
+        self.levels
+            .iter()
+            .find(|level| screen_size >= level.min_screen_size)
+            .or(self.levels.last())
+    }
+}
+
+/// The projected diameter of a sphere on a screen of the given size, in pixels. Spheres that contain the camera are
+/// infinitely large, spheres behind it have no size.
+pub fn screen_space_size(camera: &Camera, sphere: Sphere, screen_size: Vec2) -> f32 {
+    let view_center = camera.view_matrix().transform_point3(sphere.center);
+    if view_center.length() <= sphere.radius {
+        return f32::INFINITY;
+    }
+    // cameras look down their -Z axis
+    if view_center.z >= 0.0 {
+        return 0.0;
+    }
+    let up = camera.view_matrix().inverse().col(1).truncate();
+    let (Some(center), Some(edge)) = (
+        camera.world_to_screen(sphere.center, screen_size),
+        camera.world_to_screen(sphere.center + up * sphere.radius, screen_size),
+    ) else {
+        return 0.0;
+    };
+    center.distance(edge) * 2.0
+}
+
+/// Sets the `Handle<Mesh>` of every entity with a [`MeshLod`] to the level that fits its size on the screen of the
+/// first active [`Camera`]. The screen is as large as the window, whose size is kept in the [`WindowSettings`].
+#[allow(clippy::type_complexity)]
+pub async fn select_mesh_lods(
+    commands: Commands,
+    window: Option<Res<WindowSettings>>,
+    mut cameras: Query<&Camera>,
+    mut lods: Query<(
+        Entity,
+        &MeshLod,
+        &Transform,
+        Option<&GlobalTransform>,
+        Option<&mut Handle<Mesh>>,
+    )>,
+    meshes: Res<Assets<Mesh>>,
+) {
+    let Some(window) = window else {
+        return;
+    };
+    let Some(camera) = cameras
+        .iter()
+        .find(|camera| camera.active())
+        .map(|camera| *camera)
+    else {
+        return;
+    };
+    let screen_size = Vec2::new(window.width as f32, window.height as f32);
+
+    let mut missing = Vec::new();
+    for (entity, lod, transform, global, mesh) in lods.iter() {
+        // the first level is loaded first, and its bounds hold for the others too
+        let Some(first) = lod.levels.first() else {
+            continue;
+        };
+        let Some(aabb) = meshes.peek(&first.mesh).map(|mesh| mesh.aabb) else {
+            continue;
+        };
+        let transform = match global {
+            Some(global) => global.to_transform(),
+            None => *transform,
+        };
+        let sphere = aabb.transformed(transform).bounding_sphere();
+        let Some(level) = lod.select(screen_space_size(&camera, sphere, screen_size)) else {
+            continue;
+        };
+
+        match mesh {
+            Some(mut mesh) => {
+                if *mesh != level.mesh {
+                    *mesh = level.mesh.clone();
+                }
+            }
+            None => missing.push((entity, level.mesh.clone())),
+        }
+    }
+
+    drop((cameras, lods));
+    for (entity, mesh) in missing {
+        commands.insert_component(entity, mesh);
+    }
+}
+
+/// Switches the meshes of entities with a [`MeshLod`] every frame.
+pub struct MeshLodPlugin;
+
+impl Plugin for MeshLodPlugin {
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<CoreTypesPlugin>()]
+    }
+
+    fn build(&self, app: &mut App) -> Result<()> {
+        app.add_system(
+            select_mesh_lods.after(propagate_transforms),
+            AppStage::PostUpdate,
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use weaver_asset::AssetApp;
+    use weaver_core::{
+        prelude::Vec3,
+        primitive::{CubePrimitive, Primitive},
+    };
+    use weaver_task::{task_pool::TaskPool, usages::GlobalTaskPool};
+
+    use super::*;
+
+    #[test]
+    fn test_select_by_screen_size() {
+        let lod = MeshLod::from_chain(
+            (0..3).map(|id| Handle::<Mesh>::from_u128(id + 1)).collect(),
+            400.0,
+        );
+        assert_eq!(lod.select(1000.0).unwrap().mesh, lod.levels[0].mesh);
+        assert_eq!(lod.select(300.0).unwrap().mesh, lod.levels[1].mesh);
+        assert_eq!(lod.select(1.0).unwrap().mesh, lod.levels[2].mesh);
+
+        let camera = Camera::perspective_lookat(
+            Vec3::Z * 10.0,
+            Vec3::ZERO,
+            Vec3::Y,
+            90f32.to_radians(),
+            1.0,
+            0.1,
+            100.0,
+        );
+        let screen = Vec2::splat(1000.0);
+        let sphere = |center: Vec3| Sphere {
+            center,
+            radius: 1.0,
+        };
+        // a sphere of radius 1 at a distance of 10 covers a tenth of a 90 degree field of view
+        let size = screen_space_size(&camera, sphere(Vec3::ZERO), screen);
+        assert!((size - 100.0).abs() < 1.0, "{size}");
+        assert!(screen_space_size(&camera, sphere(Vec3::Z * -40.0), screen) < size);
+        assert_eq!(
+            screen_space_size(&camera, sphere(Vec3::Z * 20.0), screen),
+            0.0
+        );
+        assert_eq!(
+            screen_space_size(&camera, sphere(Vec3::Z * 10.5), screen),
+            f32::INFINITY
+        );
+    }
+
+    #[test]
+    fn test_select_mesh_lods_system() {
+        GlobalTaskPool::get_or_init(TaskPool::new);
+
+        let mut app = App::new();
+        app.add_asset::<Mesh>();
+        app.main_app().world().insert_resource(WindowSettings {
+            title: String::new(),
+            width: 1000,
+            height: 1000,
+        });
+        app.add_system(select_mesh_lods, AppStage::Update);
+        app.init();
+
+        let chain = {
+            let mut meshes = app
+                .main_app()
+                .world()
+                .get_resource_mut::<Assets<Mesh>>()
+                .unwrap();
+            (0..3)
+                .map(|_| meshes.insert(CubePrimitive::new(2.0, false).generate_mesh()))
+                .collect::<Vec<_>>()
+        };
+        let camera = |distance: f32| {
+            Camera::perspective_lookat(
+                Vec3::Z * distance,
+                Vec3::ZERO,
+                Vec3::Y,
+                90f32.to_radians(),
+                1.0,
+                0.1,
+                100.0,
+            )
+        };
+        let world = app.main_app_mut().world_mut();
+        let camera_entity = world.spawn((camera(10.0),));
+        let entity = world.spawn((
+            MeshLod::from_chain(chain.clone(), 400.0),
+            Transform::default(),
+        ));
+
+        // entities without a mesh get one, the cube is about 170 pixels wide at a distance of 10
+        app.update();
+        let world = app.main_app().world();
+        assert_eq!(
+            *world.query::<&Handle<Mesh>>().get(entity).unwrap(),
+            chain[2]
+        );
+
+        *world.query::<&mut Camera>().get(camera_entity).unwrap() = camera(3.0);
+        app.update();
+        let world = app.main_app().world();
+        assert_eq!(
+            *world.query::<&Handle<Mesh>>().get(entity).unwrap(),
+            chain[0]
+        );
+    }
+}
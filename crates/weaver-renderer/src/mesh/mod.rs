@@ -1,15 +1,20 @@
 use crate::asset::{ExtractRenderAssetPlugin, RenderAsset};
 use weaver_app::{App, plugin::Plugin};
 use weaver_asset::prelude::Asset;
-use weaver_core::{geometry::Aabb, mesh::Mesh};
+use weaver_core::{
+    geometry::Aabb,
+    mesh::{Mesh, MeshAttribute, MeshVertexLayout, VertexFormat},
+};
 use weaver_util::prelude::*;
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 
-pub mod primitive;
+pub mod lod;
 
 #[derive(Debug, Asset)]
 pub struct GpuMesh {
     pub aabb: Aabb,
+    /// Where the mesh's attributes are in its interleaved vertex buffer.
+    pub layout: MeshVertexLayout,
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
     pub num_indices: u32,
@@ -28,20 +33,12 @@ impl RenderAsset for GpuMesh {
     where
         Self: Sized,
     {
-        let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(&base_asset.vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-
-        let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("Index Buffer"),
-            contents: bytemuck::cast_slice(&base_asset.indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+        let vertex_buffer = create_vertex_buffer(device, &base_asset.vertex_buffer_data());
+        let index_buffer = create_index_buffer(device, bytemuck::cast_slice(&base_asset.indices));
 
         Some(Self {
             aabb: base_asset.aabb,
+            layout: base_asset.vertex_layout(),
             vertex_buffer,
             index_buffer,
             num_indices: base_asset.indices.len() as u32,
@@ -60,6 +57,124 @@ impl RenderAsset for GpuMesh {
     {
         Ok(())
     }
+
+    fn reload_render_asset(
+        &mut self,
+        base_asset: &Self::Source,
+        _: &mut (),
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let vertices = base_asset.vertex_buffer_data();
+        let indices: &[u8] = bytemuck::cast_slice(&base_asset.indices);
+
+        // buffers are only recreated when the mesh changed size
+        if vertices.len() as u64 == self.vertex_buffer.size() {
+            queue.write_buffer(&self.vertex_buffer, 0, &vertices);
+        } else {
+            self.vertex_buffer = create_vertex_buffer(device, &vertices);
+        }
+        if indices.len() as u64 == self.index_buffer.size() {
+            queue.write_buffer(&self.index_buffer, 0, indices);
+        } else {
+            self.index_buffer = create_index_buffer(device, indices);
+        }
+
+        self.aabb = base_asset.aabb;
+        self.layout = base_asset.vertex_layout();
+        self.num_indices = base_asset.indices.len() as u32;
+        Ok(())
+    }
+}
+
+fn create_vertex_buffer(device: &wgpu::Device, vertices: &[u8]) -> wgpu::Buffer {
+    device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Vertex Buffer"),
+        contents: vertices,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+    })
+}
+
+fn create_index_buffer(device: &wgpu::Device, indices: &[u8]) -> wgpu::Buffer {
+    device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Index Buffer"),
+        contents: indices,
+        usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+    })
+}
+
+pub fn vertex_format(format: VertexFormat) -> wgpu::VertexFormat {
+    match format {
+        VertexFormat::Float32 => wgpu::VertexFormat::Float32,
+        VertexFormat::Float32x2 => wgpu::VertexFormat::Float32x2,
+        VertexFormat::Float32x3 => wgpu::VertexFormat::Float32x3,
+        VertexFormat::Float32x4 => wgpu::VertexFormat::Float32x4,
+        VertexFormat::Uint32 => wgpu::VertexFormat::Uint32,
+        VertexFormat::Uint16x4 => wgpu::VertexFormat::Uint16x4,
+    }
+}
+
+/// The shader def that's set when a mesh has an attribute, e.g. `VERTEX_COLOR` for [`MeshAttribute::COLOR`].
+pub fn shader_def(attribute: &MeshAttribute) -> String {
+    format!("VERTEX_{}", attribute.name.to_uppercase())
+}
+
+/// The attributes of a mesh's vertex buffer that a pipeline reads, and the shader defs that tell its shader which of
+/// the optional ones are there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuVertexLayout {
+    pub stride: u64,
+    pub attributes: Vec<wgpu::VertexAttribute>,
+    pub shader_defs: Vec<String>,
+}
+
+impl GpuVertexLayout {
+    /// Picks attributes out of a mesh's vertex layout, failing if any of `required` is missing. Missing `optional`
+    /// attributes are left out.
+    pub fn new(
+        layout: &MeshVertexLayout,
+        required: &[MeshAttribute],
+        optional: &[MeshAttribute],
+    ) -> Result<Self> {
+        let mut attributes = Vec::new();
+        let mut shader_defs = Vec::new();
+        for attribute in required {
+            let offset = layout
+                .offset(attribute)
+                .ok_or_else(|| anyhow!("Mesh has no {:?} attribute", attribute.name))?;
+            attributes.push(Self::attribute(attribute, offset));
+        }
+        for attribute in optional {
+            if let Some(offset) = layout.offset(attribute) {
+                attributes.push(Self::attribute(attribute, offset));
+                shader_defs.push(shader_def(attribute));
+            }
+        }
+        Ok(Self {
+            stride: layout.stride,
+            attributes,
+            shader_defs,
+        })
+    }
+
+    fn attribute(attribute: &MeshAttribute, offset: u64) -> wgpu::VertexAttribute {
+        wgpu::VertexAttribute {
+            format: vertex_format(attribute.format),
+            offset,
+            shader_location: attribute.location,
+        }
+    }
+
+    pub fn buffer_layout(&self) -> wgpu::VertexBufferLayout<'_> {
+        wgpu::VertexBufferLayout {
+            array_stride: self.stride,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &self.attributes,
+        }
+    }
 }
 
 pub struct MeshPlugin;
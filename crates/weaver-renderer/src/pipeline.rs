@@ -1,10 +1,11 @@
 use std::{any::TypeId, ops::Deref, sync::Arc};
 
-use weaver_app::{plugin::Plugin, App};
+use weaver_app::{App, plugin::Plugin};
+use weaver_core::mesh::MeshVertexLayout;
 use weaver_ecs::component::{Res, ResMut};
 use weaver_util::prelude::*;
 
-use crate::{bind_group::BindGroupLayoutCache, RenderStage, WgpuDevice};
+use crate::{RenderStage, WgpuDevice, bind_group::BindGroupLayoutCache, shader::ShaderDir};
 
 define_atomic_id!(PipelineId);
 
@@ -13,6 +14,8 @@ pub struct RenderPipelineCache {
     layout_cache: FxHashMap<PipelineId, RenderPipelineLayout>,
     pipeline_cache: FxHashMap<PipelineId, RenderPipeline>,
     ids: TypeIdMap<PipelineId>,
+    /// The [shader revision](ShaderDir::revision) that each pipeline was created from.
+    revisions: FxHashMap<PipelineId, u64>,
 }
 
 impl RenderPipelineCache {
@@ -56,22 +59,25 @@ impl RenderPipelineCache {
     pub fn create_for<T>(
         &mut self,
         device: &wgpu::Device,
+        shader_dir: &ShaderDir,
         bind_group_layout_cache: &mut BindGroupLayoutCache,
     ) -> Result<PipelineId>
     where
         T: CreateRenderPipeline,
     {
-        if let Some(id) = self.ids.get(&TypeId::of::<T>()) {
-            return Ok(*id);
-        }
-
-        let id = PipelineId::new();
+        // pipelines created from an older revision of the shaders are created again under the same id
+        let id = match self.ids.get(&TypeId::of::<T>()) {
+            Some(id) if self.revisions.get(id) == Some(&shader_dir.revision()) => return Ok(*id),
+            Some(id) => *id,
+            None => PipelineId::new(),
+        };
 
         let layout = T::create_render_pipeline_layout(device, bind_group_layout_cache);
-        let pipeline = T::create_render_pipeline(device, &layout);
+        let pipeline = T::create_render_pipeline(device, shader_dir, &layout);
         self.layout_cache.insert(id, layout);
         self.pipeline_cache.insert(id, pipeline);
         self.ids.insert(TypeId::of::<T>(), id);
+        self.revisions.insert(id, shader_dir.revision());
 
         Ok(id)
     }
@@ -139,6 +145,7 @@ pub trait CreateRenderPipeline: DowncastSync {
         Self: Sized;
     fn create_render_pipeline(
         device: &wgpu::Device,
+        shader_dir: &ShaderDir,
         cached_layout: &wgpu::PipelineLayout,
     ) -> RenderPipeline
     where
@@ -162,18 +169,125 @@ impl<T: CreateRenderPipeline> Plugin for RenderPipelinePlugin<T> {
 
 async fn extract_render_pipeline<T: CreateRenderPipeline>(
     device: Res<WgpuDevice>,
+    shader_dir: Res<ShaderDir>,
     mut pipeline_cache: ResMut<RenderPipelineCache>,
     mut bind_group_layout_cache: ResMut<BindGroupLayoutCache>,
 ) {
     pipeline_cache
-        .create_for::<T>(&device, &mut bind_group_layout_cache)
+        .create_for::<T>(&device, &shader_dir, &mut bind_group_layout_cache)
         .unwrap();
 }
 
+/// A render pipeline that's created once for every vertex layout of the meshes it draws and every [`Key`] it's drawn
+/// with, sharing the pipeline layout of its [`CreateRenderPipeline`] implementation.
+///
+/// [`Key`]: SpecializeMeshPipeline::Key
+pub trait SpecializeMeshPipeline: CreateRenderPipeline {
+    /// Further state that the pipeline depends on besides the vertex layout, e.g. from the material being drawn.
+    type Key: Clone + Eq + std::hash::Hash + Send + Sync + 'static;
+
+    fn specialize(
+        device: &wgpu::Device,
+        shader_dir: &ShaderDir,
+        cached_layout: &wgpu::PipelineLayout,
+        vertex_layout: &MeshVertexLayout,
+        key: &Self::Key,
+    ) -> Result<RenderPipeline>
+    where
+        Self: Sized;
+}
+
+/// The pipelines of a [`SpecializeMeshPipeline`] for the vertex layouts and keys it has been specialized for.
+pub struct MeshPipelines<T: SpecializeMeshPipeline> {
+    /// `None` for layouts that the pipeline can't draw.
+    pipelines: FxHashMap<T::Key, FxHashMap<MeshVertexLayout, Option<RenderPipeline>>>,
+    /// The [shader revision](ShaderDir::revision) that the pipelines were created from.
+    revision: u64,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T: SpecializeMeshPipeline> Default for MeshPipelines<T> {
+    fn default() -> Self {
+        Self {
+            pipelines: FxHashMap::default(),
+            revision: 0,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T: SpecializeMeshPipeline> MeshPipelines<T> {
+    /// Creates the pipeline for a vertex layout and key unless it exists already. Layouts that the pipeline can't draw
+    /// are logged once per key.
+    pub fn specialize(
+        &mut self,
+        device: &wgpu::Device,
+        shader_dir: &ShaderDir,
+        pipeline_cache: &RenderPipelineCache,
+        vertex_layout: &MeshVertexLayout,
+        key: &T::Key,
+    ) {
+        if self.revision != shader_dir.revision() {
+            self.pipelines.clear();
+            self.revision = shader_dir.revision();
+        }
+        let pipelines = self.pipelines.entry(key.clone()).or_default();
+        if pipelines.contains_key(vertex_layout) {
+            return;
+        }
+        let Some(layout) = pipeline_cache.get_layout_for::<T>() else {
+            return;
+        };
+        let pipeline = match T::specialize(device, shader_dir, layout, vertex_layout, key) {
+            Ok(pipeline) => Some(pipeline),
+            Err(e) => {
+                log::error!(
+                    "Cannot draw meshes with {:?} in {}: {}",
+                    vertex_layout
+                        .attributes
+                        .iter()
+                        .map(|(attribute, _)| &attribute.name)
+                        .collect::<Vec<_>>(),
+                    std::any::type_name::<T>(),
+                    e
+                );
+                None
+            }
+        };
+        pipelines.insert(vertex_layout.clone(), pipeline);
+    }
+
+    pub fn get(&self, vertex_layout: &MeshVertexLayout, key: &T::Key) -> Option<&RenderPipeline> {
+        self.pipelines
+            .get(key)
+            .and_then(|pipelines| pipelines.get(vertex_layout))
+            .and_then(Option::as_ref)
+    }
+}
+
+/// Creates the pipeline layout of a [`SpecializeMeshPipeline`] and the cache of its specialized pipelines.
+pub struct MeshPipelinePlugin<T: SpecializeMeshPipeline>(std::marker::PhantomData<T>);
+
+impl<T: SpecializeMeshPipeline> Default for MeshPipelinePlugin<T> {
+    fn default() -> Self {
+        Self(std::marker::PhantomData)
+    }
+}
+
+impl<T: SpecializeMeshPipeline> Plugin for MeshPipelinePlugin<T> {
+    fn build(&self, render_app: &mut App) -> Result<()> {
+        render_app.add_plugin(RenderPipelinePlugin::<T>::default())?;
+        render_app.init_resource::<MeshPipelines<T>>();
+        Ok(())
+    }
+}
+
 #[derive(Default)]
 pub struct ComputePipelineCache {
     layout_cache: TypeIdMap<ComputePipelineLayout>,
     pipeline_cache: TypeIdMap<ComputePipeline>,
+    /// The [shader revision](ShaderDir::revision) that the pipelines were created from.
+    revision: u64,
 }
 
 impl ComputePipelineCache {
@@ -215,16 +329,21 @@ impl ComputePipelineCache {
     pub fn get_or_create_pipeline<T>(
         &mut self,
         device: &wgpu::Device,
+        shader_dir: &ShaderDir,
         bind_group_layout_cache: &mut BindGroupLayoutCache,
     ) -> ComputePipeline
     where
         T: CreateComputePipeline,
     {
+        if self.revision != shader_dir.revision() {
+            self.pipeline_cache.clear();
+            self.revision = shader_dir.revision();
+        }
         if let Some(cached_pipeline) = self.pipeline_cache.get(&TypeId::of::<T>()) {
             cached_pipeline.clone()
         } else {
             let layout = self.get_or_create_layout::<T>(device, bind_group_layout_cache);
-            let pipeline = T::create_compute_pipeline(device, &layout);
+            let pipeline = T::create_compute_pipeline(device, shader_dir, &layout);
             self.pipeline_cache.insert(TypeId::of::<T>(), pipeline);
             self.pipeline_cache.get(&TypeId::of::<T>()).unwrap().clone()
         }
@@ -277,13 +396,14 @@ impl ComputePipeline {
 
     pub fn get_or_create<T>(
         device: &wgpu::Device,
+        shader_dir: &ShaderDir,
         pipeline_cache: &mut ComputePipelineCache,
         bind_group_layout_cache: &mut BindGroupLayoutCache,
     ) -> Self
     where
         T: CreateComputePipeline,
     {
-        pipeline_cache.get_or_create_pipeline::<T>(device, bind_group_layout_cache)
+        pipeline_cache.get_or_create_pipeline::<T>(device, shader_dir, bind_group_layout_cache)
     }
 }
 
@@ -304,6 +424,7 @@ pub trait CreateComputePipeline: DowncastSync {
         Self: Sized;
     fn create_compute_pipeline(
         device: &wgpu::Device,
+        shader_dir: &ShaderDir,
         cached_layout: &wgpu::PipelineLayout,
     ) -> ComputePipeline
     where
@@ -329,8 +450,9 @@ impl<T: CreateComputePipeline> Plugin for ComputePipelinePlugin<T> {
 
 async fn extract_compute_pipeline<T: CreateComputePipeline>(
     device: Res<WgpuDevice>,
+    shader_dir: Res<ShaderDir>,
     mut pipeline_cache: ResMut<ComputePipelineCache>,
     mut bind_group_layout_cache: ResMut<BindGroupLayoutCache>,
 ) {
-    pipeline_cache.get_or_create_pipeline::<T>(&device, &mut bind_group_layout_cache);
+    pipeline_cache.get_or_create_pipeline::<T>(&device, &shader_dir, &mut bind_group_layout_cache);
 }
@@ -4,11 +4,100 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use naga_oil::compose::{ComposableModuleDescriptor, Composer, NagaModuleDescriptor};
-use weaver_asset::prelude::*;
-use weaver_ecs::prelude::Commands;
+use naga_oil::compose::{
+    ComposableModuleDescriptor, Composer, NagaModuleDescriptor, ShaderDefValue,
+};
+use weaver_asset::{hot_reload::AssetWatcher, prelude::*};
+use weaver_ecs::prelude::{Commands, Res, ResMut};
 use weaver_util::prelude::*;
 
+/// The directory that shaders and their imports are loaded from. Inserted into the render world by the
+/// `RendererPlugin` from the app's asset settings.
+#[derive(Debug, Clone)]
+pub struct ShaderDir {
+    path: PathBuf,
+    revision: u64,
+}
+
+impl Default for ShaderDir {
+    fn default() -> Self {
+        Self::new("assets/shaders")
+    }
+}
+
+impl ShaderDir {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            revision: 0,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Bumped whenever a shader in the directory changes. Pipelines created from an older revision are created again.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+}
+
+/// Watches the [`ShaderDir`] when the app watches its assets for changes, see [`reload_changed_shaders`].
+pub struct ShaderWatcher(AssetWatcher);
+
+impl ShaderWatcher {
+    pub fn new(shader_dir: &ShaderDir) -> Result<Self> {
+        Ok(Self(AssetWatcher::new(&[shader_dir.path().to_path_buf()])?))
+    }
+
+    /// Bumps the revision of the shader directory if any of its shaders changed, returning whether it did. Shaders that
+    /// fail to compile are reported instead, and keep the pipelines of the previous revision running.
+    pub fn reload_changed(&self, shader_dir: &mut ShaderDir) -> Result<bool> {
+        let changed = self
+            .0
+            .changed_files()
+            .into_iter()
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "wgsl")
+            })
+            .collect::<Vec<_>>();
+        if changed.is_empty() {
+            return Ok(false);
+        }
+
+        // the watcher reports canonical paths, while imports are resolved relative to the shader directory
+        let dir = std::fs::canonicalize(shader_dir.path())?;
+        for path in changed.iter() {
+            let path = match path.strip_prefix(&dir) {
+                Ok(relative) => shader_dir.path().join(relative),
+                Err(_) => path.clone(),
+            };
+            compose_shader(
+                path.to_str().unwrap(),
+                shader_dir.path().to_str().unwrap(),
+                &[],
+            )
+            .map_err(|e| anyhow!("{:?}: {}", path, e))?;
+        }
+
+        log::info!("Shaders changed, recreating pipelines: {:?}", changed);
+        shader_dir.revision += 1;
+        Ok(true)
+    }
+}
+
+/// Recreates the pipelines when a shader changes.
+pub(crate) async fn reload_changed_shaders(
+    watcher: Res<ShaderWatcher>,
+    mut shader_dir: ResMut<ShaderDir>,
+) {
+    if let Err(e) = watcher.reload_changed(&mut shader_dir) {
+        log::error!("Not reloading shaders: {}", e);
+    }
+}
+
 #[derive(Debug, Clone, Asset)]
 pub struct Shader {
     pub path: PathBuf,
@@ -16,13 +105,25 @@ pub struct Shader {
 }
 
 impl Shader {
-    pub fn new(path: impl AsRef<Path>) -> Self {
-        let path = path.as_ref();
-        let module = preprocess_shader(path.to_str().unwrap(), "assets/shaders/");
-        Self {
-            path: path.into(),
-            module,
-        }
+    /// Loads and preprocesses a shader. Relative paths are relative to the shader directory.
+    pub fn new(shader_dir: &ShaderDir, path: impl AsRef<Path>) -> Self {
+        Self::with_defs(shader_dir, path, &[])
+    }
+
+    /// Loads and preprocesses a shader with shader defs set, which its `#ifdef` blocks check.
+    pub fn with_defs(
+        shader_dir: &ShaderDir,
+        path: impl AsRef<Path>,
+        shader_defs: &[String],
+    ) -> Self {
+        let shader_dir = shader_dir.path();
+        let path = shader_dir.join(path);
+        let module = preprocess_shader_with_defs(
+            path.to_str().unwrap(),
+            shader_dir.to_str().unwrap(),
+            shader_defs,
+        );
+        Self { path, module }
     }
 
     pub fn create_shader_module(&self, device: &wgpu::Device) -> wgpu::ShaderModule {
@@ -34,13 +135,16 @@ impl Shader {
 }
 
 #[derive(Default)]
-pub struct ShaderLoader;
+pub struct ShaderLoader {
+    pub shader_dir: ShaderDir,
+}
 
 impl LoadFrom<PathBuf> for ShaderLoader {
     type Asset = Shader;
+    type Settings = ();
 
-    async fn load(&self, source: PathBuf, _commands: &Commands) -> Result<Shader> {
-        Ok(Shader::new(source))
+    async fn load(&self, source: PathBuf, _settings: &(), _commands: &Commands) -> Result<Shader> {
+        Ok(Shader::new(&self.shader_dir, source))
     }
 }
 
@@ -97,26 +201,85 @@ fn try_every_shader_file(
     Ok(())
 }
 
-pub fn preprocess_shader(
+pub fn preprocess_shader(file_path: &str, base_include_path: &str) -> wgpu::ShaderSource<'static> {
+    preprocess_shader_with_defs(file_path, base_include_path, &[])
+}
+
+pub fn preprocess_shader_with_defs(
     file_path: &str,
-    base_include_path: &'static str,
+    base_include_path: &str,
+    shader_defs: &[String],
 ) -> wgpu::ShaderSource<'static> {
+    compose_shader(file_path, base_include_path, shader_defs).unwrap_or_else(|e| {
+        log::error!("Failed to compile shader {}: {}", file_path, e);
+        panic!("{}", e);
+    })
+}
+
+/// Preprocesses a shader like [`preprocess_shader_with_defs`], returning an error if it doesn't compile.
+pub fn compose_shader(
+    file_path: &str,
+    base_include_path: &str,
+    shader_defs: &[String],
+) -> Result<wgpu::ShaderSource<'static>> {
     let mut composer = Composer::non_validating();
 
-    let shader = std::fs::read_to_string(file_path).unwrap();
+    let shader = std::fs::read_to_string(file_path)?;
 
-    try_every_shader_file(&mut composer, file_path, base_include_path, 100).unwrap();
+    try_every_shader_file(&mut composer, file_path, base_include_path, 100)?;
 
     let module = composer
         .make_naga_module(NagaModuleDescriptor {
             file_path,
             source: shader.as_str(),
+            shader_defs: shader_defs
+                .iter()
+                .map(|def| (def.clone(), ShaderDefValue::Bool(true)))
+                .collect(),
             ..Default::default()
         })
-        .unwrap_or_else(|e| {
-            log::error!("Failed to compile shader {}: {}", file_path, e.inner);
-            panic!("{}", e.inner);
-        });
+        .map_err(|e| anyhow!("{}", e.inner))?;
+
+    Ok(wgpu::ShaderSource::Naga(Cow::Owned(module)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
 
-    wgpu::ShaderSource::Naga(Cow::Owned(module))
+    use super::*;
+
+    /// Polls the watcher until it noticed a change.
+    fn wait_for_reload(watcher: &ShaderWatcher, shader_dir: &mut ShaderDir) -> Result<bool> {
+        let start = Instant::now();
+        loop {
+            let result = watcher.reload_changed(shader_dir);
+            if !matches!(result, Ok(false)) || start.elapsed() > Duration::from_secs(10) {
+                return result;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_reload_changed_shaders() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let shader =
+            "@fragment\nfn main() -> @location(0) vec4<f32> {\n    return vec4<f32>(1.0);\n}\n";
+        std::fs::write(dir.join("color.wgsl"), shader).unwrap();
+
+        let mut shader_dir = ShaderDir::new(dir);
+        let watcher = ShaderWatcher::new(&shader_dir).unwrap();
+        assert!(!watcher.reload_changed(&mut shader_dir).unwrap());
+
+        std::fs::write(dir.join("color.wgsl"), shader.replace("1.0", "0.5")).unwrap();
+        assert!(wait_for_reload(&watcher, &mut shader_dir).unwrap());
+        assert_eq!(shader_dir.revision(), 1);
+
+        // a shader that doesn't compile keeps the previous revision
+        std::fs::write(dir.join("color.wgsl"), "fn main( {").unwrap();
+        assert!(wait_for_reload(&watcher, &mut shader_dir).is_err());
+        assert_eq!(shader_dir.revision(), 1);
+    }
 }
@@ -2,20 +2,78 @@ use std::sync::Arc;
 
 use weaver_app::{plugin::Plugin, prelude::App};
 use weaver_asset::AssetApp;
-use weaver_core::texture::Texture;
+use weaver_core::texture::{ColorSpace, Texture, TextureDimension, TextureFormat};
 use weaver_util::prelude::*;
 use wgpu::util::DeviceExt;
 
 pub mod texture_format {
     pub use wgpu::TextureFormat;
     pub const VIEW_FORMAT: TextureFormat = TextureFormat::Bgra8UnormSrgb;
-    pub const SDR_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
     pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
     pub const HDR_CUBE_FORMAT: TextureFormat = TextureFormat::Rgba32Float;
-    pub const NORMAL_FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
     pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
 }
 
+/// The GPU format of textures of the given format, the sRGB variant for sRGB textures of formats that have one.
+pub fn gpu_format(format: TextureFormat, color_space: ColorSpace) -> wgpu::TextureFormat {
+    use wgpu::{AstcBlock, AstcChannel, TextureFormat as Gpu};
+    let srgb = color_space == ColorSpace::Srgb;
+    let pick = |linear: Gpu, srgb_format: Gpu| if srgb { srgb_format } else { linear };
+    let astc = |block: AstcBlock| Gpu::Astc {
+        block,
+        channel: if srgb {
+            AstcChannel::UnormSrgb
+        } else {
+            AstcChannel::Unorm
+        },
+    };
+    match format {
+        TextureFormat::R8Unorm => Gpu::R8Unorm,
+        TextureFormat::Rg8Unorm => Gpu::Rg8Unorm,
+        TextureFormat::Rgba8Unorm => pick(Gpu::Rgba8Unorm, Gpu::Rgba8UnormSrgb),
+        TextureFormat::R16Unorm => Gpu::R16Unorm,
+        TextureFormat::Rg16Unorm => Gpu::Rg16Unorm,
+        TextureFormat::Rgba16Unorm => Gpu::Rgba16Unorm,
+        TextureFormat::R16Float => Gpu::R16Float,
+        TextureFormat::Rg16Float => Gpu::Rg16Float,
+        TextureFormat::Rgba16Float => Gpu::Rgba16Float,
+        TextureFormat::R32Float => Gpu::R32Float,
+        TextureFormat::Rg32Float => Gpu::Rg32Float,
+        TextureFormat::Rgba32Float => Gpu::Rgba32Float,
+        TextureFormat::Rgb9e5Ufloat => Gpu::Rgb9e5Ufloat,
+        TextureFormat::Rg11b10Ufloat => Gpu::Rg11b10Ufloat,
+        TextureFormat::Bc1RgbaUnorm => pick(Gpu::Bc1RgbaUnorm, Gpu::Bc1RgbaUnormSrgb),
+        TextureFormat::Bc2RgbaUnorm => pick(Gpu::Bc2RgbaUnorm, Gpu::Bc2RgbaUnormSrgb),
+        TextureFormat::Bc3RgbaUnorm => pick(Gpu::Bc3RgbaUnorm, Gpu::Bc3RgbaUnormSrgb),
+        TextureFormat::Bc4RUnorm => Gpu::Bc4RUnorm,
+        TextureFormat::Bc4RSnorm => Gpu::Bc4RSnorm,
+        TextureFormat::Bc5RgUnorm => Gpu::Bc5RgUnorm,
+        TextureFormat::Bc5RgSnorm => Gpu::Bc5RgSnorm,
+        TextureFormat::Bc6hRgbUfloat => Gpu::Bc6hRgbUfloat,
+        TextureFormat::Bc6hRgbFloat => Gpu::Bc6hRgbFloat,
+        TextureFormat::Bc7RgbaUnorm => pick(Gpu::Bc7RgbaUnorm, Gpu::Bc7RgbaUnormSrgb),
+        TextureFormat::Etc2Rgb8Unorm => pick(Gpu::Etc2Rgb8Unorm, Gpu::Etc2Rgb8UnormSrgb),
+        TextureFormat::Etc2Rgb8A1Unorm => pick(Gpu::Etc2Rgb8A1Unorm, Gpu::Etc2Rgb8A1UnormSrgb),
+        TextureFormat::Etc2Rgba8Unorm => pick(Gpu::Etc2Rgba8Unorm, Gpu::Etc2Rgba8UnormSrgb),
+        TextureFormat::EacR11Unorm => Gpu::EacR11Unorm,
+        TextureFormat::EacRg11Unorm => Gpu::EacRg11Unorm,
+        TextureFormat::Astc4x4Unorm => astc(AstcBlock::B4x4),
+        TextureFormat::Astc5x4Unorm => astc(AstcBlock::B5x4),
+        TextureFormat::Astc5x5Unorm => astc(AstcBlock::B5x5),
+        TextureFormat::Astc6x5Unorm => astc(AstcBlock::B6x5),
+        TextureFormat::Astc6x6Unorm => astc(AstcBlock::B6x6),
+        TextureFormat::Astc8x5Unorm => astc(AstcBlock::B8x5),
+        TextureFormat::Astc8x6Unorm => astc(AstcBlock::B8x6),
+        TextureFormat::Astc8x8Unorm => astc(AstcBlock::B8x8),
+        TextureFormat::Astc10x5Unorm => astc(AstcBlock::B10x5),
+        TextureFormat::Astc10x6Unorm => astc(AstcBlock::B10x6),
+        TextureFormat::Astc10x8Unorm => astc(AstcBlock::B10x8),
+        TextureFormat::Astc10x10Unorm => astc(AstcBlock::B10x10),
+        TextureFormat::Astc12x10Unorm => astc(AstcBlock::B12x10),
+        TextureFormat::Astc12x12Unorm => astc(AstcBlock::B12x12),
+    }
+}
+
 #[derive(Clone)]
 pub struct GpuTexture {
     pub texture: Arc<wgpu::Texture>,
@@ -54,12 +112,57 @@ impl GpuTexture {
         }
     }
 
-    pub fn from_image(
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        image: &Texture,
-        format: wgpu::TextureFormat,
-    ) -> Option<Self> {
+    /// Uploads a texture with all of its mip levels and layers, in the sRGB variant of its format if it's an sRGB
+    /// texture. Formats the device can't sample as they are, 16-bit normalized formats on devices that don't support
+    /// them and sRGB textures of formats without an sRGB variant, are converted to linear 16-bit floats. Returns
+    /// `None` for compressed formats the device doesn't support.
+    pub fn from_image(device: &wgpu::Device, queue: &wgpu::Queue, image: &Texture) -> Option<Self> {
+        let float_format = match image.format() {
+            TextureFormat::R8Unorm | TextureFormat::R16Unorm => Some(TextureFormat::R16Float),
+            TextureFormat::Rg8Unorm | TextureFormat::Rg16Unorm => Some(TextureFormat::Rg16Float),
+            TextureFormat::Rgba16Unorm => Some(TextureFormat::Rgba16Float),
+            _ => None,
+        };
+        let unsupported_16bit_norm = matches!(
+            image.format(),
+            TextureFormat::R16Unorm | TextureFormat::Rg16Unorm | TextureFormat::Rgba16Unorm
+        ) && !device
+            .features()
+            .contains(wgpu::Features::TEXTURE_FORMAT_16BIT_NORM);
+        let converted;
+        let image = match float_format {
+            Some(format) if unsupported_16bit_norm || image.color_space() == ColorSpace::Srgb => {
+                converted = image.to_linear(format).ok()?;
+                &converted
+            }
+            _ => image,
+        };
+
+        let format = gpu_format(image.format(), image.color_space());
+        if !device.features().contains(format.required_features()) {
+            log::error!(
+                "Texture format {:?} is not supported by this device",
+                image.format()
+            );
+            return None;
+        }
+
+        let (dimension, view_dimension) = match image.dimension() {
+            TextureDimension::D2 => (wgpu::TextureDimension::D2, wgpu::TextureViewDimension::D2),
+            TextureDimension::D2Array => (
+                wgpu::TextureDimension::D2,
+                wgpu::TextureViewDimension::D2Array,
+            ),
+            TextureDimension::Cube => {
+                (wgpu::TextureDimension::D2, wgpu::TextureViewDimension::Cube)
+            }
+            TextureDimension::CubeArray => (
+                wgpu::TextureDimension::D2,
+                wgpu::TextureViewDimension::CubeArray,
+            ),
+            TextureDimension::D3 => (wgpu::TextureDimension::D3, wgpu::TextureViewDimension::D3),
+        };
+
         let texture = device.create_texture_with_data(
             queue,
             &wgpu::TextureDescriptor {
@@ -67,20 +170,23 @@ impl GpuTexture {
                 size: wgpu::Extent3d {
                     width: image.width(),
                     height: image.height(),
-                    depth_or_array_layers: 1,
+                    depth_or_array_layers: image.depth_or_array_layers(),
                 },
-                mip_level_count: 1,
+                mip_level_count: image.mip_level_count(),
                 sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
+                dimension,
                 format,
                 usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
                 view_formats: &[],
             },
-            wgpu::util::TextureDataOrder::LayerMajor,
-            &image.to_rgba8(),
+            wgpu::util::TextureDataOrder::MipMajor,
+            &image.to_bytes(),
         );
 
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(view_dimension),
+            ..Default::default()
+        });
 
         Some(Self {
             texture: Arc::new(texture),
@@ -121,12 +227,19 @@ impl GpuTextureArray {
                 .all(|image| image.height() == images[0].height()),
             "All images in texture array must have the same height"
         );
+        assert!(
+            images
+                .iter()
+                .all(|image| image.format() == images[0].format()),
+            "All images in texture array must have the same format"
+        );
         let width = images[0].width();
         let height = images[0].height();
+        let format = gpu_format(images[0].format(), images[0].color_space());
 
         let data = images
             .iter()
-            .flat_map(|image| image.to_rgba8())
+            .flat_map(|image| image.levels()[0].iter().copied())
             .collect::<Vec<u8>>();
 
         let texture = device.create_texture_with_data(
@@ -141,7 +254,7 @@ impl GpuTextureArray {
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                format,
                 usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
                 view_formats: &[],
             },
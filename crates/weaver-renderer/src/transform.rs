@@ -8,7 +8,7 @@ use weaver_ecs::{
 };
 use weaver_util::prelude::*;
 
-use weaver_core::transform::Transform;
+use weaver_core::{hierarchy::GlobalTransform, transform::Transform};
 
 use crate::{
     RenderStage, WgpuDevice, WgpuQueue,
@@ -20,13 +20,17 @@ use crate::{
 };
 
 impl ExtractComponent for Transform {
-    type ExtractQueryFetch = &'static Transform;
+    type ExtractQueryFetch = (&'static Transform, Option<&'static GlobalTransform>);
     type Out = GpuTransform;
 
     fn extract_render_component(
-        item: QueryableItem<'_, Self::ExtractQueryFetch>,
+        (transform, global): QueryableItem<'_, Self::ExtractQueryFetch>,
     ) -> Option<Self::Out> {
-        let matrix = item.matrix();
+        // entities in a hierarchy are rendered where their ancestors put them
+        let matrix = match global {
+            Some(global) => global.matrix(),
+            None => transform.matrix(),
+        };
 
         Some(GpuTransform { matrix })
     }
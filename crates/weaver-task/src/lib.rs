@@ -6,6 +6,9 @@ pub mod usages;
 
 pub use futures_lite;
 
+/// Runs blocking code like file IO on a separate thread pool, so that it doesn't stall the task pool's workers.
+pub use blocking::unblock;
+
 pub trait SendFuture: Future + Send {}
 impl<T: Future + Send> SendFuture for T {}
 
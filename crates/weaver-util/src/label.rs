@@ -130,20 +130,19 @@ macro_rules! define_label {
             fn ref_eq(&self, other: &Self) -> bool {
                 use ::core::ptr;
 
-                // Test that both the type id and pointer address are equivalent.
-                self.as_dyn_eq().type_id() == other.as_dyn_eq().type_id()
-                    && ptr::addr_eq(ptr::from_ref::<Self>(self), ptr::from_ref::<Self>(other))
+                // Test that both the type id and pointer address are equivalent. Labels interned by a dynamically
+                // loaded plugin live in its own copy of the interner, so labels at different addresses are still
+                // compared by value.
+                (self.as_dyn_eq().type_id() == other.as_dyn_eq().type_id()
+                    && ptr::addr_eq(ptr::from_ref::<Self>(self), ptr::from_ref::<Self>(other)))
+                    || self == other
             }
 
             fn ref_hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
-                use ::core::{hash::Hash, ptr};
+                use ::core::hash::Hash;
 
-                // Hash the type id...
-                self.as_dyn_eq().type_id().hash(state);
-
-                // ...and the pointer address.
-                // Cast to a unit `()` first to discard any pointer metadata.
-                ptr::from_ref::<Self>(self).cast::<()>().hash(state);
+                // Hash the value rather than the address, to agree with `ref_eq`.
+                self.hash(state);
             }
         }
 
@@ -1,7 +1,13 @@
 use std::{ops::Deref, sync::Arc};
 
 use pollster::FutureExt;
-use weaver_app::{Runner, plugin::Plugin, prelude::App};
+use serde::{Deserialize, Serialize};
+use weaver_app::{
+    AppExit, Runner,
+    plugin::{Plugin, PluginId},
+    prelude::App,
+    settings::SettingsSection,
+};
 use weaver_core::input::Input;
 use weaver_util::prelude::*;
 use winit::{
@@ -22,13 +28,20 @@ pub struct Window {
     pub display_handle: OwnedDisplayHandle,
 }
 
-#[derive(Debug, Clone)]
+/// The `[window]` section of the app settings, inserted as a resource by [`WindowPlugin`].
+///
+/// Once the window is created, the resource holds its current size in physical pixels.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WindowSettings {
     pub title: String,
     pub width: u32,
     pub height: u32,
 }
 
+impl SettingsSection for WindowSettings {
+    const NAME: &'static str = "window";
+}
+
 impl Deref for Window {
     type Target = winit::window::Window;
 
@@ -48,6 +61,7 @@ pub struct WindowResized {
     pub height: u32,
 }
 
+/// Creates the window settings. The plugin's fields are the defaults for the `[window]` settings section.
 pub struct WindowPlugin {
     pub window_title: &'static str,
     pub initial_size: (u32, u32),
@@ -66,11 +80,12 @@ impl Default for WindowPlugin {
 
 impl Plugin for WindowPlugin {
     fn build(&self, app: &mut App) -> Result<()> {
-        app.main_app().world().insert_resource(WindowSettings {
+        let settings = app.settings().section_or(WindowSettings {
             title: self.window_title.to_string(),
             width: self.initial_size.0,
             height: self.initial_size.1,
-        });
+        })?;
+        app.main_app().world().insert_resource(settings);
         app.add_event::<WindowResized>();
         Ok(())
     }
@@ -82,6 +97,10 @@ impl Plugin for WindowPlugin {
 }
 
 impl Plugin for WinitPlugin {
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<WindowPlugin>()]
+    }
+
     fn build(&self, app: &mut App) -> Result<()> {
         let event_loop = winit::event_loop::EventLoop::new()?;
         app.set_runner(WinitRunner {
@@ -97,7 +116,7 @@ struct WinitRunner {
 }
 
 impl Runner for WinitRunner {
-    fn run(&self, app: &mut App) -> Result<()> {
+    fn run(&self, app: &mut App) -> Result<AppExit> {
         app.init();
 
         let event_loop = self.event_loop.write().take().unwrap();
@@ -112,11 +131,12 @@ impl Runner for WinitRunner {
             app,
             window_title: window_settings.title.clone(),
             initial_size: (window_settings.width, window_settings.height),
+            exit: None,
         };
 
         event_loop.run_app(&mut winit_app)?;
 
-        Ok(())
+        Ok(winit_app.exit.unwrap_or_default())
     }
 }
 
@@ -124,6 +144,23 @@ struct WinitRunnerApp<'app> {
     app: &'app mut App,
     window_title: String,
     initial_size: (u32, u32),
+    exit: Option<AppExit>,
+}
+
+impl WinitRunnerApp<'_> {
+    /// Shuts the app down and stops the event loop if an exit has been requested.
+    fn handle_exit(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        if self.exit.is_some() {
+            return;
+        }
+
+        if let Some(exit) = self.app.exit_requested() {
+            log::debug!("Exiting: {:?}", exit);
+            self.app.shutdown();
+            self.exit = Some(exit);
+            event_loop.exit();
+        }
+    }
 }
 
 impl winit::application::ApplicationHandler for WinitRunnerApp<'_> {
@@ -135,6 +172,17 @@ impl winit::application::ApplicationHandler for WinitRunnerApp<'_> {
                     .with_inner_size(LogicalSize::new(self.initial_size.0, self.initial_size.1)),
             )
             .unwrap();
+        // the window is created with a logical size, but its size is kept in physical pixels once it's resized
+        let size = window.inner_size();
+        if let Some(mut window_size) = self
+            .app
+            .main_app()
+            .world()
+            .get_resource_mut::<WindowSettings>()
+        {
+            window_size.width = size.width;
+            window_size.height = size.height;
+        }
         let display_handle = event_loop.owned_display_handle();
         let window = Window {
             window: Arc::new(window),
@@ -219,13 +267,14 @@ impl winit::application::ApplicationHandler for WinitRunnerApp<'_> {
                 }
             }
             WindowEvent::CloseRequested => {
-                self.app.shutdown();
-                event_loop.exit();
+                self.app.exit(AppExit::Success);
             }
-            WindowEvent::RedrawRequested => {
+            WindowEvent::RedrawRequested if self.exit.is_none() => {
                 self.app.update();
             }
             _ => {}
         }
+
+        self.handle_exit(event_loop);
     }
 }
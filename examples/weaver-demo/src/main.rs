@@ -1,29 +1,23 @@
-use std::path::PathBuf;
-
 use weaver::prelude::*;
+use weaver_asset::AssetApp;
 use weaver_diagnostics::prelude::*;
 
 pub mod camera;
 pub mod transform_gizmo;
 
-fn main() -> Result<()> {
+fn main() -> Result<AppExit> {
     App::new()
-        .add_plugin(CoreTypesPlugin)?
-        .add_plugin(WindowPlugin::default())?
-        .configure_plugin::<WindowPlugin>(|plugin| {
-            plugin.initial_size = (1600, 900);
-        })
-        .add_plugin(WinitPlugin)?
-        .add_plugin(TimePlugin)?
-        .add_plugin(InputPlugin)?
-        .add_plugin(RendererPlugin)?
-        .add_plugin(ClearColorPlugin(Color::new(0.1, 0.1, 0.1, 1.0)))?
-        .add_plugin(PbrPlugin)?
+        .add_plugins(DefaultPlugins.build().set(WindowPlugin {
+            initial_size: (1600, 900),
+            ..Default::default()
+        }))?
         .add_plugin(LogFrameTimePlugin {
             log_interval: std::time::Duration::from_secs(1),
         })?
-        .insert_resource(Skybox::new("assets/skyboxes/meadow_2k.hdr"))
+        .insert_resource(Skybox::new("assets/skyboxes/clear_sky.hdr"))
+        .init_asset_collection::<FloorTextures>()
         .add_system(setup, Init)
+        .add_system(spawn_floor, Update)
         .add_system(camera::update_camera, Update)
         .add_system(camera::update_aspect_ratio, Update)
         .run()
@@ -31,8 +25,8 @@ fn main() -> Result<()> {
 
 async fn setup(
     commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<Material>>,
+    asset_server: Res<AssetServer>,
+    scene_spawner: Res<SceneSpawner>,
 ) {
     commands.spawn((
         Camera::default(),
@@ -59,18 +53,57 @@ async fn setup(
         },
     ));
 
-    let mut material_mesh = GltfMaterialModelLoader
-        .load(
-            PathBuf::from("assets/meshes/stanford_dragon_pbr.glb"),
-            &commands,
-        )
-        .await
-        .unwrap();
+    match asset_server.load::<GltfScene>("meshes/stanford_dragon_pbr.glb") {
+        Ok(dragon) => {
+            scene_spawner.spawn(&commands, dragon, Transform::default());
+        }
+        Err(e) => log::error!("Failed to load dragon: {}", e),
+    }
+}
 
-    let LoadedMaterialMeshPrimitive { material, mesh, .. } = material_mesh.primitives.remove(0);
+/// The textures of the floor, loaded together from their folder.
+#[derive(TypedAssetCollection)]
+struct FloorTextures {
+    #[asset(folder = "materials/Wood_025_SD")]
+    wood: Handle<AssetCollection>,
+}
 
-    let mesh = meshes.insert(mesh);
-    let material = materials.insert(material);
+/// Spawns the floor once its textures are loaded.
+async fn spawn_floor(
+    commands: Commands,
+    floor_textures: Option<Res<FloorTextures>>,
+    mut collections: ResMut<Assets<AssetCollection>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<Material>>,
+) {
+    let Some(floor_textures) = floor_textures else {
+        return;
+    };
+    let Some(wood) = collections.get(&floor_textures.wood) else {
+        return;
+    };
+
+    let mut material = Material::from(Color::WHITE);
+    if let Some(diffuse) = wood.get::<Texture>("Wood_025_basecolor.jpg") {
+        material.diffuse_texture = diffuse;
+    }
+    if let Some(normal) = wood.get::<Texture>("Wood_025_normal.jpg") {
+        material.normal_texture = normal;
+    }
+    if let Some(ao) = wood.get::<Texture>("Wood_025_ambientOcclusion.jpg") {
+        material.ao_texture = ao;
+    }
+    material.roughness = 0.7;
+    material.texture_scale = 20.0;
+
+    commands.spawn((
+        meshes.insert(PlanePrimitive::new(Vec2::splat(400.0)).generate_mesh()),
+        materials.insert(material),
+        Transform::default(),
+    ));
 
-    commands.spawn((mesh, material, Transform::default()));
+    // the material keeps the textures it uses alive
+    drop(wood);
+    drop(floor_textures);
+    commands.remove_resource::<FloorTextures>();
 }
@@ -1,3 +1,4 @@
+pub use weaver_animation;
 pub use weaver_app;
 pub use weaver_asset;
 pub use weaver_core;
@@ -12,6 +13,7 @@ pub use weaver_winit;
 
 pub mod prelude {
     pub use super::*;
+    pub use weaver_animation::prelude::*;
     pub use weaver_app::prelude::*;
     pub use weaver_asset::prelude::*;
     pub use weaver_core::prelude::*;
@@ -24,23 +26,35 @@ pub mod prelude {
     pub use weaver_winit::prelude::*;
 }
 
+/// The plugins needed for a windowed app with PBR rendering.
+///
+/// Individual plugins can be replaced or disabled through the [`PluginGroupBuilder`](weaver_app::plugin::PluginGroupBuilder):
+/// ```ignore
+/// app.add_plugins(
+///     DefaultPlugins
+///         .build()
+///         .set(ClearColorPlugin(Color::WHITE))
+///         .disable::<PbrPlugin>(),
+/// )?;
+/// ```
 pub struct DefaultPlugins;
 
-impl weaver_app::plugin::Plugin for DefaultPlugins {
-    fn build(&self, app: &mut weaver_app::App) -> weaver_util::prelude::Result<()> {
+impl weaver_app::plugin::PluginGroup for DefaultPlugins {
+    fn build(self) -> weaver_app::plugin::PluginGroupBuilder {
         use crate::prelude::*;
         use weaver_core::CoreTypesPlugin;
-        use weaver_renderer::clear_color::ClearColorPlugin;
 
-        app.add_plugin(CoreTypesPlugin)?
-            .add_plugin(WindowPlugin::default())?
-            .add_plugin(WinitPlugin)?
-            .add_plugin(TimePlugin)?
-            .add_plugin(InputPlugin)?
-            .add_plugin(RendererPlugin)?
-            .add_plugin(ClearColorPlugin(Color::new(0.1, 0.1, 0.1, 1.0)))?
-            .add_plugin(PbrPlugin)?;
-
-        Ok(())
+        PluginGroupBuilder::start::<Self>()
+            .add(AssetPlugin)
+            .add(CoreTypesPlugin)
+            .add(WindowPlugin::default())
+            .add(WinitPlugin)
+            .add(TimePlugin)
+            .add(AnimationPlugin)
+            .add(InputPlugin)
+            .add(RendererPlugin::default())
+            .add(MeshLodPlugin)
+            .add(ClearColorPlugin(Color::new(0.1, 0.1, 0.1, 1.0)))
+            .add(PbrPlugin)
     }
 }